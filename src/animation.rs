@@ -0,0 +1,370 @@
+//! Brief flourishes played over the clock face between ticks, like the flash
+//! when the hour rolls over, the numerals coming in one by one at startup,
+//! and numerals morphing into the next with `--morph`.
+//!
+//! While one's running, the clock redraws every [`FRAME_INTERVAL`] instead of
+//! only when the time changes. None of them run with `--reduce-motion`, or in
+//! high contrast mode, which promises nothing but the two colors.
+
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::{
+    buf2d::Vec2d,
+    colors::{self, Color},
+    config::Config,
+    frame::{Canvas, Number, Sign},
+    glyphs::{GlyphBuffer, Glyphs, MaskBuffer},
+    synth,
+};
+
+/// How often to redraw while something's moving.
+pub const FRAME_INTERVAL: Duration = Duration::from_millis(33);
+
+/// How long the rollover flash lasts, start to finish.
+const FLASH_FOR: Duration = Duration::from_millis(700);
+
+/// How much of the flash is spent getting brighter, before it fades.
+const FLASH_RISE: f32 = 0.15;
+
+/// Whether the clock's allowed to animate at all.
+pub fn allowed(config: &Config) -> bool {
+    !config.reduce_motion && !config.glyphs.high_contrast
+}
+
+/// The background flashing toward the digits' color, when the hour rolls over.
+#[derive(Debug, Clone, Copy)]
+pub struct Flash {
+    started: Instant,
+    /// How bright it gets at its brightest, from 0 to 1.
+    intensity: f32,
+}
+
+impl Flash {
+    pub fn new(started: Instant, intensity: f32) -> Self {
+        Self { started, intensity }
+    }
+
+    /// How far toward the digits' color the background is at `now`, or `None`
+    /// once the flash is over.
+    pub fn strength(&self, now: Instant) -> Option<f32> {
+        let t = now.saturating_duration_since(self.started).as_secs_f32() / FLASH_FOR.as_secs_f32();
+        if t >= 1.0 {
+            return None;
+        }
+        // Up quickly, then easing back down
+        let envelope = if t < FLASH_RISE { t / FLASH_RISE } else { ((1.0 - t) / (1.0 - FLASH_RISE)).powi(2) };
+        Some(envelope * self.intensity)
+    }
+}
+
+/// Blends all of `frame` toward `fg` by `strength`, which lights up the
+/// background and leaves the digits (already `fg`) as they are.
+pub fn draw_flash(frame: &mut GlyphBuffer, fg: Color, strength: f32) {
+    for y in 0..frame.height() {
+        for px in frame.row_mut(y) {
+            *px = colors::lerp(*px, fg, strength);
+        }
+    }
+}
+
+/// How long each numeral takes to come in, at startup.
+const REVEAL_NUMERAL_FOR: Duration = Duration::from_millis(300);
+
+/// How long after one numeral starts coming in the next one does.
+const REVEAL_STAGGER: Duration = Duration::from_millis(150);
+
+/// Draws the clock face the usual way, but with the numerals coming in one at a
+/// time, each wiped in from the left as it fades up, and everything else fading
+/// up with the first. For the reveal at startup.
+pub struct Reveal<'a> {
+    buf: &'a mut GlyphBuffer,
+    /// How long it's been going.
+    elapsed: Duration,
+    /// How many numerals have been drawn so far.
+    numerals: u32,
+    /// Whether everything drawn so far is all the way in.
+    done: bool,
+}
+
+impl<'a> Reveal<'a> {
+    /// Draws onto `buf` as of `elapsed` into the reveal.
+    pub fn new(buf: &'a mut GlyphBuffer, elapsed: Duration) -> Self {
+        Self { buf, elapsed, numerals: 0, done: true }
+    }
+
+    /// Whether it's all been drawn all the way in, so it's over.
+    pub fn done(&self) -> bool {
+        self.done
+    }
+
+    /// How far in something that started coming in at `start` is, from 0 to 1.
+    fn amount(&mut self, start: Duration) -> f32 {
+        let amount = self.elapsed.saturating_sub(start).as_secs_f32() / REVEAL_NUMERAL_FOR.as_secs_f32();
+        self.done &= amount >= 1.0;
+        amount.min(1.0)
+    }
+
+    /// Draws something other than a numeral, faded up as far as the first.
+    fn faded(&mut self, glyphs: &mut Glyphs, draw: impl FnOnce(&mut GlyphBuffer, &mut Glyphs)) {
+        let amount = self.amount(Duration::ZERO);
+        if amount <= 0.0 {
+            return;
+        }
+        let (fg, bg) = (glyphs.fg(), glyphs.bg());
+        glyphs.set_colors(colors::lerp(bg, fg, amount), bg);
+        draw(self.buf, glyphs);
+        glyphs.set_colors(fg, bg);
+    }
+}
+
+impl Canvas for Reveal<'_> {
+    fn number(&mut self, glyphs: &mut Glyphs, number: Number, x: usize, y: usize) {
+        let amount = self.amount(REVEAL_STAGGER * self.numerals);
+        self.numerals += 1;
+        if amount <= 0.0 {
+            return;
+        }
+        let color = colors::lerp(glyphs.bg(), glyphs.fg(), amount);
+        let raster = number.raster(glyphs);
+        let width = (raster.width() as f32 * amount).ceil() as usize;
+        self.buf.tint_from(x, y, raster.sub_view(0, 0, width.min(raster.width()), raster.height()), color);
+    }
+
+    fn colon(&mut self, glyphs: &mut Glyphs, x: usize, y: usize) {
+        self.faded(glyphs, |buf, glyphs| buf.colon(glyphs, x, y));
+    }
+
+    fn sign(&mut self, glyphs: &mut Glyphs, sign: Sign, x: usize, y: usize) {
+        self.faded(glyphs, |buf, glyphs| buf.sign(glyphs, sign, x, y));
+    }
+
+    fn fg(&self, glyphs: &Glyphs) -> Color {
+        self.buf.fg(glyphs)
+    }
+
+    fn set_fg(&mut self, glyphs: &mut Glyphs, fg: Color) {
+        self.buf.set_fg(glyphs, fg);
+    }
+
+    fn label(&mut self, glyphs: &mut Glyphs, text: &str, x: usize, y: usize, width: usize, height: usize) {
+        self.faded(glyphs, |buf, glyphs| buf.label(glyphs, text, x, y, width, height));
+    }
+
+    fn ring(
+        &mut self,
+        glyphs: &mut Glyphs,
+        x: usize,
+        y: usize,
+        diameter: usize,
+        thickness: f32,
+        progress: f32,
+    ) {
+        self.faded(glyphs, |buf, glyphs| buf.ring(glyphs, x, y, diameter, thickness, progress));
+    }
+}
+
+/// How long a numeral takes to morph into the next.
+const MORPH_FOR: Duration = Duration::from_millis(400);
+
+/// How much of the morph each pixel takes to fade in or out.
+const MORPH_SOFTNESS: f32 = 0.25;
+
+/// Each numeral that's changed since the last tick flowing into its new value,
+/// with `--morph`. It's a cross-dissolve, but each pixel's is timed by how far
+/// it is from the other numeral's strokes: the old strokes furthest from the
+/// new ones go first, and the new ones grow out from where the old ones were,
+/// so strokes seem to slide and stretch into place. It starts and finishes on
+/// the numerals exactly as they are.
+///
+/// Numerals are matched up by the order they're drawn in, and only morph when
+/// they're in the same place, the same size, as they were.
+pub struct Morph {
+    started: Instant,
+    /// The time it's morphing from.
+    from: DateTime<FixedOffset>,
+    /// The numerals as they were drawn for `from`, in order, and where, once
+    /// they've been found (see [`Self::recorder`]).
+    was: Option<Vec<(Number, usize, usize)>>,
+    /// Numerals' masks and distance fields (see [`synth::signed_distance`]),
+    /// made the first time they're needed, and kept for the rest of the
+    /// morph.
+    shapes: Vec<(Number, MaskBuffer, Vec2d<f32>)>,
+    /// Where each frame's in-between numeral is put together.
+    scratch: MaskBuffer,
+}
+
+impl Morph {
+    pub fn new(started: Instant, from: DateTime<FixedOffset>) -> Self {
+        Self { started, from, was: None, shapes: Vec::new(), scratch: MaskBuffer::default() }
+    }
+
+    pub fn from(&self) -> DateTime<FixedOffset> {
+        self.from
+    }
+
+    /// How far along it is at `now`, from 0 to 1, eased in and out, or `None`
+    /// once it's over.
+    pub fn progress(&self, now: Instant) -> Option<f32> {
+        let t = now.saturating_duration_since(self.started).as_secs_f32() / MORPH_FOR.as_secs_f32();
+        (t < 1.0).then_some(t * t * (3.0 - 2.0 * t))
+    }
+
+    /// Whether the numerals as they were have been found yet.
+    pub fn recorded(&self) -> bool {
+        self.was.is_some()
+    }
+
+    /// A canvas that finds the numerals as they were, when the clock face for
+    /// [`Self::from`] is drawn onto it. Nothing's actually drawn.
+    pub fn recorder(&mut self) -> impl Canvas + '_ {
+        Recorder(self.was.insert(Vec::new()))
+    }
+
+    /// A canvas that draws onto `buf`, with the numerals that changed
+    /// `progress` of the way from what they were.
+    pub fn canvas<'a>(&'a mut self, buf: &'a mut GlyphBuffer, progress: f32) -> Morphing<'a> {
+        Morphing { buf, morph: self, progress, numerals: 0 }
+    }
+
+    /// Where `number`'s mask and distance field are in [`Self::shapes`],
+    /// making them if they aren't there yet.
+    fn shape(&mut self, glyphs: &mut Glyphs, number: Number) -> usize {
+        if let Some(i) = self.shapes.iter().position(|(n, ..)| *n == number) {
+            return i;
+        }
+        let raster = number.raster(glyphs);
+        self.shapes.push((number, raster.to_vec2d(), synth::signed_distance(raster)));
+        self.shapes.len() - 1
+    }
+}
+
+/// Finds where the numerals are, for [`Morph::recorder`].
+struct Recorder<'a>(&'a mut Vec<(Number, usize, usize)>);
+
+impl Canvas for Recorder<'_> {
+    fn number(&mut self, _: &mut Glyphs, number: Number, x: usize, y: usize) {
+        self.0.push((number, x, y));
+    }
+
+    fn colon(&mut self, _: &mut Glyphs, _: usize, _: usize) {}
+
+    fn sign(&mut self, _: &mut Glyphs, _: Sign, _: usize, _: usize) {}
+
+    fn fg(&self, glyphs: &Glyphs) -> Color {
+        glyphs.fg()
+    }
+
+    fn set_fg(&mut self, _: &mut Glyphs, _: Color) {}
+
+    fn label(&mut self, _: &mut Glyphs, _: &str, _: usize, _: usize, _: usize, _: usize) {}
+
+    fn ring(&mut self, _: &mut Glyphs, _: usize, _: usize, _: usize, _: f32, _: f32) {}
+}
+
+/// Draws the clock face the usual way, but with numerals morphing, for
+/// [`Morph::canvas`].
+pub struct Morphing<'a> {
+    buf: &'a mut GlyphBuffer,
+    morph: &'a mut Morph,
+    progress: f32,
+    /// How many numerals have been drawn so far.
+    numerals: usize,
+}
+
+impl Morphing<'_> {
+    /// Puts together the in-between of the numerals at `from` and `to` in
+    /// [`Morph::shapes`] in [`Morph::scratch`], if they're the same size.
+    fn blend(&mut self, from: usize, to: usize) -> bool {
+        let ((_, from_mask, from_field), (_, to_mask, to_field)) = (&self.morph.shapes[from], &self.morph.shapes[to]);
+        let (width, height) = (to_mask.width(), to_mask.height());
+        if (from_mask.width(), from_mask.height()) != (width, height) {
+            return false;
+        }
+        // How far anything has to go, so the furthest pixels go first (or
+        // come last)
+        let mut reach = 1.0_f32;
+        for y in 0..height {
+            let pixels = from_mask.row(y).iter().zip(to_mask.row(y));
+            let fields = from_field.row(y).iter().zip(to_field.row(y));
+            for ((&a, &b), (&from_distance, &to_distance)) in pixels.zip(fields) {
+                if a > 0 {
+                    reach = reach.max(to_distance);
+                }
+                if b > 0 {
+                    reach = reach.max(from_distance);
+                }
+            }
+        }
+        // When each pixel's halfway in or out, kept far enough from either end
+        // that it's all the way there at the start and the finish
+        let when = |distance: f32| MORPH_SOFTNESS / 2.0 + (1.0 - MORPH_SOFTNESS) * (distance.max(0.0) / reach);
+        let t = self.progress;
+        self.morph.scratch.reset(0, width, height);
+        for y in 0..height {
+            let pixels = from_mask.row(y).iter().zip(to_mask.row(y));
+            let fields = from_field.row(y).iter().zip(to_field.row(y));
+            let out = self.morph.scratch.row_mut(y);
+            for (out, ((&a, &b), (&from_distance, &to_distance))) in out.iter_mut().zip(pixels.zip(fields)) {
+                let out_at = 1.0 - when(to_distance);
+                let in_at = when(from_distance);
+                let staying = ((out_at - t) / MORPH_SOFTNESS + 0.5).clamp(0.0, 1.0);
+                let coming = ((t - in_at) / MORPH_SOFTNESS + 0.5).clamp(0.0, 1.0);
+                *out = (f32::from(a) * staying).max(f32::from(b) * coming).round() as u8;
+            }
+        }
+        true
+    }
+}
+
+impl Canvas for Morphing<'_> {
+    fn number(&mut self, glyphs: &mut Glyphs, number: Number, x: usize, y: usize) {
+        let was = self.morph.was.as_ref().and_then(|was| was.get(self.numerals).copied());
+        self.numerals += 1;
+        match was {
+            Some((was, was_x, was_y)) if was != number && (was_x, was_y) == (x, y) => {
+                let (from, to) = (self.morph.shape(glyphs, was), self.morph.shape(glyphs, number));
+                if self.blend(from, to) {
+                    let fg = glyphs.fg();
+                    self.buf.tint_from(x, y, &self.morph.scratch, fg);
+                    return;
+                }
+            }
+            _ => {}
+        }
+        self.buf.number(glyphs, number, x, y);
+    }
+
+    fn colon(&mut self, glyphs: &mut Glyphs, x: usize, y: usize) {
+        self.buf.colon(glyphs, x, y);
+    }
+
+    fn sign(&mut self, glyphs: &mut Glyphs, sign: Sign, x: usize, y: usize) {
+        self.buf.sign(glyphs, sign, x, y);
+    }
+
+    fn fg(&self, glyphs: &Glyphs) -> Color {
+        self.buf.fg(glyphs)
+    }
+
+    fn set_fg(&mut self, glyphs: &mut Glyphs, fg: Color) {
+        self.buf.set_fg(glyphs, fg);
+    }
+
+    fn label(&mut self, glyphs: &mut Glyphs, text: &str, x: usize, y: usize, width: usize, height: usize) {
+        self.buf.label(glyphs, text, x, y, width, height);
+    }
+
+    fn ring(
+        &mut self,
+        glyphs: &mut Glyphs,
+        x: usize,
+        y: usize,
+        diameter: usize,
+        thickness: f32,
+        progress: f32,
+    ) {
+        self.buf.ring(glyphs, x, y, diameter, thickness, progress);
+    }
+}
@@ -0,0 +1,91 @@
+//! Packing lots of little glyphs into one big buffer.
+
+use crate::{
+    buf2d::Vec2d,
+    glyphs::{MaskBuffer, MaskView},
+};
+
+/// Where a glyph is in an [`Atlas`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// A single buffer holding many glyphs, so they don't each need their own
+/// allocation, and they sit close together in memory.
+///
+/// Glyphs are packed in shelves: left to right until the row is full, then on
+/// to a new shelf below the tallest glyph so far. It's not the tightest packing,
+/// but glyphs at one scale are all about the same height, so it's close, and
+/// it means the atlas only ever grows downward, which never moves anything.
+pub struct Atlas {
+    buf: MaskBuffer,
+    /// Where the next glyph goes on the current shelf.
+    shelf_x: usize,
+    /// The top of the current shelf.
+    shelf_y: usize,
+    /// The tallest glyph on the current shelf.
+    shelf_height: usize,
+}
+
+impl Atlas {
+    /// Makes an empty atlas, `width` pixels across. (It gets wider if a glyph
+    /// comes along that wouldn't fit otherwise.)
+    pub fn new(width: usize) -> Self {
+        Self {
+            buf: Vec2d::new(0, width, 0),
+            shelf_x: 0,
+            shelf_y: 0,
+            shelf_height: 0,
+        }
+    }
+
+    /// Makes room for about `count` more glyphs of the given size, to save
+    /// reallocating as they come in one by one.
+    pub fn reserve(&mut self, count: usize, width: usize, height: usize) {
+        let per_shelf = (self.buf.width() / width.max(1)).max(1);
+        self.buf.reserve_rows(count.div_ceil(per_shelf) * height);
+    }
+
+    /// Copies `glyph` in, returning where it went.
+    pub fn insert<'a>(&mut self, glyph: impl Into<MaskView<'a>>) -> Rect {
+        let glyph = glyph.into();
+        if glyph.width() > self.buf.width() {
+            self.widen(glyph.width());
+        }
+        if self.shelf_x + glyph.width() > self.buf.width() {
+            self.shelf_y += self.shelf_height;
+            self.shelf_x = 0;
+            self.shelf_height = 0;
+        }
+        let rect = Rect {
+            x: self.shelf_x,
+            y: self.shelf_y,
+            width: glyph.width(),
+            height: glyph.height(),
+        };
+        self.shelf_x += rect.width;
+        self.shelf_height = self.shelf_height.max(rect.height);
+        let needed_height = self.shelf_y + self.shelf_height;
+        if self.buf.height() < needed_height {
+            self.buf.resize_height(needed_height, 0);
+        }
+        self.buf.copy_to_from(rect.x, rect.y, glyph);
+        rect
+    }
+
+    /// Borrows the glyph at `rect`.
+    pub fn get(&self, rect: Rect) -> MaskView<'_> {
+        self.buf.sub_view(rect.x, rect.y, rect.width, rect.height)
+    }
+
+    /// Makes the atlas at least `width` across. Everything stays where it was.
+    fn widen(&mut self, width: usize) {
+        let mut buf = Vec2d::new(0, width, self.buf.height());
+        buf.copy_to_from(0, 0, &self.buf);
+        self.buf = buf;
+    }
+}
@@ -0,0 +1,125 @@
+//! The `battery` complication: how charged the battery is, as a D'ni digit (in
+//! 24ths, so 24 is full), with a `+` while it's charging.
+//!
+//! It's read from `/sys/class/power_supply`, so this only knows anything on
+//! Linux; elsewhere (or without a battery) it just says so.
+
+use std::{
+    fs,
+    path::Path,
+    sync::{Arc, Mutex, Once},
+    thread,
+    time::Duration,
+};
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::{
+    complication::Complication,
+    config::Config,
+    frame::{self, Canvas, Number},
+    glyphs::Glyphs,
+    layout::Line,
+    labels,
+    locale::Locale,
+};
+
+/// How often to check. Batteries don't change fast.
+const INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy)]
+struct Charge {
+    /// From 0 to 1.
+    level: f32,
+    charging: bool,
+}
+
+/// What's known about the battery.
+#[derive(Debug, Clone, Copy, Default)]
+enum Reading {
+    /// It hasn't been read yet.
+    #[default]
+    Waiting,
+    /// There isn't one (or it can't be read).
+    Missing,
+    Charge(Charge),
+}
+
+#[derive(Debug)]
+pub struct Battery {
+    locale: Locale,
+    reading: Arc<Mutex<Reading>>,
+    /// Checking starts the first time it's drawn.
+    started: Once,
+}
+
+impl Battery {
+    pub fn new(locale: Locale) -> Self {
+        Self { locale, reading: Arc::default(), started: Once::new() }
+    }
+
+    fn start(&self) {
+        let reading = self.reading.clone();
+        thread::spawn(move || loop {
+            *reading.lock().unwrap() = read_charge().map_or(Reading::Missing, Reading::Charge);
+            thread::sleep(INTERVAL);
+        });
+    }
+}
+
+impl Complication for Battery {
+    fn name(&self) -> &str {
+        "battery"
+    }
+
+    fn measure(&self, scale: f32, config: &Config) -> (usize, usize) {
+        let number_width = frame::numbers_width(&[Number::OneDigit(0)], scale, config);
+        (number_width + labels::line_height(scale), frame::line_height(scale, config))
+    }
+
+    fn render(
+        &self,
+        canvas: &mut dyn Canvas,
+        glyphs: &mut Glyphs,
+        _time: DateTime<FixedOffset>,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) {
+        self.started.call_once(|| self.start());
+        let line = Line::new().within(x, y, width, height);
+        let charge = match *self.reading.lock().unwrap() {
+            Reading::Charge(charge) => charge,
+            reading => {
+                let message = if let Reading::Waiting = reading { "..." } else { self.locale.text("none") };
+                line.small(message).render_into(canvas, glyphs);
+                return;
+            }
+        };
+        let level = Number::OneDigit((charge.level.clamp(0.0, 1.0) * 24.0).round() as u8);
+        let line = line.number(level);
+        let line = if charge.charging { line.small("+") } else { line };
+        line.render_into(canvas, glyphs);
+    }
+}
+
+/// Reads the first battery's charge, if there is one.
+fn read_charge() -> Option<Charge> {
+    let entries = fs::read_dir("/sys/class/power_supply").ok()?;
+    let battery = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .find(|path| read(path, "type").as_deref() == Some("Battery"))?;
+    let capacity: f32 = read(&battery, "capacity")?.parse().ok()?;
+    let status = read(&battery, "status");
+    Some(Charge {
+        level: capacity / 100.0,
+        charging: status.as_deref() == Some("Charging"),
+    })
+}
+
+/// The contents of the sysfs attribute `name` of `device`, trimmed.
+fn read(device: &Path, name: &str) -> Option<String> {
+    fs::read_to_string(device.join(name)).ok().map(|s| s.trim().to_owned())
+}
@@ -0,0 +1,191 @@
+//! `dni-clock bench`: rough timings of the expensive bits, to compare before
+//! and after performance work.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use chrono::{FixedOffset, TimeZone};
+
+use crate::{
+    buf2d::Vec2d,
+    colors,
+    config::Config,
+    fonts::Fonts,
+    frame,
+    glyphs::{GlyphBuffer, Glyphs},
+};
+
+/// The scales to measure everything at.
+const SCALES: [f32; 4] = [25.0, 50.0, 100.0, 200.0];
+
+/// About how long to spend on each measurement.
+const TARGET: Duration = Duration::from_millis(300);
+
+/// The system allocator, but counting how many times it's asked for memory, so
+/// the benchmarks and tests can check that drawing a frame doesn't allocate.
+/// It's only installed for the tests, and for `dni-clock bench` when built
+/// with the `count-allocations` feature.
+pub struct CountingAllocator;
+
+thread_local! {
+    /// How many allocations (and reallocations) there have been so far on
+    /// this thread, so other threads (like other tests) don't get counted.
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// How many allocations this thread's made so far, if they're being counted.
+pub fn allocations() -> Option<usize> {
+    cfg!(any(test, feature = "count-allocations")).then(|| ALLOCATIONS.with(Cell::get))
+}
+
+fn count_allocation() {
+    // Not at all while the thread's going away
+    let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+}
+
+// SAFETY: It all goes straight to `System`, which upholds the contract.
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count_allocation();
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count_allocation();
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count_allocation();
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+}
+
+/// Runs the benchmarks and prints a table of the results.
+pub fn run(config: &Config, fonts: &Fonts) {
+    println!(
+        "{:>6}  {:>12}  {:>12}  {:>12}  {:>12}  {:>14}",
+        "scale", "cache gen", "numerals", "frame", "allocs/frame", "blit",
+    );
+    for scale in SCALES {
+        let cache = time_per_iter(|| {
+            let mut glyphs = Glyphs::with_starting_scale(scale, &config.glyphs, fonts);
+            glyphs.warm_up();
+            black_box(glyphs);
+        });
+
+        // Composing all 60 numerals into a fresh cache
+        let mut glyphs = Glyphs::with_starting_scale(scale, &config.glyphs, fonts);
+        let numerals = time_per_iter(|| {
+            glyphs = Glyphs::with_starting_scale(scale, &config.glyphs, fonts);
+            glyphs.warm_up();
+            for n in 0..60 {
+                black_box(glyphs.get_dni_number_two_digits(n));
+            }
+        }).saturating_sub(cache);
+
+        // Once the cache is warm, like it is after the first minute
+        let time = FixedOffset::east(0).ymd(2000, 1, 1).and_hms(12, 34, 56);
+        let frame = time_per_iter(|| {
+            black_box(frame::build(time, &mut glyphs, scale, config));
+        });
+
+        // Drawing into the same buffer a minute's worth of times, after the
+        // first minute (so the cache and the buffer are all set), should
+        // allocate nothing
+        let mut buf = GlyphBuffer::default();
+        let minute = |second| FixedOffset::east(0).ymd(2000, 1, 1).and_hms(12, 34, second);
+        for second in 0..60 {
+            frame::build_into(&mut buf, minute(second), &mut glyphs, scale, config);
+        }
+        let before = allocations();
+        for second in 0..60 {
+            frame::build_into(&mut buf, minute(second), &mut glyphs, scale, config);
+        }
+        let allocations = before.zip(allocations()).map(|(before, after)| (after - before) as f64 / 60.0);
+
+        let digit = glyphs.get_dni_number_two_digits(59).to_vec2d();
+        let (width, height) = frame::window_size(scale, config);
+        let mut target = Vec2d::new(colors::BG, width.max(digit.width()), height.max(digit.height()));
+        let blit = time_per_iter(|| {
+            target.tint_from(0, 0, black_box(&digit), colors::FG);
+        });
+        let pixels = (digit.width() * digit.height()) as f64;
+        let megapixels_per_sec = pixels / blit.as_secs_f64() / 1e6;
+
+        println!(
+            "{scale:>6}  {:>12}  {:>12}  {:>12}  {:>12}  {:>9.0} Mpx/s",
+            format_duration(cache),
+            format_duration(numerals),
+            format_duration(frame),
+            // Only counted with the `count-allocations` feature
+            allocations.map_or_else(|| "-".to_owned(), |allocations| format!("{allocations:.1}")),
+            megapixels_per_sec,
+        );
+    }
+}
+
+/// Runs `f` over and over for about [`TARGET`], and returns the average time
+/// each run took.
+fn time_per_iter(mut f: impl FnMut()) -> Duration {
+    // Once to warm up, and to guess how many runs will fit
+    let start = Instant::now();
+    f();
+    let once = start.elapsed().max(Duration::from_nanos(1));
+    let iters = (TARGET.as_nanos() / once.as_nanos()).clamp(1, 1_000_000) as u32;
+
+    let start = Instant::now();
+    for _ in 0..iters {
+        f();
+    }
+    start.elapsed() / iters
+}
+
+fn format_duration(duration: Duration) -> String {
+    let micros = duration.as_secs_f64() * 1e6;
+    if micros >= 1000.0 {
+        format!("{:.2} ms", micros / 1000.0)
+    } else {
+        format!("{micros:.1} µs")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tracing_subscriber::prelude::*;
+
+    use crate::timing::Timings;
+
+    #[test]
+    fn drawing_another_minute_allocates_nothing() {
+        let mut config = Config::default();
+        let all_fonts = Fonts::load_all(&config.glyphs).unwrap();
+        let fonts = &all_fonts[0];
+        config.line_metrics = fonts.line_metrics(&config.glyphs);
+        let scale = 64.0;
+        let mut glyphs = Glyphs::with_starting_scale(scale, &config.glyphs, fonts);
+        let mut buf = GlyphBuffer::default();
+        let minute = |second| FixedOffset::east(0).ymd(2000, 1, 1).and_hms(12, 34, second);
+        // Not even while it's being timed, like in the running clock
+        tracing::subscriber::with_default(tracing_subscriber::registry().with(Timings), || {
+            for second in 0..60 {
+                frame::build_into(&mut buf, minute(second), &mut glyphs, scale, &config);
+            }
+            let before = allocations().unwrap();
+            for second in 0..60 {
+                frame::build_into(&mut buf, minute(second), &mut glyphs, scale, &config);
+            }
+            assert_eq!(allocations().unwrap() - before, 0);
+        });
+    }
+}
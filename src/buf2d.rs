@@ -2,7 +2,7 @@
 
 use std::ops::{Index, IndexMut};
 
-use crate::util::ToUsize;
+use crate::{atlas::Rect, util::ToUsize};
 
 /// A 2D array type but with 1D access.
 ///
@@ -10,13 +10,43 @@ use crate::util::ToUsize;
 /// need access to the elements as a continuous sequence too (like `Vec<T>`).
 /// This is a wrapper to give the convenience of the former while allowing a
 /// zero-cost conversion to the latter.
-#[derive(Clone, Default)]
+#[derive(Default)]
 pub struct Vec2d<T> {
     vec: Vec<T>,
     width: usize,
 }
 
+// Not derived, so `clone_from` reuses the allocation
+impl<T: Clone> Clone for Vec2d<T> {
+    fn clone(&self) -> Self {
+        Self { vec: self.vec.clone(), width: self.width }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.vec.clone_from(&source.vec);
+        self.width = source.width;
+    }
+}
+
 impl<T> Vec2d<T> {
+    /// Wraps `vec`, taking each `width` elements as a row.
+    ///
+    /// Returns `None` if there isn't a whole number of rows.
+    pub fn from_vec(vec: Vec<T>, width: usize) -> Option<Self> {
+        // (Nothing but 0 is a multiple of 0, so it has to be empty then)
+        vec.len().is_multiple_of(width).then_some(Self { vec, width })
+    }
+
+    /// Creates a `width`×`height` `Vec2d`, calling `f(x, y)` for each element,
+    /// a row at a time.
+    pub fn from_fn(width: usize, height: usize, mut f: impl FnMut(usize, usize) -> T) -> Self {
+        let mut vec = Vec::with_capacity(width * height);
+        for y in 0..height {
+            vec.extend((0..width).map(|x| f(x, y)));
+        }
+        Self { vec, width }
+    }
+
     pub fn width(&self) -> usize {
         self.width
     }
@@ -26,12 +56,75 @@ impl<T> Vec2d<T> {
         self.vec.len().checked_div(self.width).unwrap_or(0)
     }
 
+    /// Borrow the whole thing as a [`View2d`].
+    pub fn view(&self) -> View2d<'_, T> {
+        self.sub_view(0, 0, self.width(), self.height())
+    }
+
+    /// Borrow a `width`×`height` rectangle of it, with the top left at `(x, y)`.
+    ///
+    /// Panics if the rectangle doesn't fit.
+    pub fn sub_view(&self, x: usize, y: usize, width: usize, height: usize) -> View2d<'_, T> {
+        assert!(
+            x + width <= self.width() && y + height <= self.height(),
+            "{width}x{height} view at ({x}, {y}) doesn't fit in a {}x{} buffer",
+            self.width(),
+            self.height(),
+        );
+        View2d { vec: &self.vec, stride: self.width, x, y, width, height }
+    }
+
+    /// Copies the `src_rect` part of `src` into `self`, with its top left at
+    /// `(dst_x, dst_y)`, converting each element with `f` on the way (like
+    /// tinting a mask's coverage into colors).
+    ///
+    /// Panics if `src_rect` isn't all in `src`, or won't fit into `self`.
+    pub fn copy_region_map<'a, S: Copy + 'a>(
+        &mut self,
+        src: impl Into<View2d<'a, S>>,
+        src_rect: Rect,
+        (dst_x, dst_y): (usize, usize),
+        f: impl Fn(S) -> T,
+    ) {
+        let src = src.into().sub_view(src_rect.x, src_rect.y, src_rect.width, src_rect.height);
+        assert!(
+            dst_x + src.width() <= self.width() &&
+            dst_y + src.height() <= self.height(),
+            "`src_rect` won't fit into `self`, at least not starting from ({dst_x}, {dst_y})"
+        );
+
+        let dst_x_range = dst_x..(dst_x+src.width());
+        for src_y in 0..src.height() {
+            let dst_row = &mut self.row_mut(src_y + dst_y)[dst_x_range.clone()];
+            for (dst, &src) in dst_row.iter_mut().zip(src.row(src_y)) {
+                *dst = f(src);
+            }
+        }
+    }
+
     /// Get a 1D view of the Vec (which is how it's stored anyway, so it's free).
     #[inline(always)]
     pub fn as_1d(&self) -> &[T] {
         &self.vec
     }
 
+    /// Gets the element at `(x, y)`, or `None` if that's outside it. Unlike
+    /// indexing, an `x` past the end of a row doesn't wrap onto the next one.
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        if x >= self.width {
+            return None;
+        }
+        self.vec.get(Self::index_2d_to_1d(self.width, x, y))
+    }
+
+    /// Gets the element at `(x, y)`, or `None` if that's outside it.
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+        if x >= self.width {
+            return None;
+        }
+        self.vec.get_mut(Self::index_2d_to_1d(self.width, x, y))
+    }
+
     /// Gets the row at `y` (or panics if it doesn't exist).
     pub fn row(&self, y: usize) -> &[T] {
         &self.vec[Self::row_range(self.width, y)]
@@ -65,16 +158,80 @@ impl<T: Copy> Vec2d<T> {
         }
     }
 
+    /// Adds or removes rows at the bottom, so it's `height` tall. New rows are
+    /// filled with `value`.
+    ///
+    /// Nothing that's already there moves, since the rows are stored in order.
+    pub fn resize_height(&mut self, height: usize, value: T) {
+        self.vec.resize(self.width * height, value);
+    }
+
+    /// Makes it `width`×`height` and fills it with `value`, like [`Self::new`],
+    /// but reusing the allocation if it's big enough.
+    pub fn reset(&mut self, value: T, width: usize, height: usize) {
+        self.vec.clear();
+        self.vec.resize(width * height, value);
+        self.width = width;
+    }
+
+    /// Makes it a copy of `src`, like [`Clone::clone_from`], but reusing the
+    /// allocation if it's big enough.
+    pub fn copy_from(&mut self, src: &Self) {
+        self.vec.clear();
+        self.vec.extend_from_slice(&src.vec);
+        self.width = src.width;
+    }
+
+    /// Makes room for at least `additional` more rows without reallocating.
+    pub fn reserve_rows(&mut self, additional: usize) {
+        self.vec.reserve(self.width * additional);
+    }
+
+    /// Copies out the top-left `width`×`height` corner (or less, if `self` is
+    /// smaller than that).
+    pub fn cropped(&self, width: usize, height: usize) -> Self {
+        let width = width.min(self.width());
+        let height = height.min(self.height());
+        let mut vec = Vec::with_capacity(width * height);
+        for y in 0..height {
+            vec.extend_from_slice(&self.row(y)[..width]);
+        }
+        Self { vec, width }
+    }
+
+    /// Copies it out stretched (or squashed) to `width`×`height`, taking the
+    /// nearest pixel for each. It's blocky, but it's quick.
+    pub fn stretched(&self, width: usize, height: usize) -> Self {
+        let mut stretched = Self { vec: Vec::new(), width: 0 };
+        stretched.stretch_from(self, width, height);
+        stretched
+    }
+
+    /// Makes it a copy of `src` stretched to `width`×`height`, like
+    /// [`Self::stretched`], but reusing the allocation if it's big enough.
+    pub fn stretch_from(&mut self, src: &Self, width: usize, height: usize) {
+        self.vec.clear();
+        self.width = width;
+        if src.width() == 0 || src.height() == 0 {
+            return;
+        }
+        for y in 0..height {
+            let row = src.row(y * src.height() / height);
+            self.vec.extend((0..width).map(|x| row[x * src.width() / width]));
+        }
+    }
+
     /// Copy `src` into `self`. The top left of `src` goes into
     /// `self[(start_x, start_y)]`.
     ///
     /// Panics if `src` won't fit.
-    pub fn copy_to_from(
+    pub fn copy_to_from<'a>(
         &mut self,
         start_x: usize,
         start_y: usize,
-        src: &Self,
-    ) {
+        src: impl Into<View2d<'a, T>>,
+    ) where T: 'a {
+        let src = src.into();
         assert!(
             start_x + src.width() <= self.width() &&
             start_y + src.height() <= self.height(),
@@ -93,26 +250,27 @@ impl<T: Copy> Vec2d<T> {
     /// goes into `self[(start_x, start_y)]`.
     ///
     /// Panics if `src` won't fit.
-    pub fn copy_to_from_if(
+    pub fn copy_to_from_if<'a>(
         &mut self,
         start_x: usize,
         start_y: usize,
-        src: &Self,
+        src: impl Into<View2d<'a, T>>,
         mut should_overwrite: impl FnMut(T) -> bool,
-    ) {
+    ) where T: 'a {
+        let src = src.into();
         assert!(
             start_x + src.width() <= self.width() &&
             start_y + src.height() <= self.height(),
             "`src` won't fit into `self`, at least not starting from ({start_x}, {start_y})"
         );
 
-        for src_x in 0..src.width() {
-            for src_y in 0..src.height() {
-                let self_x = src_x + start_x;
-                let self_y = src_y + start_y;
-                let current_value = self[(self_x, self_y)];
-                if should_overwrite(current_value) {
-                    self[(self_x, self_y)] = src[(src_x, src_y)];
+        let self_x_range = start_x..(start_x+src.width());
+        for src_y in 0..src.height() {
+            let self_y = src_y + start_y;
+            let self_row = &mut self.row_mut(self_y)[self_x_range.clone()];
+            for (current_value, &new_value) in self_row.iter_mut().zip(src.row(src_y)) {
+                if should_overwrite(*current_value) {
+                    *current_value = new_value;
                 }
             }
         }
@@ -132,3 +290,82 @@ impl<T, Idx: ToUsize> IndexMut<(Idx, Idx)> for Vec2d<T> {
         &mut self.vec[Self::index_2d_to_1d(self.width, x, y)]
     }
 }
+
+/// A borrowed rectangle of a [`Vec2d`], which might be all of it.
+///
+/// It's cheap to make and to copy around, so it's what gets passed around when
+/// the pixels themselves aren't owned, like glyphs in an atlas.
+pub struct View2d<'a, T> {
+    vec: &'a [T],
+    /// The width of the whole `Vec2d`, to step from one row to the next.
+    stride: usize,
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+// Not derived, because that would require `T: Copy`
+impl<T> Clone for View2d<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for View2d<'_, T> {}
+
+impl<'a, T> View2d<'a, T> {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Narrows it down to a `width`×`height` rectangle, with the top left at
+    /// `(x, y)` (relative to this view's).
+    ///
+    /// Panics if the rectangle doesn't fit.
+    pub fn sub_view(&self, x: usize, y: usize, width: usize, height: usize) -> Self {
+        assert!(
+            x + width <= self.width && y + height <= self.height,
+            "{width}x{height} view at ({x}, {y}) doesn't fit in a {}x{} view",
+            self.width,
+            self.height,
+        );
+        Self { x: self.x + x, y: self.y + y, width, height, ..*self }
+    }
+
+    /// Gets the row at `y` (or panics if it doesn't exist).
+    pub fn row(&self, y: usize) -> &'a [T] {
+        assert!(y < self.height, "row {y} is out of bounds");
+        let start = (self.y + y) * self.stride + self.x;
+        &self.vec[start..(start + self.width)]
+    }
+}
+
+impl<T: Copy> View2d<'_, T> {
+    /// Copies it out into its own `Vec2d`.
+    pub fn to_vec2d(self) -> Vec2d<T> {
+        let mut vec = Vec::with_capacity(self.width * self.height);
+        for y in 0..self.height {
+            vec.extend_from_slice(self.row(y));
+        }
+        Vec2d { vec, width: self.width }
+    }
+}
+
+impl<'a, T> From<&'a Vec2d<T>> for View2d<'a, T> {
+    fn from(vec: &'a Vec2d<T>) -> Self {
+        vec.view()
+    }
+}
+
+impl<T, Idx: ToUsize> Index<(Idx, Idx)> for View2d<'_, T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (Idx, Idx)) -> &Self::Output {
+        &self.row(y.to_usize())[x.to_usize()]
+    }
+}
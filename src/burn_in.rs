@@ -0,0 +1,78 @@
+//! `--burn-in`: looking after OLED screens that show the clock all night.
+//! Nothing on them should stay lit in exactly the same place at exactly the
+//! same brightness for hours, so the whole frame wanders around a few pixels,
+//! one a minute, and dims a little and back over every hour.
+//!
+//! It's done to the finished frame, just before it's shown, so it works the
+//! same whatever's on it.
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::{
+    atlas::Rect,
+    colors::{self, Color},
+    config::Config,
+    glyphs::GlyphBuffer,
+};
+
+/// How much darker it gets, at the dimmest (half past the hour).
+const DIM: f32 = 0.1;
+
+pub struct BurnIn {
+    /// How far it can wander from where it would be, in pixels, each way.
+    reach: usize,
+    /// A chroma key, which has to stay exactly as it is to be keyed out, so
+    /// it's left undimmed.
+    key: Option<Color>,
+    /// Reused for every frame, so moving one doesn't have to allocate.
+    canvas: GlyphBuffer,
+}
+
+impl BurnIn {
+    pub fn new(config: &Config) -> Self {
+        Self { reach: config.burn_in, key: config.chroma_key, canvas: GlyphBuffer::default() }
+    }
+
+    /// `frame`, moved and dimmed the way it is at `time`, in a frame with
+    /// room for it to move in all round.
+    pub fn place(&mut self, frame: &GlyphBuffer, time: DateTime<FixedOffset>) -> &GlyphBuffer {
+        let minutes = time.timestamp().div_euclid(60);
+        // Back and forth a pixel a minute, and a pixel down (or up) at each
+        // end, so it goes over every spot in turn
+        let steps = self.reach * 2 + 1;
+        let x = ping_pong(minutes, steps);
+        let y = ping_pong(minutes.div_euclid(steps as i64 - 1), steps);
+        // Down to the dimmest and back up over the hour
+        let into_hour = minutes.rem_euclid(60) as f32;
+        let dim = DIM * (1.0 - (into_hour - 30.0).abs() / 30.0);
+
+        let key = self.key;
+        let dimmed = |px: Color| if Some(px) == key { px } else { colors::lerp(px, 0, dim) };
+        let bg = frame.as_1d().first().copied().map_or(0, dimmed);
+        let (width, height) = (frame.width(), frame.height());
+        self.canvas.reset(bg, width + self.reach * 2, height + self.reach * 2);
+        let whole = Rect { x: 0, y: 0, width, height };
+        if dim == 0.0 {
+            self.canvas.copy_to_from(x, y, frame);
+        } else {
+            self.canvas.copy_region_map(frame, whole, (x, y), dimmed);
+        }
+        &self.canvas
+    }
+}
+
+/// How big a frame `(width, height)` is once it's shown, with room for it to
+/// wander in if it's going to.
+pub fn with_room((width, height): (usize, usize), config: &Config) -> (usize, usize) {
+    (width + config.burn_in * 2, height + config.burn_in * 2)
+}
+
+/// Counts `t` up from 0 to `steps - 1`, then back down, and so on.
+fn ping_pong(t: i64, steps: usize) -> usize {
+    let period = (steps as i64 - 1) * 2;
+    if period == 0 {
+        return 0;
+    }
+    let into = t.rem_euclid(period) as usize;
+    into.min(period as usize - into)
+}
@@ -0,0 +1,146 @@
+//! The `calendar` complication: how long until the next event in an ICS file
+//! (or a directory of them), with what it is underneath. The files are re-read
+//! now and then on a background thread, so edits show up without a restart.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, Once},
+    thread,
+    time::Duration,
+};
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::{
+    complication::Complication,
+    config::Config,
+    dni_time::{DniDuration, DurationUnits},
+    frame::{self, Canvas, Number},
+    glyphs::Glyphs,
+    layout::Line,
+    ics::{self, Event},
+    labels,
+    locale::Locale,
+};
+
+/// How often to re-read the calendar.
+const INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug)]
+pub struct Calendar {
+    path: PathBuf,
+    /// What units the time until the next event is shown in.
+    units: DurationUnits,
+    locale: Locale,
+    /// Every event found, soonest first. `None` until they've been read.
+    events: Arc<Mutex<Option<Vec<Event>>>>,
+    /// Reading starts the first time it's drawn.
+    started: Once,
+}
+
+impl Calendar {
+    pub fn new(path: PathBuf, units: DurationUnits, locale: Locale) -> Self {
+        Self { path, units, locale, events: Arc::default(), started: Once::new() }
+    }
+
+    fn start(&self) {
+        let (path, events) = (self.path.clone(), self.events.clone());
+        thread::spawn(move || loop {
+            let mut read = read_events(&path);
+            read.sort_by_key(|event| event.start);
+            *events.lock().unwrap() = Some(read);
+            thread::sleep(INTERVAL);
+        });
+    }
+}
+
+impl Complication for Calendar {
+    fn name(&self) -> &str {
+        "next event"
+    }
+
+    fn measure(&self, scale: f32, config: &Config) -> (usize, usize) {
+        let numbers = [Number::OneDigit(0), Number::TwoDigits(0)];
+        let width = frame::numbers_width(&numbers, scale, config);
+        (width, frame::line_height(scale, config) + labels::line_height(scale))
+    }
+
+    fn render(
+        &self,
+        canvas: &mut dyn Canvas,
+        glyphs: &mut Glyphs,
+        time: DateTime<FixedOffset>,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) {
+        self.started.call_once(|| self.start());
+        let label_height = glyphs.get_label("0").height();
+        let line_height = height - label_height;
+        let label = |canvas: &mut dyn Canvas, glyphs: &mut Glyphs, text: &str, x: usize, y: usize| {
+            let text = glyphs.ellipsize_label(text, width);
+            let text_width = glyphs.get_label(&text).width();
+            canvas.label(glyphs, &text, x, y, text_width, label_height);
+        };
+
+        let next = match &*self.events.lock().unwrap() {
+            None => Err("..."),
+            Some(events) => events.iter().find(|event| event.start > time).cloned().ok_or("nothing coming up"),
+        };
+        let next = match next {
+            Ok(next) => next,
+            Err(message) => {
+                label(canvas, glyphs, message, x, y);
+                return;
+            }
+        };
+        let until = next.start.with_timezone(time.offset()) - time;
+        let minutes = until.num_minutes();
+        let (hours, minutes) = (minutes / 60, minutes % 60);
+        let line = Line::new().within(x, y, width, line_height);
+        let line = if self.units == DurationUnits::Dni {
+            let until = DniDuration::from_duration(until);
+            if until.yahrtee < 5 {
+                let [gartahvotee, tahvotee, _] = until.numerals();
+                line.number(gartahvotee).sep().number(tahvotee)
+            } else {
+                line.number(Number::TwoDigits(until.yahrtee.min(59).try_into().unwrap()))
+                    .small("yahrtee")
+            }
+        } else if hours < 25 {
+            line.number(Number::OneDigit(hours.try_into().unwrap()))
+                .sep()
+                .number(Number::TwoDigits(minutes.try_into().unwrap()))
+        } else {
+            // Two digits go up to 59 days, which is far enough ahead
+            line.number(Number::TwoDigits((hours / 24).min(59).try_into().unwrap()))
+                .small(self.locale.text("days"))
+        };
+        line.render_into(canvas, glyphs);
+        label(canvas, glyphs, &next.summary, x, y + line_height);
+    }
+}
+
+/// Reads every event from `path`: an ICS file, or a directory of them. Ones
+/// that can't be read are warned about and skipped.
+fn read_events(path: &Path) -> Vec<Event> {
+    let files = match fs::read_dir(path) {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|e| e.eq_ignore_ascii_case("ics")))
+            .collect(),
+        Err(_) => vec![path.to_owned()],
+    };
+    files.iter()
+        .flat_map(|file| match fs::read_to_string(file) {
+            Ok(ics) => ics::parse(&ics),
+            Err(e) => {
+                eprintln!("warning: couldn't read calendar {}: {e}", file.display());
+                Vec::new()
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,139 @@
+//! `dni-clock chart`: a printable table of all 25 D'ni digits (or however many
+//! the numeral system has), each with its value underneath, for learning to
+//! read them.
+
+use std::{fs, path::PathBuf, str::FromStr};
+
+use crate::{
+    buf2d::Vec2d,
+    colors,
+    config::Config,
+    export,
+    fonts::Fonts,
+    frame::{Canvas, Number},
+    glyphs::Glyphs,
+    svg::Svg,
+};
+
+/// Digits per row. The digits are built in fives, so a 5×5 grid puts each
+/// next to its relatives.
+const COLUMNS: u8 = 5;
+
+/// Space around each cell, as a fraction of the scale.
+const PADDING: f32 = 0.3;
+
+/// The colors to print the chart in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    /// Like the clock: light on dark.
+    Dark,
+    /// Dark on light, to save ink.
+    Light,
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dark" => Ok(Self::Dark),
+            "light" => Ok(Self::Light),
+            _ => Err("expected `dark` or `light`".to_owned()),
+        }
+    }
+}
+
+/// What `dni-clock chart` was asked to do.
+#[derive(Debug, Clone)]
+pub struct ChartOptions {
+    /// Where to write it. The extension picks the format: `.png` or `.svg`.
+    pub out: PathBuf,
+    /// How big the digits are.
+    pub scale: f32,
+    pub theme: Theme,
+}
+
+/// Draws the chart and saves it where `options` says.
+pub fn render(config: &Config, fonts: &Fonts, options: &ChartOptions) -> Result<(), String> {
+    let mut glyphs = Glyphs::with_starting_scale(options.scale, &config.glyphs, fonts);
+    let layout = Layout::new(&mut glyphs, options.scale);
+
+    let extension = options.out.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let result = match extension.to_ascii_lowercase().as_str() {
+        "png" => {
+            let mut buf = Vec2d::new(colors::BG, layout.width, layout.height);
+            layout.draw(&mut buf, &mut glyphs);
+            if options.theme == Theme::Light {
+                colors::invert_all(&mut buf);
+            }
+            export::write_png(&options.out, std::slice::from_ref(&buf))
+        }
+        "svg" => {
+            let mut svg = Svg::new(layout.width, layout.height);
+            if options.theme == Theme::Light {
+                svg.set_colors(colors::invert(colors::FG), colors::invert(colors::BG));
+            }
+            layout.draw(&mut svg, &mut glyphs);
+            fs::write(&options.out, svg.finish()).map_err(Into::into)
+        }
+        _ => return Err(format!("don't know how to write `{}`; try .png or .svg", options.out.display())),
+    };
+    result.map_err(|e| format!("couldn't write {}: {e}", options.out.display()))
+}
+
+/// How big everything in the chart is.
+struct Layout {
+    cell_width: usize,
+    /// How tall the digits are, all told.
+    digit_height: usize,
+    label_height: usize,
+    padding: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Layout {
+    fn new(glyphs: &mut Glyphs, scale: f32) -> Self {
+        let base = glyphs.options().numerals.base();
+        let (mut digit_width, mut digit_height) = (0, 0);
+        for n in 0..base {
+            let digit = glyphs.get_dni_number_one_digit(n);
+            digit_width = digit_width.max(digit.width());
+            digit_height = digit_height.max(digit.height());
+        }
+        let label_height = glyphs.get_label(&(base - 1).to_string()).height();
+        let padding = (scale * PADDING).round() as usize;
+        let cell_width = digit_width + padding * 2;
+        let cell_height = digit_height + label_height + padding * 2;
+        let rows = base.div_ceil(COLUMNS);
+        Self {
+            cell_width,
+            digit_height,
+            label_height,
+            padding,
+            width: cell_width * usize::from(COLUMNS),
+            height: cell_height * usize::from(rows),
+        }
+    }
+
+    /// Draws every digit and its value, each centered in its cell.
+    fn draw(&self, canvas: &mut impl Canvas, glyphs: &mut Glyphs) {
+        let cell_height = self.digit_height + self.label_height + self.padding * 2;
+        for n in 0..glyphs.options().numerals.base() {
+            let cell_x = usize::from(n % COLUMNS) * self.cell_width;
+            let cell_y = usize::from(n / COLUMNS) * cell_height;
+
+            let digit = glyphs.get_dni_number_one_digit(n);
+            let (width, height) = (digit.width(), digit.height());
+            let x = cell_x + (self.cell_width - width) / 2;
+            let y = cell_y + self.padding + (self.digit_height - height) / 2;
+            canvas.number(glyphs, Number::OneDigit(n), x, y);
+
+            let text = n.to_string();
+            let width = glyphs.get_label(&text).width();
+            let x = cell_x + (self.cell_width - width) / 2;
+            let y = cell_y + self.padding + self.digit_height;
+            canvas.label(glyphs, &text, x, y, width, self.label_height);
+        }
+    }
+}
@@ -1,3 +1,7 @@
+use std::str::FromStr;
+
+use crate::buf2d::Vec2d;
+
 /// The color of a single pixel, stored as 0xAARRGGBB (but alpha isn't used).
 pub type Color = u32;
 
@@ -8,6 +12,14 @@ const WHITE: Color = from_u8_rgb(255, 255, 255);
 pub const BG: Color = BLACK;
 /// Foreground color
 pub const FG: Color = WHITE;
+/// What a T-minus countdown turns once it's past zero: amber, like a warning
+/// light.
+pub const T_PLUS: Color = from_u8_rgb(255, 176, 0);
+
+/// The usual chroma key colors, as pure as they go, which nothing else on
+/// screen is likely to be.
+pub const KEY_GREEN: Color = from_u8_rgb(0, 255, 0);
+pub const KEY_MAGENTA: Color = from_u8_rgb(255, 0, 255);
 
 /// Create a color from red, green, and blue parts. Alpha is set to 0.
 pub const fn from_u8_rgb(r: u8, g: u8, b: u8) -> Color {
@@ -15,22 +27,221 @@ pub const fn from_u8_rgb(r: u8, g: u8, b: u8) -> Color {
     (r << 16) | (g << 8) | b
 }
 
+/// Swaps light and dark throughout `buf`, which is quick enough to do to every
+/// frame (rather than re-rendering everything in new colors).
+pub fn invert_all(buf: &mut Vec2d<Color>) {
+    for y in 0..buf.height() {
+        for px in buf.row_mut(y) {
+            *px = invert(*px);
+        }
+    }
+}
+
+/// Takes an image from the `image` crate as a buffer, dropping alpha like
+/// everywhere else.
+#[cfg(feature = "image")]
+impl From<&image::RgbaImage> for Vec2d<Color> {
+    fn from(image: &image::RgbaImage) -> Self {
+        Vec2d::from_fn(image.width() as usize, image.height() as usize, |x, y| {
+            let image::Rgba([r, g, b, _a]) = *image.get_pixel(x as u32, y as u32);
+            from_u8_rgb(r, g, b)
+        })
+    }
+}
+
+/// Reads a color written like `#ff8800` (the `#` is optional).
+pub fn parse(s: &str) -> Result<Color, String> {
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if hex.len() != 6 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err("expected a color like `#ff8800`".to_owned());
+    }
+    Color::from_str_radix(hex, 16).map_err(|e| e.to_string())
+}
+
+/// Reads a chroma key color: `green`, `magenta`, or any color [`parse`] reads.
+pub fn parse_key(s: &str) -> Result<Color, String> {
+    match s {
+        "green" => Ok(KEY_GREEN),
+        "magenta" => Ok(KEY_MAGENTA),
+        _ => parse(s).map_err(|_| "expected `green`, `magenta`, or a color like `#00b140`".to_owned()),
+    }
+}
+
 /// Unpack a color into red, green, and blue parts. Alpha is ignored.
 pub const fn to_u8_rgb(color: Color) -> (u8, u8, u8) {
     let [_a, r, g, b] = color.to_be_bytes();
     (r, g, b)
 }
 
-/// Darkens a color to a percent (0.0 to 1.0) of its brightness.
+/// Converts pixels to the RGBA byte order most image formats want, fully opaque.
+pub fn to_rgba_bytes(pixels: &[Color]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(pixels.len() * PixelFormat::Rgba8888.bytes_per_pixel());
+    PixelFormat::Rgba8888.encode_into(pixels, &mut bytes);
+    bytes
+}
+
+/// How a backend lays out a pixel in memory. Frames are always drawn as
+/// [`Color`]s (which is [`Self::Xrgb8888`]), and converted at the edge for
+/// backends that want something else.
 ///
-///  - 1.0 returns the color unchanged
-///  - 0.0 returns black
-pub fn darken(color: Color, percent: f32) -> Color {
-    let percent = percent.clamp(0.0, u8::MAX.into());
+/// The packed formats are little-endian words, as framebuffers and DRM have
+/// them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// 8 bits per channel in a 32-bit word, the top byte unused. The same as a
+    /// [`Color`].
+    Xrgb8888,
+    /// 8 bits per channel, in that byte order, fully opaque.
+    Rgba8888,
+    /// 5 bits of red, 6 of green, and 5 of blue in a 16-bit word, for small
+    /// embedded displays.
+    Rgb565,
+    /// 10 bits per channel in a 32-bit word, the top two bits unused, for
+    /// 30-bit ("deep color") displays.
+    Xrgb2101010,
+}
+
+impl PixelFormat {
+    pub const fn bytes_per_pixel(self) -> usize {
+        match self {
+            Self::Rgb565 => 2,
+            Self::Xrgb8888 | Self::Rgba8888 | Self::Xrgb2101010 => 4,
+        }
+    }
+
+    /// Converts `pixels` and adds them to the end of `out`.
+    pub fn encode_into(self, pixels: &[Color], out: &mut Vec<u8>) {
+        out.reserve(pixels.len() * self.bytes_per_pixel());
+        for &color in pixels {
+            let (r, g, b) = to_u8_rgb(color);
+            match self {
+                Self::Xrgb8888 => out.extend_from_slice(&(color & 0xFF_FFFF).to_le_bytes()),
+                Self::Rgba8888 => out.extend_from_slice(&[r, g, b, u8::MAX]),
+                Self::Rgb565 => {
+                    let word = (scale_channel(r, 5) << 11) | (scale_channel(g, 6) << 5) | scale_channel(b, 5);
+                    out.extend_from_slice(&(word as u16).to_le_bytes());
+                }
+                Self::Xrgb2101010 => {
+                    let word = (scale_channel(r, 10) << 20) | (scale_channel(g, 10) << 10) | scale_channel(b, 10);
+                    out.extend_from_slice(&word.to_le_bytes());
+                }
+            }
+        }
+    }
+}
+
+impl FromStr for PixelFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "xrgb8888" => Ok(Self::Xrgb8888),
+            "rgba" | "rgba8888" => Ok(Self::Rgba8888),
+            "rgb565" => Ok(Self::Rgb565),
+            "xrgb2101010" | "rgb30" => Ok(Self::Xrgb2101010),
+            _ => Err("expected `xrgb8888`, `rgba`, `rgb565`, or `xrgb2101010`".to_owned()),
+        }
+    }
+}
+
+/// Rescales an 8-bit channel to `bits`, so full brightness stays full (255
+/// becomes 1023 in 10 bits, not 1020) and the rest round to the nearest step.
+fn scale_channel(c: u8, bits: u32) -> u32 {
+    let max = (1 << bits) - 1;
+    (u32::from(c) * max + 127) / 255
+}
+
+/// Blends from `a` (at 0.0) to `b` (at 1.0), a channel at a time.
+pub fn lerp(a: Color, b: Color, t: f32) -> Color {
+    let ((ar, ag, ab), (br, bg, bb)) = (to_u8_rgb(a), to_u8_rgb(b));
+    let mix = |a: u8, b: u8| (f32::from(a) + (f32::from(b) - f32::from(a)) * t).round() as u8;
+    from_u8_rgb(mix(ar, br), mix(ag, bg), mix(ab, bb))
+}
+
+/// Splits a color into hue (in turns, so from 0 to 1, going around from red),
+/// saturation, and lightness, each from 0 to 1.
+pub fn to_hsl(color: Color) -> (f32, f32, f32) {
+    let (r, g, b) = to_u8_rgb(color);
+    let [r, g, b] = [r, g, b].map(|c| f32::from(c) / f32::from(u8::MAX));
+    let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+    let lightness = (max + min) / 2.0;
+    let chroma = max - min;
+    if chroma == 0.0 {
+        return (0.0, 0.0, lightness);
+    }
+    let saturation = chroma / (1.0 - (2.0 * lightness - 1.0).abs());
+    let sixths = if max == r {
+        ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / chroma + 2.0
+    } else {
+        (r - g) / chroma + 4.0
+    };
+    (sixths / 6.0, saturation, lightness)
+}
+
+/// Puts a color back together from [`to_hsl`]'s parts.
+pub fn from_hsl(hue: f32, saturation: f32, lightness: f32) -> Color {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation.clamp(0.0, 1.0);
+    let sixths = hue.rem_euclid(1.0) * 6.0;
+    let second = chroma * (1.0 - (sixths % 2.0 - 1.0).abs());
+    let (r, g, b) = match sixths as u8 {
+        0 => (chroma, second, 0.0),
+        1 => (second, chroma, 0.0),
+        2 => (0.0, chroma, second),
+        3 => (0.0, second, chroma),
+        4 => (second, 0.0, chroma),
+        _ => (chroma, 0.0, second),
+    };
+    let base = lightness - chroma / 2.0;
+    let channel = |c: f32| ((c + base).clamp(0.0, 1.0) * f32::from(u8::MAX)).round() as u8;
+    from_u8_rgb(channel(r), channel(g), channel(b))
+}
+
+/// The hue things turn as they warm up: orange, like lamplight.
+const WARM_HUE: f32 = 30.0 / 360.0;
+/// The hue things turn as they cool down: sky blue.
+const COOL_HUE: f32 = 210.0 / 360.0;
+/// How far [`with_warmth`] goes at the most.
+const MAX_WARMTH: f32 = 0.4;
+/// How far [`with_warmth`] turns a color's own hue at the most, so it's still
+/// recognizably the color it was.
+const MAX_HUE_TURN: f32 = 20.0 / 360.0;
+
+/// `color` shifted warmer (toward orange), for `warmth` above 0, up to 1, or
+/// cooler (toward blue), below 0, down to -1. Whites and light grays have no
+/// hue of their own, so they're darkened a little, to make room for one.
+pub fn with_warmth(color: Color, warmth: f32) -> Color {
+    let amount = warmth.clamp(-1.0, 1.0).abs() * MAX_WARMTH;
+    if amount == 0.0 {
+        return color;
+    }
+    let (hue, saturation, lightness) = to_hsl(color);
+    let target = if warmth > 0.0 { WARM_HUE } else { COOL_HUE };
+    let hue = if saturation == 0.0 {
+        target
+    } else {
+        // The short way around
+        let turn = (target - hue + 0.5).rem_euclid(1.0) - 0.5;
+        hue + (turn * amount).clamp(-MAX_HUE_TURN, MAX_HUE_TURN)
+    };
+    let saturation = saturation + (1.0 - saturation) * amount;
+    let lightness = lightness - (lightness - 0.5).max(0.0) * amount;
+    from_hsl(hue, saturation, lightness)
+}
+
+/// `under`, with `color` drawn over it as far as `coverage` covers it (from a
+/// mask, where [`u8::MAX`] is all the way).
+pub fn tint(under: Color, color: Color, coverage: u8) -> Color {
+    match coverage {
+        0 => under,
+        u8::MAX => color,
+        _ => lerp(under, color, f32::from(coverage) / f32::from(u8::MAX)),
+    }
+}
+
+/// Swaps light and dark: the color on the opposite side of the RGB cube.
+pub const fn invert(color: Color) -> Color {
     let (r, g, b) = to_u8_rgb(color);
-    from_u8_rgb(
-        (f32::from(r) * percent).round() as u8,
-        (f32::from(g) * percent).round() as u8,
-        (f32::from(b) * percent).round() as u8,
-    )
+    from_u8_rgb(u8::MAX - r, u8::MAX - g, u8::MAX - b)
 }
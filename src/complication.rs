@@ -0,0 +1,244 @@
+//! Complications: small extras shown in a row under the time, like the date,
+//! each drawn by its own widget rather than by the clock face's layout.
+//!
+//! To add one, implement [`Complication`] and give it a name in [`REGISTRY`],
+//! and `--complication <name>` (or `<name>=<settings>`) will show it.
+
+use std::{fmt, sync::Arc};
+
+use chrono::{DateTime, Datelike, FixedOffset, Timelike, Utc, TimeZone};
+
+use crate::{
+    battery::Battery,
+    calendar::Calendar,
+    config::Config,
+    dni_time::DniTime,
+    frame::{self, Canvas, Number},
+    glyphs::Glyphs,
+    sound::Sound,
+    system_stats::SystemStats,
+    weather::Weather,
+};
+
+/// A widget shown under the time.
+///
+/// They're kept in the [`Config`], which gets shared around, so any state
+/// they keep has to be behind something thread-safe.
+pub trait Complication: fmt::Debug + Send + Sync {
+    /// What it's captioned with.
+    fn name(&self) -> &str;
+
+    /// How much room it needs at `scale`, not counting the caption. This is
+    /// worked out before anything's rendered, to size the window, so it can
+    /// only be an estimate, and should err on the big side.
+    fn measure(&self, scale: f32, config: &Config) -> (usize, usize);
+
+    /// Draws it as of `time` into the `width`×`height` region (as big as
+    /// [`Self::measure`] said) with its top left at `x`, `y`.
+    #[allow(clippy::too_many_arguments)]
+    fn render(
+        &self,
+        canvas: &mut dyn Canvas,
+        glyphs: &mut Glyphs,
+        time: DateTime<FixedOffset>,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    );
+
+    /// A sound to make as of `time`, if it's time for one. Asked once a tick.
+    fn sound(&self, _time: DateTime<FixedOffset>) -> Option<Sound> {
+        None
+    }
+
+    /// Whether it's served its purpose as of `time`, and can be taken down.
+    /// Most are there for good.
+    fn expired(&self, _time: DateTime<FixedOffset>) -> bool {
+        false
+    }
+}
+
+/// Makes a new complication, from whatever came after the `=` in its
+/// `--complication` (if anything), and the rest of the config.
+type Constructor = fn(Option<&str>, &Config) -> Result<Arc<dyn Complication>, String>;
+
+/// The complications there are, by the name `--complication` takes.
+const REGISTRY: &[(&str, Constructor)] = &[
+    ("battery", |settings, config| no_settings(settings).map(|()| Arc::new(Battery::new(config.locale)) as _)),
+    ("calendar", |settings, config| {
+        let path = settings.ok_or("`calendar` needs an ICS file or directory, like `calendar=work.ics`")?;
+        Ok(Arc::new(Calendar::new(path.into(), config.duration_units, config.locale)))
+    }),
+    ("date", |settings, _| no_settings(settings).map(|()| Arc::new(Date) as _)),
+    ("moon", |settings, _| no_settings(settings).map(|()| Arc::new(Moon) as _)),
+    ("ring", |settings, _| {
+        let span = match settings {
+            None | Some("pahrtahvo") => Span::Pahrtahvo,
+            Some("hour") => Span::Hour,
+            Some(_) => return Err("expected `ring=pahrtahvo` or `ring=hour`".to_owned()),
+        };
+        Ok(Arc::new(Ring { span }))
+    }),
+    ("system", |settings, _| no_settings(settings).map(|()| Arc::new(SystemStats::new()) as _)),
+    ("weather", |settings, config| {
+        let settings = settings.ok_or("`weather` needs a location, like `weather=52.52,13.41`")?;
+        Ok(Arc::new(Weather::new(settings.parse()?, config)))
+    }),
+];
+
+/// Makes the complication `spec` (a `--complication` value) asks for.
+pub fn from_spec(spec: &str, config: &Config) -> Result<Arc<dyn Complication>, String> {
+    let (name, settings) = match spec.split_once('=') {
+        Some((name, settings)) => (name, Some(settings)),
+        None => (spec, None),
+    };
+    match REGISTRY.iter().find(|&&(n, _)| n == name) {
+        Some((_, new)) => new(settings, config),
+        None => {
+            let names: Vec<_> = REGISTRY.iter().map(|&(n, _)| format!("`{n}`")).collect();
+            Err(format!("expected one of {}", names.join(", ")))
+        }
+    }
+}
+
+fn no_settings(settings: Option<&str>) -> Result<(), String> {
+    match settings {
+        Some(_) => Err("this one doesn't take any settings".to_owned()),
+        None => Ok(()),
+    }
+}
+
+/// The day of the month, then the month.
+#[derive(Debug)]
+struct Date;
+
+impl Date {
+    fn numbers(time: DateTime<FixedOffset>) -> [Number; 2] {
+        [
+            Number::TwoDigits(time.day().try_into().unwrap()),
+            Number::OneDigit(time.month().try_into().unwrap()),
+        ]
+    }
+}
+
+impl Complication for Date {
+    fn name(&self) -> &str {
+        "date"
+    }
+
+    fn measure(&self, scale: f32, config: &Config) -> (usize, usize) {
+        let numbers = Self::numbers(config.time_source.now());
+        (frame::numbers_width(&numbers, scale, config), frame::line_height(scale, config))
+    }
+
+    fn render(
+        &self,
+        canvas: &mut dyn Canvas,
+        glyphs: &mut Glyphs,
+        time: DateTime<FixedOffset>,
+        x: usize,
+        y: usize,
+        _width: usize,
+        height: usize,
+    ) {
+        frame::write_numbers(canvas, glyphs, &Self::numbers(time), x, y, height);
+    }
+}
+
+/// How many days it's been since the last new moon.
+#[derive(Debug)]
+struct Moon;
+
+impl Moon {
+    /// How long from one new moon to the next, on average, in days.
+    const SYNODIC_MONTH: f64 = 29.530_588_853;
+
+    /// The moon's age on `time`, in whole days (0-29).
+    fn age(time: DateTime<FixedOffset>) -> u8 {
+        // A known new moon, to count from
+        let reference = Utc.ymd(2000, 1, 6).and_hms(18, 14, 0);
+        let days = (time.with_timezone(&Utc) - reference).num_seconds() as f64 / 86_400.0;
+        days.rem_euclid(Self::SYNODIC_MONTH) as u8
+    }
+}
+
+impl Complication for Moon {
+    fn name(&self) -> &str {
+        "moon"
+    }
+
+    fn measure(&self, scale: f32, config: &Config) -> (usize, usize) {
+        let widest = [Number::TwoDigits(0)];
+        (frame::numbers_width(&widest, scale, config), frame::line_height(scale, config))
+    }
+
+    fn render(
+        &self,
+        canvas: &mut dyn Canvas,
+        glyphs: &mut Glyphs,
+        time: DateTime<FixedOffset>,
+        x: usize,
+        y: usize,
+        _width: usize,
+        height: usize,
+    ) {
+        frame::write_numbers(canvas, glyphs, &[Number::TwoDigits(Self::age(time))], x, y, height);
+    }
+}
+
+/// What a [`Ring`] shows the progress through.
+#[derive(Debug, Clone, Copy)]
+enum Span {
+    /// The current D'ni pahrtahvo (about an hour and ten minutes).
+    Pahrtahvo,
+    /// The current hour.
+    Hour,
+}
+
+/// A ring that fills up over the course of a pahrtahvo, or an hour.
+#[derive(Debug)]
+struct Ring {
+    span: Span,
+}
+
+impl Ring {
+    /// How far through the span it is as of `time`, from 0.0 to 1.0.
+    fn progress(&self, time: DateTime<FixedOffset>) -> f32 {
+        match self.span {
+            Span::Pahrtahvo => DniTime::from_surface(&time).pahrtahvo_progress(),
+            Span::Hour => (time.minute() * 60 + time.second()) as f32 / 3600.0,
+        }
+    }
+}
+
+impl Complication for Ring {
+    fn name(&self) -> &str {
+        match self.span {
+            Span::Pahrtahvo => "pahrtahvo",
+            Span::Hour => "hour",
+        }
+    }
+
+    fn measure(&self, scale: f32, config: &Config) -> (usize, usize) {
+        let line_height = frame::line_height(scale, config);
+        (line_height, line_height)
+    }
+
+    fn render(
+        &self,
+        canvas: &mut dyn Canvas,
+        glyphs: &mut Glyphs,
+        time: DateTime<FixedOffset>,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) {
+        let diameter = width.min(height);
+        // About as thick as the digits' strokes
+        let thickness = diameter as f32 / 8.0 + glyphs.options().stroke_weight() as f32;
+        let (x, y) = (x + (width - diameter) / 2, y + (height - diameter) / 2);
+        canvas.ring(glyphs, x, y, diameter, thickness, self.progress(time));
+    }
+}
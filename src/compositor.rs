@@ -0,0 +1,162 @@
+//! Putting each frame together from layers, bottom to top: the background,
+//! the digits, the complications, effects (like the rollover flash), and
+//! overlays (like the tooltip). Each layer has its own dirty flag, so only
+//! what's changed is drawn again: a new second redraws the seconds, not the
+//! whole face, even with a tooltip up over it.
+//!
+//! The bottom three are the clock face, drawn by [`frame`] into a buffer of
+//! their own. The effects and overlays are drawn over a copy of it, so taking
+//! them down again is just a matter of not drawing them.
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::{
+    colors,
+    config::Config,
+    frame,
+    glyphs::{GlyphBuffer, Glyphs},
+};
+
+/// One of the layers a frame's made of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    /// Everything that changes only with the minute, or with the settings:
+    /// the colors, the size, the hours and minutes, and world clocks.
+    Background,
+    /// The seconds.
+    Digits,
+    /// The row of complications under the clock.
+    Complications,
+    /// Things that move over the face, like the rollover flash.
+    Effects,
+    /// Things on top of everything, like the tooltip, or the dot saying a
+    /// chime was held back.
+    Overlay,
+}
+
+impl Layer {
+    /// All of them, bottom to top.
+    pub const ALL: [Self; 5] = [Self::Background, Self::Digits, Self::Complications, Self::Effects, Self::Overlay];
+
+    /// The ones drawn over the face, bottom to top.
+    const OVER_FACE: [Self; 2] = [Self::Effects, Self::Overlay];
+}
+
+/// The layers, and the frames they're drawn into.
+pub struct Compositor {
+    /// The clock face: the background, the digits, and the complications.
+    face: GlyphBuffer,
+    /// The face with the effects and overlays drawn over it.
+    composed: GlyphBuffer,
+    dirty: [bool; Layer::ALL.len()],
+    /// Which of the layers over the face have anything in them.
+    shown: [bool; Layer::ALL.len()],
+    /// Whether what was last put together is `composed`, rather than just
+    /// the face.
+    over_face: bool,
+}
+
+impl Compositor {
+    /// Starts with every layer dirty, and nothing over the face.
+    pub fn new() -> Self {
+        Self {
+            face: GlyphBuffer::default(),
+            composed: GlyphBuffer::default(),
+            dirty: [true; Layer::ALL.len()],
+            shown: [false; Layer::ALL.len()],
+            over_face: false,
+        }
+    }
+
+    /// Marks `layer` to be drawn again.
+    pub fn invalidate(&mut self, layer: Layer) {
+        self.dirty[layer as usize] = true;
+    }
+
+    /// Marks every layer to be drawn again.
+    pub fn invalidate_all(&mut self) {
+        self.dirty = [true; Layer::ALL.len()];
+    }
+
+    pub fn is_dirty(&self, layer: Layer) -> bool {
+        self.dirty[layer as usize]
+    }
+
+    /// Whether anything needs drawing again.
+    pub fn any_dirty(&self) -> bool {
+        self.dirty.contains(&true)
+    }
+
+    /// Whether any of the face's layers need drawing again.
+    pub fn face_dirty(&self) -> bool {
+        self.is_dirty(Layer::Background) || self.is_dirty(Layer::Digits) || self.is_dirty(Layer::Complications)
+    }
+
+    /// Clears every dirty flag, after the frame's been drawn some other way
+    /// (like as SVG).
+    pub fn mark_clean(&mut self) {
+        self.dirty = [false; Layer::ALL.len()];
+    }
+
+    /// Puts something in `layer` (an effect or an overlay), or takes it out,
+    /// marking it dirty if that's a change.
+    pub fn show(&mut self, layer: Layer, shown: bool) {
+        debug_assert!(Layer::OVER_FACE.contains(&layer), "only layers over the face can be shown and hidden");
+        if self.shown[layer as usize] != shown {
+            self.shown[layer as usize] = shown;
+            self.invalidate(layer);
+        }
+    }
+
+    /// The frame that was last put together.
+    pub fn frame(&self) -> &GlyphBuffer {
+        if self.over_face { &self.composed } else { &self.face }
+    }
+
+    /// The face, for drawing something else in its place (like the quiz, or
+    /// the splash). It's what [`Self::frame`] is until the next
+    /// [`Self::composite`].
+    pub fn face_mut(&mut self) -> &mut GlyphBuffer {
+        self.over_face = false;
+        &mut self.face
+    }
+
+    /// Draws the face's dirty layers for `time`, as little of it as it can.
+    pub fn draw_face(&mut self, time: DateTime<FixedOffset>, glyphs: &mut Glyphs, scale: f32, config: &Config) {
+        let face = &mut self.face;
+        let redrawn = !self.dirty[Layer::Background as usize]
+            && (!self.dirty[Layer::Digits as usize] || frame::update_seconds(face, time, glyphs, scale, config))
+            && (!self.dirty[Layer::Complications as usize]
+                || frame::update_complications(face, time, glyphs, scale, config));
+        if !redrawn {
+            frame::build_into(face, time, glyphs, scale, config);
+        }
+    }
+
+    /// Puts the frame together over the face, which is taken to be drawn
+    /// already, and clears every dirty flag. `draw` draws each layer over the
+    /// face that's shown, in order, onto the frame. Then it's all flipped, for
+    /// `--invert`.
+    pub fn composite(
+        &mut self,
+        glyphs: &mut Glyphs,
+        config: &Config,
+        mut draw: impl FnMut(Layer, &mut GlyphBuffer, &mut Glyphs),
+    ) -> &GlyphBuffer {
+        let layers = Layer::OVER_FACE.into_iter().filter(|&layer| self.shown[layer as usize]);
+        self.over_face = layers.clone().next().is_some() || config.invert;
+        if self.over_face && self.any_dirty() {
+            self.composed.copy_from(&self.face);
+            for layer in layers {
+                draw(layer, &mut self.composed, glyphs);
+            }
+            // Cached glyphs are all light on dark, so it's cheaper to flip
+            // the finished frame than to keep a second set
+            if config.invert {
+                colors::invert_all(&mut self.composed);
+            }
+        }
+        self.mark_clean();
+        self.frame()
+    }
+}
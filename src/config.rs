@@ -0,0 +1,989 @@
+//! Runtime configuration, parsed from the command line.
+
+use std::{path::PathBuf, str::FromStr, sync::Arc, time::Duration};
+
+use chrono::NaiveTime;
+
+use crate::{
+    chart::{ChartOptions, Theme},
+    colors::{self, Color},
+    complication::{self, Complication},
+    dni_time::DurationUnits,
+    export::RenderOptions,
+    fonts::LineMetrics,
+    frame::{Face, Margins, Midnight, SecondsToggle},
+    glyphs::GlyphOptions,
+    labels::{self, Labels},
+    locale::Locale,
+    ntp::NtpClock,
+    numerals,
+    pipe::PipeFormat,
+    profiles,
+    sound::Volumes,
+    speech,
+    placement::{Anchor, Geometry, MonitorSelector},
+    theme::ThemeScript,
+    time_source::{self, ClockChange, FakeClock, TimeSource},
+    timer::{self, TimerOptions},
+    title::Title,
+    weather,
+    webhooks::Webhook,
+    window::ResizePolicy,
+    zones::{self, Tz, Zone},
+};
+
+/// What the program should do.
+#[derive(Debug, Clone)]
+pub enum Command {
+    /// Show the clock (the usual).
+    Run,
+    /// Draw the clock to an image file and exit.
+    Render(RenderOptions),
+    /// Time how long drawing takes.
+    Bench,
+    /// Draw a reference chart of the digits to an image file and exit.
+    Chart(ChartOptions),
+    /// Ask the user to read random numbers, instead of showing the time.
+    Quiz,
+    /// Count down, in the running clock if there is one (or else as one).
+    Timer(TimerOptions),
+    /// Write the time to stdout as text, once or (if `follow`) every time it
+    /// changes.
+    Print { follow: bool },
+    /// Turn high contrast mode on or off in the running clock.
+    HighContrast(bool),
+    /// Swap light and dark in the running clock.
+    Invert,
+    /// Change the running clock's colors.
+    Colors(Color, Color),
+    /// Print how long the running clock's been taking to draw.
+    Stats,
+    /// Have the running clock tell the time right away, out loud if `say` and
+    /// in a banner if `show`.
+    Announce { say: bool, show: bool },
+    /// Move the running clock's time around, if it's on `--fake-clock`.
+    FakeClock(ClockChange),
+    /// Show or hide the running clock's seconds, or switch them (`None`).
+    Seconds(Option<bool>),
+    /// Switch the running clock over to a profile, by name.
+    Profile(String),
+    /// Check that fonts, time zones, the window, and such all work, and say
+    /// what to do about any that don't.
+    Doctor,
+}
+
+/// Everything the user can tweak without recompiling.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub command: Command,
+    /// Which monitor to open the window on. `None` leaves it up to the OS.
+    pub monitor: Option<MonitorSelector>,
+    /// Where on the monitor to put the window. `None` leaves it up to the OS,
+    /// unless a monitor was picked, in which case it's centered.
+    pub anchor: Option<Anchor>,
+    /// How big the window can be and where it goes, maybe as shares of the
+    /// monitor. Its position beats `anchor`.
+    pub geometry: Option<Geometry>,
+    /// Room around the clock face, inside the window.
+    pub margins: Margins,
+    /// Extra room on either side of the colons between the hours, minutes, and
+    /// seconds.
+    pub field_padding: usize,
+    /// Distance (in pixels) between the window and the monitor edge it's
+    /// anchored to.
+    pub screen_margin: usize,
+    /// What to call the window.
+    pub title: Title,
+    /// Put the time in words in the title, so screen readers have something
+    /// to say about the window.
+    pub accessible: bool,
+    /// Hide the title bar and frame, and let the window be dragged around by
+    /// any part of it instead.
+    pub borderless: bool,
+    /// Show the window on every workspace (virtual desktop), not just the one
+    /// it opened on.
+    pub sticky: bool,
+    /// How close (in pixels) a dragged window has to get to a monitor edge to
+    /// snap to it. 0 turns snapping off.
+    pub snap_threshold: usize,
+    /// Fill the whole monitor, hide the cursor, keep the screen awake, and
+    /// refuse to close except with Ctrl+Shift+Q.
+    pub kiosk: bool,
+    /// Keep the screen from blanking or sleeping while the window's open
+    /// (which kiosk mode always does).
+    pub keep_awake: bool,
+    /// Put a picture of the clock on the lock screen, every minute, while
+    /// it's running.
+    pub lock_screen: bool,
+    /// Wander the clock around by up to this many pixels each way, and dim it
+    /// a little now and then, so OLED screens don't burn in. 0 leaves it be.
+    pub burn_in: usize,
+    /// Let the window be resized, and fill it like this. `None` keeps it
+    /// exactly as big as the clock.
+    pub resize: Option<ResizePolicy>,
+    /// Seconds the cursor has to rest over the window before it's hidden. 0
+    /// means never hide it.
+    pub cursor_timeout: f32,
+    /// Captions for the fields, and where they go.
+    pub labels: Labels,
+    /// Add a second row with the native D'ni time, under the surface time.
+    pub show_dni_time: bool,
+    /// Show the seconds, and not just the hours and minutes. It can be
+    /// switched while the clock's running (by pressing S).
+    pub show_seconds: bool,
+    /// What happens to the window when the seconds are switched.
+    pub seconds_toggle: SecondsToggle,
+    /// How the surface time's laid out.
+    pub face: Face,
+    /// Show a clock for each of these, side by side, instead of just local time.
+    pub zones: Vec<Zone>,
+    /// Widgets to show in a row under the time, in order.
+    pub complications: Vec<Arc<dyn Complication>>,
+    /// What units timers and countdowns show how long is left in.
+    pub duration_units: DurationUnits,
+    /// How hour zero is shown.
+    pub midnight: Midnight,
+    /// Where `--complication weather` gets the temperature from: an `https://`
+    /// (or `http://`) URL answering like Open-Meteo, with `{lat}` and `{lon}`
+    /// to fill in.
+    pub weather_url: String,
+    /// How long to wait between checking the weather.
+    pub weather_interval: Duration,
+    /// Show the D'ni time in the user's Discord status, as the Discord
+    /// application with this client ID.
+    pub discord: Option<String>,
+    /// Where to POST the time, and when.
+    pub webhooks: Vec<Webhook>,
+    /// Make no sounds at all.
+    pub mute: bool,
+    pub volumes: Volumes,
+    /// Tick every second.
+    pub tick: bool,
+    /// Chime on the hour.
+    pub chime: bool,
+    /// Chime and ring alarms even when the system's set to do not disturb.
+    pub ignore_dnd: bool,
+    /// Write a line to stdout every time the clock face changes.
+    pub log_ticks: bool,
+    /// Flash the background this brightly (from 0 to 1) when the hour rolls
+    /// over.
+    pub rollover_flash: Option<f32>,
+    /// Morph each numeral into the next as it changes.
+    pub morph: bool,
+    /// Don't animate anything.
+    pub reduce_motion: bool,
+    /// Keep changing how the clock looks, to show it off.
+    pub demo: bool,
+    /// What language to write captions and such in.
+    pub locale: Locale,
+    /// Say the time out loud on the hour (and when T is pressed), like this.
+    pub announce: Option<speech::Style>,
+    /// Where the time comes from.
+    pub time_source: TimeSource,
+    /// Run without a window at all.
+    pub headless: bool,
+    /// How the glyphs should look.
+    pub glyphs: GlyphOptions,
+    /// How tall lines come out in the fonts, so there's room for them. Only
+    /// known once the fonts are loaded (see [`crate::fonts::Fonts::line_metrics`]).
+    pub line_metrics: LineMetrics,
+    /// Swap light and dark, for when the window is against a light backdrop.
+    /// Toggled with I, or `dni-clock invert`.
+    pub invert: bool,
+    /// What color the clock is drawn in, and on. Changed with `dni-clock
+    /// colors`.
+    pub fg: Color,
+    pub bg: Color,
+    /// Fill the background with this color and nothing else, for streaming
+    /// software to key out: no tracks, boxes, flashes, inverting, or dimming.
+    pub chroma_key: Option<Color>,
+    /// Shift `fg` through the day: cooler around noon, and warmer at night.
+    pub color_temperature: bool,
+    /// Work out the colors from the time with this script.
+    pub theme: Option<Arc<ThemeScript>>,
+    /// What color T-minus countdowns turn once they're past zero.
+    pub t_plus_color: Color,
+    /// Render all the digits on a background thread at startup, instead of
+    /// only as they're needed.
+    pub warm_up: bool,
+    /// Keep rendered glyphs on disk, to load next time instead of rendering.
+    pub disk_cache: bool,
+    /// Write video frames to stdout instead of showing a window.
+    pub pipe_frames: Option<PipeFormat>,
+    /// How many frames a second to pipe.
+    pub pipe_fps: u32,
+    /// Draw each frame as an SVG document to this file (or stdout, for `-`)
+    /// instead of showing a window.
+    pub svg_frames: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            command: Command::Run,
+            monitor: None,
+            anchor: None,
+            geometry: None,
+            margins: Margins::default(),
+            field_padding: 0,
+            screen_margin: 0,
+            title: Title::default(),
+            accessible: false,
+            borderless: false,
+            sticky: false,
+            snap_threshold: 16,
+            kiosk: false,
+            keep_awake: false,
+            lock_screen: false,
+            burn_in: 0,
+            resize: None,
+            cursor_timeout: 3.0,
+            labels: Labels::default(),
+            show_dni_time: false,
+            show_seconds: true,
+            seconds_toggle: SecondsToggle::default(),
+            face: Face::default(),
+            zones: Vec::new(),
+            complications: Vec::new(),
+            duration_units: DurationUnits::default(),
+            midnight: Midnight::default(),
+            weather_url: weather::OPEN_METEO_URL.to_owned(),
+            weather_interval: Duration::from_secs(15 * 60),
+            discord: None,
+            webhooks: Vec::new(),
+            mute: false,
+            volumes: Volumes::default(),
+            tick: false,
+            chime: false,
+            ignore_dnd: false,
+            log_ticks: false,
+            rollover_flash: None,
+            morph: false,
+            reduce_motion: false,
+            demo: false,
+            locale: Locale::default(),
+            announce: None,
+            time_source: TimeSource::default(),
+            headless: false,
+            glyphs: GlyphOptions::default(),
+            line_metrics: LineMetrics::default(),
+            invert: false,
+            fg: colors::FG,
+            bg: colors::BG,
+            chroma_key: None,
+            color_temperature: false,
+            theme: None,
+            t_plus_color: colors::T_PLUS,
+            warm_up: false,
+            disk_cache: false,
+            pipe_frames: None,
+            pipe_fps: 30,
+            svg_frames: None,
+        }
+    }
+}
+
+impl Config {
+    /// Parses the process's arguments, or prints the error and exits if they're
+    /// bad.
+    pub fn from_env() -> Self {
+        match Self::from_args(std::env::args().skip(1)) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("error: {e}");
+                eprintln!();
+                eprintln!("{USAGE}");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    /// Parses arguments (not including the program name).
+    pub fn from_args(args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut config = Self { locale: Locale::from_env(), ..Self::default() };
+        let mut args = profiles::expand(args)?.into_iter().peekable();
+        let mut render_animate = false;
+        // For `render` and `chart`
+        let mut out = None;
+        let mut render_time = None;
+        let mut render_number = None;
+        let mut chart_scale = DEFAULT_CHART_SCALE;
+        let mut chart_theme = Theme::Dark;
+        let (mut print, mut follow) = (false, false);
+        let mut complications = Vec::new();
+        let rendering = args.next_if(|arg| arg == "render").is_some();
+        let charting = !rendering && args.next_if(|arg| arg == "chart").is_some();
+        if args.next_if(|arg| arg == "bench").is_some() {
+            config.command = Command::Bench;
+        } else if args.next_if(|arg| arg == "quiz").is_some() {
+            config.command = Command::Quiz;
+        } else if args.next_if(|arg| arg == "doctor").is_some() {
+            config.command = Command::Doctor;
+        }
+        let timer = args.next_if(|arg| arg == "timer").is_some();
+        let timer_duration = if timer {
+            let value = args.next().ok_or("`timer` needs a duration, like `timer 5m`")?;
+            Some(timer::parse_duration(&value).map_err(|e| format!("bad duration `{value}`: {e}"))?)
+        } else {
+            None
+        };
+        let mut timer_name = "timer".to_owned();
+        let mut t_minus = false;
+        let high_contrast = if args.next_if(|arg| arg == "high-contrast").is_some() {
+            match args.next().as_deref() {
+                Some("on") => Some(true),
+                Some("off") => Some(false),
+                _ => return Err("`high-contrast` needs `on` or `off`".to_owned()),
+            }
+        } else {
+            None
+        };
+        let invert = args.next_if(|arg| arg == "invert").is_some();
+        let stats = args.next_if(|arg| arg == "stats").is_some();
+        let announce = if args.next_if(|arg| arg == "announce").is_some() {
+            let say = args.next_if(|arg| arg == "say").is_some();
+            let show = args.next_if(|arg| arg == "show").is_some();
+            // Both, unless it's asked for just one
+            Some(if say || show { (say, show) } else { (true, true) })
+        } else {
+            None
+        };
+        let clock_change = if args.next_if(|arg| arg == "fake-clock").is_some() {
+            let kind = args.next().ok_or("`fake-clock` needs a change, like `fake-clock advance 1h`")?;
+            let value = match kind.as_str() {
+                "set" | "advance" | "suspend" => args.next(),
+                _ => None,
+            };
+            Some(ClockChange::parse(&kind, value.as_deref()).map_err(|e| format!("bad `fake-clock` change: {e}"))?)
+        } else {
+            None
+        };
+        let seconds = if args.next_if(|arg| arg == "seconds").is_some() {
+            match args.next_if(|arg| !arg.starts_with('-')).as_deref() {
+                Some("on") => Some(Some(true)),
+                Some("off") => Some(Some(false)),
+                None => Some(None),
+                Some(_) => return Err("`seconds` takes `on`, `off`, or nothing (to switch them)".to_owned()),
+            }
+        } else {
+            None
+        };
+        let switch_profile = if args.next_if(|arg| arg == "profile").is_some() {
+            let name = args.next().ok_or("`profile` needs a name")?;
+            // Caught here, so it's not just the running clock that knows
+            profiles::load(&name).map_err(|e| format!("bad profile `{name}`: {e}"))?;
+            Some(name)
+        } else {
+            None
+        };
+        let new_colors = if args.next_if(|arg| arg == "colors").is_some() {
+            let mut color = || {
+                let value = args.next().ok_or("`colors` needs two colors, like `colors #ffcc88 #201008`")?;
+                colors::parse(&value).map_err(|e| format!("bad color `{value}`: {e}"))
+            };
+            Some((color()?, color()?))
+        } else {
+            None
+        };
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => {
+                    println!("{USAGE}");
+                    std::process::exit(0);
+                }
+                "--monitor" => config.monitor = Some(parse_value(&arg, args.next())?),
+                "--anchor" => config.anchor = Some(parse_value(&arg, args.next())?),
+                "--geometry" => config.geometry = Some(parse_value(&arg, args.next())?),
+                "--face-margin" => config.margins = parse_value(&arg, args.next())?,
+                "--field-padding" => config.field_padding = parse_value(&arg, args.next())?,
+                "--margin" => config.screen_margin = parse_value(&arg, args.next())?,
+                "--title" => config.title = parse_value(&arg, args.next())?,
+                "--accessible" => config.accessible = true,
+                "--borderless" => config.borderless = true,
+                "--sticky" => config.sticky = true,
+                "--snap" => config.snap_threshold = parse_value(&arg, args.next())?,
+                "--kiosk" => config.kiosk = true,
+                "--keep-awake" => config.keep_awake = true,
+                "--lock-screen" => config.lock_screen = true,
+                "--burn-in" => config.burn_in = parse_value(&arg, args.next())?,
+                "--resize" => config.resize = Some(parse_value(&arg, args.next())?),
+                "--hide-cursor-after" => {
+                    let seconds: f32 = parse_value(&arg, args.next())?;
+                    if seconds.is_nan() || seconds < 0.0 {
+                        return Err(format!("`{arg}` has to be 0 or more"));
+                    }
+                    // So the window can make a `Duration` of it
+                    Duration::try_from_secs_f32(seconds).map_err(|_| format!("`{arg}` is too long"))?;
+                    config.cursor_timeout = seconds;
+                }
+                "--label" => {
+                    let value = args.next().ok_or_else(|| format!("`{arg}` needs a value"))?;
+                    let (field, text) = labels::parse_label(&value)
+                        .map_err(|e| format!("bad value `{value}` for `{arg}`: {e}"))?;
+                    config.labels.set(field, text);
+                }
+                "--dni-time" => config.show_dni_time = true,
+                "--no-seconds" => config.show_seconds = false,
+                "--seconds-toggle" => config.seconds_toggle = parse_value(&arg, args.next())?,
+                "--face" => config.face = parse_value(&arg, args.next())?,
+                "--mute" => config.mute = true,
+                "--volume" => {
+                    let value = args.next().ok_or_else(|| format!("`{arg}` needs a value"))?;
+                    config.volumes.set(&value).map_err(|e| format!("bad value `{value}` for `{arg}`: {e}"))?;
+                }
+                "--tick" => config.tick = true,
+                "--chime" => config.chime = true,
+                "--ignore-dnd" => config.ignore_dnd = true,
+                "--rollover-flash" => {
+                    let intensity: f32 = parse_value(&arg, args.next())?;
+                    if !(0.0..=1.0).contains(&intensity) {
+                        return Err(format!("`{arg}` has to be from 0 to 1"));
+                    }
+                    config.rollover_flash = Some(intensity);
+                }
+                "--morph" => config.morph = true,
+                "--reduce-motion" => config.reduce_motion = true,
+                "--demo" => config.demo = true,
+                "--log-ticks" => config.log_ticks = true,
+                "--locale" => config.locale = parse_value(&arg, args.next())?,
+                "--announce" => config.announce = Some(parse_value(&arg, args.next())?),
+                "--ntp" => {
+                    let server = args.next().ok_or_else(|| format!("`{arg}` needs a value"))?;
+                    config.time_source.clock = Arc::new(NtpClock::new(server));
+                }
+                "--fake-clock" => {
+                    let value = args.next().ok_or_else(|| format!("`{arg}` needs a value"))?;
+                    let start = time_source::parse_time(&value)
+                        .map_err(|e| format!("bad value `{value}` for `{arg}`: {e}"))?;
+                    config.time_source.clock = Arc::new(FakeClock::new(start));
+                }
+                "--offset" => {
+                    let value = args.next().ok_or_else(|| format!("`{arg}` needs a value"))?;
+                    let offset = zones::parse_offset(&value)
+                        .map_err(|e| format!("bad value `{value}` for `{arg}`: {e}"))?;
+                    config.time_source.zone = Some(Tz::Fixed(offset));
+                }
+                "--timezone" => config.time_source.zone = Some(parse_value(&arg, args.next())?),
+                "--headless" => config.headless = true,
+                "--print" => print = true,
+                "--follow" => follow = true,
+                "--overlap" => config.glyphs.digit_overlap = parse_value(&arg, args.next())?,
+                "--weight" => config.glyphs.weight = parse_value(&arg, args.next())?,
+                "--high-contrast" => config.glyphs.high_contrast = true,
+                "--normalize-strokes" => config.glyphs.normalize_strokes = true,
+                "--slant" => {
+                    let degrees: f32 = parse_value(&arg, args.next())?;
+                    if !(-45.0..=45.0).contains(&degrees) {
+                        return Err(format!("`{arg}` has to be between -45 and 45 degrees"));
+                    }
+                    config.glyphs.slant = degrees;
+                }
+                "--pixel-art" => {
+                    let scale: f32 = parse_value(&arg, args.next())?;
+                    if scale.is_nan() || scale < 1.0 {
+                        return Err(format!("`{arg}` has to be at least 1"));
+                    }
+                    config.glyphs.pixel_scale = Some(scale);
+                }
+                "--dni-font" => config.glyphs.dni_fonts.push(parse_value(&arg, args.next())?),
+                "--numerals" => {
+                    let value = args.next().ok_or_else(|| format!("`{arg}` needs a value"))?;
+                    config.glyphs.numerals = numerals::by_name(&value)
+                        .map_err(|e| format!("bad value `{value}` for `{arg}`: {e}"))?;
+                }
+                "--font-index" => config.glyphs.font_index = parse_value(&arg, args.next())?,
+                "--label-font" => config.glyphs.label_font = Some(parse_value(&arg, args.next())?),
+                "--digit-axis" => config.glyphs.digit_axes.push(parse_value(&arg, args.next())?),
+                "--label-axis" => config.glyphs.label_axes.push(parse_value(&arg, args.next())?),
+                "--theme-script" => {
+                    let path: PathBuf = parse_value(&arg, args.next())?;
+                    config.theme = Some(Arc::new(ThemeScript::load(&path)?));
+                }
+                "--warm-up" => config.warm_up = true,
+                "--glyph-cache" => config.disk_cache = true,
+                "--pipe-frames" => config.pipe_frames = Some(parse_value(&arg, args.next())?),
+                "--pipe-fps" => config.pipe_fps = parse_value(&arg, args.next())?,
+                "--svg-frames" => config.svg_frames = Some(parse_value(&arg, args.next())?),
+                // Made once everything else is parsed, since they can depend
+                // on other settings
+                "--complication" => {
+                    let value = args.next().ok_or_else(|| format!("`{arg}` needs a value"))?;
+                    complications.push(value);
+                }
+                "--weather-url" => config.weather_url = parse_value(&arg, args.next())?,
+                "--weather-every" => {
+                    let minutes: f32 = parse_value(&arg, args.next())?;
+                    if minutes.is_nan() || minutes < 1.0 {
+                        return Err(format!("`{arg}` has to be at least 1 minute"));
+                    }
+                    config.weather_interval = Duration::try_from_secs_f32(minutes * 60.0)
+                        .map_err(|_| format!("`{arg}` is too long"))?;
+                }
+                "--discord" => {
+                    let value = args.next().ok_or_else(|| format!("`{arg}` needs a value"))?;
+                    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+                        return Err(format!("bad value `{value}` for `{arg}`: expected a client ID, which is all digits"));
+                    }
+                    config.discord = Some(value);
+                }
+                "--webhook" => config.webhooks.push(parse_value(&arg, args.next())?),
+                "--zone" => config.zones.push(parse_value(&arg, args.next())?),
+                "--durations" => config.duration_units = parse_value(&arg, args.next())?,
+                "--midnight" => config.midnight = parse_value(&arg, args.next())?,
+                "--fg" => config.fg = parse_color(&arg, args.next())?,
+                "--bg" => config.bg = parse_color(&arg, args.next())?,
+                "--color-temperature" => config.color_temperature = true,
+                "--chroma-key" => {
+                    let value = args.next().ok_or_else(|| format!("`{arg}` needs a value"))?;
+                    let key = colors::parse_key(&value).map_err(|e| format!("bad value `{value}` for `{arg}`: {e}"))?;
+                    config.chroma_key = Some(key);
+                }
+                "--label-position" => config.labels.position = parse_value(&arg, args.next())?,
+                "--name" if timer => timer_name = parse_value(&arg, args.next())?,
+                "--t-minus" if timer => t_minus = true,
+                "--t-plus-color" => config.t_plus_color = parse_color(&arg, args.next())?,
+                "--animate" if rendering => render_animate = true,
+                "--out" if rendering || charting => out = Some(parse_value(&arg, args.next())?),
+                "--time" if rendering => {
+                    let value = args.next().ok_or_else(|| format!("`{arg}` needs a value"))?;
+                    let time = parse_time(&value)
+                        .ok_or_else(|| format!("bad value `{value}` for `{arg}`: expected HH:MM or HH:MM:SS"))?;
+                    render_time = Some(time);
+                }
+                "--number" if rendering => render_number = Some(parse_value(&arg, args.next())?),
+                "--scale" if charting => {
+                    chart_scale = parse_value(&arg, args.next())?;
+                    if chart_scale.is_nan() || chart_scale <= 0.0 {
+                        return Err(format!("`{arg}` has to be more than 0"));
+                    }
+                }
+                "--theme" if charting => chart_theme = parse_value(&arg, args.next())?,
+                _ => return Err(format!("unrecognized argument `{arg}`")),
+            }
+        }
+        for spec in complications {
+            let complication = complication::from_spec(&spec, &config)
+                .map_err(|e| format!("bad value `{spec}` for `--complication`: {e}"))?;
+            config.complications.push(complication);
+        }
+        if let Some(key) = config.chroma_key {
+            config.bg = key;
+            config.glyphs.keyed_background = true;
+            // It'd flash the key color itself, which can't be keyed out
+            config.rollover_flash = None;
+        }
+        if config.accessible {
+            config.title = config.title.spoken();
+        }
+        if config.geometry.is_some() && config.kiosk {
+            return Err("`--geometry` doesn't go with `--kiosk`, which fills the monitor".to_owned());
+        }
+        if follow && !print {
+            return Err("`--follow` only goes with `--print`".to_owned());
+        }
+        let svg_to_stdout = config.svg_frames.as_deref().is_some_and(|path| path.as_os_str() == "-");
+        if config.log_ticks && (config.pipe_frames.is_some() || svg_to_stdout) {
+            return Err("`--log-ticks` can't share stdout with the frames".to_owned());
+        }
+        if invert {
+            config.command = Command::Invert;
+        } else if stats {
+            config.command = Command::Stats;
+        } else if let Some((say, show)) = announce {
+            config.command = Command::Announce { say, show };
+        } else if let Some(change) = clock_change {
+            config.command = Command::FakeClock(change);
+        } else if let Some((fg, bg)) = new_colors {
+            config.command = Command::Colors(fg, bg);
+        } else if let Some(on) = high_contrast {
+            config.command = Command::HighContrast(on);
+        } else if let Some(on) = seconds {
+            config.command = Command::Seconds(on);
+        } else if let Some(name) = switch_profile {
+            config.command = Command::Profile(name);
+        } else if let Some(duration) = timer_duration {
+            config.command = Command::Timer(TimerOptions { duration, name: timer_name, t_minus });
+        } else if print {
+            config.command = Command::Print { follow };
+        } else if rendering {
+            let out = out.ok_or("`render` needs `--out <file>`")?;
+            config.command = Command::Render(RenderOptions {
+                animate: render_animate,
+                out,
+                time: render_time,
+                number: render_number,
+            });
+        } else if charting {
+            let out = out.ok_or("`chart` needs `--out <file>`")?;
+            config.command = Command::Chart(ChartOptions { out, scale: chart_scale, theme: chart_theme });
+        }
+        Ok(config)
+    }
+}
+
+/// How big the digits in a chart are if `--scale` doesn't say.
+const DEFAULT_CHART_SCALE: f32 = 100.0;
+
+const USAGE: &str = "\
+usage: dni-clock [options]
+       dni-clock render --out <file> [--animate] [options]
+       dni-clock bench [options]
+       dni-clock chart --out <file> [--scale <px>] [--theme <theme>] [options]
+       dni-clock quiz [options]     (practice reading random numbers)
+       dni-clock doctor [options]   (check that the fonts, time zones, and
+                                    window all work, and say what to do if
+                                    they don't; for bug reports)
+       dni-clock timer <duration> [--name <name>] [--t-minus] [options]
+                                    (count down, like `timer 1h30m`, in the
+                                    clock that's already running if there
+                                    is one; with --t-minus, keep counting
+                                    up past zero, like a launch)
+       dni-clock high-contrast <on|off>
+                                    (switch high contrast mode in the clock
+                                    that's already running)
+       dni-clock invert             (swap light and dark in the clock that's
+                                    already running, like pressing I)
+       dni-clock seconds [on|off]   (show or hide the seconds in the clock
+                                    that's already running, or switch them,
+                                    like pressing S)
+       dni-clock profile <name>     (switch the clock that's already running
+                                    over to a profile, as if it had been
+                                    started with --profile)
+       dni-clock colors <fg> <bg>   (change the colors of the clock that's
+                                    already running, like `#ffcc88 #201008`)
+       dni-clock stats              (show how long the clock that's already
+                                    running has been taking to draw)
+       dni-clock announce [say|show]
+                                    (have the clock that's already running
+                                    say the time out loud, and show it in
+                                    plain digits for a few seconds, or just
+                                    one or the other; for a hotkey)
+       dni-clock fake-clock <change>
+                                    (move the time of a clock that's already
+                                    running with --fake-clock: `set <time>`,
+                                    `advance <duration>`, `suspend
+                                    <duration>` (like advance, but as if
+                                    asleep), `pause`, or `resume`)
+
+options:
+    --profile <name>        use the options saved under [name] in the
+                            profiles file (dni-clock/profiles in the
+                            config directory, one option per line), as
+                            if they'd been typed in its place
+    --monitor <which>       the monitor to open on: `primary`, its name
+                            (like `HDMI-1`; `dni-clock doctor` lists
+                            them), or its number counting from 0 (which
+                            can change as monitors come and go)
+    --anchor <position>     top-left, top, top-right, left, center, right,
+                            bottom-left, bottom, or bottom-right
+    --geometry <geometry>   the most room the window takes up and where it
+                            goes, like `400x100+0-0` (X11-style; `-`
+                            counts from the right or bottom), in pixels
+                            or percentages of the monitor, like
+                            `25%x10%+70%+5%`; either half can be left off
+    --margin <px>           space between the window and the anchored edges
+                            (and the edges it snaps to)
+    --face-margin <px>      room around the clock face, inside the window:
+                            one number for every side, two for top and
+                            bottom then the sides, or four for top, right,
+                            bottom, and left, like CSS (default 10,0,10,10,
+                            since there's a bit of room after the numbers
+                            anyway)
+    --field-padding <px>    extra room on either side of the colons between
+                            the hours, minutes, and seconds (default 0)
+    --title <text>          the window's title (default `D'ni Clock`), which
+                            can include {time}, {dni} (the D'ni time),
+                            {spoken} (the time in words), and {mode}
+                            (clock or quiz)
+    --accessible            name the window with the time in words, like
+                            `D'ni Clock: It's 3:17 PM`, for screen readers
+                            (which can't read the numerals themselves)
+    --borderless            no title bar; drag the window by any part of it
+    --sticky                show the window on every workspace (on X11,
+                            with wmctrl or xdotool)
+    --snap <px>             snap to monitor edges when dragged within this
+                            distance (default 16, 0 to disable)
+    --kiosk                 fullscreen on the chosen monitor, cursor hidden,
+                            screen kept awake; exit with Ctrl+Shift+Q
+    --keep-awake            keep the screen from blanking or sleeping while
+                            the clock's open, like kiosk mode does (for a
+                            fullscreen or bedside clock)
+    --lock-screen           keep the time on the lock screen, as a picture
+                            drawn every minute (the desktop picture on
+                            macOS, GNOME's screensaver picture on Linux)
+    --burn-in <px>          for OLED screens: wander around up to this far,
+                            a pixel a minute, dimming a little each hour
+    --resize <policy>       let the window be resized, filling it by
+                            `stretch`ing the clock to fit, drawing it as
+                            big as fits (`letterbox`), or blowing it up
+                            by a whole number of times (`integer`)
+    --hide-cursor-after <s> hide the cursor after it rests over the window
+                            this many seconds (default 3, 0 to never hide)
+    --label <field>=<text>  caption a field (hours, minutes, seconds,
+                            gartahvo, tahvo, or gorahn) with some text
+    --dni-time              also show the native D'ni time (gartahvo,
+                            tahvo, gorahn), on a second row
+    --no-seconds            just the hours and minutes (press S to switch
+                            the seconds on and off)
+    --seconds-toggle <how>  when the seconds are switched, `resize` the
+                            window to fit (default), or keep it the size
+                            it was and `center` the clock in it
+    --face <style>          lay the time out in a `line` (default), or like
+                            a `watch`: the hour big on the left, with the
+                            minutes over the seconds on the right
+    --label-position <pos>  put the captions `above` (default) or `below`
+    --zone [<name>=]<zone>  add a world clock column for a time zone like
+                            Asia/Tokyo (or just tokyo), `cavern` (the
+                            cavern's clocks, on Mountain time), or a UTC
+                            offset like +09:00; can be given more than once
+    --complication <name>   show `battery` (charge, in 24ths),
+                            `calendar=<path>` (time until the next event in
+                            an ICS file or directory of them), `date` (day
+                            and month), `moon` (days since the new moon),
+                            `ring` (a ring filling up over the pahrtahvo,
+                            or `ring=hour` for the hour), `system` (CPU
+                            and memory use, in 24ths), or
+                            `weather=<lat>,<lon>` (the temperature there,
+                            in °C) under the time; can be given more than
+                            once
+    --durations <units>     show what's left on timers and until calendar
+                            events in `surface` units (hours, minutes,
+                            seconds; the default) or `dni` (gartahvotee,
+                            tahvotee, gorahntee)
+    --fg <color>            draw the clock in this color, like `#ffcc88`
+                            (default white)
+    --bg <color>            draw the clock on this color (default black)
+    --color-temperature     shift the clock's color through the day, cooler
+                            (bluer) around noon and warmer (more orange)
+                            at night
+    --chroma-key <color>    for streaming: fill the background with `green`,
+                            `magenta`, or any color, for OBS and the like
+                            to key out, and draw nothing else behind the
+                            digits (try `--profile stream-overlay`)
+    --t-plus-color <color>  what color `timer --t-minus` countdowns turn past
+                            zero (default amber, `#ffb000`)
+    --midnight <style>      show hour zero as the D'ni `zero` (the default),
+                            `25` (the single-digit 25, like 24:00), or a
+                            `mark` (an empty digit frame)
+    --weather-url <url>     where to get the weather: an https:// or http://
+                            URL that answers like Open-Meteo, with {lat}
+                            and {lon} in it (default Open-Meteo itself)
+    --weather-every <min>   how often to check the weather (default 15)
+    --discord <client-id>   show the D'ni time in your Discord status, as
+                            the Discord application with this client ID
+                            (made at discord.com/developers), updated
+                            every few minutes while Discord's running
+    --webhook <when>=<url>  POST the surface and D'ni time as JSON to an
+                            http:// URL `hourly`, each `gartahvo` or
+                            `tahvo`, `every:<minutes>`, daily at a time
+                            like `07:30`, or when a timer's `alarm` goes
+                            off (can be given more than once)
+    --tick                  tick every second
+    --chime                 chime on the hour
+    --volume <sound>=<n>    how loud `tick`, `chime`, or `alarm` (for
+                            timers) is, from 0 to 100 (defaults 30, 60,
+                            and 80)
+    --locale <language>     write captions, the tooltip, and such in `en`,
+                            `de`, `es`, `fr`, `it`, `nl`, or `pt`
+                            (default from LANG)
+    --announce <style>      say the time out loud on the hour, and when T is
+                            pressed: `surface` (like \"3:17 PM\") or `dni`
+                            (the D'ni time, in D'ni number words)
+    --mute                  make no sounds at all
+    --ignore-dnd            chime, announce, and ring alarms even when the
+                            system's set to do not disturb (normally they're
+                            held back, and a dot shows in the corner until
+                            the pointer rests on the clock)
+    --rollover-flash <n>    flash the background when the hour rolls over,
+                            from 0 (not at all) to 1 (all the way to the
+                            digits' color); 0.3 is subtle
+    --morph                 morph each numeral into the next as it changes,
+                            so the seconds flow from one to the next
+    --reduce-motion         don't animate anything (like the numerals coming
+                            in at startup, the rollover flash, or --morph);
+                            nothing is in high contrast mode either
+    --ntp <server>          get the time from this NTP server (like
+                            pool.ntp.org), instead of the system clock
+    --offset <offset>       show the time at a fixed UTC offset like
+                            +05:45, instead of in the system's time zone
+    --timezone <zone>       show the time in this time zone (named like
+                            with --zone), instead of the system's
+    --fake-clock <time>     start the clock at this made-up time, like
+                            2024-03-10T01:59:50-07:00, and then go by
+                            `dni-clock fake-clock` (for trying out
+                            midnight, clock changes, and waking up)
+    --demo                  show off: every few seconds, change colors, add
+                            the D'ni time, world clocks, or complications,
+                            or switch to high contrast or another size
+                            (for meetups, or recording with --pipe-frames)
+    --headless              run without a window (until killed)
+    --log-ticks             write a line to stdout every time the time
+                            changes: the time, its D'ni digits, and how
+                            late and slow drawing it was
+    --print                 instead of opening a window, write the time to
+                            stdout as the values of its D'ni digits, like
+                            12:[1 7]:[0 15]
+    --follow                with --print, write it again every time it
+                            changes (until killed)
+    --overlap <fraction>    how far the walls of two-digit numerals overlap,
+                            as a fraction of the scale (0 for none), or
+                            `auto` (the default) to line them up exactly
+    --weight <px>           thicken the digits by this many pixels, for a
+                            bolder look (default 0)
+    --normalize-strokes     thicken or thin the digits' strokes to the same
+                            share of their size at any scale, so they
+                            don't fade away small or look spindly big
+    --high-contrast         pure white on black with no antialiasing,
+                            bolder strokes, and bigger digits, for low
+                            vision
+    --slant <degrees>       lean the digits over, like italics (negative
+                            leans them back)
+    --pixel-art <px>        draw chunky pixel art: digits rendered as if
+                            this big (like 16), without antialiasing,
+                            then blown up in whole blocks (or use
+                            `--profile pixel`)
+    --numerals <system>     write numbers in `dni` numerals (base 25; the
+                            default) or `mayan` (base 20, which needs a
+                            --dni-font that has them, like Noto Sans Mayan
+                            Numerals)
+    --dni-font <font>       draw the digits with this font: a file (TTF,
+                            OTF, or a TTC/OTC collection), or one that's
+                            built in (just `dni`, so far); can be given more
+                            than once, then F cycles between them in the
+                            window
+    --font-index <n>        which font in a --dni-font collection to use
+                            (counting from 0)
+    --label-font <file>     draw the labels and colons with this font
+    --digit-axis <tag>=<n>  set an axis of a variable --dni-font, like
+                            wght=600 for SemiBold or wdth=75 for condensed;
+                            can be given more than once
+    --label-axis <tag>=<n>  the same, for the label font
+    --theme-script <file>   work out the colors from the time with a Rhai
+                            script, given `hour`, `minute`, `second`,
+                            `gartahvo`, `tahvo`, `gorahn`, `prorahn`, `fg`,
+                            and `bg`, that returns a map like `#{ fg:
+                            hsl(hour * 15, 0.6, 0.7) }` (with `rgb`, `hsl`,
+                            and `mix` to make colors), which can also set
+                            `glow` (0 to 1) and `labels` (like `#{ hours:
+                            \"surface\" }`)
+    --warm-up               render every digit in the background at
+                            startup, rather than as each first comes up
+    --glyph-cache           save rendered glyphs to disk, so they can be
+                            loaded instead of rendered next time
+    --pipe-frames <format>  write video frames to stdout instead of opening
+                            a window: `y4m`, or raw `rgba`, `xrgb8888`,
+                            `rgb565`, or `xrgb2101010` (30-bit) pixels
+    --pipe-fps <n>          frame rate for --pipe-frames (default 30)
+    --svg-frames <file>     instead of opening a window, draw each frame as
+                            an SVG, replacing the file each time (or
+                            writing them one after another to stdout, for -)
+    -h, --help              print this message
+
+render options:
+    --out <file>            where to save the picture (.gif, .png, or .svg
+                            for vector outlines)
+    --animate               save a whole minute, one frame per second,
+                            as a looping GIF or APNG
+    --time <HH:MM[:SS]>     show this time instead of the current one
+    --number <n>            just draw this number, in as many D'ni digits
+                            as it takes
+
+chart options:
+    --out <file>            where to save the chart (.png or .svg)
+    --scale <px>            how big the digits are (default 100)
+    --theme <theme>         `dark` (the default) or `light`, for printing";
+
+/// Parses a time of day like `13:05` or `13:05:42`.
+fn parse_time(s: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(s, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(s, "%H:%M"))
+        .ok()
+}
+
+/// Like [`parse_value`], for colors (which are plain numbers to `FromStr`).
+fn parse_color(flag: &str, value: Option<String>) -> Result<Color, String> {
+    let value = value.ok_or_else(|| format!("`{flag}` needs a value"))?;
+    colors::parse(&value).map_err(|e| format!("bad value `{value}` for `{flag}`: {e}"))
+}
+
+/// Parses the value following `flag`, complaining if it's missing or malformed.
+fn parse_value<T>(flag: &str, value: Option<String>) -> Result<T, String>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    let value = value.ok_or_else(|| format!("`{flag}` needs a value"))?;
+    value.parse().map_err(|e| format!("bad value `{value}` for `{flag}`: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        glyphs::Overlap,
+        placement::{Length, Offset},
+    };
+
+    fn parse(args: &[&str]) -> Result<Config, String> {
+        Config::from_args(args.iter().map(|&arg| arg.to_owned()))
+    }
+
+    fn margins(value: &str) -> Result<Margins, String> {
+        parse(&["--face-margin", value]).map(|config| config.margins)
+    }
+
+    #[test]
+    fn face_margins_go_around_like_css() {
+        assert_eq!(margins("10"), Ok(Margins { top: 10, right: 10, bottom: 10, left: 10 }));
+        assert_eq!(margins("4, 8"), Ok(Margins { top: 4, right: 8, bottom: 4, left: 8 }));
+        assert_eq!(margins("1,2,3,4"), Ok(Margins { top: 1, right: 2, bottom: 3, left: 4 }));
+        for bad in ["", "1,2,3", "1,2,3,4,5", "-1", "ten"] {
+            assert!(margins(bad).is_err(), "{bad:?}");
+        }
+    }
+
+    fn geometry(value: &str) -> Result<Option<Geometry>, String> {
+        parse(&["--geometry", value]).map(|config| config.geometry)
+    }
+
+    #[test]
+    fn geometry_has_a_size_a_position_or_both() {
+        let from_start = |length| Offset { from_end: false, length };
+        let from_end = |length| Offset { from_end: true, length };
+        assert_eq!(
+            geometry("400x100-0+20"),
+            Ok(Some(Geometry {
+                size: Some((Length::Pixels(400), Length::Pixels(100))),
+                position: Some((from_end(Length::Pixels(0)), from_start(Length::Pixels(20)))),
+            })),
+        );
+        assert_eq!(
+            geometry("25%x10%"),
+            Ok(Some(Geometry { size: Some((Length::Percent(25.0), Length::Percent(10.0))), position: None })),
+        );
+        assert_eq!(
+            geometry("+70%-5%"),
+            Ok(Some(Geometry {
+                size: None,
+                position: Some((from_start(Length::Percent(70.0)), from_end(Length::Percent(5.0)))),
+            })),
+        );
+        for bad in ["", "400", "400x", "x100", "101%x10%", "400x100+5", "400x100+5+a"] {
+            assert!(geometry(bad).is_err(), "{bad:?}");
+        }
+    }
+
+    fn overlap(value: &str) -> Result<Overlap, String> {
+        parse(&["--overlap", value]).map(|config| config.glyphs.digit_overlap)
+    }
+
+    #[test]
+    fn overlap_is_auto_or_a_fraction() {
+        assert_eq!(overlap("auto"), Ok(Overlap::Auto));
+        assert_eq!(overlap("0"), Ok(Overlap::Fixed(0.0)));
+        assert_eq!(overlap("0.25"), Ok(Overlap::Fixed(0.25)));
+        for bad in ["", "-0.1", "some", "NaN"] {
+            assert!(overlap(bad).is_err(), "{bad:?}");
+        }
+    }
+}
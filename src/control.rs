@@ -0,0 +1,346 @@
+//! A socket that other `dni-clock` commands use to hand things to the clock
+//! that's already running, rather than opening a window of their own.
+//!
+//! It's a Unix socket, so this only works on Unix; elsewhere every command just
+//! acts on its own. Each connection sends one request as a line of text, and
+//! gets back `ok` or `error: ...`, and after `ok`, whatever else the request
+//! asked for. Connections are read on a thread of their own, so a slow one
+//! can't hold up the clock, and the clock answers each request once it's done
+//! it.
+
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    path::PathBuf,
+    time::Duration,
+};
+
+#[cfg(unix)]
+use std::{
+    os::unix::net::{UnixListener, UnixStream},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+use crate::{
+    colors::{self, Color},
+    time_source::ClockChange,
+    timer::{self, TimerOptions},
+    timing,
+};
+
+/// How long to wait on the other end before giving up on it.
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long to wait for the clock to do what it's asked, which can take a
+/// while for something like switching profiles (with new fonts to load).
+const ANSWER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a request can be, which is far longer than any needs to be.
+const MAX_LINE: u64 = 4096;
+
+/// Something for the running clock to do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Request {
+    Timer(TimerOptions),
+    /// Turn high contrast mode on or off.
+    HighContrast(bool),
+    /// Swap light and dark.
+    Invert,
+    /// Draw in the first color, on the second.
+    Colors(Color, Color),
+    /// Send back how long things have been taking (see [`timing::report`]).
+    Stats,
+    /// Tell the time right away: out loud if `say`, and in a banner on the
+    /// clock if `show`.
+    Announce { say: bool, show: bool },
+    /// Move the time, if the clock's fake.
+    FakeClock(ClockChange),
+    /// Show or hide the seconds, or switch them (`None`).
+    Seconds(Option<bool>),
+    /// Switch over to a profile, by name.
+    Profile(String),
+}
+
+impl Request {
+    /// The request as a line of text. Fails if it has a name with a line
+    /// break in it, which would split it in two.
+    fn to_line(&self) -> Result<String, String> {
+        if let Self::Timer(TimerOptions { name, .. }) | Self::Profile(name) = self {
+            if name.contains(['\n', '\r']) {
+                return Err(format!("names can't have line breaks in them, like `{}` does", name.escape_debug()));
+            }
+        }
+        Ok(match self {
+            Self::Timer(options) => {
+                let kind = if options.t_minus { "t-minus" } else { "timer" };
+                format!("{kind} {} {}\n", options.duration.as_secs(), options.name)
+            }
+            Self::HighContrast(on) => format!("high-contrast {}\n", if *on { "on" } else { "off" }),
+            Self::Invert => "invert\n".to_owned(),
+            Self::Colors(fg, bg) => format!("colors #{fg:06x} #{bg:06x}\n"),
+            Self::Stats => "stats\n".to_owned(),
+            Self::Announce { say, show } => {
+                format!("announce{}{}\n", if *say { " say" } else { "" }, if *show { " show" } else { "" })
+            }
+            Self::FakeClock(change) => format!("fake-clock {}\n", change.to_words()),
+            Self::Seconds(on) => match on {
+                Some(on) => format!("seconds {}\n", if *on { "on" } else { "off" }),
+                None => "seconds\n".to_owned(),
+            },
+            Self::Profile(name) => format!("profile {name}\n"),
+        })
+    }
+
+    fn parse(line: &str) -> Result<Self, String> {
+        // Only the line break's taken off, so names come through as they were
+        let line = line.strip_suffix('\n').unwrap_or(line);
+        let mut words = line.splitn(3, ' ');
+        match words.next() {
+            Some(kind @ ("timer" | "t-minus")) => {
+                let seconds = words.next().and_then(|s| s.parse().ok()).ok_or("bad timer duration")?;
+                let duration = timer::check_duration(Duration::from_secs(seconds))?;
+                let name = words.next().unwrap_or_default().to_owned();
+                let t_minus = kind == "t-minus";
+                Ok(Self::Timer(TimerOptions { duration, name, t_minus }))
+            }
+            Some("high-contrast") => match words.next() {
+                Some("on") => Ok(Self::HighContrast(true)),
+                Some("off") => Ok(Self::HighContrast(false)),
+                _ => Err("expected `on` or `off`".to_owned()),
+            },
+            Some("invert") => Ok(Self::Invert),
+            Some("seconds") => match words.next() {
+                Some("on") => Ok(Self::Seconds(Some(true))),
+                Some("off") => Ok(Self::Seconds(Some(false))),
+                None => Ok(Self::Seconds(None)),
+                _ => Err("expected `on`, `off`, or nothing".to_owned()),
+            },
+            Some("profile") => {
+                let name = words.collect::<Vec<_>>().join(" ");
+                if name.is_empty() {
+                    return Err("expected a profile name".to_owned());
+                }
+                Ok(Self::Profile(name))
+            }
+            Some("stats") => Ok(Self::Stats),
+            Some("announce") => {
+                let (mut say, mut show) = (false, false);
+                for word in words.flat_map(|words| words.split(' ')) {
+                    match word {
+                        "say" => say = true,
+                        "show" => show = true,
+                        _ => return Err(format!("expected `say` or `show`, not `{word}`")),
+                    }
+                }
+                Ok(Self::Announce { say, show })
+            }
+            Some("fake-clock") => {
+                let kind = words.next().ok_or("expected a change")?;
+                ClockChange::parse(kind, words.next()).map(Self::FakeClock)
+            }
+            Some("colors") => {
+                let mut color = || colors::parse(words.next().ok_or("expected two colors")?);
+                Ok(Self::Colors(color()?, color()?))
+            }
+            _ => Err(format!("unknown request `{line}`")),
+        }
+    }
+}
+
+/// Where the socket goes: somewhere private to the user, if there is one, or
+/// else somewhere shared, under a name that's the user's own.
+#[cfg(unix)]
+fn socket_path() -> PathBuf {
+    match dirs::runtime_dir() {
+        Some(dir) => dir.join("dni-clock.sock"),
+        None => {
+            // SAFETY: `getuid` can't fail, and has nothing to go wrong with
+            let uid = unsafe { libc::getuid() };
+            std::env::temp_dir().join(format!("dni-clock-{uid}.sock"))
+        }
+    }
+}
+
+/// Sends `request` to the running clock, and returns whatever it sent back
+/// besides `ok` (usually nothing). Fails if there isn't one, or with
+/// [`io::ErrorKind::Other`] if it couldn't do what was asked.
+#[cfg(unix)]
+pub fn send(request: &Request) -> io::Result<String> {
+    let line = request.to_line().map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let mut stream = UnixStream::connect(socket_path())?;
+    stream.set_read_timeout(Some(TIMEOUT + ANSWER_TIMEOUT))?;
+    stream.write_all(line.as_bytes())?;
+    let mut reader = BufReader::new(stream);
+    let mut reply = String::new();
+    reader.read_line(&mut reply)?;
+    match reply.trim_end() {
+        "ok" => {
+            let mut rest = String::new();
+            reader.read_to_string(&mut rest)?;
+            Ok(rest)
+        }
+        error => Err(io::Error::other(error.strip_prefix("error: ").unwrap_or(error).to_owned())),
+    }
+}
+
+#[cfg(not(unix))]
+pub fn send(_request: &Request) -> io::Result<String> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// Where to send the answer to a request, once the clock's done it (or
+/// couldn't).
+pub struct Answer {
+    #[cfg(unix)]
+    sender: Sender<Result<(), String>>,
+}
+
+impl Answer {
+    /// Tells whoever asked that it's done, or else why not.
+    pub fn send(self, result: Result<(), String>) {
+        // If they've stopped waiting, there's nobody to tell
+        #[cfg(unix)]
+        let _ = self.sender.send(result);
+        #[cfg(not(unix))]
+        let _ = result;
+    }
+}
+
+/// The running clock's end of the socket.
+#[cfg(unix)]
+pub struct ControlServer {
+    requests: Receiver<(Request, Answer)>,
+    path: PathBuf,
+}
+
+#[cfg(unix)]
+impl ControlServer {
+    /// Starts listening, unless another clock already is.
+    pub fn start() -> Option<Self> {
+        let path = socket_path();
+        // A socket that's there but that nothing answers was left behind by a
+        // clock that didn't get to clean up
+        if path.exists() {
+            if UnixStream::connect(&path).is_ok() {
+                return None;
+            }
+            let _ = std::fs::remove_file(&path);
+        }
+        let listener = UnixListener::bind(&path)
+            .map_err(|e| eprintln!("warning: couldn't open the control socket {}: {e}", path.display()))
+            .ok()?;
+        let (sender, requests) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let handled = stream.and_then(|stream| handle(stream, &sender));
+                match handled {
+                    Ok(true) => {}
+                    // The clock's stopped listening
+                    Ok(false) => break,
+                    Err(e) => eprintln!("warning: trouble on the control socket: {e}"),
+                }
+            }
+        });
+        Some(Self { requests, path })
+    }
+
+    /// Whatever's been asked since the last call, each with where to send
+    /// the answer. Doesn't block.
+    pub fn poll(&mut self) -> Vec<(Request, Answer)> {
+        self.requests.try_iter().collect()
+    }
+}
+
+/// Reads a request off `stream`, hands it to the clock with `sender`, and
+/// answers it once the clock has. Returns false if the clock's stopped
+/// listening.
+#[cfg(unix)]
+fn handle(stream: UnixStream, sender: &Sender<(Request, Answer)>) -> io::Result<bool> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    let mut line = String::new();
+    BufReader::new((&stream).take(MAX_LINE)).read_line(&mut line)?;
+    let request = if line.ends_with('\n') {
+        Request::parse(&line)
+    } else {
+        Err(format!("expected a line of at most {MAX_LINE} bytes"))
+    };
+    let reply = match request {
+        // The numbers are all in atomics, so there's no need to bother the
+        // main loop for them
+        Ok(Request::Stats) => format!("ok\n{}", timing::report()),
+        Ok(request) => {
+            let (answer, answered) = mpsc::channel();
+            if sender.send((request, Answer { sender: answer })).is_err() {
+                return Ok(false);
+            }
+            match answered.recv_timeout(ANSWER_TIMEOUT) {
+                Ok(Ok(())) => "ok\n".to_owned(),
+                Ok(Err(e)) => format!("error: {e}\n"),
+                Err(_) => "error: the clock didn't get to it in time\n".to_owned(),
+            }
+        }
+        Err(e) => format!("error: {e}\n"),
+    };
+    (&stream).write_all(reply.as_bytes())?;
+    Ok(true)
+}
+
+#[cfg(unix)]
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(not(unix))]
+pub struct ControlServer;
+
+#[cfg(not(unix))]
+impl ControlServer {
+    pub fn start() -> Option<Self> {
+        None
+    }
+
+    pub fn poll(&mut self) -> Vec<(Request, Answer)> {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(request: Request) {
+        let line = request.to_line().unwrap();
+        assert_eq!(Request::parse(&line), Ok(request), "{line:?}");
+    }
+
+    #[test]
+    fn requests_come_through_as_they_were_sent() {
+        let tea = |name: &str, t_minus| {
+            Request::Timer(TimerOptions { duration: Duration::from_secs(300), name: name.to_owned(), t_minus })
+        };
+        round_trip(tea("tea", false));
+        round_trip(tea("  two  words ", true));
+        round_trip(tea("", false));
+        round_trip(Request::HighContrast(true));
+        round_trip(Request::HighContrast(false));
+        round_trip(Request::Invert);
+        round_trip(Request::Colors(0xff8800, 0x000000));
+        round_trip(Request::Stats);
+        round_trip(Request::Announce { say: false, show: false });
+        round_trip(Request::Announce { say: true, show: true });
+        round_trip(Request::FakeClock(ClockChange::Advance(Duration::from_secs(90))));
+        round_trip(Request::FakeClock(ClockChange::Pause));
+        round_trip(Request::Seconds(Some(false)));
+        round_trip(Request::Seconds(None));
+        round_trip(Request::Profile("night stand".to_owned()));
+    }
+
+    #[test]
+    fn names_cant_break_the_line() {
+        let timer = TimerOptions { duration: Duration::from_secs(300), name: "tea\ninvert".to_owned(), t_minus: false };
+        assert!(Request::Timer(timer).to_line().is_err());
+        assert!(Request::Profile("night\r\nstand".to_owned()).to_line().is_err());
+    }
+}
@@ -0,0 +1,53 @@
+//! Hiding the mouse cursor when it's left sitting on top of the clock.
+
+use std::time::{Duration, Instant};
+
+use minifb::{MouseMode, Window};
+
+/// Hides the cursor after it's been still over the window for a while, and
+/// shows it again as soon as it moves.
+pub struct CursorHider {
+    timeout: Duration,
+    /// Where the cursor was last seen over the window, and when it got there.
+    last_move: Option<((f32, f32), Instant)>,
+    hidden: bool,
+}
+
+impl CursorHider {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            last_move: None,
+            hidden: false,
+        }
+    }
+
+    /// Call once per frame.
+    pub fn update(&mut self, window: &mut Window) {
+        let Some(pos) = window.get_unscaled_mouse_pos(MouseMode::Discard) else {
+            // Not over the window, so our cursor settings don't matter; just
+            // make sure it comes back visible next time
+            self.last_move = None;
+            self.set_hidden(window, false);
+            return;
+        };
+        match self.last_move {
+            Some((last_pos, since)) if last_pos == pos => {
+                if since.elapsed() >= self.timeout {
+                    self.set_hidden(window, true);
+                }
+            }
+            _ => {
+                self.last_move = Some((pos, Instant::now()));
+                self.set_hidden(window, false);
+            }
+        }
+    }
+
+    fn set_hidden(&mut self, window: &mut Window, hidden: bool) {
+        if self.hidden != hidden {
+            self.hidden = hidden;
+            window.set_cursor_visibility(!hidden);
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! `--demo`: the clock showing off, for meetups and for recording with
+//! `--pipe-frames`. Every few seconds it moves on to another look: other
+//! colors, the D'ni time, world clocks, complications (the rings being the
+//! nearest thing to an analog face), high contrast, and other sizes.
+//!
+//! Every scene is drawn in the middle of a frame big enough for the biggest of
+//! them, so the window doesn't jump around and piped video keeps one size.
+
+use std::time::{Duration, Instant};
+
+use crate::{
+    colors::{self, Color},
+    complication,
+    config::Config,
+    frame::{self, Centered},
+    glyphs::GlyphBuffer,
+};
+
+/// How long each scene is up.
+const SCENE_FOR: Duration = Duration::from_secs(6);
+
+/// One look for the clock, changed from however it was started.
+struct Scene {
+    fg: Color,
+    bg: Color,
+    /// Relative to the scale the clock would otherwise be.
+    scale: f32,
+    dni_time: bool,
+    zones: &'static [&'static str],
+    complications: &'static [&'static str],
+    high_contrast: bool,
+}
+
+impl Scene {
+    const PLAIN: Self = Self {
+        fg: colors::FG,
+        bg: colors::BG,
+        scale: 1.0,
+        dni_time: false,
+        zones: &[],
+        complications: &[],
+        high_contrast: false,
+    };
+}
+
+const SCENES: [Scene; 6] = [
+    Scene::PLAIN,
+    Scene { fg: colors::from_u8_rgb(0xff, 0xcc, 0x88), bg: colors::from_u8_rgb(0x20, 0x10, 0x08), dni_time: true, ..Scene::PLAIN },
+    Scene {
+        fg: colors::from_u8_rgb(0x9c, 0xf0, 0xd0),
+        bg: colors::from_u8_rgb(0x08, 0x18, 0x28),
+        scale: 0.6,
+        zones: &["tokyo", "london", "cavern"],
+        ..Scene::PLAIN
+    },
+    Scene {
+        fg: colors::from_u8_rgb(0x20, 0x20, 0x20),
+        bg: colors::from_u8_rgb(0xf4, 0xf0, 0xe8),
+        complications: &["ring", "ring=hour", "date", "moon"],
+        ..Scene::PLAIN
+    },
+    Scene { high_contrast: true, scale: 1.4, ..Scene::PLAIN },
+    Scene { fg: colors::T_PLUS, scale: 1.8, ..Scene::PLAIN },
+];
+
+pub struct Demo {
+    /// The config as it was started, which each scene changes.
+    base: Config,
+    started: Instant,
+    /// Which scene is up, or `None` before the first.
+    scene: Option<usize>,
+    /// Every frame, at the size of the biggest scene.
+    centered: Centered,
+}
+
+impl Demo {
+    /// A demo of `config`, at around `scale`.
+    pub fn new(config: &Config, scale: f32) -> Self {
+        let mut demo = Self {
+            base: config.clone(),
+            started: Instant::now(),
+            scene: None,
+            centered: Centered::default(),
+        };
+        demo.fit_scale(scale);
+        demo
+    }
+
+    /// Makes room for every scene at around `scale`.
+    pub fn fit_scale(&mut self, scale: f32) {
+        self.centered.size = SCENES.iter().fold((0, 0), |(width, height), scene| {
+            let config = self.scene_config(scene);
+            let (w, h) = frame::window_size(config.glyphs.clamp_scale(scale * scene.scale), &config);
+            (width.max(w), height.max(h))
+        });
+    }
+
+    /// Switches `config` over to the scene that's due at `now`, if it's time
+    /// for another, and returns how much bigger or smaller it's drawn.
+    pub fn update(&mut self, now: Instant, config: &mut Config) -> Option<f32> {
+        let due = (now.saturating_duration_since(self.started).as_secs() / SCENE_FOR.as_secs()) as usize % SCENES.len();
+        if self.scene == Some(due) {
+            return None;
+        }
+        self.scene = Some(due);
+        let scene = &SCENES[due];
+        *config = self.scene_config(scene);
+        Some(scene.scale)
+    }
+
+    fn scene_config(&self, scene: &Scene) -> Config {
+        let mut config = self.base.clone();
+        (config.fg, config.bg) = (scene.fg, scene.bg);
+        config.show_dni_time |= scene.dni_time;
+        config.glyphs.high_contrast |= scene.high_contrast;
+        config.zones.extend(scene.zones.iter().map(|zone| zone.parse().expect("demo zones are all known")));
+        for spec in scene.complications {
+            let complication = complication::from_spec(spec, &config).expect("demo complications are all valid");
+            config.complications.push(complication);
+        }
+        config
+    }
+
+    /// `frame`, in the middle of a frame the demo's size, on its background.
+    pub fn fit(&mut self, frame: &GlyphBuffer) -> &GlyphBuffer {
+        // It only ever grows, in case the window's been moved to a bigger
+        // monitor since
+        self.centered.fit(frame)
+    }
+}
@@ -0,0 +1,166 @@
+//! `--discord`: the D'ni time in the user's Discord status (what Discord calls
+//! rich presence), for the Myst community, much of which lives there.
+//!
+//! It talks to the Discord app running on the same machine, over its IPC
+//! socket (a named pipe on Windows), on a background thread: a handshake
+//! naming the Discord application it's showing as, then the activity every
+//! few minutes. Messages are a little-endian opcode and length, then JSON. If
+//! Discord isn't running, or goes away, it tries again next time.
+
+use std::{
+    io::{self, Read, Write},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    config::{Command, Config},
+    dni_time::DniTime,
+    time_source::TimeSource,
+    util::json_string,
+};
+
+/// How often the status is updated. A tahvo is about 14½ minutes, so it's
+/// never more than a few behind.
+const INTERVAL: Duration = Duration::from_secs(3 * 60);
+
+/// Opening a connection.
+const OP_HANDSHAKE: u32 = 0;
+/// A command, or the answer to one.
+const OP_FRAME: u32 = 1;
+
+/// Either end of the IPC socket.
+trait Pipe: Read + Write {}
+
+impl<T: Read + Write> Pipe for T {}
+
+/// Starts keeping the Discord status up to date, showing as the Discord
+/// application `client_id`.
+pub fn start(client_id: String, config: &Config) {
+    let (time_source, mode) = (config.time_source.clone(), mode(config));
+    thread::spawn(move || {
+        let mut connection = None;
+        let mut nonce = 0_u64;
+        loop {
+            let result = (|| -> io::Result<()> {
+                let pipe = match &mut connection {
+                    Some(pipe) => pipe,
+                    None => connection.insert(connect(&client_id)?),
+                };
+                nonce += 1;
+                send(pipe, OP_FRAME, &activity(&time_source, mode, nonce))?;
+                receive(pipe)?;
+                Ok(())
+            })();
+            if let Err(e) = result {
+                // It's not running, most likely, which is nothing to warn
+                // about every few minutes
+                if connection.is_some() {
+                    eprintln!("warning: lost Discord: {e}");
+                }
+                connection = None;
+            }
+            thread::sleep(INTERVAL);
+        }
+    });
+}
+
+/// What the clock's showing, for under the time.
+fn mode(config: &Config) -> &'static str {
+    match config.command {
+        Command::Quiz => "practicing numerals",
+        _ if config.show_dni_time => "surface and cavern time",
+        _ if !config.zones.is_empty() => "cavern time, around the world",
+        _ => "cavern time",
+    }
+}
+
+/// The `SET_ACTIVITY` command, with the D'ni time as it is now.
+fn activity(time_source: &TimeSource, mode: &str, nonce: u64) -> String {
+    let dni = DniTime::from_surface(&time_source.now());
+    let details = format!("{} gartahvo, {} tahvo", dni.gartahvo, dni.tahvo);
+    format!(
+        r#"{{"cmd":"SET_ACTIVITY","args":{{"pid":{},"activity":{{"details":{},"state":{}}}}},"nonce":"{nonce}"}}"#,
+        std::process::id(),
+        json_string(&details),
+        json_string(mode),
+    )
+}
+
+/// Connects to Discord and says hello.
+fn connect(client_id: &str) -> io::Result<Box<dyn Pipe + Send>> {
+    let mut pipe = open()?;
+    send(&mut pipe, OP_HANDSHAKE, &format!(r#"{{"v":1,"client_id":{}}}"#, json_string(client_id)))?;
+    // It answers with a `READY` event, or closes the connection if it didn't
+    // like the client ID
+    receive(&mut pipe)?;
+    Ok(pipe)
+}
+
+fn send(pipe: &mut dyn Pipe, op: u32, json: &str) -> io::Result<()> {
+    let mut message = Vec::with_capacity(8 + json.len());
+    message.extend_from_slice(&op.to_le_bytes());
+    message.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    message.extend_from_slice(json.as_bytes());
+    pipe.write_all(&message)
+}
+
+/// Reads a message, failing if it's an error.
+fn receive(pipe: &mut dyn Pipe) -> io::Result<String> {
+    let mut header = [0; 8];
+    pipe.read_exact(&mut header)?;
+    let [op, len] = [&header[..4], &header[4..]].map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()));
+    let mut json = vec![0; len as usize];
+    pipe.read_exact(&mut json)?;
+    let json = String::from_utf8_lossy(&json).into_owned();
+    if op != OP_FRAME || json.contains(r#""evt":"ERROR""#) {
+        return Err(io::Error::other(json));
+    }
+    Ok(json)
+}
+
+/// The first of Discord's sockets that's there. It keeps them in the runtime
+/// or temporary directory (or a sandboxed app's corner of it), numbered from
+/// 0 in case more than one copy is running.
+#[cfg(unix)]
+fn open() -> io::Result<Box<dyn Pipe + Send>> {
+    use std::{os::unix::net::UnixStream, path::PathBuf};
+
+    let dirs = ["XDG_RUNTIME_DIR", "TMPDIR", "TMP", "TEMP"]
+        .iter()
+        .filter_map(|var| std::env::var_os(var).map(PathBuf::from))
+        .chain([PathBuf::from("/tmp")]);
+    let mut last_error = io::Error::from(io::ErrorKind::NotFound);
+    for dir in dirs {
+        for sandbox in ["", "app/com.discordapp.Discord", "snap.discord"] {
+            for n in 0..10 {
+                match UnixStream::connect(dir.join(sandbox).join(format!("discord-ipc-{n}"))) {
+                    Ok(stream) => {
+                        stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+                        return Ok(Box::new(stream));
+                    }
+                    Err(e) => last_error = e,
+                }
+            }
+        }
+    }
+    Err(last_error)
+}
+
+#[cfg(windows)]
+fn open() -> io::Result<Box<dyn Pipe + Send>> {
+    let mut last_error = io::Error::from(io::ErrorKind::NotFound);
+    for n in 0..10 {
+        let path = format!(r"\\?\pipe\discord-ipc-{n}");
+        match std::fs::OpenOptions::new().read(true).write(true).open(path) {
+            Ok(pipe) => return Ok(Box::new(pipe)),
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn open() -> io::Result<Box<dyn Pipe + Send>> {
+    Err(io::ErrorKind::Unsupported.into())
+}
@@ -0,0 +1,127 @@
+//! Saving rendered glyphs between runs, so big scales don't have to be
+//! rasterized all over again every launch.
+//!
+//! Each combination of fonts and scale gets its own file in the user's cache
+//! directory. (Glyphs are cached as coverage, so the colors don't matter.) The
+//! file is just a header and then one record per glyph: what it is, its size
+//! (little-endian), and a byte of coverage per pixel. Anything that
+//! doesn't look right is ignored (and re-rendered), since it's only a cache.
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::{Hash, Hasher},
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    buf2d::Vec2d,
+    glyphs::{GlyphOptions, MaskBuffer, MaskView},
+    numerals::MAX_DIGITS,
+};
+
+const MAGIC: &[u8] = b"dni-clock glyphs v2\n";
+
+/// Which cached glyph a record is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entry {
+    /// A single digit (0-24 in D'ni, and maybe 25)
+    Digit(u8),
+    /// A two-digit numeral (00-59)
+    Numeral(u8),
+}
+
+impl Entry {
+    fn to_bytes(self) -> [u8; 2] {
+        match self {
+            Self::Digit(n) => [0, n],
+            Self::Numeral(n) => [1, n],
+        }
+    }
+
+    fn from_bytes([kind, n]: [u8; 2]) -> Option<Self> {
+        match kind {
+            0 if usize::from(n) < MAX_DIGITS => Some(Self::Digit(n)),
+            1 if n < 60 => Some(Self::Numeral(n)),
+            _ => None,
+        }
+    }
+}
+
+/// Where the glyphs rendered from these fonts at this scale (with these
+/// options) would be cached.
+///
+/// The fonts are hashed whole (see [`crate::fonts::Fonts::hash`]), so swapping
+/// one out can't pick up stale glyphs. (The hash isn't guaranteed to be stable
+/// across Rust versions, but the worst that does is miss the cache once.)
+pub fn path(fonts_hash: u64, scale: f32, options: &GlyphOptions) -> Option<PathBuf> {
+    let mut hasher = DefaultHasher::new();
+    fonts_hash.hash(&mut hasher);
+    scale.to_bits().hash(&mut hasher);
+    options.hash_into(&mut hasher);
+    let dir = dirs::cache_dir()?.join("dni-clock").join("glyphs");
+    Some(dir.join(format!("{:016x}.bin", hasher.finish())))
+}
+
+/// Reads all the glyphs in the cache file at `path`, or nothing if there isn't
+/// one or it's no good.
+pub fn load(path: &Path) -> Vec<(Entry, MaskBuffer)> {
+    let Ok(contents) = fs::read(path) else { return Vec::new() };
+    parse(&contents).unwrap_or_else(|_| {
+        eprintln!("warning: ignoring corrupt glyph cache {}", path.display());
+        Vec::new()
+    })
+}
+
+/// Writes `entries` to the cache file at `path`, warning (but carrying on) if
+/// that fails.
+pub fn save<'a>(path: &Path, entries: impl Iterator<Item = (Entry, MaskView<'a>)>) {
+    let mut contents = MAGIC.to_vec();
+    for (entry, glyph) in entries {
+        contents.extend_from_slice(&entry.to_bytes());
+        contents.extend_from_slice(&(glyph.width() as u32).to_le_bytes());
+        contents.extend_from_slice(&(glyph.height() as u32).to_le_bytes());
+        for y in 0..glyph.height() {
+            contents.extend_from_slice(glyph.row(y));
+        }
+    }
+    let result = path.parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|()| fs::write(path, contents));
+    if let Err(e) = result {
+        eprintln!("warning: couldn't save glyph cache {}: {e}", path.display());
+    }
+}
+
+fn parse(mut contents: &[u8]) -> io::Result<Vec<(Entry, MaskBuffer)>> {
+    fn read_u32(contents: &mut &[u8]) -> io::Result<u32> {
+        let mut bytes = [0; 4];
+        contents.read_exact(&mut bytes)?;
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    let bad = || io::Error::from(io::ErrorKind::InvalidData);
+    let mut magic = [0; MAGIC.len()];
+    contents.read_exact(&mut magic)?;
+    if magic != MAGIC {
+        return Err(bad());
+    }
+
+    let mut entries = Vec::new();
+    while !contents.is_empty() {
+        let mut entry = [0; 2];
+        contents.read_exact(&mut entry)?;
+        let entry = Entry::from_bytes(entry).ok_or_else(bad)?;
+        let width = read_u32(&mut contents)? as usize;
+        let height = read_u32(&mut contents)? as usize;
+        if contents.len() < width * height {
+            return Err(bad());
+        }
+        let mut pixels = vec![0; width * height];
+        contents.read_exact(&mut pixels)?;
+        let glyph = Vec2d::from_vec(pixels, width).ok_or_else(bad)?;
+        entries.push((entry, glyph));
+    }
+    Ok(entries)
+}
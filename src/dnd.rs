@@ -0,0 +1,183 @@
+//! Do not disturb (or focus assist, or whatever the platform calls it). While
+//! it's on, the clock holds back its chimes and alarms, and puts a dot in the
+//! corner instead, until the pointer comes to rest on the window.
+//!
+//! No platform has a proper way to ask, so it's read from wherever the system
+//! keeps it: GNOME's settings, the notification server's `Inhibited` property
+//! (KDE's has it), dunst, or mako on Linux; Notification Center's defaults, or
+//! the Focus database, on macOS; and the notification settings in the registry
+//! on Windows. If none of them say so, it's taken to be off.
+//!
+//! That means running commands, so it's looked up every little while on a
+//! thread of its own, with a limit on how long each gets to answer, and the
+//! clock goes by the last answer.
+
+use std::{
+    io::Read,
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config::Config,
+    glyphs::{GlyphBuffer, Glyphs},
+    shapes, synth,
+};
+
+/// How often to look again.
+const INTERVAL: Duration = Duration::from_secs(15);
+
+/// How long a command gets to answer, so one that hangs can't hold up the
+/// rest.
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Whether the user's asked not to be disturbed, kept up to date in the
+/// background until it's dropped.
+pub struct DoNotDisturb {
+    on: Arc<AtomicBool>,
+}
+
+impl DoNotDisturb {
+    /// Starts looking. It's taken to be off until the first answer's in.
+    pub fn watch() -> Self {
+        let on = Arc::new(AtomicBool::new(false));
+        let watching = Arc::downgrade(&on);
+        thread::spawn(move || {
+            while let Some(on) = watching.upgrade() {
+                on.store(platform_active(), Ordering::Relaxed);
+                drop(on);
+                thread::sleep(INTERVAL);
+            }
+        });
+        Self { on }
+    }
+
+    pub fn active(&self) -> bool {
+        self.on.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn platform_active() -> bool {
+    let gnome = || {
+        output("gsettings", &["get", "org.gnome.desktop.notifications", "show-banners"])
+            .is_some_and(|banners| banners == "false")
+    };
+    let inhibited = || {
+        output("busctl", &[
+            "--user",
+            "get-property",
+            "org.freedesktop.Notifications",
+            "/org/freedesktop/Notifications",
+            "org.freedesktop.Notifications",
+            "Inhibited",
+        ])
+        .is_some_and(|inhibited| inhibited == "b true")
+    };
+    let dunst = || output("dunstctl", &["is-paused"]).is_some_and(|paused| paused == "true");
+    let mako = || output("makoctl", &["mode"]).is_some_and(|modes| modes.lines().any(|mode| mode == "do-not-disturb"));
+    gnome() || inhibited() || dunst() || mako()
+}
+
+#[cfg(target_os = "macos")]
+fn platform_active() -> bool {
+    // Before Monterey
+    let old = || {
+        output("defaults", &["-currentHost", "read", "com.apple.notificationcenterui", "doNotDisturb"])
+            .is_some_and(|on| on == "1")
+    };
+    let focus = || {
+        dirs::home_dir()
+            .and_then(|home| std::fs::read_to_string(home.join("Library/DoNotDisturb/DB/Assertions.json")).ok())
+            .is_some_and(|assertions| focus_on(&assertions))
+    };
+    old() || focus()
+}
+
+/// Whether macOS's Focus database (the `Assertions.json` in it) has a mode
+/// on. It keeps a record of each one that is, under `storeAssertionRecords`,
+/// which is left there empty once they're all off again.
+#[cfg(any(target_os = "macos", test))]
+fn focus_on(assertions: &str) -> bool {
+    let Ok(assertions) = serde_json::from_str::<serde_json::Value>(assertions) else { return false };
+    assertions["data"].as_array().is_some_and(|data| {
+        data.iter().any(|entry| entry["storeAssertionRecords"].as_array().is_some_and(|records| !records.is_empty()))
+    })
+}
+
+#[cfg(windows)]
+fn platform_active() -> bool {
+    // Set to 0 when notifications are turned off entirely
+    output("reg", &[
+        "query",
+        r"HKCU\Software\Microsoft\Windows\CurrentVersion\Notifications\Settings",
+        "/v",
+        "NOC_GLOBAL_SETTING_TOASTS_ENABLED",
+    ])
+    .is_some_and(|value| value.split_whitespace().last() == Some("0x0"))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn platform_active() -> bool {
+    false
+}
+
+/// What `program` printed, trimmed, if it ran and succeeded in time.
+fn output(program: &str, args: &[&str]) -> Option<String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    let deadline = Instant::now() + TIMEOUT;
+    let status = loop {
+        if let Some(status) = child.try_wait().ok()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return None;
+        }
+        thread::sleep(Duration::from_millis(20));
+    };
+    let mut printed = Vec::new();
+    child.stdout.take()?.read_to_end(&mut printed).ok()?;
+    status.success().then(|| String::from_utf8_lossy(&printed).trim().to_owned())
+}
+
+/// Draws the dot saying something was held back, in the top right of `frame`.
+pub fn draw_held_back(frame: &mut GlyphBuffer, glyphs: &Glyphs, config: &Config) {
+    let diameter = (frame.height() / 10).max(4);
+    let margin = diameter / 2;
+    if frame.width() < diameter + margin || frame.height() < diameter + margin {
+        return;
+    }
+    let mut dot = shapes::arc(diameter, diameter as f32, 1.0);
+    if config.glyphs.high_contrast {
+        synth::threshold(&mut dot);
+    }
+    frame.tint_from(frame.width() - diameter - margin, margin, &dot, glyphs.fg());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn focus_is_on_with_a_mode_on_record() {
+        let on = r#"{"data":[{"storeAssertionRecords":[{"assertionDetails":{"assertionDetailsModeIdentifier":
+            "com.apple.donotdisturb.mode.default"}}]}],"header":{"version":1}}"#;
+        assert!(focus_on(on));
+        assert!(!focus_on(r#"{"data":[{"storeAssertionRecords":[]}],"header":{"version":1}}"#));
+        assert!(!focus_on(r#"{"data":[{}],"header":{"version":1}}"#));
+        assert!(!focus_on("not json"));
+    }
+}
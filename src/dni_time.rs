@@ -0,0 +1,345 @@
+//! Converting surface (Earth) time to native D'ni time.
+//!
+//! The D'ni divided their day (a yahr, about 30 hours) into 5 gartahvo, each
+//! gartahvo into 25 tahvo, each tahvo into 25 gorahn, and each gorahn into 25
+//! prorahn. Their year (a hahr) is the same length as ours, and is split into
+//! 10 vailee of 29 yahrtee each.
+//!
+//! The two calendars are lined up the way the DRC did it: the first moment of
+//! Leefo 1, 9647 DE was 10:35:18 UTC on April 21, 1998. From there, every
+//! hahr is exactly 365.25 days (a Julian year), rather than following our leap
+//! years: the D'ni had no leap yahrtee, so the quarter day is spread evenly
+//! over the whole hahr instead, by making each yahr a little longer. This
+//! means Leefo 1 wanders by up to a day against our calendar over each
+//! four-year cycle, and another day every century the Gregorian calendar skips
+//! a leap year, which is how the fan-canon correlation has it.
+//!
+//! Everything's counted in whole milliseconds and prorahntee, so there's no
+//! rounding to go wrong however far from the epoch it is.
+
+use std::{fmt, str::FromStr};
+
+use chrono::{DateTime, TimeZone};
+
+use crate::frame::Number;
+
+/// A moment in D'ni time, down to the prorahn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DniTime {
+    pub hahr: i64,
+    /// 0-9
+    pub vailee: u8,
+    /// 0-28 (the D'ni counted from 1, so add one for display)
+    pub yahr: u8,
+    /// 0-4
+    pub gartahvo: u8,
+    /// 0-24
+    pub tahvo: u8,
+    /// 0-24
+    pub gorahn: u8,
+    /// 0-24
+    pub prorahn: u8,
+}
+
+/// The vailee, in order.
+pub const VAILEE_NAMES: [&str; VAILEE_PER_HAHR as usize] = [
+    "Leefo", "Leebro", "Leesahn", "Leetar", "Leevot",
+    "Leevofo", "Leevobro", "Leevosahn", "Leevotar", "Leenovoo",
+];
+
+/// Length of a hahr, in milliseconds: a Julian year of 365.25 days.
+pub const HAHR_MILLIS: i64 = 36525 * 24 * 60 * 60 * 10;
+pub const VAILEE_PER_HAHR: i64 = 10;
+pub const YAHRTEE_PER_VAILEE: i64 = 29;
+pub const YAHRTEE_PER_HAHR: i64 = VAILEE_PER_HAHR * YAHRTEE_PER_VAILEE;
+pub const PRORAHNTEE_PER_YAHR: i64 = 5 * 25 * 25 * 25;
+pub const PRORAHNTEE_PER_HAHR: i64 = PRORAHNTEE_PER_YAHR * YAHRTEE_PER_HAHR;
+
+/// The hahr that started at [`EPOCH_UNIX_MILLIS`].
+pub const EPOCH_HAHR: i64 = 9647;
+
+/// When Leefo 1, 9647 DE began (10:35:18 UTC, April 21, 1998), in
+/// milliseconds since the Unix epoch.
+pub const EPOCH_UNIX_MILLIS: i64 = 893_154_918_000;
+
+impl DniTime {
+    /// Converts a surface time.
+    pub fn from_surface<Tz: TimeZone>(time: &DateTime<Tz>) -> Self {
+        let since_epoch = time.timestamp_millis() - EPOCH_UNIX_MILLIS;
+        // Rounded down (even before the epoch), so it's whichever prorahn
+        // `time` falls in. It'd overflow an `i64` a few hundred thousand years
+        // out, so it's worked out wider
+        let prorahntee = i128::from(since_epoch) * i128::from(PRORAHNTEE_PER_HAHR);
+        let prorahntee = prorahntee.div_euclid(i128::from(HAHR_MILLIS)) as i64;
+
+        let hahrtee = prorahntee.div_euclid(PRORAHNTEE_PER_HAHR);
+        let into_hahr = prorahntee.rem_euclid(PRORAHNTEE_PER_HAHR);
+        let yahrtee = into_hahr / PRORAHNTEE_PER_YAHR;
+        let prorahntee = into_hahr % PRORAHNTEE_PER_YAHR;
+
+        Self {
+            hahr: EPOCH_HAHR + hahrtee,
+            vailee: (yahrtee / YAHRTEE_PER_VAILEE) as u8,
+            yahr: (yahrtee % YAHRTEE_PER_VAILEE) as u8,
+            gartahvo: (prorahntee / (25 * 25 * 25)) as u8,
+            tahvo: (prorahntee / (25 * 25) % 25) as u8,
+            gorahn: (prorahntee / 25 % 25) as u8,
+            prorahn: (prorahntee % 25) as u8,
+        }
+    }
+
+    /// When this prorahn began, in milliseconds since the Unix epoch: the
+    /// first millisecond that [`Self::from_surface`] makes it, since it
+    /// doesn't begin on a whole one.
+    pub fn start_unix_millis(&self) -> i64 {
+        let yahrtee = i64::from(self.vailee) * YAHRTEE_PER_VAILEE + i64::from(self.yahr);
+        let into_yahr = ((i64::from(self.gartahvo) * 25 + i64::from(self.tahvo)) * 25 + i64::from(self.gorahn)) * 25
+            + i64::from(self.prorahn);
+        let prorahntee = i128::from(self.hahr - EPOCH_HAHR) * i128::from(PRORAHNTEE_PER_HAHR)
+            + i128::from(yahrtee * PRORAHNTEE_PER_YAHR + into_yahr);
+        // Rounded up, the other way from `from_surface`
+        let per_hahr = i128::from(PRORAHNTEE_PER_HAHR);
+        let millis = (prorahntee * i128::from(HAHR_MILLIS) + per_hahr - 1).div_euclid(per_hahr);
+        EPOCH_UNIX_MILLIS + millis as i64
+    }
+
+    /// The date, written out like `Leevot 27, 9675 DE`.
+    pub fn date(&self) -> String {
+        format!("{} {}, {} DE", VAILEE_NAMES[usize::from(self.vailee)], self.yahr + 1, self.hahr)
+    }
+
+    /// How far through the current pahrtahvo (a fifth of a gartahvo, or 5
+    /// tahvo) it is, from 0.0 to 1.0.
+    pub fn pahrtahvo_progress(&self) -> f32 {
+        const PRORAHNTEE_PER_PAHRTAHVO: u32 = 5 * 25 * 25;
+        let prorahntee = u32::from(self.tahvo % 5) * 25 * 25
+            + u32::from(self.gorahn) * 25
+            + u32::from(self.prorahn);
+        prorahntee as f32 / PRORAHNTEE_PER_PAHRTAHVO as f32
+    }
+}
+
+/// A length of time in D'ni units, each one counted up to the next (so there
+/// are at most 24 tahvotee, say, before they make a gartahvo).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DniDuration {
+    pub yahrtee: i64,
+    /// 0-4
+    pub gartahvotee: u8,
+    /// 0-24
+    pub tahvotee: u8,
+    /// 0-24
+    pub gorahntee: u8,
+    /// 0-24
+    pub prorahntee: u8,
+}
+
+impl DniDuration {
+    /// Converts a surface duration, rounding down to the prorahn. Negative
+    /// durations come out as nothing at all.
+    pub fn from_duration(duration: chrono::Duration) -> Self {
+        let millis = duration.num_milliseconds().max(0);
+        let prorahntee = i128::from(millis) * i128::from(PRORAHNTEE_PER_HAHR) / i128::from(HAHR_MILLIS);
+        let prorahntee = prorahntee as i64;
+        let into_yahr = prorahntee % PRORAHNTEE_PER_YAHR;
+        Self {
+            yahrtee: prorahntee / PRORAHNTEE_PER_YAHR,
+            gartahvotee: (into_yahr / (25 * 25 * 25)) as u8,
+            tahvotee: (into_yahr / (25 * 25) % 25) as u8,
+            gorahntee: (into_yahr / 25 % 25) as u8,
+            prorahntee: (into_yahr % 25) as u8,
+        }
+    }
+
+    /// All of it, in gartahvotee (up to 24, the most a digit can show, even if
+    /// it's longer than that).
+    pub fn total_gartahvotee(&self) -> u8 {
+        (self.yahrtee * 5 + i64::from(self.gartahvotee)).min(24) as u8
+    }
+
+    /// The gartahvotee, tahvotee, and gorahntee, as numerals to show like a
+    /// clock does. Whole yahrtee are counted as gartahvotee.
+    pub fn numerals(&self) -> [Number; 3] {
+        [
+            Number::OneDigit(self.total_gartahvotee()),
+            Number::OneDigit(self.tahvotee),
+            Number::OneDigit(self.gorahntee),
+        ]
+    }
+}
+
+impl fmt::Display for DniDuration {
+    /// Spelled out, biggest unit first, leaving out the ones there are none
+    /// of, like "2 tahvotee 13 gorahntee".
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let units = [
+            (self.yahrtee, "yahr"),
+            (self.gartahvotee.into(), "gartahvo"),
+            (self.tahvotee.into(), "tahvo"),
+            (self.gorahntee.into(), "gorahn"),
+            (self.prorahntee.into(), "prorahn"),
+        ];
+        let mut any = false;
+        for (n, unit) in units.into_iter().filter(|&(n, _)| n > 0) {
+            if any {
+                f.write_str(" ")?;
+            }
+            // D'ni plurals end in "-tee"
+            let plural = if n == 1 { "" } else { "tee" };
+            write!(f, "{n} {unit}{plural}")?;
+            any = true;
+        }
+        if !any {
+            f.write_str("0 prorahntee")?;
+        }
+        Ok(())
+    }
+}
+
+/// Which units to show lengths of time (like what's left on a timer) in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DurationUnits {
+    /// Hours, minutes, and seconds.
+    #[default]
+    Surface,
+    /// Gartahvotee, tahvotee, and gorahntee.
+    Dni,
+}
+
+impl FromStr for DurationUnits {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "surface" => Ok(Self::Surface),
+            "dni" => Ok(Self::Dni),
+            _ => Err("expected `surface` or `dni`".to_owned()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn at(millis: i64) -> DniTime {
+        DniTime::from_surface(&Utc.timestamp_millis(millis))
+    }
+
+    fn dni(hahr: i64, vailee: u8, yahr: u8, [gartahvo, tahvo, gorahn, prorahn]: [u8; 4]) -> DniTime {
+        DniTime { hahr, vailee, yahr, gartahvo, tahvo, gorahn, prorahn }
+    }
+
+    /// The first and last moments of the D'ni time.
+    const START: [u8; 4] = [0, 0, 0, 0];
+    const END: [u8; 4] = [4, 24, 24, 24];
+
+    /// A yahr, in milliseconds (which isn't a whole number of them).
+    const YAHR_MILLIS: f64 = HAHR_MILLIS as f64 / YAHRTEE_PER_HAHR as f64;
+
+    #[test]
+    fn epoch_is_where_the_drc_put_it() {
+        let epoch = Utc.ymd(1998, 4, 21).and_hms(10, 35, 18);
+        assert_eq!(epoch.timestamp_millis(), EPOCH_UNIX_MILLIS);
+        assert_eq!(HAHR_MILLIS, (365.25 * 24.0 * 60.0 * 60.0 * 1000.0) as i64);
+        assert_eq!(DniTime::from_surface(&epoch), dni(EPOCH_HAHR, 0, 0, START));
+        assert_eq!(DniTime::from_surface(&epoch).date(), "Leefo 1, 9647 DE");
+        assert_eq!(dni(EPOCH_HAHR, 0, 0, START).start_unix_millis(), EPOCH_UNIX_MILLIS);
+    }
+
+    #[test]
+    fn just_before_the_epoch_is_the_end_of_the_last_hahr() {
+        assert_eq!(at(EPOCH_UNIX_MILLIS - 1), dni(EPOCH_HAHR - 1, 9, 28, END));
+        assert_eq!(at(EPOCH_UNIX_MILLIS - 1).date(), "Leenovoo 29, 9646 DE");
+    }
+
+    #[test]
+    fn hahrtee_are_julian_years_both_ways() {
+        for hahrtee in [-10_000, -1, 1, 4, 100, 400, 10_000] {
+            let start = EPOCH_UNIX_MILLIS + hahrtee * HAHR_MILLIS;
+            assert_eq!(at(start), dni(EPOCH_HAHR + hahrtee, 0, 0, START), "{hahrtee} hahrtee out");
+            assert_eq!(at(start - 1), dni(EPOCH_HAHR + hahrtee - 1, 9, 28, END), "{hahrtee} hahrtee out");
+            assert_eq!(dni(EPOCH_HAHR + hahrtee, 0, 0, START).start_unix_millis(), start);
+        }
+    }
+
+    #[test]
+    fn leefo_wanders_over_the_four_year_cycle() {
+        // The quarter day's spread over the hahr, so each one starts 6 hours
+        // later in the day than the last, and a leap day (like February 29,
+        // 2000) takes a day back
+        let starts: Vec<_> = (0..5)
+            .map(|hahrtee| Utc.timestamp_millis(EPOCH_UNIX_MILLIS + hahrtee * HAHR_MILLIS))
+            .map(|start| start.format("%m-%d %H:%M:%S").to_string())
+            .collect();
+        assert_eq!(starts, ["04-21 10:35:18", "04-21 16:35:18", "04-20 22:35:18", "04-21 04:35:18", "04-21 10:35:18"]);
+    }
+
+    #[test]
+    fn vailee_and_yahrtee_turn_over_on_time() {
+        for hahr in [EPOCH_HAHR - 3000, EPOCH_HAHR - 1, EPOCH_HAHR, EPOCH_HAHR + 29, EPOCH_HAHR + 100_000] {
+            for vailee in 0..VAILEE_PER_HAHR as u8 {
+                for yahr in [0, 1, 14, 28] {
+                    let start = dni(hahr, vailee, yahr, START).start_unix_millis();
+                    assert_eq!(at(start), dni(hahr, vailee, yahr, START));
+                    let before = match (vailee, yahr) {
+                        (0, 0) => dni(hahr - 1, 9, 28, END),
+                        (_, 0) => dni(hahr, vailee - 1, 28, END),
+                        _ => dni(hahr, vailee, yahr - 1, END),
+                    };
+                    assert_eq!(at(start - 1), before);
+                    // Whole yahrtee from the start of the hahr, to within a
+                    // millisecond of rounding
+                    let hahr_start = EPOCH_UNIX_MILLIS + (hahr - EPOCH_HAHR) * HAHR_MILLIS;
+                    let yahrtee = f64::from(vailee) * YAHRTEE_PER_VAILEE as f64 + f64::from(yahr);
+                    assert!(((start - hahr_start) as f64 - yahrtee * YAHR_MILLIS).abs() <= 1.0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn far_from_the_epoch() {
+        // As far as chrono goes, both ways: about 262,000 years
+        let first = Utc.ymd(-262_000, 1, 1).and_hms(0, 0, 0);
+        let last = Utc.ymd(262_000, 12, 31).and_hms(23, 59, 59);
+        for time in [first, last] {
+            let dni = DniTime::from_surface(&time);
+            let hahrtee = (time.timestamp_millis() - EPOCH_UNIX_MILLIS).div_euclid(HAHR_MILLIS);
+            assert_eq!(dni.hahr, EPOCH_HAHR + hahrtee);
+            assert_eq!(DniTime::from_surface(&Utc.timestamp_millis(dni.start_unix_millis())), dni);
+        }
+    }
+
+    #[test]
+    fn round_trips_through_the_start_of_each_prorahn() {
+        // A prorahn is a little under 1.4 seconds
+        let prorahn_millis = HAHR_MILLIS / PRORAHNTEE_PER_HAHR + 1;
+        let mut rng = fastrand::Rng::with_seed(1998);
+        let range = Utc.ymd(-250_000, 1, 1).and_hms(0, 0, 0).timestamp_millis()
+            ..Utc.ymd(250_000, 1, 1).and_hms(0, 0, 0).timestamp_millis();
+        let near_epoch = EPOCH_UNIX_MILLIS - 100 * HAHR_MILLIS..EPOCH_UNIX_MILLIS + 100 * HAHR_MILLIS;
+        for i in 0..20_000 {
+            let millis = rng.i64(if i % 2 == 0 { range.clone() } else { near_epoch.clone() });
+            let time = at(millis);
+            let start = time.start_unix_millis();
+            assert!(start <= millis && millis - start < prorahn_millis, "{millis}: {time:?} starts at {start}");
+            assert_eq!(at(start), time, "{millis}");
+            assert_ne!(at(start - 1), time, "{millis}");
+            assert!(time.vailee < 10 && time.yahr < 29 && time.gartahvo < 5);
+            assert!(time.tahvo < 25 && time.gorahn < 25 && time.prorahn < 25);
+        }
+    }
+
+    #[test]
+    fn durations_come_out_in_whole_units() {
+        let yahr = chrono::Duration::milliseconds(HAHR_MILLIS / YAHRTEE_PER_HAHR + 1);
+        let duration = DniDuration::from_duration(yahr);
+        assert_eq!((duration.yahrtee, duration.gartahvotee, duration.tahvotee), (1, 0, 0));
+        assert_eq!(DniDuration::from_duration(-yahr).to_string(), "0 prorahntee");
+        let hour = DniDuration::from_duration(chrono::Duration::hours(1));
+        assert_eq!(hour.to_string(), "4 tahvotee 3 gorahntee 9 prorahntee");
+    }
+}
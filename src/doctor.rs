@@ -0,0 +1,318 @@
+//! `dni-clock doctor`: checking that everything the clock leans on works on
+//! this machine, and saying what to do about what doesn't. It's meant to be
+//! pasted into bug reports, so it says what it checked even when it's fine.
+
+use std::{
+    env,
+    process::{Command, Stdio},
+};
+
+use chrono::{Local, Offset};
+use minifb::{Window, WindowOptions};
+
+use crate::{
+    config::Config,
+    dni_time::DniTime,
+    fonts::{DniFont, Fonts},
+    frame::DEFAULT_SCALE,
+    glyphs,
+    placement::Monitor,
+    profiles,
+    zones::Tz,
+};
+
+/// Set for the copy of itself that's started to try opening a window, which
+/// can take the whole process down if there's no display.
+const WINDOW_PROBE_VAR: &str = "DNI_CLOCK_DOCTOR_WINDOW_PROBE";
+
+/// The scales the digits are tried at: small, the usual, and big.
+const SCALES: [f32; 3] = [12.0, DEFAULT_SCALE, 400.0];
+
+/// Checks everything, and prints how it went. Returns the exit code: 1 if
+/// anything's broken.
+pub fn run(config: &Config) -> i32 {
+    if env::var_os(WINDOW_PROBE_VAR).is_some() {
+        return probe_window();
+    }
+    println!("dni-clock {} on {} ({})", env!("CARGO_PKG_VERSION"), env::consts::OS, env::consts::ARCH);
+    let mut report = Report::default();
+    check_options(&mut report);
+    check_fonts(&mut report, config);
+    check_time(&mut report, config);
+    check_window(&mut report, config);
+    #[cfg(all(unix, not(target_os = "macos")))]
+    check_helpers(&mut report, config);
+    println!();
+    match report.problems {
+        0 => {
+            println!("everything looks fine");
+            0
+        }
+        1 => {
+            println!("found a problem");
+            1
+        }
+        problems => {
+            println!("found {problems} problems");
+            1
+        }
+    }
+}
+
+/// What's been printed so far.
+#[derive(Default)]
+struct Report {
+    problems: usize,
+}
+
+impl Report {
+    fn section(&self, name: &str) {
+        println!("\n{name}");
+    }
+
+    fn ok(&self, what: &str) {
+        println!("  ok    {what}");
+    }
+
+    /// Something that doesn't stop the clock, but might not be what the user
+    /// wants.
+    fn warn(&self, what: &str, fix: &str) {
+        println!("  warn  {what}");
+        println!("        {fix}");
+    }
+
+    fn fail(&mut self, what: &str, fix: &str) {
+        self.problems += 1;
+        println!("  FAIL  {what}");
+        println!("        {fix}");
+    }
+}
+
+/// The options were parsed to get this far, but profiles are only parsed when
+/// they're used, so each one is tried.
+fn check_options(report: &mut Report) {
+    report.section("options");
+    report.ok("the command line parses");
+    let path = profiles::path().map_or_else(|| "the profiles file".to_owned(), |path| path.display().to_string());
+    let names = match profiles::names() {
+        Ok(names) => names,
+        Err(e) => return report.fail(&e, "check that the file's readable"),
+    };
+    if names.is_empty() {
+        report.ok(&format!("no profiles in {path} (just the built-in ones)"));
+        return;
+    }
+    let mut broken = false;
+    for name in &names {
+        let args = ["--profile".to_owned(), name.clone()];
+        if let Err(e) = Config::from_args(args.into_iter()) {
+            broken = true;
+            report.fail(&format!("profile `{name}`: {e}"), &format!("fix it in {path}"));
+        }
+    }
+    if !broken {
+        report.ok(&format!("{} profiles in {path} parse: {}", names.len(), names.join(", ")));
+    }
+}
+
+/// Loads every font the clock would, and draws every digit at a few sizes.
+fn check_fonts(report: &mut Report, config: &Config) {
+    report.section("fonts");
+    let all_fonts = match Fonts::load_all(&config.glyphs) {
+        Ok(all_fonts) => all_fonts,
+        Err(e) => {
+            let fix = "check the paths given to --dni-font and --label-font, and --font-index";
+            return report.fail(&e, fix);
+        }
+    };
+    let numerals = config.glyphs.numerals;
+    let scales = SCALES.map(|scale| config.glyphs.clamp_scale(scale));
+    for (fonts, choice) in all_fonts.iter().zip(DniFont::choices(&config.glyphs)) {
+        let mut failures = Vec::new();
+        let mut borrowed = Vec::new();
+        for n in 0..numerals.base() {
+            let Some(c) = numerals.glyph(n) else {
+                failures.push((n.to_string(), "there's no character for it".to_owned()));
+                continue;
+            };
+            let results = scales.map(|scale| glyphs::check_glyph(fonts, c, scale));
+            if let Some((scale, Err(e))) = scales.iter().zip(&results).find(|(_, result)| result.is_err()) {
+                // Usually it's all of them or none, so it's only said once
+                let at = if results.iter().all(Result::is_err) { String::new() } else { format!(" at {scale}") };
+                failures.push((format!("{n} ({c:?}){at}"), e.clone()));
+            } else if results.contains(&Ok(false)) {
+                borrowed.push(n.to_string());
+            }
+        }
+        for scale in scales {
+            if let Err(e) = glyphs::check_glyph(fonts, ':', scale) {
+                failures.push((format!("the colon at {scale}"), e));
+            }
+        }
+        // When every digit's broken, it's usually all the same way
+        let all_alike = failures.len() >= usize::from(numerals.base())
+            && failures.iter().all(|(_, e)| *e == failures[0].1);
+        let failures: Vec<String> = if all_alike {
+            vec![format!("none of the digits draw: {}", failures[0].1)]
+        } else {
+            failures.into_iter().map(|(what, e)| format!("{what}: {e}")).collect()
+        };
+        if !borrowed.is_empty() && failures.is_empty() {
+            report.warn(
+                &format!("{choice} is missing {}, so they're drawn in the label font", borrowed.join(", ")),
+                "use a font with all the digits (like the built-in one, --dni-font dni)",
+            );
+        } else if failures.is_empty() {
+            let [small, usual, big] = scales;
+            report.ok(&format!(
+                "all {} digits in {choice} draw at {small}, {usual}, and {big}",
+                numerals.base(),
+            ));
+        } else {
+            let fix = match numerals.base() {
+                25 => "use a font with the D'ni digits (like the built-in one, --dni-font dni)",
+                _ => "use a --dni-font that has these numerals",
+            };
+            report.fail(&format!("in {choice}, {}", failures.join("; ")), fix);
+        }
+    }
+}
+
+/// Works out the time zones, and converts the time.
+fn check_time(report: &mut Report, config: &Config) {
+    report.section("time");
+    match system_zone() {
+        Some(name) => match name.parse::<Tz>() {
+            Ok(_) => report.ok(&format!("the system time zone is {name}")),
+            Err(e) => report.warn(
+                &format!("the system time zone `{name}` isn't one the clock knows: {e}"),
+                "the time still comes from the system; use --timezone to show the D'ni time for another",
+            ),
+        },
+        None => report.ok("the system time zone is unnamed, so it's just the offset"),
+    }
+    let offset = Local::now().offset().fix();
+    report.ok(&format!("local time is UTC{offset}"));
+    if let Some(zone) = config.time_source.zone {
+        report.ok(&format!("showing the time in {} instead", zone.name()));
+    }
+    for zone in &config.zones {
+        report.ok(&format!("world clock `{}` is {}", zone.name, zone.tz.name()));
+    }
+    let now = config.time_source.now();
+    let dni = DniTime::from_surface(&now);
+    report.ok(&format!(
+        "it's {}, or {}:{}:{}, {}",
+        now.format("%Y-%m-%d %H:%M:%S %:z"),
+        dni.gartahvo,
+        dni.tahvo,
+        dni.gorahn,
+        dni.date(),
+    ));
+}
+
+/// The name of the system's time zone, like `Europe/Berlin`, if it has one.
+fn system_zone() -> Option<String> {
+    if let Some(tz) = env::var_os("TZ") {
+        let tz = tz.to_string_lossy();
+        return Some(tz.strip_prefix(':').unwrap_or(&tz).to_owned());
+    }
+    #[cfg(unix)]
+    if let Ok(target) = std::fs::read_link("/etc/localtime") {
+        let target = target.to_string_lossy().into_owned();
+        return target.split_once("zoneinfo/").map(|(_, name)| name.to_owned());
+    }
+    None
+}
+
+/// Tries opening a window, in another copy of itself, unless the clock
+/// wouldn't use one anyway.
+fn check_window(report: &mut Report, config: &Config) {
+    report.section("window");
+    const ELSE: &str = "without a window, try --headless, --pipe-frames, --svg-frames, or --print";
+    if config.headless || config.pipe_frames.is_some() || config.svg_frames.is_some() {
+        report.ok("not needed, with the output asked for");
+        return;
+    }
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        let wayland = env::var_os("WAYLAND_DISPLAY").is_some();
+        let x11 = env::var_os("DISPLAY").is_some();
+        match (wayland, x11) {
+            (false, false) => {
+                return report.fail("there's no display (neither WAYLAND_DISPLAY nor DISPLAY is set)", ELSE);
+            }
+            (true, true) => report.ok("there's a display (Wayland, and X11 to fall back on)"),
+            (true, false) => report.ok("there's a display (Wayland)"),
+            (false, true) => report.ok("there's a display (X11)"),
+        }
+    }
+    let exe = match env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => return report.fail(&format!("couldn't find itself to try opening a window: {e}"), ELSE),
+    };
+    let probe = Command::new(exe)
+        .arg("doctor")
+        .env(WINDOW_PROBE_VAR, "1")
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output();
+    match probe {
+        Ok(probe) if probe.status.success() => {
+            report.ok("a window opens");
+            for (n, monitor) in Monitor::all().iter().enumerate() {
+                let name = monitor.name().unwrap_or_else(|| "no name".to_owned());
+                let primary = if monitor.is_primary { ", primary" } else { "" };
+                report.ok(&format!(
+                    "monitor {n}: {name} ({}x{}+{}+{}{primary})",
+                    monitor.width, monitor.height, monitor.x, monitor.y,
+                ));
+            }
+        }
+        Ok(probe) => {
+            let stderr = String::from_utf8_lossy(&probe.stderr);
+            // The first thing it said, not a backtrace
+            let said = stderr.lines()
+                .map(str::trim)
+                .find(|line| !line.is_empty() && !line.starts_with("thread ") && !line.starts_with("stack backtrace"))
+                .unwrap_or("nothing");
+            report.fail(&format!("a window doesn't open ({}): {said}", probe.status), ELSE);
+        }
+        Err(e) => report.fail(&format!("couldn't try opening a window: {e}"), ELSE),
+    }
+}
+
+/// Opens a window and closes it again, in the copy started by
+/// [`check_window`].
+fn probe_window() -> i32 {
+    match Window::new("D'ni Clock", 64, 64, WindowOptions::default()) {
+        Ok(_) => 0,
+        Err(e) => {
+            eprintln!("{e}");
+            1
+        }
+    }
+}
+
+/// Finds the programs the clock runs for things it can't do itself. Missing
+/// ones only matter for what's turned on.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn check_helpers(report: &mut Report, config: &Config) {
+    report.section("helper programs");
+    let helpers: [(&str, &[&str], bool); 4] = [
+        ("sounds", &["paplay", "pw-play", "aplay"], (config.tick || config.chime) && !config.mute),
+        ("--announce", &["spd-say", "espeak-ng", "espeak"], config.announce.is_some()),
+        ("--sticky", &["wmctrl", "xdotool"], config.sticky),
+        ("--lock-screen", &["gsettings"], config.lock_screen),
+    ];
+    for (feature, programs, used) in helpers {
+        match programs.iter().find(|program| crate::util::on_path(program)) {
+            Some(program) => report.ok(&format!("{feature}: {program}")),
+            None if used => report.warn(
+                &format!("{feature}: none of {} is installed", programs.join(", ")),
+                &format!("install one of them, or {feature} won't do anything"),
+            ),
+            None => report.ok(&format!("{feature}: none of {} (but it's not turned on)", programs.join(", "))),
+        }
+    }
+}
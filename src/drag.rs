@@ -0,0 +1,59 @@
+//! Moving a borderless window by dragging it with the mouse.
+//!
+//! Without a title bar the window manager gives you nothing to grab, so the
+//! whole window acts as the handle instead.
+
+use minifb::{MouseButton, MouseMode, Window};
+
+use crate::placement::{self, Monitor};
+
+/// Tracks an in-progress drag.
+#[derive(Default)]
+pub struct WindowDrag {
+    /// Where in the window the mouse grabbed it, if it's being dragged.
+    grab: Option<(f32, f32)>,
+}
+
+impl WindowDrag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call once per frame. Starts, continues, or ends a drag depending on the
+    /// left mouse button, snapping to monitor edges if `snap_threshold` isn't 0.
+    pub fn update(
+        &mut self,
+        window: &mut Window,
+        monitors: &[Monitor],
+        snap_threshold: usize,
+        snap_margin: usize,
+    ) {
+        if !window.get_mouse_down(MouseButton::Left) {
+            self.grab = None;
+            return;
+        }
+        let Some(mouse) = window.get_unscaled_mouse_pos(MouseMode::Pass) else { return };
+        let Some(grab) = self.grab else {
+            // Only start dragging if the press was on the window itself
+            if window.get_unscaled_mouse_pos(MouseMode::Discard).is_some() {
+                self.grab = Some(mouse);
+            }
+            return;
+        };
+
+        let (x, y) = window.get_position();
+        let position = (
+            x + (mouse.0 - grab.0).round() as isize,
+            y + (mouse.1 - grab.1).round() as isize,
+        );
+        let (width, height) = window.get_size();
+        let position = if snap_threshold > 0 {
+            placement::snap(monitors, position, width, height, snap_threshold, snap_margin)
+        } else {
+            position
+        };
+        if position != (x, y) {
+            window.set_position(position.0, position.1);
+        }
+    }
+}
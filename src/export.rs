@@ -0,0 +1,126 @@
+//! Rendering the clock to image files, for `dni-clock render`.
+
+use std::{fs::{self, File}, io::BufWriter, path::{Path, PathBuf}};
+
+use chrono::{DurationRound, NaiveTime};
+
+use crate::{
+    colors,
+    config::Config,
+    fonts::Fonts,
+    frame,
+    glyphs::{GlyphBuffer, Glyphs},
+    line,
+};
+
+/// Frames per second of an animation. There's nothing moving between ticks
+/// yet, so one per tick is plenty.
+const FPS: u16 = 1;
+
+/// What `dni-clock render` was asked to do.
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// Render a whole minute, instead of just the current time.
+    pub animate: bool,
+    /// Where to write it. The extension picks the format: `.gif`, `.png`
+    /// (which is an APNG, if animated), or `.svg` (vector outlines).
+    pub out: PathBuf,
+    /// Show this time of day instead of the current time.
+    pub time: Option<NaiveTime>,
+    /// Just draw this number, instead of a clock.
+    pub number: Option<u64>,
+}
+
+/// Renders the clock as `options` says, at `scale`.
+pub fn render(config: &Config, fonts: &Fonts, options: &RenderOptions, scale: f32) -> Result<(), String> {
+    let mut glyphs = Glyphs::with_starting_scale(scale, &config.glyphs, fonts);
+    glyphs.set_colors(config.fg, config.bg);
+    if config.disk_cache {
+        glyphs.use_disk_cache();
+    }
+    let now = match options.time {
+        Some(time) => config.time_source.now().date().and_time(time).ok_or("that time doesn't exist today")?,
+        None => config.time_source.now(),
+    };
+
+    let extension = options.out.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let extension = extension.to_ascii_lowercase();
+    if extension == "svg" {
+        if options.animate {
+            return Err("SVGs can't be animated; try .gif or .png".to_owned());
+        }
+        let svg = match options.number {
+            Some(n) => line::draw_svg(&mut glyphs, &[config.glyphs.numerals.digits(n, 1)]),
+            None => frame::build_svg(now, &mut glyphs, scale, config),
+        };
+        glyphs.save_to_disk();
+        return fs::write(&options.out, svg)
+            .map_err(|e| format!("couldn't write {}: {e}", options.out.display()));
+    }
+
+    let frames = if let Some(n) = options.number {
+        if options.animate {
+            return Err("a number can't be animated".to_owned());
+        }
+        vec![line::draw(&mut glyphs, &[config.glyphs.numerals.digits(n, 1)])]
+    } else if options.animate {
+        // Start from the top of the minute so it loops at a sensible spot
+        let start = now.duration_trunc(chrono::Duration::minutes(1)).unwrap();
+        (0..60 * i64::from(FPS))
+            .map(|i| start + chrono::Duration::milliseconds(i * 1000 / i64::from(FPS)))
+            .map(|time| frame::build(time, &mut glyphs, scale, config))
+            .collect()
+    } else {
+        vec![frame::build(now, &mut glyphs, scale, config)]
+    };
+
+    glyphs.save_to_disk();
+
+    let result = match extension.as_str() {
+        "gif" => write_gif(&options.out, &frames),
+        "png" | "apng" => write_png(&options.out, &frames),
+        _ => return Err(format!("don't know how to write `{}`; try .gif, .png, or .svg", options.out.display())),
+    };
+    result.map_err(|e| format!("couldn't write {}: {e}", options.out.display()))
+}
+
+/// Writes `frames` as a looping GIF.
+fn write_gif(path: &Path, frames: &[GlyphBuffer]) -> Result<(), Box<dyn std::error::Error>> {
+    let (width, height) = frame_size(frames)?;
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = gif::Encoder::new(file, width.try_into()?, height.try_into()?, &[])?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+    for frame in frames {
+        let mut rgba = colors::to_rgba_bytes(frame.as_1d());
+        let mut frame = gif::Frame::from_rgba_speed(width.try_into()?, height.try_into()?, &mut rgba, 10);
+        // In hundredths of a second
+        frame.delay = 100 / FPS;
+        encoder.write_frame(&frame)?;
+    }
+    Ok(())
+}
+
+/// Writes `frames` as a PNG, or a looping APNG if there's more than one.
+pub fn write_png(path: &Path, frames: &[GlyphBuffer]) -> Result<(), Box<dyn std::error::Error>> {
+    let (width, height) = frame_size(frames)?;
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, width.try_into()?, height.try_into()?);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    if frames.len() > 1 {
+        encoder.set_animated(frames.len().try_into()?, 0)?;
+        encoder.set_frame_delay(1, FPS)?;
+    }
+    let mut writer = encoder.write_header()?;
+    for frame in frames {
+        writer.write_image_data(&colors::to_rgba_bytes(frame.as_1d()))?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+/// The size every frame is (they all have to match).
+fn frame_size(frames: &[GlyphBuffer]) -> Result<(usize, usize), String> {
+    let first = frames.first().ok_or("no frames to write")?;
+    Ok((first.width(), first.height()))
+}
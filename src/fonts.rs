@@ -0,0 +1,293 @@
+//! Loading fonts, either the ones built into the binary or the user's own.
+
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use ab_glyph::{Font, FontArc, FontRef, FontVec, ScaleFont, VariableFont};
+
+use crate::glyphs::GlyphOptions;
+
+/// The D'ni typefaces built into the binary, by name. The first is the
+/// default.
+///
+/// There's only the one so far, so F only has fonts to cycle between when
+/// `--dni-font` gives them.
+const BUNDLED_DNI_FONTS: &[(&str, &[u8])] = &[
+    ("dni", include_bytes!("../fonts/Dni.ttf")),
+];
+const ASCII_FONT: &[u8] = include_bytes!("../fonts/Source_Sans_Pro/SourceSansPro-Regular.ttf");
+
+/// The fonts everything's drawn in.
+#[derive(Clone)]
+pub struct Fonts {
+    /// For the numbers.
+    pub dni: FontArc,
+    /// For colons and labels.
+    pub ascii: FontArc,
+    /// Identifies exactly which fonts these are, for keying caches.
+    pub hash: u64,
+}
+
+impl Fonts {
+    /// Loads every set of fonts `options` asks for: one per D'ni font to
+    /// choose between (all the bundled ones, if it doesn't name any), each
+    /// with the same label font, and with their variation axes set.
+    pub fn load_all(options: &GlyphOptions) -> Result<Vec<Self>, String> {
+        DniFont::choices(options).iter().map(|dni| Self::load(dni, options)).collect()
+    }
+
+    /// Loads `dni`, and the label font `options` asks for (or the built-in
+    /// one).
+    fn load(dni: &DniFont, options: &GlyphOptions) -> Result<Self, String> {
+        let mut hasher = DefaultHasher::new();
+        let dni = match dni {
+            DniFont::Bundled(i) => {
+                load_role(None, BUNDLED_DNI_FONTS[*i].1, 0, &options.digit_axes, &mut hasher)?
+            }
+            DniFont::File(path) => {
+                let (_, default) = BUNDLED_DNI_FONTS[0];
+                load_role(Some(path), default, options.font_index, &options.digit_axes, &mut hasher)?
+            }
+        };
+        let ascii = load_role(
+            options.label_font.as_deref(), ASCII_FONT, 0, &options.label_axes, &mut hasher,
+        )?;
+        Ok(Self { dni, ascii, hash: hasher.finish() })
+    }
+
+    /// Measures how tall a line of digits comes out in these fonts, with the
+    /// digits `options` asks for.
+    pub fn line_metrics(&self, options: &GlyphOptions) -> LineMetrics {
+        let scale = METRICS_SCALE;
+        // By definition, the scale is the font's ascent to its descent, but
+        // nothing stops a glyph from poking out past them
+        let ink_height = |font: &FontArc, c: char| {
+            let id = font.glyph_id(c);
+            // ID 0 is `.notdef`, for characters the font doesn't have
+            if id.0 == 0 {
+                return None;
+            }
+            let glyph = font.outline_glyph(id.with_scale(scale))?;
+            Some(glyph.px_bounds().height())
+        };
+        let numerals = options.numerals;
+        let tallest = (0..numerals.base())
+            .filter_map(|n| numerals.glyph(n))
+            .filter_map(|c| ink_height(&self.dni, c).or_else(|| ink_height(&self.ascii, c)))
+            .chain(ink_height(&self.ascii, ':'))
+            .fold(self.dni.as_scaled(scale).height(), f32::max);
+        LineMetrics { digits: tallest / scale }
+    }
+}
+
+/// How big glyphs are measured at, for [`Fonts::line_metrics`]. It's all
+/// proportional, so anything big enough to be precise would do.
+const METRICS_SCALE: f32 = 100.0;
+
+/// How tall lines come out in a set of fonts, in proportion to the scale.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineMetrics {
+    /// A line of digits (and colons): the font's ascent to descent, or more
+    /// if any of them stick out past that.
+    pub digits: f32,
+}
+
+impl Default for LineMetrics {
+    /// Lines exactly as tall as the scale, like fonts usually have them.
+    fn default() -> Self {
+        Self { digits: 1.0 }
+    }
+}
+
+/// Where a D'ni font comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DniFont {
+    /// One built into the binary, by its place in the list.
+    Bundled(usize),
+    File(PathBuf),
+}
+
+impl DniFont {
+    /// The D'ni fonts `options` has to choose between, in order: the ones it
+    /// names, or else all the bundled ones.
+    pub fn choices(options: &GlyphOptions) -> Vec<Self> {
+        if options.dni_fonts.is_empty() {
+            (0..BUNDLED_DNI_FONTS.len()).map(Self::Bundled).collect()
+        } else {
+            options.dni_fonts.clone()
+        }
+    }
+}
+
+impl fmt::Display for DniFont {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bundled(i) => write!(f, "the built-in `{}`", BUNDLED_DNI_FONTS[*i].0),
+            Self::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+impl FromStr for DniFont {
+    type Err = String;
+
+    /// The name of a bundled font, or else a path to a font file.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match BUNDLED_DNI_FONTS.iter().position(|&(name, _)| name == s) {
+            Some(i) => Ok(Self::Bundled(i)),
+            None => Ok(Self::File(PathBuf::from(s))),
+        }
+    }
+}
+
+/// A setting for one of a variable font's axes, like `wght=600`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Axis {
+    /// The four-letter tag, like `wght` (weight) or `wdth` (width).
+    pub tag: [u8; 4],
+    pub value: f32,
+}
+
+impl FromStr for Axis {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (tag, value) = s.split_once('=').ok_or("expected `tag=value`, like `wght=600`")?;
+        let tag = tag.as_bytes().try_into()
+            .map_err(|_| format!("axis tags are four letters, like `wght`, not `{tag}`"))?;
+        let value = value.parse().map_err(|e| format!("bad axis value `{value}`: {e}"))?;
+        Ok(Self { tag, value })
+    }
+}
+
+/// Loads the font at `path` (or `built_in`, if there's no path) and applies
+/// `axes` to it, feeding whatever identifies the result into `hasher`.
+fn load_role(
+    path: Option<&Path>,
+    built_in: &'static [u8],
+    index: u32,
+    axes: &[Axis],
+    hasher: &mut impl Hasher,
+) -> Result<FontArc, String> {
+    let font = match path {
+        Some(path) => {
+            let data = fs::read(path)
+                .map_err(|e| format!("couldn't read {}: {e}", path.display()))?;
+            data.hash(hasher);
+            index.hash(hasher);
+            let mut font = load_font(data, index)
+                .map_err(|e| format!("couldn't load {}: {e}", path.display()))?;
+            set_axes(&mut font, axes)
+                .map_err(|e| format!("couldn't vary {}: {e}", path.display()))?;
+            FontArc::new(font)
+        }
+        None => {
+            built_in.hash(hasher);
+            let mut font = FontRef::try_from_slice(built_in).unwrap();
+            set_axes(&mut font, axes)
+                .map_err(|e| format!("couldn't vary the built-in font: {e}"))?;
+            FontArc::new(font)
+        }
+    };
+    for axis in axes {
+        axis.tag.hash(hasher);
+        axis.value.to_bits().hash(hasher);
+    }
+    Ok(font)
+}
+
+/// Sets each of `axes` on `font`, complaining about any it doesn't have.
+fn set_axes(font: &mut impl VariableFont, axes: &[Axis]) -> Result<(), String> {
+    for axis in axes {
+        if !font.set_variation(&axis.tag, axis.value) {
+            let tags: Vec<_> = font.variations().into_iter()
+                .map(|v| String::from_utf8_lossy(&v.tag).into_owned())
+                .collect();
+            let tag = String::from_utf8_lossy(&axis.tag);
+            return Err(if tags.is_empty() {
+                format!("it isn't a variable font, so it has no `{tag}` axis")
+            } else {
+                format!("it has no `{tag}` axis (it has {})", tags.join(", "))
+            });
+        }
+    }
+    Ok(())
+}
+
+/// The kinds of font file there are, going by the first few bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    /// TrueType outlines (`.ttf`, and some `.otf`)
+    TrueType,
+    /// OpenType with CFF outlines (most `.otf`)
+    OpenTypeCff,
+    /// Several fonts in one file (`.ttc`, `.otc`)
+    Collection,
+    /// Compressed for the web (`.woff`, `.woff2`), which isn't supported
+    Woff,
+    Unknown,
+}
+
+impl Format {
+    fn detect(data: &[u8]) -> Self {
+        match data.get(..4) {
+            Some([0, 1, 0, 0] | b"true") => Self::TrueType,
+            Some(b"OTTO") => Self::OpenTypeCff,
+            Some(b"ttcf") => Self::Collection,
+            Some(b"wOFF" | b"wOF2") => Self::Woff,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// Why a font file couldn't be used.
+#[derive(Debug)]
+enum LoadError {
+    Woff,
+    Unknown,
+    /// The collection only has this many fonts in it.
+    IndexOutOfRange(u32),
+    /// It's not a collection, so there's only font 0.
+    NotACollection,
+    Invalid,
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Woff => write!(f, "WOFF fonts aren't supported; convert it to TTF or OTF first"),
+            Self::Unknown => write!(f, "not a TrueType, OpenType, or collection font file"),
+            Self::IndexOutOfRange(count) => {
+                write!(f, "the collection only has {count} fonts (counting from 0)")
+            }
+            Self::NotACollection => write!(f, "`--font-index` only applies to font collections"),
+            Self::Invalid => write!(f, "the font is damaged or uses features that aren't supported"),
+        }
+    }
+}
+
+fn load_font(data: Vec<u8>, index: u32) -> Result<FontVec, LoadError> {
+    match Format::detect(&data) {
+        Format::TrueType | Format::OpenTypeCff if index != 0 => return Err(LoadError::NotACollection),
+        Format::TrueType | Format::OpenTypeCff => {}
+        Format::Collection => {
+            // The number of fonts is right after the tag and version
+            let count = data.get(8..12)
+                .map(|bytes| u32::from_be_bytes(bytes.try_into().unwrap()))
+                .ok_or(LoadError::Invalid)?;
+            if index >= count {
+                return Err(LoadError::IndexOutOfRange(count));
+            }
+        }
+        Format::Woff => return Err(LoadError::Woff),
+        Format::Unknown => return Err(LoadError::Unknown),
+    }
+    FontVec::try_from_vec_and_index(data, index)
+        .map_err(|_| LoadError::Invalid)
+}
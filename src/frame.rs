@@ -0,0 +1,1092 @@
+//! Laying out the clock face: rows of numbers, with their labels.
+
+use std::{borrow::Cow, ops::Range, str::FromStr, time::Duration};
+
+use chrono::{DateTime, FixedOffset, Timelike};
+
+use crate::{
+    animation::{Morph, Reveal},
+    buf2d::Vec2d,
+    colors::{self, Color},
+    config::{Command, Config},
+    dni_time::DniTime,
+    glyphs::{GlyphBuffer, Glyphs, MaskView},
+    labels::{self, Field},
+    numerals::NumeralSystem,
+    layout::{Align, Container},
+    quiz,
+    shapes,
+    svg::Svg,
+    synth,
+    theme::Look,
+};
+
+/// The glyph scale (which is also the line height) if nothing says otherwise.
+pub const DEFAULT_SCALE: f32 = 50.0;
+
+/// How far a theme script's glow reaches out from the numbers, as a fraction
+/// of the clock's scale.
+const GLOW_REACH: f32 = 0.1;
+
+/// What happens to the window when the seconds are shown or hidden while the
+/// clock's running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SecondsToggle {
+    /// It's made to fit the clock, like at startup.
+    #[default]
+    Resize,
+    /// It stays the size it was, with the clock in the middle.
+    Center,
+}
+
+impl FromStr for SecondsToggle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "resize" => Ok(Self::Resize),
+            "center" => Ok(Self::Center),
+            _ => Err("expected `resize` or `center`".to_owned()),
+        }
+    }
+}
+
+/// How the surface time's laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Face {
+    /// All in a line, separated by colons.
+    #[default]
+    Line,
+    /// Like some watch faces: the hour big on the left, and the minutes and
+    /// seconds stacked on the right, as tall together as it is.
+    Watch,
+}
+
+impl FromStr for Face {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "line" => Ok(Self::Line),
+            "watch" => Ok(Self::Watch),
+            _ => Err("expected `line` or `watch`".to_owned()),
+        }
+    }
+}
+
+/// Room between complications, side by side.
+const COMPLICATION_SPACING: usize = 10;
+
+/// Room left around the clock face, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Margins {
+    pub top: usize,
+    /// Past the end of the room set aside for the numbers, which is already a
+    /// little more than they take up (see [`column_width`]), so none is
+    /// needed by default.
+    pub right: usize,
+    pub bottom: usize,
+    pub left: usize,
+}
+
+impl Default for Margins {
+    fn default() -> Self {
+        Self { top: 10, right: 0, bottom: 10, left: 10 }
+    }
+}
+
+impl FromStr for Margins {
+    type Err = String;
+
+    /// Like CSS: one number for all four sides, two for top and bottom then
+    /// left and right, or four for top, right, bottom, and left.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const BAD: &str = "expected 1, 2, or 4 numbers of pixels separated by commas, like `10` or `4,8`";
+        let sides: Vec<usize> = s.split(',').map(|side| side.trim().parse().map_err(|_| BAD)).collect::<Result<_, _>>()?;
+        match sides[..] {
+            [all] => Ok(Self { top: all, right: all, bottom: all, left: all }),
+            [vertical, horizontal] => Ok(Self { top: vertical, right: horizontal, bottom: vertical, left: horizontal }),
+            [top, right, bottom, left] => Ok(Self { top, right, bottom, left }),
+            _ => Err(BAD.to_owned()),
+        }
+    }
+}
+
+/// A number on the clock face. It's named for how it's written in D'ni; in
+/// other numeral systems, it takes however many digits it takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Number {
+    /// 0-24, as a single D'ni digit (or 25, for hour zero with
+    /// [`Midnight::TwentyFive`])
+    OneDigit(u8),
+    /// 00-59, padded to two D'ni digits (or as many as 59 takes)
+    TwoDigits(u8),
+    /// Hour zero with [`Midnight::Mark`]: the frame of a digit, with nothing
+    /// in it.
+    MidnightMark,
+}
+
+impl Number {
+    /// Its digits in `numerals`, most significant first.
+    pub fn digits(self, numerals: &dyn NumeralSystem) -> Vec<u8> {
+        match self {
+            Self::OneDigit(n) if numerals.glyph(n).is_some() => vec![n],
+            Self::OneDigit(n) => numerals.digits(n.into(), 1),
+            Self::TwoDigits(n) => numerals.digits(n.into(), numerals.padded_len()),
+            Self::MidnightMark => vec![],
+        }
+    }
+
+    /// How many digits it has in `numerals` (without allocating, unlike
+    /// [`Self::digits`]).
+    fn digit_count(self, numerals: &dyn NumeralSystem) -> usize {
+        match self {
+            Self::OneDigit(n) if numerals.glyph(n).is_none() => 2,
+            Self::OneDigit(_) | Self::MidnightMark => 1,
+            Self::TwoDigits(_) => numerals.padded_len(),
+        }
+    }
+
+    /// Its digits' values in `numerals`, written out: `12` for one, `[3 7]`
+    /// for two, and `*` for the midnight mark.
+    fn text(self, numerals: &dyn NumeralSystem) -> String {
+        match self.digits(numerals).as_slice() {
+            [] => "*".to_owned(),
+            [digit] => digit.to_string(),
+            digits => {
+                let digits: Vec<_> = digits.iter().map(u8::to_string).collect();
+                format!("[{}]", digits.join(" "))
+            }
+        }
+    }
+
+    /// Its raster glyph.
+    pub fn raster(self, glyphs: &mut Glyphs) -> MaskView<'_> {
+        match self {
+            // Too big for one digit, but no bigger than a padded numeral
+            Self::OneDigit(n) if glyphs.options().numerals.glyph(n).is_none() => {
+                glyphs.get_dni_number_two_digits(n)
+            }
+            Self::OneDigit(n) => glyphs.get_dni_number_one_digit(n),
+            Self::TwoDigits(n) => glyphs.get_dni_number_two_digits(n),
+            Self::MidnightMark => glyphs.get_midnight_mark(),
+        }
+    }
+
+    /// The hour, shown the way `midnight` says when it's zero.
+    fn hour(hour: u8, midnight: Midnight) -> Self {
+        match (hour, midnight) {
+            (0, Midnight::TwentyFive) => Self::OneDigit(25),
+            (0, Midnight::Mark) => Self::MidnightMark,
+            _ => Self::OneDigit(hour),
+        }
+    }
+}
+
+/// How hour zero is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Midnight {
+    /// The D'ni zero, like any other hour.
+    #[default]
+    Zero,
+    /// The D'ni 25 (a single digit, not `[1 0]`), like a surface clock that
+    /// shows midnight as 24:00.
+    TwentyFive,
+    /// An empty digit frame.
+    Mark,
+}
+
+impl FromStr for Midnight {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "zero" | "0" => Ok(Self::Zero),
+            "25" => Ok(Self::TwentyFive),
+            "mark" => Ok(Self::Mark),
+            _ => Err("expected `zero`, `25`, or `mark`".to_owned()),
+        }
+    }
+}
+
+/// Which side of zero a T-minus countdown is on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sign {
+    Minus,
+    Plus,
+}
+
+/// The most fields a row has.
+const MAX_FIELDS: usize = 3;
+
+/// One line of numbers, separated by colons.
+struct Row {
+    /// Says which clock the row is, when there's more than one.
+    caption: Option<&'static str>,
+    /// The first `len` of these. It's an array, rather than a `Vec`, so
+    /// drawing a frame doesn't allocate.
+    fields: [(Field, Number); MAX_FIELDS],
+    len: usize,
+}
+
+impl Row {
+    fn fields(&self) -> &[(Field, Number)] {
+        &self.fields[..self.len]
+    }
+}
+
+/// The rows to show, top to bottom.
+fn rows(time: DateTime<FixedOffset>, config: &Config) -> impl Iterator<Item = Row> {
+    let surface = Row {
+        caption: config.show_dni_time.then_some("surface"),
+        fields: [
+            (Field::Hours, Number::hour(time.hour().try_into().unwrap(), config.midnight)),
+            (Field::Minutes, Number::TwoDigits(time.minute().try_into().unwrap())),
+            (Field::Seconds, Number::TwoDigits(time.second().try_into().unwrap())),
+        ],
+        len: if config.show_seconds { 3 } else { 2 },
+    };
+
+    let cavern = config.show_dni_time.then(|| {
+        let dni = DniTime::from_surface(&time);
+        Row {
+            caption: Some("cavern"),
+            fields: [
+                (Field::Gartahvo, Number::OneDigit(dni.gartahvo)),
+                (Field::Tahvo, Number::OneDigit(dni.tahvo)),
+                (Field::Gorahn, Number::OneDigit(dni.gorahn)),
+            ],
+            len: 3,
+        }
+    });
+    std::iter::once(surface).chain(cavern)
+}
+
+/// The clock face for `time` as plain text, with each number's digit values
+/// written out (see [`Number::text`]): a row per line, and, if there are
+/// zones, a block of them per zone, each under its name.
+pub fn text(time: DateTime<FixedOffset>, config: &Config) -> String {
+    let clock = |time: DateTime<FixedOffset>| {
+        rows(time, config)
+            .map(|row| {
+                let numbers: Vec<_> = row.fields().iter().map(|(_, number)| number.text(config.glyphs.numerals)).collect();
+                let numbers = numbers.join(":");
+                match row.caption {
+                    Some(caption) => format!("{caption} {numbers}"),
+                    None => numbers,
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    if config.zones.is_empty() {
+        return clock(time);
+    }
+    config.zones.iter()
+        .map(|zone| format!("{}\n{}", zone.name, clock(zone.tz.convert(&time))))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// How many rows [`rows`] will give.
+fn row_count(config: &Config) -> usize {
+    if config.show_dni_time { 2 } else { 1 }
+}
+
+/// How tall all of one clock's rows are, with their labels.
+fn clock_height(scale: f32, config: &Config) -> usize {
+    let row_height = line_height(scale, config) + label_height(scale, config);
+    match config.face {
+        Face::Line => row_height * row_count(config),
+        // Two rows' worth for the stack, and then any after the surface time
+        Face::Watch => row_height * (row_count(config) + 1),
+    }
+}
+
+/// How tall the hour is with `--face watch`: from the top of the minutes to
+/// the bottom of the seconds, label and all in between.
+fn watch_hour_height(scale: f32, config: &Config) -> usize {
+    line_height(scale, config) * 2 + label_height(scale, config)
+}
+
+/// The scale the hour's drawn at with `--face watch`, so its line is no taller
+/// than [`watch_hour_height`].
+fn watch_hour_scale(scale: f32, config: &Config) -> f32 {
+    let height = watch_hour_height(scale, config) - config.glyphs.stroke_weight() * 2;
+    (height as f32 / config.line_metrics.digits).floor()
+}
+
+/// The room between the hour and the stack with `--face watch`, at `scale`.
+fn watch_gap(scale: f32, config: &Config) -> usize {
+    line_height(scale, config) / 5
+}
+
+/// How tall the strip of labels next to each row is (nothing, if there's no
+/// text to go in it).
+fn label_height(scale: f32, config: &Config) -> usize {
+    if config.labels.is_empty() && !config.show_dni_time {
+        0
+    } else {
+        labels::line_height(scale)
+    }
+}
+
+/// How many clocks there are side by side: one per world clock zone, or just
+/// the local one.
+fn column_count(config: &Config) -> usize {
+    config.zones.len().max(1)
+}
+
+/// How tall the row of zone names under the world clock is (nothing, if it's
+/// not a world clock).
+fn zone_label_height(scale: f32, config: &Config) -> usize {
+    if config.zones.is_empty() {
+        0
+    } else {
+        labels::line_height(scale)
+    }
+}
+
+/// How tall a line of numbers is: as tall as the font says, at `scale`.
+/// Synthetic bold makes the glyphs bigger than that.
+pub fn line_height(scale: f32, config: &Config) -> usize {
+    (scale * config.line_metrics.digits).round() as usize + config.glyphs.stroke_weight() * 2
+}
+
+/// How wide one clock is, including its margins at the sides.
+///
+/// It has to be worked out before anything's rendered, so the numbers are
+/// guessed at like in [`numbers_width`], which comes to a tenth or so more
+/// than they really take. Up to a default left margin's worth of that is
+/// given back, so the margins don't pile up on room that's already there.
+fn column_width(scale: f32, config: &Config) -> usize {
+    if config.face == Face::Watch {
+        let hour = numbers_width(&[Number::OneDigit(0)], watch_hour_scale(scale, config), config);
+        let stack = numbers_width(&[Number::TwoDigits(0)], scale, config);
+        // Unlike in a line, there are no colons to guess too wide for, so
+        // there's a tenth more room than that for digits wider than they're
+        // tall
+        let guess = hour + watch_gap(scale, config) + stack;
+        let numbers = guess + guess / 10;
+        let watch = config.margins.left + numbers + config.margins.right;
+        // The D'ni time under it is still in a line
+        return if config.show_dni_time { watch.max(line_width(scale, config)) } else { watch };
+    }
+    line_width(scale, config)
+}
+
+/// How wide one clock is all in a line, as worked out for [`column_width`].
+fn line_width(scale: f32, config: &Config) -> usize {
+    let line_height = line_height(scale, config);
+    // Numbers and colons, each of which slanting makes wider
+    let glyphs = if config.show_seconds { 5 } else { 3 };
+    let colons = glyphs / 2;
+    let slant_width = synth::slant_width(line_height, config.glyphs.slant);
+    let guess = line_height * if config.show_seconds { 6 } else { 4 } + slant_width * glyphs;
+    let numbers = guess - (guess / 10).min(Margins::default().left);
+    config.margins.left + numbers + config.field_padding * 2 * colons + config.margins.right
+}
+
+/// About how wide `numbers` are in a line, separated by colons, so there's room
+/// for them before they've been rendered.
+pub fn numbers_width(numbers: &[Number], scale: f32, config: &Config) -> usize {
+    let line_height = line_height(scale, config);
+    // Digits are about as wide as they are tall, and colons half that
+    let halves: usize = numbers.iter()
+        .map(|number| number.digit_count(config.glyphs.numerals) * 2)
+        .sum::<usize>() + numbers.len().saturating_sub(1);
+    let glyphs = numbers.len() * 2 - 1;
+    let slant_width = synth::slant_width(line_height, config.glyphs.slant);
+    line_height * halves / 2 + slant_width * glyphs
+}
+
+/// The row of complications under the clock: each a caption over the
+/// complication itself, with margins at the sides and in between.
+fn complications_layout(scale: f32, config: &Config) -> Container {
+    let caption_height = labels::line_height(scale);
+    let mut row = Container::hbox().spacing(COMPLICATION_SPACING).padding(config.margins.left, 0);
+    for complication in &config.complications {
+        let (w, h) = complication.measure(scale, config);
+        let column = Container::vbox().align(Align::Center).leaf(w, caption_height).leaf(w, h);
+        row = row.nest(column);
+    }
+    row
+}
+
+/// How wide the row of complications under the clock is, margins included,
+/// and how tall (nothing, if there aren't any).
+fn complications_size(scale: f32, config: &Config) -> (usize, usize) {
+    if config.complications.is_empty() {
+        return (0, 0);
+    }
+    complications_layout(scale, config).size()
+}
+
+/// The world clocks, side by side: each a clock over its zone's name.
+fn columns_layout(scale: f32, config: &Config) -> Container {
+    let width = column_width(scale, config);
+    let clock_height = clock_height(scale, config);
+    let column = Container::vbox().leaf(width, clock_height).leaf(width, zone_label_height(scale, config));
+    config.zones.iter().fold(Container::hbox().padding(0, config.margins.top), |row, _| row.nest(column.clone()))
+}
+
+/// How big the window needs to be to fit the clock at `scale`.
+pub fn window_size(scale: f32, config: &Config) -> (usize, usize) {
+    if let Command::Quiz = config.command {
+        return quiz::window_size(scale, config);
+    }
+    let (complications_width, complications_height) = complications_size(scale, config);
+    let width = (column_width(scale, config) * column_count(config)).max(complications_width);
+    let height = clock_height(scale, config) + zone_label_height(scale, config) + complications_height;
+    (width, config.margins.top + height + config.margins.bottom)
+}
+
+/// Frames shown in the middle of a bigger one, on their own background, so
+/// whatever they're shown in can stay one size while they change.
+#[derive(Default)]
+pub struct Centered {
+    /// The size frames are shown at. It only ever grows, so anything bigger
+    /// still fits.
+    pub size: (usize, usize),
+    /// Reused for every frame, so fitting one in doesn't have to allocate.
+    canvas: GlyphBuffer,
+}
+
+impl Centered {
+    pub fn new(size: (usize, usize)) -> Self {
+        Self { size, canvas: GlyphBuffer::default() }
+    }
+
+    /// `frame`, in the middle of a frame [`Self::size`] big.
+    pub fn fit(&mut self, frame: &GlyphBuffer) -> &GlyphBuffer {
+        self.size = (self.size.0.max(frame.width()), self.size.1.max(frame.height()));
+        let (width, height) = self.size;
+        let bg = frame.row(0).first().copied().unwrap_or(colors::BG);
+        self.canvas.reset(bg, width, height);
+        self.canvas.copy_to_from((width - frame.width()) / 2, (height - frame.height()) / 2, frame);
+        &self.canvas
+    }
+}
+
+/// Something a clock face can be drawn on. The layout's all worked out in
+/// pixels from the raster glyphs, so everything lands in the same place
+/// whichever it is.
+pub trait Canvas {
+    /// Draws `number`, with the top left of its raster glyph at `x`, `y`.
+    fn number(&mut self, glyphs: &mut Glyphs, number: Number, x: usize, y: usize);
+
+    /// Draws a colon, with the top left of its raster glyph at `x`, `y`.
+    fn colon(&mut self, glyphs: &mut Glyphs, x: usize, y: usize);
+
+    /// Draws `sign`, with the top left of its raster glyph at `x`, `y`.
+    fn sign(&mut self, glyphs: &mut Glyphs, sign: Sign, x: usize, y: usize);
+
+    /// What color everything's being drawn in.
+    fn fg(&self, glyphs: &Glyphs) -> Color;
+
+    /// Draws everything from here on in `fg`, until it's set back.
+    fn set_fg(&mut self, glyphs: &mut Glyphs, fg: Color);
+
+    /// Draws `text` at label size with its top left at `x`, `y`, cut off at
+    /// `width`×`height`.
+    fn label(&mut self, glyphs: &mut Glyphs, text: &str, x: usize, y: usize, width: usize, height: usize);
+
+    /// Draws a progress ring `diameter` across and `thickness` thick with its
+    /// top left at `x`, `y`, filled clockwise from the top `progress` of the
+    /// way around (from 0.0 to 1.0). The rest of it is drawn dimmer, unless
+    /// it's in high contrast mode.
+    #[allow(clippy::too_many_arguments)]
+    fn ring(
+        &mut self,
+        glyphs: &mut Glyphs,
+        x: usize,
+        y: usize,
+        diameter: usize,
+        thickness: f32,
+        progress: f32,
+    );
+}
+
+impl Canvas for GlyphBuffer {
+    fn number(&mut self, glyphs: &mut Glyphs, number: Number, x: usize, y: usize) {
+        let fg = glyphs.fg();
+        self.tint_from(x, y, number.raster(glyphs), fg);
+    }
+
+    fn colon(&mut self, glyphs: &mut Glyphs, x: usize, y: usize) {
+        self.tint_from(x, y, glyphs.get_colon(), glyphs.fg());
+    }
+
+    fn sign(&mut self, glyphs: &mut Glyphs, sign: Sign, x: usize, y: usize) {
+        let fg = glyphs.fg();
+        self.tint_from(x, y, glyphs.get_sign(sign), fg);
+    }
+
+    fn fg(&self, glyphs: &Glyphs) -> Color {
+        glyphs.fg()
+    }
+
+    fn set_fg(&mut self, glyphs: &mut Glyphs, fg: Color) {
+        glyphs.set_colors(fg, glyphs.bg());
+    }
+
+    fn label(&mut self, glyphs: &mut Glyphs, text: &str, x: usize, y: usize, width: usize, height: usize) {
+        let fg = glyphs.fg();
+        let label = glyphs.get_label(text);
+        let (width, height) = (width.min(label.width()), height.min(label.height()));
+        self.tint_from(x, y, label.sub_view(0, 0, width, height), fg);
+    }
+
+    fn ring(
+        &mut self,
+        glyphs: &mut Glyphs,
+        x: usize,
+        y: usize,
+        diameter: usize,
+        thickness: f32,
+        progress: f32,
+    ) {
+        let high_contrast = glyphs.options().high_contrast;
+        if glyphs.options().draws_tracks() {
+            let track = colors::lerp(glyphs.bg(), glyphs.fg(), shapes::TRACK_BRIGHTNESS);
+            self.tint_from(x, y, &shapes::arc(diameter, thickness, 1.0), track);
+        }
+        let mut arc = shapes::arc(diameter, thickness, progress);
+        if high_contrast {
+            synth::threshold(&mut arc);
+        }
+        self.tint_from(x, y, &arc, glyphs.fg());
+    }
+}
+
+/// Draws the whole clock face for `time`.
+pub fn build(
+    time: DateTime<FixedOffset>,
+    glyphs: &mut Glyphs,
+    scale: f32,
+    config: &Config,
+) -> GlyphBuffer {
+    let mut buf = GlyphBuffer::default();
+    build_into(&mut buf, time, glyphs, scale, config);
+    buf
+}
+
+/// Draws the whole clock face for `time` into `buf`, resizing it to fit.
+///
+/// Once all the glyphs it needs are cached, and `buf` has been drawn into
+/// before at this size, this doesn't allocate at all (unless there are world
+/// clocks with names that need shortening, complications, or a theme script).
+pub fn build_into(
+    buf: &mut GlyphBuffer,
+    time: DateTime<FixedOffset>,
+    glyphs: &mut Glyphs,
+    scale: f32,
+    config: &Config,
+) {
+    let look = set_colors_for(glyphs, time, config);
+    let themed = with_labels(config, look.as_ref());
+    let (width, height) = window_size(scale, &themed);
+    buf.reset(glyphs.bg(), width, height);
+    draw(buf, time, glyphs, scale, &themed);
+    draw_glow(buf, glyphs, look.as_ref(), scale, config);
+}
+
+/// Draws the whole clock face for `time` into `buf` like [`build_into`], but
+/// as of `elapsed` into the reveal at startup (see [`Reveal`]). Returns
+/// whether it's over, and `buf` has the face drawn in full.
+pub fn build_revealing(
+    buf: &mut GlyphBuffer,
+    time: DateTime<FixedOffset>,
+    glyphs: &mut Glyphs,
+    scale: f32,
+    config: &Config,
+    elapsed: Duration,
+) -> bool {
+    let look = set_colors_for(glyphs, time, config);
+    let themed = with_labels(config, look.as_ref());
+    let (width, height) = window_size(scale, &themed);
+    buf.reset(glyphs.bg(), width, height);
+    let mut reveal = Reveal::new(buf, elapsed);
+    draw(&mut reveal, time, glyphs, scale, &themed);
+    let done = reveal.done();
+    draw_glow(buf, glyphs, look.as_ref(), scale, config);
+    done
+}
+
+/// Draws the whole clock face for `time` into `buf` like [`build_into`], but
+/// with the numerals that changed since [`Morph::from`] morphing into their
+/// new values, `progress` of the way.
+pub fn build_morphing(
+    buf: &mut GlyphBuffer,
+    time: DateTime<FixedOffset>,
+    glyphs: &mut Glyphs,
+    scale: f32,
+    config: &Config,
+    morph: &mut Morph,
+    progress: f32,
+) {
+    let look = set_colors_for(glyphs, time, config);
+    let themed = with_labels(config, look.as_ref());
+    if !morph.recorded() {
+        let from = morph.from();
+        draw(&mut morph.recorder(), from, glyphs, scale, &themed);
+    }
+    let (width, height) = window_size(scale, &themed);
+    buf.reset(glyphs.bg(), width, height);
+    draw(&mut morph.canvas(buf, progress), time, glyphs, scale, &themed);
+    draw_glow(buf, glyphs, look.as_ref(), scale, config);
+}
+
+/// Redraws just the seconds in `buf`, which [`build_into`] last drew for a
+/// time in the same minute (with nothing drawn over it since), as `time`'s.
+/// The hours and minutes are left as they are, so a frame each second costs a
+/// numeral, not the whole line.
+///
+/// Returns false, without drawing anything, if more than the seconds might
+/// have changed: there's a D'ni time row or world clocks, or `buf` isn't the
+/// size it should be. Then it needs a [`build_into`]. The complications, which
+/// are under the clock, are left alone: see [`update_complications`].
+pub fn update_seconds(
+    buf: &mut GlyphBuffer,
+    time: DateTime<FixedOffset>,
+    glyphs: &mut Glyphs,
+    scale: f32,
+    config: &Config,
+) -> bool {
+    let simple = config.show_seconds
+        && config.face == Face::Line
+        && !config.show_dni_time
+        && config.zones.is_empty();
+    let (width, height) = window_size(scale, config);
+    if !simple || (buf.width(), buf.height()) != (width, height) {
+        return false;
+    }
+    let Some(row) = rows(time, config).next() else { return false };
+    let line_height = line_height(scale, config);
+    let label_height = label_height(scale, config);
+    let (numbers_y, labels_y) = match config.labels.position {
+        labels::Position::Above => (config.margins.top + label_height, config.margins.top),
+        labels::Position::Below => (config.margins.top, config.margins.top + line_height),
+    };
+
+    // Where everything was put last time, which hasn't moved, since the hours
+    // and minutes (and so how wide they are) are the same
+    let mut spans = [0..0, 0..0, 0..0];
+    let mut x = config.margins.left;
+    for (span, &(_, number)) in spans.iter_mut().zip(&row.fields()[..2]) {
+        let number_width = number.raster(glyphs).width();
+        *span = x..(x + number_width);
+        x += number_width + config.field_padding + glyphs.get_colon().width() + config.field_padding;
+    }
+    let bg = glyphs.bg();
+    for y in numbers_y..numbers_y + line_height {
+        buf.row_mut(y)[x..].fill(bg);
+    }
+    let (_, seconds) = row.fields()[2];
+    place_numbers(buf, glyphs, std::iter::once(seconds), x, numbers_y, line_height, 0, |_, span| spans[2] = span);
+
+    // Labels can stick out over their neighbors, so they're all drawn again
+    if !config.labels.is_empty() {
+        for y in labels_y..labels_y + label_height {
+            buf.row_mut(y).fill(bg);
+        }
+        for (&(field, _), span) in row.fields().iter().zip(spans) {
+            let Some(text) = config.labels.get(field) else { continue };
+            write_label(buf, width, glyphs, text, span, labels_y, label_height);
+        }
+    }
+    true
+}
+
+/// Redraws just the complications in `buf`, which [`build_into`] last drew
+/// (with nothing drawn over it since), as of `time`. Returns false, without
+/// drawing anything, if `buf` isn't the size it should be.
+pub fn update_complications(
+    buf: &mut GlyphBuffer,
+    time: DateTime<FixedOffset>,
+    glyphs: &mut Glyphs,
+    scale: f32,
+    config: &Config,
+) -> bool {
+    let (width, height) = window_size(scale, config);
+    if (buf.width(), buf.height()) != (width, height) {
+        return false;
+    }
+    let (_, row_height) = complications_size(scale, config);
+    let bg = glyphs.bg();
+    let bottom = height - config.margins.bottom;
+    for y in bottom - row_height..bottom {
+        buf.row_mut(y).fill(bg);
+    }
+    write_complications(buf, width, height, time, glyphs, scale, config);
+    true
+}
+
+/// Draws the whole clock face for `time` as an SVG document.
+pub fn build_svg(
+    time: DateTime<FixedOffset>,
+    glyphs: &mut Glyphs,
+    scale: f32,
+    config: &Config,
+) -> String {
+    let look = set_colors_for(glyphs, time, config);
+    let themed = with_labels(config, look.as_ref());
+    let (width, height) = window_size(scale, &themed);
+    let mut svg = Svg::new(width, height);
+    if config.invert {
+        svg.set_colors(colors::invert(glyphs.fg()), colors::invert(glyphs.bg()));
+    } else {
+        svg.set_colors(glyphs.fg(), glyphs.bg());
+    }
+    if let Some(strength) = glow_strength(glyphs, look.as_ref(), config) {
+        svg.set_glow(scale * GLOW_REACH, strength);
+    }
+    draw(&mut svg, time, glyphs, scale, &themed);
+    svg.finish()
+}
+
+/// Colors the glyphs for the time of day, with `--theme-script`, and then
+/// tints them with `--color-temperature`. The temperature's worked out to the
+/// minute, so seconds drawn over a frame from earlier in the minute match it.
+/// The glyphs are tinted as they're drawn, so changing it costs nothing.
+///
+/// Returns what the theme script made of the time, if there is one, for the
+/// rest of how the face looks.
+fn set_colors_for(glyphs: &mut Glyphs, time: DateTime<FixedOffset>, config: &Config) -> Option<Look> {
+    let look = config.theme.as_ref().map(|theme| theme.look(&time, config.fg, config.bg));
+    let (mut fg, bg) = match &look {
+        // A chroma key has to stay the one color to be keyed out
+        Some(look) => (look.fg, if config.chroma_key.is_some() { config.bg } else { look.bg }),
+        None if config.color_temperature => (config.fg, glyphs.bg()),
+        None => return None,
+    };
+    if config.color_temperature {
+        let into_day = (time.hour() * 60 + time.minute()) as f32 / (24 * 60) as f32;
+        // All the way warm at midnight, and all the way cool at noon
+        let warmth = (into_day * std::f32::consts::TAU).cos();
+        fg = colors::with_warmth(fg, warmth);
+    }
+    glyphs.set_colors(fg, bg);
+    look
+}
+
+/// `config`, with the labels a theme script gave (if it gave any) over its
+/// own.
+fn with_labels<'a>(config: &'a Config, look: Option<&Look>) -> Cow<'a, Config> {
+    let Some(look) = look.filter(|look| !look.labels.is_empty()) else {
+        return Cow::Borrowed(config);
+    };
+    let mut config = config.clone();
+    for (field, text) in &look.labels {
+        config.labels.set(*field, text.clone());
+    }
+    Cow::Owned(config)
+}
+
+/// How strongly the numbers glow, going by `look`, if they do at all. They
+/// don't with a chroma key, which has to stay the one color, or in high
+/// contrast mode, which is only ever the two.
+fn glow_strength(glyphs: &Glyphs, look: Option<&Look>, config: &Config) -> Option<f32> {
+    let strength = look?.glow;
+    let plain = config.chroma_key.is_some() || glyphs.options().high_contrast || glyphs.fg() == glyphs.bg();
+    (strength > 0.0 && !plain).then_some(strength)
+}
+
+/// Lights up the background around the numbers (and everything else drawn in
+/// the glyphs' color) in `buf`, for a theme script's glow: right next to them,
+/// as far toward their color as the glow's strength, fading out over
+/// [`GLOW_REACH`] of `scale`.
+fn draw_glow(buf: &mut GlyphBuffer, glyphs: &Glyphs, look: Option<&Look>, scale: f32, config: &Config) {
+    let Some(strength) = glow_strength(glyphs, look, config) else { return };
+    let reach = (scale * GLOW_REACH / 2.0).round().max(1.0) as usize;
+    let (fg, bg) = (colors::to_u8_rgb(glyphs.fg()), colors::to_u8_rgb(glyphs.bg()));
+    let towards = [fg.0, fg.1, fg.2].map(f32::from);
+    let from = [bg.0, bg.1, bg.2].map(f32::from);
+    let span: f32 = towards.iter().zip(from).map(|(t, f)| (t - f).powi(2)).sum();
+    // How far each pixel is from the background to the glyphs' color
+    let mut lit = Vec2d::from_fn(buf.width(), buf.height(), |x, y| {
+        let (r, g, b) = colors::to_u8_rgb(buf[(x, y)]);
+        let along: f32 = [r, g, b].iter().zip(towards.iter().zip(from))
+            .map(|(&c, (t, f))| (f32::from(c) - f) * (t - f))
+            .sum();
+        (along / span).clamp(0.0, 1.0)
+    });
+    // Twice over with a box is near enough to a smooth falloff
+    for _ in 0..2 {
+        box_blur(&mut lit, reach);
+    }
+    let fg = glyphs.fg();
+    for y in 0..buf.height() {
+        for (px, &lit) in buf.row_mut(y).iter_mut().zip(lit.row(y)) {
+            // Blurring leaves it about half lit right at the edges
+            *px = colors::lerp(*px, fg, (lit * strength * 2.0).min(1.0));
+        }
+    }
+}
+
+/// Averages everything in `values` with what's up to `reach` away, across and
+/// then down (counting what's off the edges as 0).
+fn box_blur(values: &mut Vec2d<f32>, reach: usize) {
+    let mut line = Vec::with_capacity(values.width().max(values.height()));
+    for y in 0..values.height() {
+        line.clear();
+        line.extend_from_slice(values.row(y));
+        blur_line(&line, reach, |x, value| values[(x, y)] = value);
+    }
+    for x in 0..values.width() {
+        line.clear();
+        line.extend((0..values.height()).map(|y| values[(x, y)]));
+        blur_line(&line, reach, |y, value| values[(x, y)] = value);
+    }
+}
+
+/// Averages each of `line` with what's up to `reach` either side of it,
+/// handing each average to `set`.
+fn blur_line(line: &[f32], reach: usize, mut set: impl FnMut(usize, f32)) {
+    let window = (reach * 2 + 1) as f32;
+    let mut sum: f32 = line.iter().take(reach).sum();
+    for i in 0..line.len() {
+        if let Some(entering) = line.get(i + reach) {
+            sum += entering;
+        }
+        if i > reach {
+            sum -= line[i - reach - 1];
+        }
+        set(i, sum / window);
+    }
+}
+
+/// Draws the whole clock face for `time` onto `canvas`, which is
+/// [`window_size`] big.
+fn draw(
+    canvas: &mut impl Canvas,
+    time: DateTime<FixedOffset>,
+    glyphs: &mut Glyphs,
+    scale: f32,
+    config: &Config,
+) {
+    let (width, height) = window_size(scale, config);
+    if config.zones.is_empty() {
+        write_clock(canvas, width, glyphs, scale, config, time, 0);
+    } else {
+        let columns = columns_layout(scale, config).place(0, 0);
+        for (zone, column) in config.zones.iter().zip(columns.chunks(2)) {
+            let [clock, label] = column else { unreachable!("each column is a clock and a label") };
+            write_clock(canvas, width, glyphs, scale, config, zone.tz.convert(&time), clock.x);
+
+            let name = glyphs.ellipsize_label(&zone.name, label.width.saturating_sub(config.margins.left));
+            let span = (label.x + config.margins.left)..(label.x + label.width);
+            write_label(canvas, width, glyphs, &name, span, label.y, label.height);
+        }
+    }
+    write_complications(canvas, width, height, time, glyphs, scale, config);
+}
+
+/// Writes the complications in a row along the bottom of a canvas
+/// `width`×`height`, each captioned with its name.
+fn write_complications(
+    canvas: &mut impl Canvas,
+    width: usize,
+    height: usize,
+    time: DateTime<FixedOffset>,
+    glyphs: &mut Glyphs,
+    scale: f32,
+    config: &Config,
+) {
+    if config.complications.is_empty() {
+        return;
+    }
+    let layout = complications_layout(scale, config);
+    let (_, row_height) = layout.size();
+    let boxes = layout.place(0, height - config.margins.bottom - row_height);
+    for (complication, boxes) in config.complications.iter().zip(boxes.chunks(2)) {
+        let [caption, body] = boxes else { unreachable!("each complication is a caption and a body") };
+        let span = caption.x..(caption.x + caption.width);
+        let name = config.locale.text(complication.name());
+        write_label(canvas, width, glyphs, name, span, caption.y, caption.height);
+        complication.render(canvas, glyphs, time, body.x, body.y, body.width, body.height);
+    }
+}
+
+/// Writes one clock's rows, with their labels, starting at the top of a
+/// canvas `width` wide, `column_x` pixels in.
+fn write_clock(
+    canvas: &mut impl Canvas,
+    width: usize,
+    glyphs: &mut Glyphs,
+    scale: f32,
+    config: &Config,
+    time: DateTime<FixedOffset>,
+    column_x: usize,
+) {
+    let line_height = line_height(scale, config);
+    let label_height = label_height(scale, config);
+    let mut row_top = config.margins.top;
+
+    for row in rows(time, config) {
+        let (numbers_y, labels_y) = match config.labels.position {
+            labels::Position::Above => (row_top + label_height, row_top),
+            labels::Position::Below => (row_top, row_top + line_height),
+        };
+        let x = column_x + config.margins.left;
+        if config.face == Face::Watch && row.fields()[0].0 == Field::Hours {
+            write_watch_row(canvas, width, glyphs, scale, config, &row, x, row_top);
+            row_top += (line_height + label_height) * 2;
+            continue;
+        }
+        let spans = write_row(canvas, glyphs, &row, x, numbers_y, line_height, config.field_padding);
+
+        if let Some(caption) = row.caption {
+            let caption_x = x;
+            let caption = config.locale.text(caption);
+            canvas.label(glyphs, caption, caption_x, labels_y, width - caption_x, label_height);
+        }
+        for (&(field, _), span) in row.fields().iter().zip(spans) {
+            let Some(text) = config.labels.get(field) else { continue };
+            write_label(canvas, width, glyphs, text, span.clone(), labels_y, label_height);
+        }
+
+        row_top += line_height + label_height;
+    }
+}
+
+/// Writes the surface time's row like a watch face (see [`Face::Watch`]),
+/// with the top left of it (labels included) at `x`, `row_top`, on a canvas
+/// `width` wide. The hour's drawn at its own, bigger scale, centered in a line
+/// as tall as the stack, so it lines up with the top of the minutes and the
+/// bottom of the seconds.
+#[allow(clippy::too_many_arguments)]
+fn write_watch_row(
+    canvas: &mut impl Canvas,
+    width: usize,
+    glyphs: &mut Glyphs,
+    scale: f32,
+    config: &Config,
+    row: &Row,
+    x: usize,
+    row_top: usize,
+) {
+    let line_height = line_height(scale, config);
+    let label_height = label_height(scale, config);
+    let hour_height = watch_hour_height(scale, config);
+    let above = config.labels.position == labels::Position::Above;
+
+    let [(_, hour), stack @ ..] = row.fields() else { unreachable!("there's always an hour") };
+    let hour_y = if above { row_top + label_height } else { row_top };
+    let mut hour_span = 0..0;
+    let big = glyphs.at_scale(watch_hour_scale(scale, config));
+    place_numbers(canvas, big, std::iter::once(*hour), x, hour_y, hour_height, 0, |_, span| hour_span = span);
+    if let Some(text) = config.labels.get(Field::Hours) {
+        let labels_y = if above { row_top } else { row_top + hour_height };
+        write_label(canvas, width, glyphs, text, hour_span.clone(), labels_y, label_height);
+    }
+    if let Some(caption) = row.caption {
+        let labels_y = if above { row_top } else { row_top + hour_height };
+        canvas.label(glyphs, config.locale.text(caption), x, labels_y, width - x, label_height);
+    }
+
+    // Without the seconds, the minutes are by themselves in the middle
+    let slot_height = line_height + label_height;
+    let mut slot_top = row_top + (slot_height * 2 - slot_height * stack.len()) / 2;
+    let stack_x = hour_span.end + watch_gap(scale, config);
+    for &(field, number) in stack {
+        let (numbers_y, labels_y) = if above {
+            (slot_top + label_height, slot_top)
+        } else {
+            (slot_top, slot_top + line_height)
+        };
+        let mut span = 0..0;
+        place_numbers(canvas, glyphs, std::iter::once(number), stack_x, numbers_y, line_height, 0, |_, placed| span = placed);
+        if let Some(text) = config.labels.get(field) {
+            write_label(canvas, width, glyphs, text, span, labels_y, label_height);
+        }
+        slot_top += slot_height;
+    }
+}
+
+/// Writes a row's numbers in a line `line_height` tall, from `x`, `y`,
+/// returning where each field ended up (the first `row.len` of them) so the
+/// labels can be centered over them.
+fn write_row(
+    canvas: &mut impl Canvas,
+    glyphs: &mut Glyphs,
+    row: &Row,
+    x: usize,
+    y: usize,
+    line_height: usize,
+    padding: usize,
+) -> [Range<usize>; MAX_FIELDS] {
+    let mut spans = [0..0, 0..0, 0..0];
+    let numbers = row.fields().iter().map(|&(_, number)| number);
+    place_numbers(canvas, glyphs, numbers, x, y, line_height, padding, |i, span| spans[i] = span);
+    spans
+}
+
+/// Writes `numbers`, separated by colons, in a line `line_height` tall, from
+/// `x`, `y`. Returns where each one ended up.
+pub fn write_numbers(
+    canvas: &mut (impl Canvas + ?Sized),
+    glyphs: &mut Glyphs,
+    numbers: &[Number],
+    x: usize,
+    y: usize,
+    line_height: usize,
+) -> Vec<Range<usize>> {
+    let mut spans = Vec::with_capacity(numbers.len());
+    place_numbers(canvas, glyphs, numbers.iter().copied(), x, y, line_height, 0, |_, span| spans.push(span));
+    spans
+}
+
+/// Like [`write_numbers`], but with `padding` more pixels on either side of
+/// each colon, and handing where each number ended up (along with its index)
+/// to `placed`, instead of collecting them.
+#[allow(clippy::too_many_arguments)]
+fn place_numbers(
+    canvas: &mut (impl Canvas + ?Sized),
+    glyphs: &mut Glyphs,
+    numbers: impl Iterator<Item = Number>,
+    mut x: usize,
+    y: usize,
+    line_height: usize,
+    padding: usize,
+    mut placed: impl FnMut(usize, Range<usize>),
+) {
+    // Glyphs shorter than the line are centered in it
+    let centered = |height: usize| {
+        y + line_height.checked_sub(height).expect("glyph was taller than the line") / 2
+    };
+    for (i, number) in numbers.enumerate() {
+        if i > 0 {
+            let colon = glyphs.get_colon();
+            let (width, height) = (colon.width(), colon.height());
+            canvas.colon(glyphs, x + padding, centered(height));
+            x += padding + width + padding;
+        }
+        let glyph = number.raster(glyphs);
+        let (width, height) = (glyph.width(), glyph.height());
+        canvas.number(glyphs, number, x, centered(height));
+        placed(i, x..(x + width));
+        x += width;
+    }
+}
+
+/// Writes `text` centered over `span`, in the strip of labels starting at `y`,
+/// on a canvas `width` wide.
+fn write_label(
+    canvas: &mut impl Canvas,
+    width: usize,
+    glyphs: &mut Glyphs,
+    text: &str,
+    span: Range<usize>,
+    y: usize,
+    height: usize,
+) {
+    // Too-long labels get cut off at the edges of the window, rather than
+    // making the window wider
+    let label_width = glyphs.get_label(text).width().min(width);
+    let center = (span.start + span.end) / 2;
+    let x = center
+        .saturating_sub(label_width / 2)
+        .min(width - label_width);
+    canvas.label(glyphs, text, x, y, label_width, height);
+}
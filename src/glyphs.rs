@@ -1,21 +1,76 @@
 //! Glyph rendering and types.
-//!
-//! The fonts used by the crate are included in the binary.
 
-use std::array;
+use std::{
+    borrow::Cow,
+    cell::OnceCell,
+    collections::{BTreeSet, HashMap},
+    hash::{Hash, Hasher},
+    panic::{self, AssertUnwindSafe},
+    str::FromStr,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        Mutex,
+    },
+    thread,
+};
 
-use ab_glyph::{Font, FontRef};
+use ab_glyph::{point, Font, FontArc, Glyph, ScaleFont};
+use tracing::trace_span;
 
-use crate::{buf2d::Vec2d, colors::{self, Color}};
+use crate::{
+    atlas::{Atlas, Rect},
+    buf2d::{Vec2d, View2d},
+    colors::{self, Color},
+    disk_cache::{self, Entry},
+    fonts::{Axis, DniFont, Fonts},
+    frame::Sign,
+    labels,
+    numerals::{self, Composition, NumeralSystem, MAX_DIGITS},
+    svg,
+    synth,
+};
 
-/// A glyph rendered to pixels.
+/// Pixels in color, like a whole frame.
 pub type GlyphBuffer = Vec2d<Color>;
 
+/// A glyph rendered to pixels, as how much of each one it covers, from 0 (not
+/// at all) to [`u8::MAX`] (completely). Glyphs are cached like this, without
+/// any color, and tinted whatever color they're meant to be as they're drawn
+/// (see [`GlyphBuffer::tint_from`]), so changing colors doesn't mean rendering
+/// them all again.
+pub type MaskBuffer = Vec2d<u8>;
+
+/// A glyph borrowed from somewhere, like the cache.
+pub type MaskView<'a> = View2d<'a, u8>;
+
+impl GlyphBuffer {
+    /// Draws `mask` in `color`, with its top left at `(x, y)`: each pixel goes
+    /// as far toward `color` as the mask covers it.
+    ///
+    /// Panics if `mask` won't fit.
+    pub fn tint_from<'a>(&mut self, x: usize, y: usize, mask: impl Into<MaskView<'a>>, color: Color) {
+        let _span = trace_span!("blit").entered();
+        let mask = mask.into();
+        assert!(
+            x + mask.width() <= self.width() && y + mask.height() <= self.height(),
+            "`mask` won't fit, at least not starting from ({x}, {y})"
+        );
+        for mask_y in 0..mask.height() {
+            let row = &mut self.row_mut(y + mask_y)[x..(x + mask.width())];
+            for (px, &coverage) in row.iter_mut().zip(mask.row(mask_y)) {
+                *px = colors::tint(*px, color, coverage);
+            }
+        }
+    }
+}
+
 /// A simple wrapper over a buffer. It lets you write glyphs in a row.
 #[derive(Default)]
 pub struct TextBuffer {
     /// The buffer being written into.
-    pub buf: GlyphBuffer,
+    pub buf: MaskBuffer,
     /// Where the next glyph should start, horizontally.
     pub x: usize,
     /// The top of the line of text (for the next glyph). The glyph is centered
@@ -26,159 +81,682 @@ pub struct TextBuffer {
 }
 
 impl TextBuffer {
-    /// Create a new empty one. It won't work in this state.
-    pub fn new() -> Self {
-        Self::default()
-    }
-
-    /// Writes a glyph and advances by its width.
-    pub fn write_glyph(&mut self, glyph: &GlyphBuffer) {
-        self._write_glyph::<false>(glyph)
-    }
-
     /// Writes a glyph and advances by its width, only overwriting pixels that are
     /// somewhat transparent (i.e., so you can compose it with the previous glyph).
-    pub fn write_glyph_composing(&mut self, glyph: &GlyphBuffer) {
-        self._write_glyph::<true>(glyph)
-    }
-
-    fn _write_glyph<const COMPOSE: bool>(&mut self, glyph: &GlyphBuffer) {
+    pub fn write_glyph_composing(&mut self, glyph: MaskView<'_>) {
         let height_diff = self.height.checked_sub(glyph.height())
             .expect("glyph was taller than the line");
         let centered_y = self.y + height_diff / 2;
 
-        if COMPOSE {
-            self.buf.copy_to_from_if(
-                self.x,
-                centered_y,
-                glyph,
-                Self::pixel_is_somewhat_transparent,
-            );
-        } else {
-            self.buf.copy_to_from(
-                self.x,
-                centered_y,
-                glyph,
-            );
-        }
+        self.buf.copy_to_from_if(
+            self.x,
+            centered_y,
+            glyph,
+            Self::pixel_is_somewhat_transparent,
+        );
 
         self.x += glyph.width();
     }
 
-    /// Whether the pixel should be considered transparent against a background of
-    /// `colors::BG` (i.e., should be overwritten, when composing glyphs).
-    fn pixel_is_somewhat_transparent(px: Color) -> bool {
-        #[allow(clippy::assertions_on_constants)] // no duh it's optimized out, clippy
-        const _: () = assert!(colors::BG == 0, "this algorithm relies on BG being black");
+    /// Whether the pixel should be considered transparent (i.e., should be
+    /// overwritten, when composing glyphs): whether it's covered less than a
+    /// certain threshold short of completely.
+    fn pixel_is_somewhat_transparent(px: u8) -> bool {
+        const THRESHOLD: u8 = 100; // out of u8::MAX
+        px < u8::MAX - THRESHOLD
+    }
+}
+
+/// How much the walls of consecutive digits overlap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Overlap {
+    /// Work it out for each pair of digits from their shapes, so the walls
+    /// line up exactly.
+    Auto,
+    /// A fraction of the scale. 0 puts them side by side.
+    Fixed(f32),
+}
+
+impl FromStr for Overlap {
+    type Err = String;
 
-        /// Adds the red, green, and blue components
-        const fn sum_rgb(color: Color) -> u16 {
-            let (r, g, b) = colors::to_u8_rgb(color);
-            r as u16 + g as u16 + b as u16
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "auto" {
+            return Ok(Self::Auto);
+        }
+        match s.parse::<f32>() {
+            Ok(fraction) if fraction >= 0.0 => Ok(Self::Fixed(fraction)),
+            _ => Err("expected `auto` or a number that's 0 or more".to_owned()),
         }
+    }
+}
 
-        const FG_RGB_SUM: u16 = sum_rgb(colors::FG);
+/// Settings that change how glyphs look.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlyphOptions {
+    /// How numbers are written.
+    pub numerals: &'static dyn NumeralSystem,
+    pub digit_overlap: Overlap,
+    /// How many pixels to thicken the digits' (and colons') strokes by on
+    /// each side, for fonts that don't come in bold.
+    pub weight: usize,
+    /// How many degrees to lean the digits (and colons) over by, for fonts
+    /// that don't come in italic. Negative leans them back.
+    pub slant: f32,
+    /// The fonts to choose between for the D'ni digits, the first to start
+    /// with. Empty means all the bundled ones.
+    pub dni_fonts: Vec<DniFont>,
+    /// Which font to use, from any of `dni_fonts` that are collections.
+    pub font_index: u32,
+    /// A font file to use for the labels and colons instead of the built-in one.
+    pub label_font: Option<PathBuf>,
+    /// Variation settings for the digits' font, if it's a variable font.
+    pub digit_axes: Vec<Axis>,
+    /// Variation settings for the labels' font, if it's a variable font.
+    pub label_axes: Vec<Axis>,
+    /// Make everything as easy to see as possible, for low-vision users: only
+    /// pure foreground and background (no antialiasing), strokes at least
+    /// [`HIGH_CONTRAST_WEIGHT`] thick, and glyphs at least
+    /// [`HIGH_CONTRAST_MIN_SCALE`] big.
+    pub high_contrast: bool,
+    /// Thicken or thin the digits' (and colons') strokes to [`NORMAL_STROKE`]
+    /// of the scale, whatever the font's own weight, so they look about as
+    /// heavy at any size. Happens before [`Self::weight`] is added.
+    pub normalize_strokes: bool,
+    /// The background's a chroma key, for streaming software to cut out, so
+    /// nothing in between it and the foreground is drawn behind the digits
+    /// (like tracks, or the tooltip's box), which would be left showing.
+    pub keyed_background: bool,
+    /// Draw glyphs like pixel art: rendered as if this big, with no
+    /// antialiasing, then blown up a whole number of times to about the size
+    /// they should be. `None` draws them smooth.
+    pub pixel_scale: Option<f32>,
+}
 
-        const THRESHOLD: u16 = 100; // out of u8::MAX
+/// How thick strokes are made by [`GlyphOptions::normalize_strokes`], as a
+/// fraction of the scale, at [`NORMAL_STROKE_SCALE`]. It grows a little faster
+/// than the scale does (see [`NORMAL_STROKE_GROWTH`]).
+const NORMAL_STROKE: f32 = 0.07;
+/// The scale strokes are exactly [`NORMAL_STROKE`] thick at: the default.
+const NORMAL_STROKE_SCALE: f32 = 100.0;
+/// How much faster than the scale normalized strokes grow, as an exponent.
+/// Huge digits look spindly with strokes that are only in proportion, next
+/// to everything around them that stays the same size.
+const NORMAL_STROKE_GROWTH: f32 = 0.1;
+/// How thin [`GlyphOptions::normalize_strokes`] lets strokes get, in pixels,
+/// however small the scale, so they don't vanish.
+const MIN_NORMAL_STROKE: f32 = 2.0;
+/// The smallest labels are rendered at with [`GlyphOptions::pixel_scale`],
+/// before they're blown up. Any smaller, and letters run together.
+const MIN_PIXEL_LABEL_SCALE: f32 = 9.0;
 
-        // The idea here is to check if the brightness of FG minus the brightness of px
-        // is less than a certain threshold. The concept of "brightness" here is a
-        // simple one (if not very accurate to human perception): just average together
-        // the color's RGB values. Averaging requires dividing by the number of elements
-        // (3) however, which we avoid by multiplying the threshold instead (by 3).
-        FG_RGB_SUM - sum_rgb(px) > THRESHOLD * 3
+/// The least synthetic bold high contrast mode uses.
+const HIGH_CONTRAST_WEIGHT: usize = 3;
+/// The smallest scale high contrast mode lets glyphs be drawn at.
+const HIGH_CONTRAST_MIN_SCALE: f32 = 72.0;
+
+/// The widest or tallest a glyph can be drawn, in pixels. Anything bigger is
+/// a broken font or scale, not a clock anyone could see all of.
+const MAX_GLYPH_SIDE: usize = 16_384;
+
+impl Default for GlyphOptions {
+    fn default() -> Self {
+        Self {
+            numerals: numerals::SYSTEMS[0],
+            digit_overlap: Overlap::Auto,
+            weight: 0,
+            slant: 0.0,
+            dni_fonts: Vec::new(),
+            font_index: 0,
+            label_font: None,
+            digit_axes: Vec::new(),
+            label_axes: Vec::new(),
+            high_contrast: false,
+            normalize_strokes: false,
+            keyed_background: false,
+            pixel_scale: None,
+        }
+    }
+}
+
+impl GlyphOptions {
+    /// Feeds everything that affects the pixels into `hasher`, for keying caches
+    /// (except the font, which is hashed when it's loaded).
+    pub fn hash_into(&self, hasher: &mut impl Hasher) {
+        self.numerals.name().hash(hasher);
+        match self.digit_overlap {
+            Overlap::Auto => u32::MAX.hash(hasher),
+            Overlap::Fixed(fraction) => fraction.to_bits().hash(hasher),
+        }
+        self.stroke_weight().hash(hasher);
+        self.slant.to_bits().hash(hasher);
+        self.high_contrast.hash(hasher);
+        self.normalize_strokes.hash(hasher);
+        self.pixel_scale.map(f32::to_bits).hash(hasher);
+    }
+
+    /// How many pixels strokes are actually thickened by on each side, which
+    /// high contrast mode can make more than [`Self::weight`].
+    pub fn stroke_weight(&self) -> usize {
+        if self.high_contrast {
+            self.weight.max(HIGH_CONTRAST_WEIGHT)
+        } else {
+            self.weight
+        }
+    }
+
+    /// How many times over glyphs meant for `scale` are blown up from the
+    /// size they're rendered at: 1, unless [`Self::pixel_scale`] is set.
+    pub fn pixel_factor(&self, scale: f32) -> usize {
+        self.pixel_scale.map_or(1, |small| (scale / small).round().max(1.0) as usize)
+    }
+
+    /// Whether dimmer tracks are drawn behind progress rings and bars. They're
+    /// neither foreground nor background, so high contrast mode has no room
+    /// for them, and a chroma key would fringe around them.
+    pub fn draws_tracks(&self) -> bool {
+        !self.high_contrast && !self.keyed_background
+    }
+
+    /// `scale`, or bigger if high contrast mode needs it to be.
+    pub fn clamp_scale(&self, scale: f32) -> f32 {
+        if self.high_contrast {
+            scale.max(HIGH_CONTRAST_MIN_SCALE)
+        } else {
+            scale
+        }
     }
 }
 
 /// Handles glyph rendering and caches them.
-// todo: The commented-out fields and rescale method are for when I implement window resizing.
 pub struct Glyphs {
-    // dni_font: FontRef<'static>,
-    // ascii_font: FontRef<'static>,
+    dni_font: FontArc,
+    ascii_font: FontArc,
+    /// Identifies the fonts, for the disk cache.
+    fonts_hash: u64,
     cache: Cache,
+    label_cache: LabelCache,
+    /// Digits being rendered ahead of time on another thread, if
+    /// [`Self::warm_up_in_background`] was called.
+    warming_up: Option<Receiver<(u8, MaskBuffer)>>,
+    /// Where to save rendered glyphs, if [`Self::use_disk_cache`] was called.
+    disk_cache: Option<PathBuf>,
+    /// Whether anything's been rendered since the last save.
+    unsaved: bool,
+    /// What color glyphs are drawn in, and on.
+    fg: Color,
+    bg: Color,
+    /// The same glyphs at another scale, if [`Self::at_scale`] asked for them.
+    scaled: Option<Box<Glyphs>>,
 }
 
 impl Glyphs {
-    /// Initializes its cache with the given scale.
-    pub fn with_starting_scale(scale: f32) -> Self {
-        let dni_font = get_dni_font();
-        let ascii_font = get_ascii_font();
+    /// Initializes its cache with the given scale. Nothing is actually rendered
+    /// until it's needed.
+    pub fn with_starting_scale(scale: f32, options: &GlyphOptions, fonts: &Fonts) -> Self {
         Self {
-            cache: Cache::generate(scale, &dni_font, &ascii_font),
-            // dni_font,
-            // ascii_font,
+            cache: Cache::generate(scale, options.clone(), &fonts.dni, &fonts.ascii),
+            label_cache: LabelCache::new(scale * labels::SCALE_FRACTION),
+            dni_font: fonts.dni.clone(),
+            ascii_font: fonts.ascii.clone(),
+            fonts_hash: fonts.hash,
+            warming_up: None,
+            disk_cache: None,
+            unsaved: false,
+            fg: colors::FG,
+            bg: colors::BG,
+            scaled: None,
         }
     }
 
-    // /// Change the text scale of the glyphs
-    // pub fn rescale(&mut self, scale: f32) {
-    //     if self.cache.scale != scale {
-    //         self.cache = Cache::generate(scale, &self.dni_font, &self.ascii_font);
-    //     }
-    // }
+    /// These glyphs, but at `scale`, for drawing part of the face bigger or
+    /// smaller than the rest (like the hour of `--face watch`), in the same
+    /// colors. They're kept, so it's only slow the first time at a scale.
+    pub fn at_scale(&mut self, scale: f32) -> &mut Glyphs {
+        if scale == self.cache.scale {
+            return self;
+        }
+        if self.scaled.as_ref().is_none_or(|scaled| scaled.cache.scale != scale) {
+            let fonts = Fonts { dni: self.dni_font.clone(), ascii: self.ascii_font.clone(), hash: self.fonts_hash };
+            self.scaled = Some(Box::new(Self::with_starting_scale(scale, &self.cache.options, &fonts)));
+        }
+        let (fg, bg) = (self.fg, self.bg);
+        let scaled = self.scaled.as_mut().expect("made above if it wasn't there");
+        scaled.set_colors(fg, bg);
+        scaled
+    }
 
-    /// Get a single-digit numeral (0-24)
-    pub fn get_dni_number_one_digit(&self, n: u8) -> &GlyphBuffer {
-        &self.cache.dni_digits[usize::from(n)]
+    /// Draws glyphs in `fg` on `bg` from the next frame on. The glyphs are
+    /// cached as coverage, not colors, so nothing has to be rendered again,
+    /// and it's cheap enough to do every frame (say, to fade between them).
+    pub fn set_colors(&mut self, fg: Color, bg: Color) {
+        self.fg = fg;
+        self.bg = bg;
     }
 
-    /// Get a numeral, padded to two digits (00-59)
-    pub fn get_dni_number_two_digits(&mut self, n: u8) -> &GlyphBuffer {
+    /// What color glyphs are drawn in.
+    pub fn fg(&self) -> Color {
+        self.fg
+    }
+
+    /// What color glyphs are drawn on.
+    pub fn bg(&self) -> Color {
+        self.bg
+    }
+
+    /// Loads whatever glyphs a previous run saved at this scale, and sets up
+    /// [`Self::save_to_disk`] to save any new ones.
+    pub fn use_disk_cache(&mut self) {
+        let Some(path) = disk_cache::path(self.fonts_hash, self.cache.scale, &self.cache.options) else { return };
+        let cache = &mut self.cache;
+        for (entry, glyph) in disk_cache::load(&path) {
+            let slot = match entry {
+                Entry::Digit(n) => &mut cache.dni_digits[usize::from(n)],
+                Entry::Numeral(n) => &mut cache.dni_numerals[usize::from(n)],
+            };
+            *slot = Some(cache.atlas.insert(&glyph));
+        }
+        self.disk_cache = Some(path);
+    }
+
+    /// Saves everything rendered so far, if there's anything new and
+    /// [`Self::use_disk_cache`] was called.
+    pub fn save_to_disk(&mut self) {
+        let Some(path) = &self.disk_cache else { return };
+        if !self.unsaved {
+            return;
+        }
+        let cache = &self.cache;
+        let digits = cache.dni_digits.iter().enumerate()
+            .filter_map(|(n, rect)| Some((Entry::Digit(n as u8), (*rect)?)));
+        let numerals = cache.dni_numerals.iter().enumerate()
+            .filter_map(|(n, rect)| Some((Entry::Numeral(n as u8), (*rect)?)));
+        let entries = digits.chain(numerals).map(|(entry, rect)| (entry, cache.atlas.get(rect)));
+        disk_cache::save(path, entries);
+        self.unsaved = false;
+    }
+
+    /// Renders all the digits now, instead of as they come up.
+    pub fn warm_up(&mut self) {
+        for n in 0..self.cache.options.numerals.base() {
+            self.digit(n);
+        }
+    }
+
+    /// Starts rendering all the digits on another thread, so they're ready by
+    /// the time they're needed without holding anything up in the meantime.
+    /// Any that are needed before then get rendered on the spot, as usual.
+    ///
+    /// Does nothing if it's already been started.
+    pub fn warm_up_in_background(&mut self) {
+        if self.warming_up.is_some() {
+            return;
+        }
+        let (scale, stroke_delta) = (self.cache.scale, self.cache.stroke_delta);
+        let options = self.cache.options.clone();
+        let (dni_font, ascii_font) = (self.dni_font.clone(), self.ascii_font.clone());
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let numerals = options.numerals;
+            for n in 0..numerals.base() {
+                let c = numerals.glyph(n).expect("every digit below the base has a glyph");
+                let _span = trace_span!("render").entered();
+                let digit = render_styled_glyph(&[&dni_font, &ascii_font], c, scale, &options, stroke_delta);
+                if sender.send((n, digit)).is_err() {
+                    // Nobody's listening anymore (e.g., it was rescaled)
+                    break;
+                }
+            }
+        });
+        self.warming_up = Some(receiver);
+    }
+
+    /// Get a single digit (0-24 in D'ni, or 25 for midnight). Panics if the
+    /// numeral system has no glyph for it.
+    pub fn get_dni_number_one_digit(&mut self, n: u8) -> MaskView<'_> {
+        let rect = self.digit(n);
+        self.cache.atlas.get(rect)
+    }
+
+    /// Get a numeral (00-59), padded to as many digits as 59 takes (two, in
+    /// D'ni)
+    pub fn get_dni_number_two_digits(&mut self, n: u8) -> MaskView<'_> {
+        let numerals = self.cache.options.numerals;
+        if numerals.padded_len() == 1 {
+            return self.get_dni_number_one_digit(n);
+        }
+        let rect = match self.cache.dni_numerals[usize::from(n)] {
+            Some(rect) => rect,
+            None => {
+                let [digit2, digit1] = *numerals.digits(n.into(), 2) else {
+                    unreachable!("59 takes two digits");
+                };
+                let (digit1, digit2) = (self.digit(digit1), self.digit(digit2));
+
+                let cache = &mut self.cache;
+                cache.compose_numeral(digit1, digit2);
+                let rect = cache.atlas.insert(&cache.scratch);
+                cache.dni_numerals[usize::from(n)] = Some(rect);
+                self.unsaved = true;
+                rect
+            }
+        };
+        self.cache.atlas.get(rect)
+    }
+
+    /// Where the digit `n` is in the atlas, rendering it first if need be.
+    fn digit(&mut self, n: u8) -> Rect {
+        self.collect_warmed_up();
+        let cache = &mut self.cache;
+        *cache.dni_digits[usize::from(n)].get_or_insert_with(|| {
+            let fonts = [&self.dni_font, &self.ascii_font];
+            let c = cache.options.numerals.glyph(n).expect("no glyph for that digit");
+            let _span = trace_span!("render").entered();
+            let digit = render_styled_glyph(&fonts, c, cache.scale, &cache.options, cache.stroke_delta);
+            self.unsaved = true;
+            cache.atlas.insert(&digit)
+        })
+    }
+
+    /// Whether every digit is rendered, so drawing won't hold anything up.
+    /// (Digits past the base, like D'ni 25, don't count; they're only shown at
+    /// midnight, and only if asked for.)
+    pub fn warmed_up(&mut self) -> bool {
+        self.collect_warmed_up();
+        let base = usize::from(self.cache.options.numerals.base());
+        self.cache.dni_digits[..base].iter().all(Option::is_some)
+    }
+
+    /// How far along rendering all the digits is, from 0.0 to 1.0 (when
+    /// [`Self::warmed_up`]).
+    pub fn warm_up_progress(&mut self) -> f32 {
+        self.collect_warmed_up();
+        let base = usize::from(self.cache.options.numerals.base());
+        let done = self.cache.dni_digits[..base].iter().filter(|digit| digit.is_some()).count();
+        done as f32 / base as f32
+    }
+
+    /// Renders `word` in the D'ni font at `scale`, spelled out the way the
+    /// font expects (its letters stand in for the D'ni ones), for a caption
+    /// in D'ni script. It's not cached, so it's for one-offs.
+    pub fn render_dni_word(&self, word: &str, scale: f32) -> MaskBuffer {
+        let _span = trace_span!("render").entered();
+        render_scaled_text(&self.dni_font, word, scale)
+    }
+
+    /// Adds whatever digits the warm-up thread has finished to the cache.
+    fn collect_warmed_up(&mut self) {
+        let Some(receiver) = &self.warming_up else { return };
         let cache = &mut self.cache;
-        cache
-            .dni_numerals[usize::from(n)]
-            .get_or_insert_with(|| Cache::compose_numeral(cache.scale, &cache.dni_digits, n))
+        for (n, digit) in receiver.try_iter() {
+            let slot = &mut cache.dni_digits[usize::from(n)];
+            if slot.is_none() {
+                *slot = Some(cache.atlas.insert(&digit));
+                self.unsaved = true;
+            }
+        }
+    }
+
+    /// Get the midnight mark: the frame of a zero, with nothing in it
+    pub fn get_midnight_mark(&mut self) -> MaskView<'_> {
+        let mark_box = self.mark_box();
+        let cache = &mut self.cache;
+        let rect = *cache.midnight_mark.get_or_insert_with(|| {
+            let _span = trace_span!("render").entered();
+            let (width, height, stroke) = mark_box;
+            let mark = style_glyph(render_box(width, height, stroke), &cache.options, cache.stroke_delta);
+            cache.atlas.insert(&mark)
+        });
+        cache.atlas.get(rect)
+    }
+
+    /// Get the sign a T-minus countdown is shown with: a bar for minus, and a
+    /// cross for plus, as tall as a digit
+    pub fn get_sign(&mut self, sign: Sign) -> MaskView<'_> {
+        let mark_box = self.mark_box();
+        let cache = &mut self.cache;
+        let rect = *cache.signs[sign as usize].get_or_insert_with(|| {
+            let _span = trace_span!("render").entered();
+            let sign = render_sign(sign, sign_box(mark_box));
+            let sign = style_glyph(sign, &cache.options, cache.stroke_delta);
+            cache.atlas.insert(&sign)
+        });
+        cache.atlas.get(rect)
     }
 
     /// Get a colon (`':'`) glyph
-    pub fn get_colon(&self) -> &GlyphBuffer {
-        &self.cache.colon
+    pub fn get_colon(&self) -> MaskView<'_> {
+        self.cache.atlas.get(self.cache.colon)
+    }
+
+    /// How far the digit `right` overlaps the digit `left` (0-24 each), when
+    /// `left` comes right before it in a numeral.
+    pub fn overlap(&mut self, left: u8, right: u8) -> usize {
+        let (left, right) = (self.digit(left), self.digit(right));
+        self.cache.overlap(left, right)
+    }
+
+    /// The settings the glyphs were rendered with.
+    pub fn options(&self) -> &GlyphOptions {
+        &self.cache.options
+    }
+
+    /// The outline of the digit `n` (0-25), as an SVG path lined up with its
+    /// raster glyph.
+    pub fn dni_digit_path(&self, n: u8) -> String {
+        let c = self.cache.options.numerals.glyph(n).expect("no glyph for that digit");
+        self.path(c, &[&self.dni_font, &self.ascii_font])
+    }
+
+    /// The outline of the midnight mark, as an SVG path lined up with its
+    /// raster glyph.
+    pub fn midnight_mark_path(&self) -> String {
+        let (width, height, stroke) = self.mark_box();
+        svg::box_path(width as f32, height as f32, stroke as f32)
+    }
+
+    /// The outline of a sign, as an SVG path lined up with its raster glyph.
+    pub fn sign_path(&self, sign: Sign) -> String {
+        let (width, height, stroke) = sign_box(self.mark_box());
+        let (top, left, bar_top) = sign_bars(width, height, stroke);
+        let f = |n: usize| n as f32;
+        svg::sign_path(sign, f(width), f(top), f(left), f(bar_top), f(stroke))
+    }
+
+    /// The size of the midnight mark, and how thick its walls are (see
+    /// [`midnight_mark_box`]), worked out the first time it's needed.
+    fn mark_box(&self) -> (usize, usize, usize) {
+        *self.cache.mark_box.get_or_init(|| {
+            midnight_mark_box(&[&self.dni_font, &self.ascii_font], self.cache.scale, &self.cache.options)
+        })
+    }
+
+    /// The outline of a colon, as an SVG path lined up with its raster glyph.
+    pub fn colon_path(&self) -> String {
+        self.path(':', &[&self.ascii_font, &self.dni_font])
+    }
+
+    /// The outline of a line of `text` at label size, as an SVG path lined up
+    /// with its raster label.
+    pub fn label_path(&self, text: &str) -> String {
+        let scale = self.label_cache.scale;
+        let mut positioned = Vec::new();
+        layout_text(&self.ascii_font, text, scale, |glyph| positioned.push(glyph));
+        svg::text_path(&self.ascii_font, &positioned, scale)
+    }
+
+    /// The outline of `c`, from the first of `fonts` that has it, as an SVG path.
+    fn path(&self, c: char, fonts: &[&FontArc]) -> String {
+        let scale = self.cache.scale;
+        svg::glyph_path(fonts, c, scale).unwrap_or_else(|| svg::tofu_path(scale))
+    }
+
+    /// Get a line of plain text at label size
+    pub fn get_label(&mut self, text: &str) -> &MaskBuffer {
+        let cache = &mut self.label_cache;
+        // Not `entry`, which would need an owned key even when it's there
+        if !cache.text.contains_key(text) {
+            let options = &self.cache.options;
+            let mut label = if options.pixel_scale.is_some() {
+                let factor = label_pixel_factor(options, self.cache.scale, cache.scale);
+                synth::pixelate(&render_scaled_text(&self.ascii_font, text, cache.scale / factor as f32), factor)
+            } else {
+                render_scaled_text(&self.ascii_font, text, cache.scale)
+            };
+            if options.high_contrast {
+                synth::threshold(&mut label);
+            }
+            cache.text.insert(text.to_owned(), label);
+        }
+        &cache.text[text]
+    }
+
+    /// How wide `text` would be, at label size.
+    pub fn measure_label(&self, text: &str) -> usize {
+        let factor = label_pixel_factor(&self.cache.options, self.cache.scale, self.label_cache.scale);
+        let scale = self.label_cache.scale / factor as f32;
+        layout_text(&self.ascii_font, text, scale, |_| {}).ceil() as usize * factor
+    }
+
+    /// Shortens `text` with an ellipsis, if needed, so that it fits in
+    /// `max_width` pixels at label size.
+    pub fn ellipsize_label<'a>(&self, text: &'a str, max_width: usize) -> Cow<'a, str> {
+        if self.measure_label(text) <= max_width {
+            return Cow::Borrowed(text);
+        }
+        let mut shortened = text.to_owned();
+        while shortened.pop().is_some() {
+            let candidate = format!("{}\u{2026}", shortened.trim_end());
+            if self.measure_label(&candidate) <= max_width {
+                return Cow::Owned(candidate);
+            }
+        }
+        Cow::Borrowed("")
+    }
+}
+
+/// Rendered label text. Labels are at a smaller scale than everything else, and
+/// there's no telling ahead of time what they'll say, so they get their own
+/// cache that fills up as they're asked for.
+struct LabelCache {
+    /// The amount the text is scaled by
+    scale: f32,
+    /// Whole lines of text, keyed by what they say
+    text: HashMap<String, MaskBuffer>,
+}
+
+impl LabelCache {
+    fn new(scale: f32) -> Self {
+        Self {
+            scale,
+            text: HashMap::new(),
+        }
     }
 }
 
 struct Cache {
     /// The amount the glyph is scaled by
     scale: f32,
-    /// The digits 0-24
-    dni_digits: [GlyphBuffer; 25],
-    /// Numerals from 00-59, padded to two digits
-    dni_numerals: [Option<GlyphBuffer>; 60],
+    options: GlyphOptions,
+    /// Where all the glyphs below actually are
+    atlas: Atlas,
+    /// The digits, 0-24 in D'ni (and 25, which only comes up at midnight, if
+    /// it's shown that way)
+    dni_digits: [Option<Rect>; MAX_DIGITS],
+    /// Numerals from 00-59, padded to two digits (if 59 takes two)
+    dni_numerals: [Option<Rect>; 60],
     /// ASCII colon `':'`
-    colon: GlyphBuffer,
+    colon: Rect,
+    /// The empty frame hour zero can be shown as
+    midnight_mark: Option<Rect>,
+    /// Its size and wall thickness, which signs are sized from too
+    mark_box: OnceCell<(usize, usize, usize)>,
+    /// Minus and plus, for T-minus countdowns
+    signs: [Option<Rect>; 2],
+    /// How far [`GlyphOptions::normalize_strokes`] moves each side of every
+    /// stroke at this scale (see [`stroke_delta`]).
+    stroke_delta: f32,
+    /// Where numerals are put together before they go in the atlas, kept
+    /// around so it doesn't have to be allocated again for each one.
+    scratch: MaskBuffer,
 }
 
 impl Cache {
-    /// Generates a cache with the given scale in the given fonts.
-    fn generate(scale: f32, dni_font: &impl Font, ascii_font: &impl Font) -> Self {
+    /// Generates a cache with the given scale. Only the colon is rendered up
+    /// front; the digits are filled in as they're needed.
+    fn generate(
+        scale: f32,
+        options: GlyphOptions,
+        dni_font: &FontArc,
+        ascii_font: &FontArc,
+    ) -> Self {
+        // Wide enough for a handful of numerals per shelf
+        let mut atlas = Atlas::new((scale * 8.0).ceil() as usize);
+        let span = trace_span!("render").entered();
+        let stroke_delta = stroke_delta(&[dni_font, ascii_font], scale, &options);
+        let colon = atlas.insert(&render_styled_glyph(&[ascii_font, dni_font], ':', scale, &options, stroke_delta));
+        // The digits and numerals will be filling in soon enough (they're
+        // roughly square, and numerals are about two digits wide)
+        let size = scale.ceil() as usize;
+        drop(span);
+        atlas.reserve(options.numerals.base().into(), size, size);
+        atlas.reserve(60, size * 2, size);
         Self {
             scale,
-            dni_digits: array::from_fn(|n|
-                render_scaled_glyph(dni_font, n_to_dni(n as u8).into(), scale)),
-            dni_numerals: array::from_fn(|_| None),
-            colon: render_scaled_glyph(ascii_font, ':', scale),
+            options,
+            atlas,
+            dni_digits: [None; MAX_DIGITS],
+            dni_numerals: [None; 60],
+            colon,
+            midnight_mark: None,
+            mark_box: OnceCell::new(),
+            signs: [None; 2],
+            stroke_delta,
+            scratch: MaskBuffer::default(),
         }
     }
 
-    /// Composes a two-digit D'ni numeral.
-    ///
-    /// This is a static method to avoid borrowing errors.
-    fn compose_numeral(scale: f32, dni_digits: &[GlyphBuffer; 25], n: u8) -> GlyphBuffer {
-        let digit1 = n % 25;
-        let digit2 = (n - digit1) / 25;
-        debug_assert_eq!(digit2 * 25 + digit1, n);
-        debug_assert!(digit2 < 25);
-
-        // Single digits are always cached
-        let digit1_buf = &dni_digits[usize::from(digit1)];
-        let digit2_buf = &dni_digits[usize::from(digit2)];
+    /// How far the digit at `right` overlaps the one at `left` when they're
+    /// side by side in a numeral.
+    fn overlap(&self, left: Rect, right: Rect) -> usize {
+        let (left_buf, right_buf) = (self.atlas.get(left), self.atlas.get(right));
+        // Slanted walls aren't straight up and down, so they're lined up as if
+        // they were upright. Both digits are the same height, so they were
+        // slanted the same amount, and the overlap just grows by that much.
+        let slant = self.options.slant;
+        let slant_width = synth::slant_width(right_buf.height(), slant);
+        if self.options.numerals.composition() == Composition::SideBySide {
+            // Only as far as slanting spread them apart
+            return slant_width.min(right_buf.width()).min(left_buf.width());
+        }
+        let upright = |digit: MaskView<'_>| synth::unslant(&digit.to_vec2d(), slant);
+        match self.options.digit_overlap {
+            Overlap::Auto if slant != 0.0 => {
+                auto_overlap(upright(left_buf).view(), upright(right_buf).view()) + slant_width
+            }
+            Overlap::Auto => auto_overlap(left_buf, right_buf),
+            // Too much overlap would have the digits passing right through each other
+            Overlap::Fixed(fraction) => (digit_overlap(self.scale, fraction) + slant_width)
+                .min(right_buf.width())
+                .min(left_buf.width()),
+        }
+    }
 
-        let overlap = digit_overlap(scale);
+    /// Composes a two-digit D'ni numeral out of the digits at `digit1` (the
+    /// ones place) and `digit2` (the twenty-fives place), into `self.scratch`.
+    fn compose_numeral(&mut self, digit1: Rect, digit2: Rect) {
+        let _span = trace_span!("compose").entered();
+        let digit1_buf = self.atlas.get(digit1);
+        let digit2_buf = self.atlas.get(digit2);
+        let overlap = self.overlap(digit2, digit1);
 
         let width = digit1_buf.width() + digit2_buf.width() - overlap;
-        let height = digit1_buf.height();
-        debug_assert_eq!(height, digit2_buf.height());
+        // D'ni digits are all the same height, but not every system's are
+        let height = digit1_buf.height().max(digit2_buf.height());
+        let mut buf = std::mem::take(&mut self.scratch);
+        buf.reset(0, width, height);
         let mut n_buf = TextBuffer {
-            buf: Vec2d::new(colors::BG, width, height),
+            buf,
             x: 0,
             y: 0,
             height,
@@ -186,51 +764,314 @@ impl Cache {
         n_buf.write_glyph_composing(digit2_buf);
         n_buf.x -= overlap;
         n_buf.write_glyph_composing(digit1_buf);
-        n_buf.buf
+        self.scratch = n_buf.buf;
     }
 }
 
-/// Renders `c` at `scale` in the `font`, to a an array of pixels.
+/// How many times over labels at `label_scale` are blown up, next to digits
+/// at `scale`: in blocks as big as the digits' if they'd still be readable.
+fn label_pixel_factor(options: &GlyphOptions, scale: f32, label_scale: f32) -> usize {
+    let readable = (label_scale / MIN_PIXEL_LABEL_SCALE).max(1.0) as usize;
+    options.pixel_factor(scale).min(readable)
+}
+
+/// Renders `c` like [`render_scaled_glyph`], then fakes whatever styles
+/// `options` asks for.
+fn render_styled_glyph(
+    fonts: &[&FontArc],
+    c: char,
+    scale: f32,
+    options: &GlyphOptions,
+    stroke_delta: f32,
+) -> MaskBuffer {
+    if options.pixel_scale.is_none() {
+        return style_glyph(render_scaled_glyph(fonts, c, scale), options, stroke_delta);
+    }
+    // Styled small, so strokes are thickened and slanted in whole blocks
+    let factor = options.pixel_factor(scale);
+    let small = render_scaled_glyph(fonts, c, scale / factor as f32);
+    synth::pixelate(&style_glyph(small, options, stroke_delta / factor as f32), factor)
+}
+
+/// Fakes whatever styles `options` asks for on an already rendered glyph,
+/// first moving the sides of its strokes by `stroke_delta` (from
+/// [`stroke_delta`]).
+fn style_glyph(glyph: MaskBuffer, options: &GlyphOptions, stroke_delta: f32) -> MaskBuffer {
+    let glyph = synth::normalize_strokes(&glyph, stroke_delta);
+    let glyph = synth::embolden(&glyph, options.stroke_weight());
+    let mut glyph = if options.slant == 0.0 {
+        glyph
+    } else {
+        synth::slant(&glyph, options.slant)
+    };
+    if options.high_contrast {
+        synth::threshold(&mut glyph);
+    }
+    glyph
+}
+
+/// Renders `c` at `scale` in the first of `fonts` that has it, to an array of
+/// pixels. If none of them do, it's drawn as a plain box instead, so at least
+/// there's something to look at. Either way, it warns about it (just the first
+/// time for each character, since it's rendered again at every new scale).
 ///
-/// Panics if `Font::outline_glyph` does, however that can happen.
-fn render_scaled_glyph(font: &impl Font, c: char, scale: f32) -> GlyphBuffer {
-    let glyph = font.glyph_id(c).with_scale(scale);
-    let glyph = font.outline_glyph(glyph).unwrap();
-    let width = glyph.px_bounds().width() as usize;
-    let height = glyph.px_bounds().height() as usize;
-    let mut buf = Vec2d::new(colors::BG, width, height);
+/// The same goes if drawing it goes wrong (a broken font, say, or a scale
+/// that makes no sense), so one bad glyph doesn't take the whole clock down.
+/// That's only warned about the first time, since it'll likely keep happening.
+fn render_scaled_glyph(fonts: &[&FontArc], c: char, scale: f32) -> MaskBuffer {
+    static FAILED: AtomicBool = AtomicBool::new(false);
+    static WARNED: Mutex<BTreeSet<char>> = Mutex::new(BTreeSet::new());
+    let first_time = || WARNED.lock().unwrap_or_else(|e| e.into_inner()).insert(c);
+    for (i, font) in fonts.iter().enumerate() {
+        match rasterize(*font, c, scale) {
+            Ok(Some(buf)) => {
+                if i > 0 && first_time() {
+                    eprintln!("warning: the font is missing {c:?}, so it's borrowed from another");
+                }
+                return buf;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                if !FAILED.swap(true, Ordering::Relaxed) {
+                    eprintln!(
+                        "warning: couldn't draw {c:?} at scale {scale} ({e}), so it's drawn as a box \
+                         (as is anything else that can't be)",
+                    );
+                }
+                return render_tofu(scale);
+            }
+        }
+    }
+    if first_time() {
+        eprintln!("warning: none of the fonts have {c:?}, so it's drawn as a box");
+    }
+    render_tofu(scale)
+}
+
+/// Checks that `c` can be drawn at `scale` in `fonts`, for `dni-clock doctor`,
+/// and whether it's the D'ni font's own (rather than borrowed from the label
+/// font). It's an error if neither font has it, or it can't be drawn.
+pub fn check_glyph(fonts: &Fonts, c: char, scale: f32) -> Result<bool, String> {
+    for (font, own) in [(&fonts.dni, true), (&fonts.ascii, false)] {
+        if rasterize(font, c, scale)?.is_some() {
+            return Ok(own);
+        }
+    }
+    Err("neither font has it".to_owned())
+}
+
+/// [`try_render_scaled_glyph`], but with a panic (from a font that's broken in
+/// some way nobody thought to check) turned into an error.
+fn rasterize(font: &impl Font, c: char, scale: f32) -> Result<Option<MaskBuffer>, String> {
+    panic::catch_unwind(AssertUnwindSafe(|| try_render_scaled_glyph(font, c, scale)))
+        .unwrap_or_else(|_| Err("it panicked".to_owned()))
+}
+
+/// Renders `c` at `scale` in the `font`, to an array of pixels, or `None` if
+/// the font doesn't have it. It's an error if it can't be drawn sensibly.
+fn try_render_scaled_glyph(font: &impl Font, c: char, scale: f32) -> Result<Option<MaskBuffer>, String> {
+    if !(scale.is_finite() && scale > 0.0) {
+        return Err("not a size anything can be drawn at".to_owned());
+    }
+    let id = font.glyph_id(c);
+    // ID 0 is `.notdef`, what fonts give for characters they don't have
+    if id.0 == 0 {
+        return Ok(None);
+    }
+    let Some(glyph) = font.outline_glyph(id.with_scale(scale)) else { return Ok(None) };
+    let bounds = glyph.px_bounds();
+    let (width, height) = (bounds.width(), bounds.height());
+    if !(width.is_finite() && height.is_finite()) || width.max(height) > MAX_GLYPH_SIDE as f32 {
+        return Err(format!("it came out {width}×{height}"));
+    }
+    let mut buf = Vec2d::new(0, width as usize, height as usize);
     glyph.draw(|x, y, c| {
-        buf[(x, y)] = colors::darken(colors::FG, c);
+        // A broken outline can stray outside its own bounds
+        if let Some(px) = buf.get_mut(x as usize, y as usize) {
+            *px = coverage(c);
+        }
     });
+    Ok(Some(buf))
+}
+
+/// Draws an empty box about the size of a digit at `scale`, to stand in for a
+/// glyph that couldn't be found anywhere (a.k.a. "tofu"), or drawn.
+fn render_tofu(scale: f32) -> MaskBuffer {
+    // Written so a scale that's NaN comes out as small as it goes
+    let width = (scale * 0.6).round().max(3.0).min(MAX_GLYPH_SIDE as f32) as usize;
+    let height = (scale * 0.8).round().max(3.0).min(MAX_GLYPH_SIDE as f32) as usize;
+    let stroke = (scale / 20.0).round().max(1.0).min((width / 3) as f32) as usize;
+    render_box(width, height, stroke)
+}
+
+/// How many pixels to thicken strokes by on each side (or thin them by, if
+/// it's negative) to bring the digits' to about [`NORMAL_STROKE`] of `scale`, if
+/// [`GlyphOptions::normalize_strokes`]. The font's own strokes are measured
+/// from its zero.
+fn stroke_delta(fonts: &[&FontArc], scale: f32, options: &GlyphOptions) -> f32 {
+    if !options.normalize_strokes {
+        return 0.0;
+    }
+    let (_, _, stroke) = midnight_mark_box(fonts, scale, options);
+    let growth = (scale / NORMAL_STROKE_SCALE).powf(NORMAL_STROKE_GROWTH);
+    let target = (scale * NORMAL_STROKE * growth).max(MIN_NORMAL_STROKE);
+    (target - stroke as f32) / 2.0
+}
+
+/// The size of the midnight mark at `scale`, and how thick its walls are: the
+/// same as the (unstyled) zero digit's, so it passes for one with its middle
+/// left out.
+fn midnight_mark_box(fonts: &[&FontArc], scale: f32, options: &GlyphOptions) -> (usize, usize, usize) {
+    let zero = options.numerals.glyph(0).expect("every system has a zero");
+    let zero = render_scaled_glyph(fonts, zero, scale);
+    let (width, height) = (zero.width(), zero.height());
+    // How thick the left wall is, halfway down
+    let wall = zero.row(height / 2).iter()
+        .skip_while(|&&px| px <= u8::MAX / 2)
+        .take_while(|&&px| px > u8::MAX / 2)
+        .count();
+    (width, height, wall.clamp(1, (width / 3).max(1)))
+}
+
+/// The size of a sign, and how thick its bars are, from the midnight mark's
+/// [`midnight_mark_box`]: as tall as a digit, two thirds as wide, and as thick
+/// as the mark's walls.
+fn sign_box((width, height, stroke): (usize, usize, usize)) -> (usize, usize, usize) {
+    ((width * 2 / 3).max(stroke * 3), height, stroke)
+}
+
+/// Where the bars of a sign `width`×`height` and `stroke` thick go: the top of
+/// the one across, and the left and top of the one up and down (which is as
+/// long as the sign is wide).
+fn sign_bars(width: usize, height: usize, stroke: usize) -> (usize, usize, usize) {
+    let top = height.saturating_sub(stroke) / 2;
+    let left = width.saturating_sub(stroke) / 2;
+    let bar_top = height.saturating_sub(width) / 2;
+    (top, left, bar_top)
+}
+
+/// Draws a sign, as big as [`sign_box`] says.
+fn render_sign(sign: Sign, (width, height, stroke): (usize, usize, usize)) -> MaskBuffer {
+    let mut buf = Vec2d::new(0, width, height);
+    let (top, left, bar_top) = sign_bars(width, height, stroke);
+    for y in top..(top + stroke).min(height) {
+        buf.row_mut(y).fill(u8::MAX);
+    }
+    if sign == Sign::Plus {
+        for y in bar_top..(bar_top + width).min(height) {
+            buf.row_mut(y)[left..(left + stroke).min(width)].fill(u8::MAX);
+        }
+    }
     buf
 }
 
-/// Converts a number to an ASCII character corresponding to a single D'ni digit.
+/// Draws an empty box `width`×`height`, with walls `stroke` thick.
+fn render_box(width: usize, height: usize, stroke: usize) -> MaskBuffer {
+    Vec2d::from_fn(width, height, |x, y| {
+        let on_edge = x < stroke || y < stroke || x >= width - stroke || y >= height - stroke;
+        if on_edge { u8::MAX } else { 0 }
+    })
+}
+
+/// Renders a line of `text` at `scale` in the `font`, to an array of pixels.
 ///
-/// Panics if the number is out of range (>25).
-fn n_to_dni(n: u8) -> u8 {
-    const DIGITS: &[u8] = b"\
-    0123456789\
-    )!@#$%^&*(\
-    []\\{}|\
-    ";
-    const _: () = assert!(DIGITS[0] == b'0');
-    const _: () = assert!(DIGITS[25] == b'|');
-    const _: () = assert!(DIGITS.len() == 26);
-    DIGITS[usize::from(n)]
+/// Unlike [`render_scaled_glyph`], the buffer is the full line height, with
+/// every character sitting on the same baseline, and characters without an
+/// outline (like spaces) are fine.
+fn render_scaled_text(font: &impl Font, text: &str, scale: f32) -> MaskBuffer {
+    let mut positioned = Vec::new();
+    let width = layout_text(font, text, scale, |glyph| positioned.push(glyph));
+    let font = font.as_scaled(scale);
+    let height = (font.ascent() - font.descent()).ceil() as usize;
+
+    let width = width.ceil() as usize;
+    let mut buf = Vec2d::new(0, width, height);
+    for glyph in positioned {
+        let Some(glyph) = font.outline_glyph(glyph) else { continue };
+        let bounds = glyph.px_bounds();
+        glyph.draw(|x, y, c| {
+            let x = (bounds.min.x as isize + x as isize) as usize;
+            let y = (bounds.min.y as isize + y as isize) as usize;
+            // Bits of italic or overhanging glyphs can poke out past the caret
+            // (and past the left, which wraps around to huge)
+            if let Some(px) = buf.get_mut(x, y) {
+                *px = (*px).max(coverage(c));
+            }
+        });
+    }
+    buf
 }
 
-/// The "walls" of consecutive digits overlap. This is the number of pixels to overlap.
-fn digit_overlap(scale: f32) -> usize {
-    (scale * 0.25).round() as usize
+/// Converts how much of a pixel an outline covers, from 0.0 to 1.0, to a
+/// [`MaskBuffer`] value.
+fn coverage(c: f32) -> u8 {
+    (c.clamp(0.0, 1.0) * f32::from(u8::MAX)).round() as u8
+}
+
+/// Positions each character of `text` along a line at `scale`, with the top
+/// of the line at 0, handing each to `place`. Returns how wide the line is.
+fn layout_text(font: &impl Font, text: &str, scale: f32, mut place: impl FnMut(Glyph)) -> f32 {
+    let font = font.as_scaled(scale);
+    let ascent = font.ascent();
+    let mut caret = 0.0;
+    let mut prev = None;
+    for c in text.chars() {
+        let id = font.glyph_id(c);
+        if let Some(prev) = prev {
+            caret += font.kern(prev, id);
+        }
+        place(id.with_scale_and_position(scale, point(caret, ascent)));
+        caret += font.h_advance(id);
+        prev = Some(id);
+    }
+    caret
 }
 
-/// Get the D'ni font from the binary.
-fn get_dni_font() -> FontRef<'static> {
-    FontRef::try_from_slice(include_bytes!("../fonts/Dni.ttf")).unwrap()
+/// The "walls" of consecutive digits overlap. This is the number of pixels to overlap.
+fn digit_overlap(scale: f32, factor: f32) -> usize {
+    (scale * factor).round() as usize
 }
 
-/// Get the regular font from the binary.
-fn get_ascii_font() -> FontRef<'static> {
-    FontRef::try_from_slice(include_bytes!("../fonts/Source_Sans_Pro/SourceSansPro-Regular.ttf")).unwrap()
+/// Works out how far `right` should overlap `left` for their walls to merge:
+/// the left wall of `right` lands right on top of the right wall of `left`. If
+/// that would have other strokes running into each other, it backs off until
+/// they don't.
+fn auto_overlap(left: MaskView<'_>, right: MaskView<'_>) -> usize {
+    /// Whether a pixel is part of a stroke, rather than background or a bit of
+    /// antialiasing.
+    fn is_ink(px: u8) -> bool {
+        // More than half covered
+        px > u8::MAX / 2
+    }
+
+    /// Whether most of column `x` is inked, like a wall is.
+    fn is_wall(glyph: MaskView<'_>, x: usize) -> bool {
+        let inked = (0..glyph.height()).filter(|&y| is_ink(glyph[(x, y)])).count();
+        inked * 5 >= glyph.height() * 3
+    }
+
+    let max = left.width().min(right.width());
+    let (Some(left_wall), Some(right_wall)) = (
+        (0..left.width()).rev().find(|&x| is_wall(left, x)),
+        (0..right.width()).find(|&x| is_wall(right, x)),
+    ) else {
+        // No walls to line up, so just butt them up against each other
+        return 0;
+    };
+    // Line up the outer edges of the walls, since they might not be equally thick
+    let aligned = (left.width() - left_wall + right_wall).min(max);
+
+    let height = left.height().min(right.height());
+    let collides = |overlap: usize| {
+        (0..overlap).any(|i| {
+            let left_x = left.width() - overlap + i;
+            let right_x = i;
+            // The walls are supposed to land on each other
+            if is_wall(left, left_x) || is_wall(right, right_x) {
+                return false;
+            }
+            (0..height).any(|y| is_ink(left[(left_x, y)]) && is_ink(right[(right_x, y)]))
+        })
+    };
+    (0..=aligned).rev().find(|&overlap| !collides(overlap)).unwrap_or(0)
 }
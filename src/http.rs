@@ -0,0 +1,55 @@
+//! Just enough HTTP to call webhooks: plain `http://`, one request to a
+//! connection, with no crate for it.
+
+use std::{
+    io::{Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+/// How long to wait on the server before giving up.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sends `json` to `url`, and returns the body of the answer.
+pub fn post_json(url: &str, json: &str) -> Result<String, String> {
+    request("POST", url, Some(json))
+}
+
+/// Asks `url` (which has to be plain `http://`) to do `method`, with `json`
+/// as the body if there is one, and returns the body of the answer.
+fn request(method: &str, url: &str, json: Option<&str>) -> Result<String, String> {
+    let rest = url.strip_prefix("http://").ok_or("only http:// URLs are supported")?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => rest.split_at(i),
+        None => (rest, "/"),
+    };
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().map_err(|_| format!("bad port `{port}`"))?),
+        None => (authority, 80),
+    };
+
+    let address = (host, port).to_socket_addrs()
+        .map_err(|e| format!("couldn't look up {host}: {e}"))?
+        .next()
+        .ok_or_else(|| format!("couldn't look up {host}"))?;
+    let mut stream = TcpStream::connect_timeout(&address, TIMEOUT).map_err(|e| e.to_string())?;
+    stream.set_read_timeout(Some(TIMEOUT)).map_err(|e| e.to_string())?;
+    // HTTP/1.0, so the answer isn't chunked and the connection closes after
+    let mut head = format!("{method} {path} HTTP/1.0\r\nHost: {authority}\r\nUser-Agent: dni-clock\r\n");
+    if let Some(json) = json {
+        head.push_str(&format!("Content-Type: application/json\r\nContent-Length: {}\r\n", json.len()));
+    }
+    head.push_str("\r\n");
+    head.push_str(json.unwrap_or(""));
+    stream.write_all(head.as_bytes()).map_err(|e| e.to_string())?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).map_err(|e| e.to_string())?;
+
+    let (head, body) = response.split_once("\r\n\r\n").ok_or("the answer was cut off")?;
+    let status = head.lines().next().unwrap_or("");
+    // Webhooks often answer with no content, or just accepted
+    if !status.split_whitespace().nth(1).is_some_and(|code| code.starts_with('2')) {
+        return Err(format!("the server said `{status}`"));
+    }
+    Ok(body.to_owned())
+}
@@ -0,0 +1,85 @@
+//! Just enough of iCalendar (RFC 5545) to find out when events start and what
+//! they're called.
+//!
+//! Recurring events only count once, at their first start, and time zones
+//! named with `TZID` are taken to be local time.
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+/// An event from a calendar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub start: DateTime<Local>,
+    pub summary: String,
+}
+
+/// Every event in `ics` that says when it starts. Anything it can't make sense
+/// of is skipped.
+pub fn parse(ics: &str) -> Vec<Event> {
+    let mut events = Vec::new();
+    let mut current: Option<(Option<DateTime<Local>>, String)> = None;
+    for line in unfold(ics) {
+        let Some((name, value)) = line.split_once(':') else { continue };
+        // Parameters come after the name, like `DTSTART;VALUE=DATE`
+        let (name, params) = name.split_once(';').unwrap_or((name, ""));
+        match (name.to_ascii_uppercase().as_str(), &mut current) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VEVENT") => current = Some((None, String::new())),
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VEVENT") => {
+                if let Some((Some(start), summary)) = current.take() {
+                    events.push(Event { start, summary });
+                }
+            }
+            ("DTSTART", Some((start, _))) => *start = parse_date_time(value, params),
+            ("SUMMARY", Some((_, summary))) => *summary = unescape(value),
+            _ => {}
+        }
+    }
+    events
+}
+
+/// Joins lines that were folded (continued on the next line, which starts with
+/// a space or tab).
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_owned()),
+        }
+    }
+    lines
+}
+
+/// Parses a `DATE-TIME` (`20261018T090000`, with a `Z` for UTC) or, for
+/// all-day events, a `DATE` (`20261018`, taken as midnight).
+fn parse_date_time(value: &str, params: &str) -> Option<DateTime<Local>> {
+    let value = value.trim();
+    if params.to_ascii_uppercase().contains("VALUE=DATE") && !value.contains('T') {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return Local.from_local_datetime(&date.and_hms(0, 0, 0)).earliest();
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(Utc.from_utc_datetime(&time).with_timezone(&Local));
+    }
+    let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    Local.from_local_datetime(&time).earliest()
+}
+
+/// Undoes the backslash escapes in a text value.
+fn unescape(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => text.push(' '),
+            Some(c) => text.push(c),
+            None => {}
+        }
+    }
+    text
+}
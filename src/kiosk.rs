@@ -0,0 +1,257 @@
+//! Kiosk mode: a fullscreen clock for a dedicated display, that a passerby
+//! can't easily close.
+
+use std::{io, process::Command};
+
+use minifb::{HasRawWindowHandle, Key, KeyRepeat, Window};
+use raw_window_handle::RawWindowHandle;
+
+use crate::placement::Monitor;
+
+/// What fraction of the screen the clock may take up, each way.
+const FILL: f32 = 0.8;
+
+/// Whether the (deliberately awkward) key combo for leaving kiosk mode,
+/// Ctrl+Shift+Q, was just pressed.
+pub fn exit_requested(window: &Window) -> bool {
+    let ctrl = window.is_key_down(Key::LeftCtrl) || window.is_key_down(Key::RightCtrl);
+    let shift = window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift);
+    ctrl && shift && window.is_key_pressed(Key::Q, KeyRepeat::No)
+}
+
+/// The biggest scale at which a clock of the given size (as a function of
+/// scale) still fits comfortably on `monitor`.
+pub fn fit_scale(monitor: &Monitor, window_size: impl Fn(f32) -> (usize, usize)) -> f32 {
+    let width = (monitor.width as f32 * FILL) as usize;
+    let height = (monitor.height as f32 * FILL) as usize;
+    fit_scale_within(width, height, window_size)
+}
+
+/// The biggest scale at which a clock of the given size (as a function of
+/// scale) fits in `width`×`height`, or 1 if nothing does.
+pub fn fit_scale_within(width: usize, height: usize, window_size: impl Fn(f32) -> (usize, usize)) -> f32 {
+    // The size is linear-ish in the scale, so measure at a reference scale and
+    // extrapolate, rather than searching
+    const REFERENCE: f32 = 100.0;
+    let (reference_width, reference_height) = window_size(REFERENCE);
+    let fit_x = width as f32 / reference_width as f32;
+    let fit_y = height as f32 / reference_height as f32;
+    let mut scale = (REFERENCE * fit_x.min(fit_y)).floor().max(1.0);
+    // Only "-ish", though (the margins don't grow with it), so it can
+    // overshoot by a little
+    let fits = |scale| {
+        let (w, h) = window_size(scale);
+        w <= width && h <= height
+    };
+    while scale > 1.0 && !fits(scale) {
+        scale -= 1.0;
+    }
+    scale
+}
+
+/// Keeps the screen from blanking, sleeping, or the screensaver from kicking
+/// in, for as long as it's held, the way each platform means that to be done:
+///
+/// - On X11, `xdg-screensaver suspend`, which asks the desktop through
+///   `org.freedesktop.ScreenSaver` (or whatever it has), and lets go by
+///   itself if the window goes away.
+/// - Elsewhere on Linux (like Wayland), `org.freedesktop.ScreenSaver` itself
+///   over D-Bus, or failing that an idle lock from logind. Either's let go by
+///   itself if this process is gone.
+/// - On macOS, `caffeinate`, which holds a display sleep assertion (and
+///   watches for this process to be gone).
+/// - On Windows, `SetThreadExecutionState`.
+///
+/// It's let go when dropped, or held for another window.
+pub struct ScreenBlankInhibitor {
+    held: Option<Held>,
+}
+
+/// An inhibition, and what it's for.
+struct Held {
+    /// The window it was taken out for, so it can be taken out again for a
+    /// new one. (Only X11 cares.)
+    window: u64,
+    how: How,
+}
+
+enum How {
+    /// With `xdg-screensaver`, for the window.
+    XdgScreensaver,
+    /// For as long as a helper that holds it keeps running.
+    #[cfg(target_os = "macos")]
+    Helper(std::process::Child),
+    #[cfg(all(unix, not(target_os = "macos")))]
+    DBus(dbus::Inhibition),
+    #[cfg(windows)]
+    ExecutionState,
+    /// It couldn't be, so it's not tried again for the same window.
+    Failed,
+}
+
+impl ScreenBlankInhibitor {
+    pub fn new() -> Self {
+        Self { held: None }
+    }
+
+    /// Holds it for `window`, if it isn't already. Cheap enough to call every
+    /// time around, in case the window's been opened again.
+    pub fn hold_for(&mut self, window: &Window) {
+        let id = match window.raw_window_handle() {
+            RawWindowHandle::Xlib(handle) => handle.window,
+            RawWindowHandle::Xcb(handle) => handle.window.into(),
+            _ => 0,
+        };
+        if self.held.as_ref().is_some_and(|held| held.window == id) {
+            return;
+        }
+        self.release();
+        match inhibit(id) {
+            Ok(how) => self.held = Some(Held { window: id, how }),
+            Err(e) => {
+                eprintln!("warning: couldn't keep the screen awake: {e}");
+                self.held = Some(Held { window: id, how: How::Failed });
+            }
+        }
+    }
+
+    /// Lets the screen blank again.
+    pub fn release(&mut self) {
+        let Some(held) = self.held.take() else { return };
+        match held.how {
+            How::XdgScreensaver => {
+                let _ = Command::new("xdg-screensaver").args(["resume", &held.window.to_string()]).status();
+            }
+            #[cfg(target_os = "macos")]
+            How::Helper(mut child) => {
+                let _ = child.kill();
+                let _ = child.wait();
+            }
+            #[cfg(all(unix, not(target_os = "macos")))]
+            How::DBus(inhibition) => inhibition.release(),
+            #[cfg(windows)]
+            How::ExecutionState => execution_state::release(),
+            How::Failed => {}
+        }
+    }
+}
+
+impl Drop for ScreenBlankInhibitor {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+/// What the helpers holding it say, to anyone who asks why the screen won't
+/// sleep.
+const WHO: &str = "D'ni Clock";
+const WHY: &str = "Showing the time";
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn inhibit(window: u64) -> io::Result<How> {
+    if window != 0 && crate::util::on_path("xdg-screensaver") {
+        let status = Command::new("xdg-screensaver").args(["suspend", &window.to_string()]).status()?;
+        if status.success() {
+            return Ok(How::XdgScreensaver);
+        }
+    }
+    dbus::inhibit().map(How::DBus).map_err(io::Error::other)
+}
+
+#[cfg(target_os = "macos")]
+fn inhibit(_window: u64) -> io::Result<How> {
+    // Until it's stopped, or this process is gone
+    Command::new("caffeinate")
+        .args(["-d", "-i", "-w", &std::process::id().to_string()])
+        .spawn()
+        .map(How::Helper)
+}
+
+#[cfg(windows)]
+fn inhibit(_window: u64) -> io::Result<How> {
+    execution_state::hold();
+    Ok(How::ExecutionState)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn inhibit(_window: u64) -> io::Result<How> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// Asking over D-Bus, which is let go of when this process's connection to the
+/// bus (or the file logind hands back) is closed, however it ends.
+#[cfg(all(unix, not(target_os = "macos")))]
+mod dbus {
+    use zbus::{
+        blocking::{Connection, Proxy},
+        zvariant::OwnedFd,
+    };
+
+    use super::{WHO, WHY};
+
+    pub enum Inhibition {
+        /// From the desktop, until it's lifted or the connection's closed.
+        ScreenSaver { connection: Connection, cookie: u32 },
+        /// An idle lock from logind, until the file's closed.
+        Logind { _lock: OwnedFd },
+    }
+
+    impl Inhibition {
+        pub fn release(self) {
+            if let Self::ScreenSaver { connection, cookie } = &self {
+                let _ = screensaver(connection).and_then(|proxy| proxy.call::<_, _, ()>("UnInhibit", &(cookie,)));
+            }
+        }
+    }
+
+    /// Asks the desktop, or failing that logind.
+    pub fn inhibit() -> zbus::Result<Inhibition> {
+        let from_desktop = Connection::session().and_then(|connection| {
+            let cookie = screensaver(&connection)?.call("Inhibit", &(WHO, WHY))?;
+            Ok(Inhibition::ScreenSaver { connection, cookie })
+        });
+        from_desktop.or_else(|_| {
+            let connection = Connection::system()?;
+            let logind = Proxy::new(
+                &connection,
+                "org.freedesktop.login1",
+                "/org/freedesktop/login1",
+                "org.freedesktop.login1.Manager",
+            )?;
+            Ok(Inhibition::Logind { _lock: logind.call("Inhibit", &("idle", WHO, WHY, "block"))? })
+        })
+    }
+
+    fn screensaver(connection: &Connection) -> zbus::Result<Proxy<'_>> {
+        Proxy::new(
+            connection,
+            "org.freedesktop.ScreenSaver",
+            "/org/freedesktop/ScreenSaver",
+            "org.freedesktop.ScreenSaver",
+        )
+    }
+}
+
+/// Windows' own way, which lasts until it's said otherwise (on the same
+/// thread).
+#[cfg(windows)]
+mod execution_state {
+    const ES_CONTINUOUS: u32 = 0x8000_0000;
+    const ES_SYSTEM_REQUIRED: u32 = 0x0000_0001;
+    const ES_DISPLAY_REQUIRED: u32 = 0x0000_0002;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn SetThreadExecutionState(flags: u32) -> u32;
+    }
+
+    pub fn hold() {
+        // SAFETY: It only takes flags, and can't fail in a way that matters
+        unsafe { SetThreadExecutionState(ES_CONTINUOUS | ES_SYSTEM_REQUIRED | ES_DISPLAY_REQUIRED) };
+    }
+
+    pub fn release() {
+        // SAFETY: As above
+        unsafe { SetThreadExecutionState(ES_CONTINUOUS) };
+    }
+}
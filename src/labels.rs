@@ -0,0 +1,89 @@
+//! Little captions over or under the clock's fields, like "surface" or "UTC".
+
+use std::str::FromStr;
+
+/// How big label text is, as a fraction of the clock's scale.
+pub const SCALE_FRACTION: f32 = 0.3;
+
+/// One of the numbers on the clock face.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Hours,
+    Minutes,
+    Seconds,
+    Gartahvo,
+    Tahvo,
+    Gorahn,
+}
+
+impl FromStr for Field {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hours" => Ok(Self::Hours),
+            "minutes" => Ok(Self::Minutes),
+            "seconds" => Ok(Self::Seconds),
+            "gartahvo" => Ok(Self::Gartahvo),
+            "tahvo" => Ok(Self::Tahvo),
+            "gorahn" => Ok(Self::Gorahn),
+            _ => Err("expected `hours`, `minutes`, `seconds`, `gartahvo`, `tahvo`, or `gorahn`".to_owned()),
+        }
+    }
+}
+
+/// Which side of the numbers the labels go on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Position {
+    #[default]
+    Above,
+    Below,
+}
+
+impl FromStr for Position {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "above" => Ok(Self::Above),
+            "below" => Ok(Self::Below),
+            _ => Err("expected `above` or `below`".to_owned()),
+        }
+    }
+}
+
+/// The text for each field's label, as given by `--label hours=surface` and so on.
+#[derive(Debug, Clone, Default)]
+pub struct Labels {
+    text: Vec<(Field, String)>,
+    pub position: Position,
+}
+
+impl Labels {
+    pub fn get(&self, field: Field) -> Option<&str> {
+        self.text.iter()
+            .find(|(f, _)| *f == field)
+            .map(|(_, text)| text.as_str())
+    }
+
+    /// Sets the label for `field`, replacing any it already had.
+    pub fn set(&mut self, field: Field, text: String) {
+        self.text.retain(|(f, _)| *f != field);
+        self.text.push((field, text));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+}
+
+/// The line height of label text, for the clock's `scale`.
+pub fn line_height(scale: f32) -> usize {
+    (scale * SCALE_FRACTION).round() as usize
+}
+
+/// Parses a `field=text` pair.
+pub fn parse_label(s: &str) -> Result<(Field, String), String> {
+    let (field, text) = s.split_once('=').ok_or("expected `field=text`")?;
+    Ok((field.parse()?, text.to_owned()))
+}
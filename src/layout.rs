@@ -0,0 +1,288 @@
+//! Arranging things without keeping track of where each piece goes by hand.
+//!
+//! A [`Line`] is numbers and text side by side: say what's in it, where it
+//! goes, and how it lines up, and [`Line::render_into`] works out the rest.
+//!
+//! ```ignore
+//! Line::new()
+//!     .number(hours)
+//!     .sep()
+//!     .number(minutes)
+//!     .small("until lunch")
+//!     .within(x, y, width, height)
+//!     .align_center()
+//!     .render_into(canvas, glyphs);
+//! ```
+//!
+//! A [`Container`] is for bigger pieces (whole clocks, complications): boxes
+//! of known sizes, in a row or a column, which can themselves be containers.
+//! It works out how big the whole thing is, and where each box ends up.
+
+use std::borrow::Cow;
+
+use crate::{
+    atlas::Rect,
+    frame::{Canvas, Number},
+    glyphs::Glyphs,
+};
+
+/// Something in a [`Line`].
+enum Item<'a> {
+    Number(Number),
+    /// A colon.
+    Sep,
+    /// Text at label size.
+    Small(Cow<'a, str>),
+}
+
+impl Item<'_> {
+    /// How big its raster glyph is.
+    fn size(&self, glyphs: &mut Glyphs) -> (usize, usize) {
+        let glyph = match self {
+            Self::Number(number) => number.raster(glyphs),
+            Self::Sep => glyphs.get_colon(),
+            Self::Small(text) => glyphs.get_label(text).view(),
+        };
+        (glyph.width(), glyph.height())
+    }
+}
+
+/// A line of numbers, colons, and text, side by side, each centered
+/// vertically in the line.
+#[derive(Default)]
+pub struct Line<'a> {
+    items: Vec<Item<'a>>,
+    x: usize,
+    y: usize,
+    /// How much room there is. Text past the right edge is cut off.
+    width: Option<usize>,
+    height: Option<usize>,
+    align: Align,
+}
+
+impl<'a> Line<'a> {
+    /// An empty line, at the top left of the canvas, with as much room as it
+    /// needs.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a D'ni number.
+    pub fn number(mut self, number: Number) -> Self {
+        self.items.push(Item::Number(number));
+        self
+    }
+
+    /// Adds a colon.
+    pub fn sep(mut self) -> Self {
+        self.items.push(Item::Sep);
+        self
+    }
+
+    /// Adds some text, at label size.
+    pub fn small(mut self, text: impl Into<Cow<'a, str>>) -> Self {
+        self.items.push(Item::Small(text.into()));
+        self
+    }
+
+    /// Puts the line in the `width`×`height` box with its top left at `x`,
+    /// `y`, instead of wherever it fits at the top left of the canvas.
+    pub fn within(mut self, x: usize, y: usize, width: usize, height: usize) -> Self {
+        (self.x, self.y) = (x, y);
+        (self.width, self.height) = (Some(width), Some(height));
+        self
+    }
+
+    /// Centers the line in its box, rather than starting it at the left.
+    pub fn align_center(mut self) -> Self {
+        self.align = Align::Center;
+        self
+    }
+
+    /// Draws the line onto `canvas`.
+    pub fn render_into(&self, canvas: &mut (impl Canvas + ?Sized), glyphs: &mut Glyphs) {
+        let (mut line_width, mut line_height) = (0, 0);
+        for item in &self.items {
+            let (width, height) = item.size(glyphs);
+            line_width += width;
+            line_height = line_height.max(height);
+        }
+        let width = self.width.unwrap_or(line_width);
+        let height = self.height.unwrap_or(line_height);
+
+        let mut x = self.x + self.align.offset(line_width, width);
+        let right = self.x + width;
+        for item in &self.items {
+            let (item_width, item_height) = item.size(glyphs);
+            let y = self.y + height.saturating_sub(item_height) / 2;
+            match item {
+                Item::Number(number) => canvas.number(glyphs, *number, x, y),
+                Item::Sep => canvas.colon(glyphs, x, y),
+                Item::Small(text) => {
+                    let room = right.saturating_sub(x).min(item_width);
+                    canvas.label(glyphs, text, x, y, room, item_height);
+                }
+            }
+            x += item_width;
+        }
+    }
+}
+
+/// Where things go in more room than they need: a [`Line`] in its box, or
+/// children across a [`Container`] that are narrower (in a column) or shorter
+/// (in a row) than it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Align {
+    /// The left, or the top.
+    #[default]
+    Start,
+    Center,
+}
+
+impl Align {
+    /// How far into `room` something `size` big goes.
+    fn offset(self, size: usize, room: usize) -> usize {
+        match self {
+            Self::Start => 0,
+            Self::Center => room.saturating_sub(size) / 2,
+        }
+    }
+}
+
+/// Which way a [`Container`] runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Row,
+    Column,
+}
+
+/// Something in a [`Container`].
+#[derive(Debug, Clone)]
+enum Child {
+    /// Just a box this big. It's up to whoever asked for it to fill it.
+    Leaf { width: usize, height: usize },
+    Container(Container),
+}
+
+impl Child {
+    fn size(&self) -> (usize, usize) {
+        match self {
+            Self::Leaf { width, height } => (*width, *height),
+            Self::Container(container) => container.size(),
+        }
+    }
+}
+
+/// Boxes side by side (an "hbox") or one above the other (a "vbox"), with
+/// `spacing` between them, and `padding` around the lot.
+#[derive(Debug, Clone)]
+pub struct Container {
+    direction: Direction,
+    children: Vec<Child>,
+    spacing: usize,
+    /// Around the sides and around the top and bottom.
+    padding: (usize, usize),
+    align: Align,
+}
+
+impl Container {
+    /// An empty row.
+    pub fn hbox() -> Self {
+        Self::new(Direction::Row)
+    }
+
+    /// An empty column.
+    pub fn vbox() -> Self {
+        Self::new(Direction::Column)
+    }
+
+    fn new(direction: Direction) -> Self {
+        Self { direction, children: Vec::new(), spacing: 0, padding: (0, 0), align: Align::Start }
+    }
+
+    /// Adds a box `width`×`height` (like one the size of a glyph buffer).
+    pub fn leaf(mut self, width: usize, height: usize) -> Self {
+        self.children.push(Child::Leaf { width, height });
+        self
+    }
+
+    /// Adds a whole other container.
+    pub fn nest(mut self, container: Container) -> Self {
+        self.children.push(Child::Container(container));
+        self
+    }
+
+    /// Leaves `spacing` pixels between children.
+    pub fn spacing(mut self, spacing: usize) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Leaves `horizontal` pixels at the left and right, and `vertical` at the
+    /// top and bottom.
+    pub fn padding(mut self, horizontal: usize, vertical: usize) -> Self {
+        self.padding = (horizontal, vertical);
+        self
+    }
+
+    /// Lines children up this way across the container.
+    pub fn align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// How big the whole thing is, padding included. Empty containers are
+    /// nothing at all, padding or not.
+    pub fn size(&self) -> (usize, usize) {
+        if self.children.is_empty() {
+            return (0, 0);
+        }
+        let (mut along, mut across) = (0, 0);
+        for child in &self.children {
+            let (child_along, child_across) = self.orient(child.size());
+            along += child_along;
+            across = usize::max(across, child_across);
+        }
+        along += self.spacing * (self.children.len() - 1);
+        let (width, height) = self.orient((along, across));
+        (width + self.padding.0 * 2, height + self.padding.1 * 2)
+    }
+
+    /// Where every leaf goes, depth first, with the container's top left at
+    /// `x`, `y`.
+    pub fn place(&self, x: usize, y: usize) -> Vec<Rect> {
+        let mut rects = Vec::new();
+        self.place_into(x, y, &mut rects);
+        rects
+    }
+
+    fn place_into(&self, x: usize, y: usize, rects: &mut Vec<Rect>) {
+        let (_, across) = self.orient(self.size());
+        let (pad_along, pad_across) = self.orient(self.padding);
+        let across = across.saturating_sub(pad_across * 2);
+        let (mut along, start_across) = self.orient((x, y));
+        along += pad_along;
+        for child in &self.children {
+            let size = child.size();
+            let (child_along, child_across) = self.orient(size);
+            let child_start = start_across + pad_across + self.align.offset(child_across, across);
+            let (child_x, child_y) = self.orient((along, child_start));
+            match child {
+                Child::Leaf { width, height } => {
+                    rects.push(Rect { x: child_x, y: child_y, width: *width, height: *height });
+                }
+                Child::Container(container) => container.place_into(child_x, child_y, rects),
+            }
+            along += child_along + self.spacing;
+        }
+    }
+
+    /// Turns a width and height into how far along the container and how far
+    /// across it they go, or back again (it's the same swap either way).
+    fn orient(&self, (a, b): (usize, usize)) -> (usize, usize) {
+        match self.direction {
+            Direction::Row => (a, b),
+            Direction::Column => (b, a),
+        }
+    }
+}
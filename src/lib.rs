@@ -0,0 +1,75 @@
+//! The clock, for embedding.
+
+// Plenty of types have a `new` that starts something up (a thread, a timer),
+// which isn't what anyone would expect from `Default`
+#![allow(clippy::new_without_default)]
+
+#[cfg(test)]
+#[global_allocator]
+static ALLOCATOR: bench::CountingAllocator = bench::CountingAllocator;
+
+pub mod animation;
+pub mod atlas;
+pub mod battery;
+pub mod bench;
+pub mod buf2d;
+pub mod burn_in;
+pub mod calendar;
+pub mod chart;
+pub mod colors;
+pub mod complication;
+pub mod compositor;
+pub mod config;
+pub mod control;
+pub mod cursor;
+pub mod demo;
+pub mod discord;
+pub mod disk_cache;
+pub mod dnd;
+pub mod doctor;
+pub mod dni_time;
+pub mod drag;
+pub mod export;
+pub mod fonts;
+pub mod frame;
+pub mod glyphs;
+pub mod http;
+pub mod ics;
+pub mod kiosk;
+pub mod labels;
+pub mod layout;
+pub mod line;
+pub mod locale;
+pub mod lock_screen;
+pub mod ntp;
+pub mod numerals;
+pub mod output;
+pub mod pipe;
+pub mod placement;
+pub mod print;
+pub mod profiles;
+pub mod quiz;
+pub mod shapes;
+pub mod sound;
+pub mod speech;
+pub mod splash;
+pub mod stream;
+pub mod state;
+pub mod svg;
+pub mod svg_output;
+pub mod synth;
+pub mod system_stats;
+pub mod theme;
+pub mod tick;
+pub mod tick_log;
+pub mod time_source;
+pub mod timer;
+pub mod timing;
+pub mod title;
+pub mod toast;
+pub mod tooltip;
+pub mod util;
+pub mod weather;
+pub mod webhooks;
+pub mod window;
+pub mod zones;
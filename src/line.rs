@@ -0,0 +1,88 @@
+//! Loose numbers, outside of the clock face: a line of them with colons in
+//! between, each number a list of D'ni digits (most significant first) drawn
+//! as one numeral, however many digits it has.
+
+use crate::{
+    atlas::Rect,
+    buf2d::Vec2d,
+    colors,
+    glyphs::{GlyphBuffer, Glyphs, MaskBuffer, TextBuffer},
+    svg::Svg,
+};
+
+/// Where a glyph in a line goes.
+struct Placed {
+    /// The digit, or `None` for a colon.
+    digit: Option<u8>,
+    /// The top left of its raster glyph.
+    x: usize,
+    y: usize,
+}
+
+/// Works out where every glyph in `line` goes, the same way numerals are
+/// composed. Also returns how big the whole line is.
+fn lay_out(glyphs: &mut Glyphs, line: &[Vec<u8>]) -> (Vec<Placed>, usize, usize) {
+    let mut sizes = Vec::new();
+    for (i, number) in line.iter().enumerate() {
+        if i > 0 {
+            let colon = glyphs.get_colon();
+            sizes.push((None, colon.width(), colon.height(), 0));
+        }
+        for (j, &digit) in number.iter().enumerate() {
+            let overlap = if j > 0 { glyphs.overlap(number[j - 1], digit) } else { 0 };
+            let glyph = glyphs.get_dni_number_one_digit(digit);
+            sizes.push((Some(digit), glyph.width(), glyph.height(), overlap));
+        }
+    }
+
+    let height = sizes.iter().map(|&(_, _, height, _)| height).max().unwrap_or(0);
+    let mut x = 0;
+    let mut placed = Vec::with_capacity(sizes.len());
+    for (digit, glyph_width, glyph_height, overlap) in sizes {
+        x -= overlap;
+        // Centered vertically, like `TextBuffer` does
+        placed.push(Placed { digit, x, y: (height - glyph_height) / 2 });
+        x += glyph_width;
+    }
+    (placed, x, height)
+}
+
+/// Draws `line` to pixels.
+pub fn draw(glyphs: &mut Glyphs, line: &[Vec<u8>]) -> GlyphBuffer {
+    let (placed, width, height) = lay_out(glyphs, line);
+    let mut buffer = TextBuffer {
+        buf: MaskBuffer::new(0, width, height),
+        x: 0,
+        y: 0,
+        height,
+    };
+    for Placed { digit, x, .. } in placed {
+        buffer.x = x;
+        match digit {
+            Some(n) => buffer.write_glyph_composing(glyphs.get_dni_number_one_digit(n)),
+            None => buffer.write_glyph_composing(glyphs.get_colon()),
+        }
+    }
+    // Every pixel's written over, so it's tinted straight from the background
+    let (fg, bg) = (glyphs.fg(), glyphs.bg());
+    let mut buf = Vec2d::new(bg, width, height);
+    let whole = Rect { x: 0, y: 0, width, height };
+    buf.copy_region_map(&buffer.buf, whole, (0, 0), |coverage| colors::tint(bg, fg, coverage));
+    buf
+}
+
+/// Draws `line` as an SVG document, with the glyphs where [`draw`] would put
+/// them.
+pub fn draw_svg(glyphs: &mut Glyphs, line: &[Vec<u8>]) -> String {
+    let (placed, width, height) = lay_out(glyphs, line);
+    let mut svg = Svg::new(width, height);
+    svg.set_colors(glyphs.fg(), glyphs.bg());
+    for Placed { digit, x, y } in placed {
+        let (path, glyph_height) = match digit {
+            Some(n) => (glyphs.dni_digit_path(n), glyphs.get_dni_number_one_digit(n).height()),
+            None => (glyphs.colon_path(), glyphs.get_colon().height()),
+        };
+        svg.glyph(&path, x as f32, y as f32, glyph_height, glyphs.options());
+    }
+    svg.finish()
+}
@@ -0,0 +1,139 @@
+//! The words the clock writes next to its numerals (captions, the tooltip,
+//! timers running out, toasts, the quiz), in the user's language.
+//!
+//! There are only a handful, so it's a plain table from the English to each
+//! language's. Anything that's not in it stays in English, and so does
+//! anything the user wrote themselves (like `--label`s and timer names).
+
+use std::str::FromStr;
+
+use chrono::Weekday;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    English,
+    German,
+    Spanish,
+    French,
+    Italian,
+    Dutch,
+    Portuguese,
+}
+
+/// English, then German, Spanish, French, Italian, Dutch, and Portuguese (in
+/// the order of [`Locale`]).
+const TEXT: &[[&str; 7]] = &[
+    // Captions
+    ["surface", "Oberfläche", "superficie", "surface", "superficie", "oppervlakte", "superfície"],
+    ["cavern", "Höhle", "caverna", "caverne", "caverna", "grot", "caverna"],
+    ["date", "Datum", "fecha", "date", "data", "datum", "data"],
+    ["moon", "Mond", "luna", "lune", "luna", "maan", "lua"],
+    ["hour", "Stunde", "hora", "heure", "ora", "uur", "hora"],
+    ["battery", "Akku", "batería", "batterie", "batteria", "accu", "bateria"],
+    ["next event", "nächster Termin", "próximo evento", "prochain événement", "prossimo evento", "volgende afspraak", "próximo evento"],
+    ["weather", "Wetter", "tiempo", "météo", "meteo", "weer", "tempo"],
+    ["cpu : memory", "CPU : Speicher", "CPU : memoria", "CPU : mémoire", "CPU : memoria", "CPU : geheugen", "CPU : memória"],
+    ["timer", "Timer", "temporizador", "minuteur", "timer", "timer", "temporizador"],
+    // In the complications themselves
+    ["done!", "fertig!", "¡listo!", "terminé !", "fatto!", "klaar!", "pronto!"],
+    ["missed!", "verpasst!", "¡perdido!", "manqué !", "perso!", "gemist!", "perdido!"],
+    ["days", "Tage", "días", "jours", "giorni", "dagen", "dias"],
+    ["none", "keiner", "ninguna", "aucune", "nessuna", "geen", "nenhuma"],
+    // Toasts
+    ["seconds on", "Sekunden an", "segundos activados", "secondes affichées", "secondi attivi", "seconden aan", "segundos ligados"],
+    ["seconds off", "Sekunden aus", "segundos desactivados", "secondes masquées", "secondi disattivati", "seconden uit", "segundos desligados"],
+    ["high contrast on", "hoher Kontrast an", "alto contraste activado", "contraste élevé activé", "alto contrasto attivo", "hoog contrast aan", "alto contraste ligado"],
+    ["high contrast off", "hoher Kontrast aus", "alto contraste desactivado", "contraste élevé désactivé", "alto contrasto disattivato", "hoog contrast uit", "alto contraste desligado"],
+    ["colors inverted", "Farben umgekehrt", "colores invertidos", "couleurs inversées", "colori invertiti", "kleuren omgekeerd", "cores invertidas"],
+    ["colors restored", "Farben normal", "colores normales", "couleurs normales", "colori normali", "kleuren normaal", "cores normais"],
+    ["colors", "Farben", "colores", "couleurs", "colori", "kleuren", "cores"],
+    ["timer set", "Timer gestellt", "temporizador puesto", "minuteur réglé", "timer impostato", "timer ingesteld", "temporizador definido"],
+    ["profile", "Profil", "perfil", "profil", "profilo", "profiel", "perfil"],
+    ["font", "Schrift", "fuente", "police", "carattere", "lettertype", "fonte"],
+    // The quiz
+    ["type its value, then press Enter", "Wert eingeben, dann Enter drücken", "escribe su valor y pulsa Intro", "tapez sa valeur, puis Entrée", "scrivi il valore, poi premi Invio", "typ de waarde en druk op Enter", "digite o valor e pressione Enter"],
+    ["right!", "richtig!", "¡correcto!", "juste !", "giusto!", "goed!", "certo!"],
+    ["no, that was", "nein, das war", "no, era", "non, c'était", "no, era", "nee, dat was", "não, era"],
+    ["score", "Punkte", "puntos", "score", "punti", "score", "pontos"],
+    ["streak", "Serie", "racha", "série", "serie", "reeks", "sequência"],
+];
+
+const WEEKDAYS: [[&str; 7]; 7] = [
+    ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"],
+    ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"],
+    ["lun", "mar", "mié", "jue", "vie", "sáb", "dom"],
+    ["lun.", "mar.", "mer.", "jeu.", "ven.", "sam.", "dim."],
+    ["lun", "mar", "mer", "gio", "ven", "sab", "dom"],
+    ["ma", "di", "wo", "do", "vr", "za", "zo"],
+    ["seg", "ter", "qua", "qui", "sex", "sáb", "dom"],
+];
+
+const MONTHS: [[&str; 12]; 7] = [
+    ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"],
+    ["Jan", "Feb", "Mär", "Apr", "Mai", "Jun", "Jul", "Aug", "Sep", "Okt", "Nov", "Dez"],
+    ["ene", "feb", "mar", "abr", "may", "jun", "jul", "ago", "sep", "oct", "nov", "dic"],
+    ["janv.", "févr.", "mars", "avr.", "mai", "juin", "juil.", "août", "sept.", "oct.", "nov.", "déc."],
+    ["gen", "feb", "mar", "apr", "mag", "giu", "lug", "ago", "set", "ott", "nov", "dic"],
+    ["jan", "feb", "mrt", "apr", "mei", "jun", "jul", "aug", "sep", "okt", "nov", "dec"],
+    ["jan", "fev", "mar", "abr", "mai", "jun", "jul", "ago", "set", "out", "nov", "dez"],
+];
+
+impl Locale {
+    const NAMES: [(&'static str, Locale); 7] = [
+        ("en", Self::English),
+        ("de", Self::German),
+        ("es", Self::Spanish),
+        ("fr", Self::French),
+        ("it", Self::Italian),
+        ("nl", Self::Dutch),
+        ("pt", Self::Portuguese),
+    ];
+
+    /// The user's language, going by the usual environment variables, or
+    /// English if it's not one there's a translation for.
+    pub fn from_env() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default()
+    }
+
+    /// `english`, in this language (or as is, if there's no translation).
+    pub fn text(self, english: &str) -> &str {
+        TEXT.iter()
+            .find(|row| row[0] == english)
+            .map_or(english, |row| row[self as usize])
+    }
+
+    /// The short name of `weekday`, like "Mon".
+    pub fn weekday(self, weekday: Weekday) -> &'static str {
+        WEEKDAYS[self as usize][weekday.num_days_from_monday() as usize]
+    }
+
+    /// The short name of month `month` (counting from 1), like "Jan".
+    pub fn month(self, month: u32) -> &'static str {
+        MONTHS[self as usize][month as usize - 1]
+    }
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    /// Takes a language code like `de`, or a whole locale like `de_AT.UTF-8`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let language = s.split(['_', '-', '.', '@']).next().unwrap_or_default().to_ascii_lowercase();
+        match language.as_str() {
+            "c" | "posix" => Ok(Self::English),
+            language => Self::NAMES.iter()
+                .find(|(name, _)| *name == language)
+                .map(|&(_, locale)| locale)
+                .ok_or_else(|| {
+                    let names: Vec<_> = Self::NAMES.iter().map(|(name, _)| format!("`{name}`")).collect();
+                    format!("expected one of {}", names.join(", "))
+                }),
+        }
+    }
+}
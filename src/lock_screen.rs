@@ -0,0 +1,431 @@
+//! `--lock-screen`: keeping the D'ni time on the lock screen, as a picture of
+//! the clock, drawn again every minute.
+//!
+//! Nothing but the system gets to draw on the lock screen itself (Windows'
+//! secure desktop, macOS' login window), so there's no window to put there.
+//! What every platform does let an app choose is the picture behind it:
+//!
+//! - On Windows, the lock screen picture, set with PowerShell through
+//!   `Windows.System.UserProfile.LockScreen`.
+//! - On macOS, the desktop picture, which the lock screen shows (blurred,
+//!   before Sonoma). So the desktop gets the clock too, on every monitor.
+//! - On GNOME, the desktop background (light and dark), which the lock screen
+//!   shows blurred, set with `gsettings`. So again the desktop gets it too.
+//!
+//! Whatever was there is put back when the clock exits. It's saved in a file
+//! as well, so if the clock's killed before it can put it back, it's put back
+//! the next time the clock starts. How soon a new picture shows up while the
+//! screen's already locked is up to the system: some only look again when
+//! they're next locked, so it can be a minute or so behind.
+//!
+//! Drawing the picture, writing it out, and setting it all happen on a thread
+//! of their own, so the clock doesn't stutter every minute.
+
+use std::{
+    fs,
+    io,
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::mpsc::{self, SyncSender, TrySendError},
+    thread::{self, JoinHandle},
+};
+
+use chrono::{DateTime, DurationRound, FixedOffset};
+
+use crate::{
+    colors::{self, Color},
+    config::Config,
+    export,
+    fonts::Fonts,
+    frame,
+    glyphs::{GlyphBuffer, Glyphs},
+    kiosk,
+    placement::Monitor,
+};
+
+use self::platform::{current, restore, set};
+
+/// How much of the screen the clock takes up, each way.
+const FILL: f32 = 0.5;
+
+/// The screen size to draw for, when there's no telling.
+const FALLBACK_SIZE: (usize, usize) = (1920, 1080);
+
+/// The picture on the lock screen, and what was there before.
+pub struct LockScreen {
+    /// Minutes for the painter to put on the lock screen, and how. `None` once
+    /// it's stopped, after failing.
+    to_paint: Option<SyncSender<(DateTime<FixedOffset>, Config)>>,
+    painter: Option<JoinHandle<()>>,
+    /// The minute the picture was last drawn for.
+    shown: Option<DateTime<FixedOffset>>,
+    /// What the lock screen showed before, to put back.
+    previous: Option<String>,
+}
+
+/// Draws pictures of the clock and puts them on the lock screen, on a thread
+/// of its own.
+struct Painter {
+    glyphs: Glyphs,
+    scale: f32,
+    /// The clock, before it's put in the middle of the picture.
+    face: GlyphBuffer,
+    /// The whole screen's worth.
+    picture: GlyphBuffer,
+    /// Which of the two files the next picture's written to. Both Windows and
+    /// macOS hang on to a picture by its path, so a new one has to have a new
+    /// name to be noticed.
+    next: usize,
+}
+
+impl LockScreen {
+    /// Starts drawing for the primary monitor, in `fonts`.
+    pub fn new(config: &Config, fonts: &Fonts) -> Self {
+        let monitors = Monitor::all();
+        let (width, height) = monitors.iter()
+            .find(|monitor| monitor.is_primary)
+            .or(monitors.first())
+            .map_or(FALLBACK_SIZE, |monitor| (monitor.width, monitor.height));
+        let config = for_lock_screen(config);
+        let fit = |size: usize| (size as f32 * FILL) as usize;
+        let scale = kiosk::fit_scale_within(fit(width), fit(height), |scale| frame::window_size(scale, &config));
+        let scale = config.glyphs.clamp_scale(scale);
+        let mut glyphs = Glyphs::with_starting_scale(scale, &config.glyphs, fonts);
+        if config.disk_cache {
+            glyphs.use_disk_cache();
+        }
+        glyphs.warm_up_in_background();
+        let previous = current();
+        match &previous {
+            Some(previous) => save_previous(previous),
+            None => eprintln!("warning: couldn't tell what's on the lock screen, so it won't be put back on exit"),
+        }
+
+        let mut painter = Painter {
+            glyphs,
+            scale,
+            face: GlyphBuffer::default(),
+            picture: GlyphBuffer::new(config.bg, width, height),
+            next: 0,
+        };
+        // Room for just the one minute, so none pile up while it's busy
+        let (to_paint, minutes) = mpsc::sync_channel(1);
+        let painter = thread::spawn(move || {
+            for (minute, config) in minutes {
+                if let Err(e) = painter.draw(minute, &config).and_then(set) {
+                    eprintln!("warning: couldn't put the time on the lock screen: {e}");
+                    return;
+                }
+            }
+        });
+        Self { to_paint: Some(to_paint), painter: Some(painter), shown: None, previous }
+    }
+
+    /// Puts `now` on the lock screen, if it's a new minute since it last was,
+    /// and the last picture's done.
+    pub fn update(&mut self, now: DateTime<FixedOffset>, config: &Config) {
+        let minute = now.duration_trunc(chrono::Duration::minutes(1)).unwrap();
+        let Some(to_paint) = &self.to_paint else { return };
+        if self.shown == Some(minute) {
+            return;
+        }
+        match to_paint.try_send((minute, for_lock_screen(config))) {
+            Ok(()) => self.shown = Some(minute),
+            // Still on the last one, so it's tried again next frame
+            Err(TrySendError::Full(_)) => {}
+            // It failed, and said so
+            Err(TrySendError::Disconnected(_)) => self.to_paint = None,
+        }
+    }
+}
+
+impl Painter {
+    /// Draws the clock for `time` in the middle of the picture, and writes it
+    /// out. Returns where to.
+    fn draw(&mut self, time: DateTime<FixedOffset>, config: &Config) -> io::Result<PathBuf> {
+        self.glyphs.set_colors(config.fg, config.bg);
+        frame::build_into(&mut self.face, time, &mut self.glyphs, self.scale, config);
+        let (width, height) = (self.picture.width(), self.picture.height());
+        self.picture.reset(self.glyphs.bg(), width, height);
+        // The face has more room on some sides than others, so it's what's
+        // drawn on it that's put in the middle
+        let (left, top, right, bottom) = drawn_within(&self.face, self.glyphs.bg());
+        let (drawn_width, drawn_height) = ((right - left).min(width), (bottom - top).min(height));
+        let x = (width - drawn_width) / 2;
+        let y = (height - drawn_height) / 2;
+        self.picture.copy_to_from(x, y, self.face.sub_view(left, top, drawn_width, drawn_height));
+        if config.invert {
+            colors::invert_all(&mut self.picture);
+        }
+        self.glyphs.save_to_disk();
+
+        let dir = dirs::cache_dir().ok_or(io::ErrorKind::NotFound)?.join("dni-clock").join("lock-screen");
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("{}.png", self.next));
+        self.next = 1 - self.next;
+        export::write_png(&path, std::slice::from_ref(&self.picture)).map_err(|e| io::Error::other(e.to_string()))?;
+        Ok(path)
+    }
+}
+
+impl Drop for LockScreen {
+    fn drop(&mut self) {
+        // Letting it finish the picture it's on, so it isn't set after the
+        // old one's put back
+        drop(self.to_paint.take());
+        if let Some(painter) = self.painter.take() {
+            let _ = painter.join();
+        }
+        if let Some(previous) = &self.previous {
+            put_back(previous);
+        }
+    }
+}
+
+/// Puts back what was on the lock screen before, if the clock was killed last
+/// time before it could.
+pub fn put_back_left_over() {
+    let Some(path) = previous_path() else { return };
+    if let Ok(previous) = fs::read_to_string(&path) {
+        put_back(&previous);
+    }
+}
+
+/// Puts `previous` back on the lock screen, and forgets it if that worked.
+fn put_back(previous: &str) {
+    match restore(previous) {
+        Ok(()) => {
+            if let Some(path) = previous_path() {
+                let _ = fs::remove_file(path);
+            }
+        }
+        Err(e) => eprintln!("warning: couldn't put the lock screen back the way it was: {e}"),
+    }
+}
+
+/// Keeps `previous` in a file, to put back next time if the clock doesn't
+/// get to.
+fn save_previous(previous: &str) {
+    let Some(path) = previous_path() else { return };
+    let saved = path.parent().map_or(Ok(()), fs::create_dir_all).and_then(|()| fs::write(&path, previous));
+    if let Err(e) = saved {
+        eprintln!("warning: couldn't save what's on the lock screen to {}: {e}", path.display());
+    }
+}
+
+/// Where what was on the lock screen is kept while the clock's on it.
+fn previous_path() -> Option<PathBuf> {
+    let dir = dirs::state_dir().or_else(dirs::data_local_dir)?;
+    Some(dir.join("dni-clock").join("lock-screen-before"))
+}
+
+/// `config`, as it's drawn on the lock screen: just the hours and minutes,
+/// since it's only drawn once a minute, and without complications, which
+/// would go stale.
+fn for_lock_screen(config: &Config) -> Config {
+    Config { show_seconds: false, complications: Vec::new(), ..config.clone() }
+}
+
+/// The smallest rectangle around everything in `face` that isn't `bg`, as
+/// its left, top, right, and bottom (past the end), or all of it if it's
+/// blank.
+fn drawn_within(face: &GlyphBuffer, bg: Color) -> (usize, usize, usize, usize) {
+    let (mut left, mut top, mut right, mut bottom) = (face.width(), face.height(), 0, 0);
+    for y in 0..face.height() {
+        let row = face.row(y);
+        if let (Some(first), Some(last)) = (row.iter().position(|&c| c != bg), row.iter().rposition(|&c| c != bg)) {
+            left = left.min(first);
+            right = right.max(last + 1);
+            top = top.min(y);
+            bottom = y + 1;
+        }
+    }
+    if left >= right {
+        return (0, 0, face.width(), face.height());
+    }
+    (left, top, right, bottom)
+}
+
+/// Runs `command`, and returns what it printed, if it worked and printed
+/// anything.
+fn output(command: &mut Command) -> Option<String> {
+    let output = command.stderr(Stdio::null()).output().ok()?;
+    let printed = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+    (output.status.success() && !printed.is_empty()).then_some(printed)
+}
+
+/// Runs `command` to the end, complaining if it fails.
+fn run(command: &mut Command) -> io::Result<()> {
+    let status = command.stdout(Stdio::null()).status()?;
+    if !status.success() {
+        let program = command.get_program().to_string_lossy().into_owned();
+        return Err(io::Error::other(format!("`{program}` failed ({status})")));
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::{io, path::PathBuf, process::Command};
+
+    /// Waits on the asynchronous operations WinRT hands back, which
+    /// PowerShell can't do by itself.
+    const AWAIT: &str = r#"
+        Add-Type -AssemblyName System.Runtime.WindowsRuntime
+        $asTask = [System.WindowsRuntimeSystemExtensions].GetMethods() | Where-Object {
+            $_.Name -eq 'AsTask' -and $_.GetParameters().Count -eq 1
+        }
+        function Await($operation, $type) {
+            $generic = $asTask | Where-Object { $_.GetParameters()[0].ParameterType.Name -eq 'IAsyncOperation`1' }
+            $task = $generic[0].MakeGenericMethod($type).Invoke($null, @($operation))
+            $task.Wait(-1) | Out-Null
+            $task.Result
+        }
+        function AwaitAction($action) {
+            $plain = $asTask | Where-Object { $_.GetParameters()[0].ParameterType.Name -eq 'IAsyncAction' }
+            $plain[0].Invoke($null, @($action)).Wait(-1) | Out-Null
+        }
+        $lockScreen = [Windows.System.UserProfile.LockScreen, Windows.System.UserProfile, ContentType = WindowsRuntime]
+        $file = [Windows.Storage.StorageFile, Windows.Storage, ContentType = WindowsRuntime]
+    "#;
+
+    fn powershell(script: &str) -> Command {
+        let mut command = Command::new("powershell");
+        command.args(["-NoProfile", "-NonInteractive", "-Command", &format!("{AWAIT}\n{script}")]);
+        command
+    }
+
+    /// PowerShell's single quotes take everything literally but themselves.
+    fn quoted(path: &str) -> String {
+        format!("'{}'", path.replace('\'', "''"))
+    }
+
+    pub fn current() -> Option<String> {
+        super::output(&mut powershell("$lockScreen::OriginalImageFile.LocalPath"))
+    }
+
+    pub fn set(path: PathBuf) -> io::Result<()> {
+        restore(path.to_str().ok_or(io::ErrorKind::InvalidInput)?)
+    }
+
+    pub fn restore(previous: &str) -> io::Result<()> {
+        super::run(&mut powershell(&format!(
+            "AwaitAction ($lockScreen::SetImageFileAsync((Await ($file::GetFileFromPathAsync({})) $file)))",
+            quoted(previous),
+        )))
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::{io, path::PathBuf, process::Command};
+
+    fn osascript(script: &str) -> Command {
+        let mut command = Command::new("osascript");
+        command.args(["-e", script]);
+        command
+    }
+
+    /// AppleScript's strings escape quotes and backslashes with backslashes.
+    fn quoted(path: &str) -> String {
+        format!("\"{}\"", path.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+
+    /// Every desktop's picture, a line each, in the order of the desktops.
+    pub fn current() -> Option<String> {
+        super::output(&mut osascript(
+            r#"tell application "System Events" to set pictures to picture of every desktop
+            set AppleScript's text item delimiters to linefeed
+            return pictures as text"#,
+        ))
+    }
+
+    pub fn set(path: PathBuf) -> io::Result<()> {
+        let path = path.to_str().ok_or(io::ErrorKind::InvalidInput)?;
+        super::run(&mut osascript(&format!(
+            r#"tell application "System Events" to tell every desktop to set picture to {}"#,
+            quoted(path),
+        )))
+    }
+
+    /// Gives each desktop its own picture back, from `current`. Any that have
+    /// come or gone since are left be.
+    pub fn restore(previous: &str) -> io::Result<()> {
+        let script: String = previous.lines()
+            .enumerate()
+            .map(|(i, picture)| {
+                let set = format!(
+                    r#"tell application "System Events" to set picture of desktop {} to {}"#,
+                    i + 1,
+                    quoted(picture),
+                );
+                format!("try\n{set}\nend try\n")
+            })
+            .collect();
+        super::run(&mut osascript(&script))
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+mod platform {
+    use std::{io, path::PathBuf, process::Command};
+
+    /// GNOME's lock screen shows the desktop background, blurred. It's long
+    /// since stopped looking at `org.gnome.desktop.screensaver`.
+    const SCHEMA: &str = "org.gnome.desktop.background";
+
+    /// The light and dark backgrounds. Before GNOME 42 there's only the one.
+    const KEYS: [&str; 2] = ["picture-uri", "picture-uri-dark"];
+
+    /// Each key there is, and its value as `gsettings get` printed it (which
+    /// `gsettings set` takes back), a line each.
+    pub fn current() -> Option<String> {
+        let values: Vec<String> = KEYS.iter()
+            .filter_map(|key| {
+                let value = super::output(Command::new("gsettings").args(["get", SCHEMA, key]))?;
+                Some(format!("{key} {value}"))
+            })
+            .collect();
+        (!values.is_empty()).then(|| values.join("\n"))
+    }
+
+    pub fn set(path: PathBuf) -> io::Result<()> {
+        let path = path.to_str().ok_or(io::ErrorKind::InvalidInput)?;
+        // As a GVariant string, quotes and all
+        let uri = format!("'file://{}'", path.replace('\\', "\\\\").replace('\'', "\\'"));
+        set_keys(KEYS.iter().map(|&key| (key, uri.as_str())))
+    }
+
+    pub fn restore(previous: &str) -> io::Result<()> {
+        set_keys(previous.lines().filter_map(|line| line.split_once(' ')))
+    }
+
+    /// Sets each key there is to its value, stopping at the first that
+    /// fails, unless it's just not there.
+    fn set_keys<'a>(values: impl Iterator<Item = (&'a str, &'a str)>) -> io::Result<()> {
+        for (key, value) in values {
+            let known = super::output(Command::new("gsettings").args(["range", SCHEMA, key])).is_some();
+            if known {
+                super::run(Command::new("gsettings").args(["set", SCHEMA, key, value]))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+mod platform {
+    use std::{io, path::PathBuf};
+
+    pub fn current() -> Option<String> {
+        None
+    }
+
+    pub fn set(_path: PathBuf) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    pub fn restore(_previous: &str) -> io::Result<()> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
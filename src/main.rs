@@ -1,82 +1,712 @@
-mod buf2d;
-mod colors;
-mod glyphs;
-mod util;
+use std::{sync::Arc, time::Instant};
 
-use chrono::{DateTime, DurationRound, Local, Timelike};
-use minifb::{Window, WindowOptions};
+use chrono::{DateTime, DurationRound, FixedOffset, Timelike, Utc};
+use tracing::trace_span;
 
-use crate::{buf2d::Vec2d, glyphs::{Glyphs, TextBuffer}};
+use dni_clock::{
+    animation::{self, Flash, Morph},
+    bench,
+    burn_in::BurnIn,
+    chart,
+    complication::Complication,
+    compositor::{Compositor, Layer},
+    config::{Command, Config},
+    control::{self, ControlServer, Request},
+    demo::Demo,
+    discord,
+    dnd::{self, DoNotDisturb},
+    doctor,
+    dni_time::{DniDuration, DurationUnits},
+    export,
+    fonts::{DniFont, Fonts},
+    frame::{self, Centered, SecondsToggle, DEFAULT_SCALE},
+    glyphs::{GlyphBuffer, Glyphs},
+    lock_screen::{self, LockScreen},
+    output::{ClockOutput, Event, HeadlessOutput},
+    pipe::PipeOutput,
+    print,
+    profiles,
+    quiz::Quiz,
+    sound::{Sound, Speaker},
+    speech::{self, Announcer},
+    splash::{self, Splash},
+    state::State,
+    svg_output::SvgOutput,
+    tick,
+    tick_log::TickLog,
+    timer::Timer,
+    timing,
+    toast::Toast,
+    tooltip,
+    webhooks::Webhooks,
+    window::MinifbOutput,
+};
 
-const SHOW_SECONDS: bool = true;
-const WINDOW_WIDTH: usize = if SHOW_SECONDS { 300 } else { 200 };
-const WINDOW_HEIGHT: usize = 70;
-const MARGIN: usize = 10;
-const LINE_HEIGHT: usize = WINDOW_HEIGHT - MARGIN - MARGIN;
+#[cfg(feature = "count-allocations")]
+#[global_allocator]
+static ALLOCATOR: bench::CountingAllocator = bench::CountingAllocator;
 
 fn main() {
     std::env::set_var("RUST_BACKTRACE", "1");
 
-    let mut glyphs = Glyphs::with_starting_scale(LINE_HEIGHT as f32);
+    let mut config = Config::from_env();
 
-    let mut buffer = TextBuffer::new();
+    // Those that haven't gone off yet, to save if they change
+    let mut timers = Vec::new();
 
-    // Start with yesterday to make sure the window gets updated right away
-    let mut time = Local::today().pred().and_hms(0, 0, 0);
+    // A timer goes to the clock that's already running, if there is one, and
+    // otherwise this becomes that clock
+    if let Command::Timer(options) = &config.command {
+        if control::send(&Request::Timer(options.clone())).is_ok() {
+            match config.duration_units {
+                DurationUnits::Surface => println!("started `{}` on the running clock", options.name),
+                DurationUnits::Dni => {
+                    let duration = DniDuration::from_duration(chrono::Duration::from_std(options.duration).unwrap());
+                    println!("started `{}` ({duration}) on the running clock", options.name);
+                }
+            }
+            return;
+        }
+        let timer = Arc::new(Timer::new(options.clone(), config.time_source.now(), &config));
+        config.complications.push(timer.clone());
+        timers.push(timer);
+    }
+
+    // These are only for the clock that's already running
+    let request = match config.command {
+        Command::HighContrast(on) => Some(Request::HighContrast(on)),
+        Command::Invert => Some(Request::Invert),
+        Command::Colors(fg, bg) => Some(Request::Colors(fg, bg)),
+        Command::Profile(ref name) => Some(Request::Profile(name.clone())),
+        Command::Stats => Some(Request::Stats),
+        Command::Announce { say, show } => Some(Request::Announce { say, show }),
+        Command::FakeClock(change) => Some(Request::FakeClock(change)),
+        Command::Seconds(on) => Some(Request::Seconds(on)),
+        _ => None,
+    };
+    if let Some(request) = request {
+        match control::send(&request) {
+            Ok(reply) => print!("{reply}"),
+            Err(e) if e.kind() == std::io::ErrorKind::Other => {
+                eprintln!("error: {e}");
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("error: couldn't reach the running clock: {e}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // Doesn't draw anything, so it doesn't need any fonts
+    if let Command::Print { follow } = config.command {
+        print::run(&config, follow);
+        return;
+    }
+    // Loads the fonts itself, to say what's wrong if they don't
+    if let Command::Doctor = config.command {
+        std::process::exit(doctor::run(&config));
+    }
+
+    let mut all_fonts = Fonts::load_all(&config.glyphs)
+        .unwrap_or_else(|e| {
+            eprintln!("error: {e}");
+            std::process::exit(1);
+        });
+    let mut font_choice = 0;
+    let fonts = &all_fonts[font_choice];
+    config.line_metrics = fonts.line_metrics(&config.glyphs);
+
+    match &config.command {
+        Command::Run | Command::Quiz | Command::Timer(_) => {}
+        Command::Print { .. }
+        | Command::HighContrast(_)
+        | Command::Invert
+        | Command::Colors(..)
+        | Command::Stats
+        | Command::Announce { .. }
+        | Command::FakeClock(_)
+        | Command::Seconds(_)
+        | Command::Profile(_)
+        | Command::Doctor => {
+            unreachable!("handled before loading fonts")
+        }
+        Command::Render(options) => {
+            if let Err(e) = export::render(&config, fonts, options, config.glyphs.clamp_scale(DEFAULT_SCALE)) {
+                eprintln!("error: {e}");
+                std::process::exit(1);
+            }
+            return;
+        }
+        Command::Chart(options) => {
+            if let Err(e) = chart::render(&config, fonts, options) {
+                eprintln!("error: {e}");
+                std::process::exit(1);
+            }
+            return;
+        }
+        Command::Bench => {
+            bench::run(&config, fonts);
+            return;
+        }
+    }
+
+    let mut output = open_output(&config);
+    // What the output asked for, which high contrast mode might make bigger
+    let mut output_scale = output.init(DEFAULT_SCALE, &config);
+    let mut scale = config.glyphs.clamp_scale(output_scale);
+
+    // For `dni-clock stats`, starting with the glyphs
+    timing::install();
+    let mut glyphs = new_glyphs(scale, &config, fonts);
+    let mut quiz = matches!(config.command, Command::Quiz).then(Quiz::new);
+    let mut control = ControlServer::start();
+    if let Some(client_id) = &config.discord {
+        discord::start(client_id.clone(), &config);
+    }
+    let mut speaker = Speaker::new(&config.volumes, config.mute);
+    let mut announcer = Announcer::new();
+    let mut tick_log = config.log_ticks.then(TickLog::new);
+    let mut demo = config.demo.then(|| Demo::new(&config, output_scale));
+    // How much bigger or smaller the demo's scene is drawn than usual
+    let mut scene_scale = 1.0;
+
+    // Timers from before a restart, which might have run out in the meantime
+    let now = config.time_source.now();
+    for saved in State::load().timers() {
+        let timer = Arc::new(Timer::resume(saved.clone(), now, &config));
+        config.complications.push(timer.clone());
+        timers.push(timer);
+    }
+    save_timers(&timers);
+
+    // Reused for every frame, so drawing one doesn't have to allocate
+    let mut compositor = Compositor::new();
+    // What scale the last frame was drawn at
+    let mut frame_scale = scale;
+    // Whether the pointer's resting on the clock, so it wants a tooltip
+    let mut hovering = false;
+    // When to take down the tooltip shown by `dni-clock announce`
+    let mut banner_until = None;
+    // Whether a chime or alarm was kept quiet for do not disturb, so the
+    // clock shows that instead
+    let mut held_back = false;
+    // The flash when the hour rolls over, while it's going
+    let mut flash = None;
+    // The numerals that changed morphing into their new values, while
+    // they're at it
+    let mut morph: Option<Morph> = None;
+    // A note saying something happened, like a key being pressed
+    let mut toast: Option<Toast> = None;
+    // When the numerals started coming in at startup, until they're all in
+    let mut reveal = (animation::allowed(&config) && quiz.is_none() && output.animates())
+        .then(|| config.time_source.monotonic_now());
+    // Shown in place of the clock until the digits are rendered, when
+    // they're big enough to take a while
+    let mut splash = (scale > splash::ABOVE_SCALE && output.animates()).then(|| Splash::new(&glyphs, &config));
+    // Every frame at one size, once the seconds have been switched with
+    // `--seconds-toggle center`
+    let mut centered = None;
+    let mut burn_in = (config.burn_in > 0).then(|| BurnIn::new(&config));
+    let mut webhooks = Webhooks::new(&config);
+    let mut dnd = (!config.ignore_dnd).then(DoNotDisturb::watch);
+    // In case the clock was killed while it was on the lock screen
+    lock_screen::put_back_left_over();
+    let mut lock_screen = config.lock_screen.then(|| LockScreen::new(&config, &all_fonts[font_choice]));
+    // Set after a rescale, until the glyphs for the new scale are rendered.
+    // Until then, the old frame is shown stretched to the new size instead
+    let mut rescaling = false;
+
+    // The time the face was last drawn for. Start with yesterday, so the
+    // first frame is drawn in full
+    let mut time = long_ago();
+    // Unlike `time`, only ever the time that was last shown, so redraws
+    // don't make sounds twice
+    let mut last_tick = time;
+
+    'running: loop {
+        let mut wake = tick::next(config.time_source.now(), config.show_seconds);
+        if flash.is_some() || morph.is_some() || reveal.is_some() || splash.is_some() {
+            wake = wake.min(Instant::now() + animation::FRAME_INTERVAL);
+        }
+        if let Some(toast) = &toast {
+            wake = wake.min(Instant::now() + toast.changes_in(config.time_source.monotonic_now()));
+        }
+        for event in output.poll_events(wake, &config) {
+            match event {
+                Event::Quit => break 'running,
+                Event::Rescale(new_scale) => {
+                    glyphs.save_to_disk();
+                    output_scale = new_scale;
+                    scale = config.glyphs.clamp_scale(output_scale * scene_scale);
+                    if let Some(demo) = &mut demo {
+                        demo.fit_scale(output_scale);
+                    }
+                    glyphs = new_glyphs(scale, &config, &all_fonts[font_choice]);
+                    glyphs.warm_up_in_background();
+                    let stretch = |size: usize| (size as f32 * scale / frame_scale).round() as usize;
+                    let frame = compositor.frame();
+                    if frame.width() > 0 && output.animates() {
+                        output.present(&frame.stretched(stretch(frame.width()), stretch(frame.height())), &config);
+                    }
+                    if let Some(Centered { size: (width, height), .. }) = centered {
+                        centered = Some(Centered::new((stretch(width), stretch(height))));
+                    }
+                    rescaling = true;
+                    compositor.invalidate_all();
+                }
+                Event::NextFont => {
+                    // With just the one font, it's only said which it is
+                    if all_fonts.len() > 1 {
+                        glyphs.save_to_disk();
+                        font_choice = (font_choice + 1) % all_fonts.len();
+                        config.line_metrics = all_fonts[font_choice].line_metrics(&config.glyphs);
+                        glyphs = new_glyphs(scale, &config, &all_fonts[font_choice]);
+                    }
+                    let font = &DniFont::choices(&config.glyphs)[font_choice];
+                    toast = Some(Toast::new(format!("{}: {font}", config.locale.text("font")), &config));
+                    compositor.invalidate_all();
+                }
+                Event::Announce => {
+                    if let (Some(style), false) = (config.announce, config.mute) {
+                        announcer.say(&speech::words(config.time_source.now(), style));
+                    }
+                }
+                // Inverting would turn the key some other color
+                Event::Invert if config.chroma_key.is_some() => {}
+                Event::Invert => {
+                    config.invert = !config.invert;
+                    toast = Some(inverted_toast(&config));
+                    compositor.invalidate_all();
+                }
+                Event::ToggleSeconds => {
+                    switch_seconds(!config.show_seconds, &mut config, compositor.frame(), &mut centered);
+                    toast = Some(switched_toast("seconds", config.show_seconds, &config));
+                    compositor.invalidate_all();
+                }
+                Event::Redraw => compositor.invalidate_all(),
+                Event::Hover(on) => {
+                    hovering = on;
+                    // Whatever was held back has been noticed
+                    held_back &= !on;
+                }
+                Event::Typed(_) | Event::Backspace | Event::Enter => {
+                    if quiz.as_mut().is_some_and(|quiz| quiz.handle(event)) {
+                        compositor.invalidate_all();
+                    }
+                }
+            }
+        }
+
+        for (request, answer) in control.iter_mut().flat_map(ControlServer::poll) {
+            let result = match request {
+                Request::Timer(options) => {
+                    let now = config.time_source.now();
+                    let ends = now + chrono::Duration::from_std(options.duration).unwrap();
+                    let text = format!(
+                        "{}: {}, {}",
+                        config.locale.text("timer set"),
+                        config.locale.text(&options.name),
+                        ends.format("%H:%M:%S"),
+                    );
+                    toast = Some(Toast::new(text, &config));
+                    let timer = Arc::new(Timer::new(options, now, &config));
+                    config.complications.push(timer.clone());
+                    timers.push(timer);
+                    save_timers(&timers);
+                    compositor.invalidate_all();
+                    Ok(())
+                }
+                Request::HighContrast(on) => {
+                    glyphs.save_to_disk();
+                    config.glyphs.high_contrast = on;
+                    scale = config.glyphs.clamp_scale(output_scale * scene_scale);
+                    glyphs = new_glyphs(scale, &config, &all_fonts[font_choice]);
+                    toast = Some(switched_toast("high contrast", on, &config));
+                    compositor.invalidate_all();
+                    Ok(())
+                }
+                Request::Invert if config.chroma_key.is_some() => {
+                    Err("can't invert a chroma key (see `--chroma-key`)".to_owned())
+                }
+                Request::Invert => {
+                    config.invert = !config.invert;
+                    toast = Some(inverted_toast(&config));
+                    compositor.invalidate_all();
+                    Ok(())
+                }
+                Request::Colors(fg, bg) => {
+                    (config.fg, config.bg) = (fg, bg);
+                    glyphs.set_colors(fg, bg);
+                    let text = format!("{}: #{fg:06x} / #{bg:06x}", config.locale.text("colors"));
+                    toast = Some(Toast::new(text, &config));
+                    compositor.invalidate_all();
+                    Ok(())
+                }
+                // Answered without bothering the clock
+                Request::Stats => Ok(()),
+                Request::Announce { say, show } => {
+                    if say && !config.mute {
+                        // Asked for outright, so even without --announce
+                        let style = config.announce.unwrap_or(speech::Style::Surface);
+                        announcer.say(&speech::words(config.time_source.now(), style));
+                    }
+                    if show {
+                        banner_until = Some(config.time_source.monotonic_now() + tooltip::BANNER_FOR);
+                    }
+                    Ok(())
+                }
+                Request::Seconds(on) => {
+                    switch_seconds(on.unwrap_or(!config.show_seconds), &mut config, compositor.frame(), &mut centered);
+                    toast = Some(switched_toast("seconds", config.show_seconds, &config));
+                    compositor.invalidate_all();
+                    Ok(())
+                }
+                Request::Profile(name) => {
+                    let switched = Config::from_args(profiles::switch_args(std::env::args().skip(1), &name).into_iter())
+                        .and_then(|new| Fonts::load_all(&new.glyphs).map(|fonts| (new, fonts)));
+                    let (mut new, fonts) = match switched {
+                        Ok(switched) => switched,
+                        Err(e) => {
+                            answer.send(Err(format!("couldn't switch to profile `{name}`: {e}")));
+                            continue;
+                        }
+                    };
+                    // What it's doing, and the timers it's running, carry on
+                    // as they were
+                    new.command = config.command.clone();
+                    new.time_source = config.time_source.clone();
+                    new.complications.extend(timers.iter().map(|timer| timer.clone() as Arc<dyn Complication>));
+                    let reopen = !same_output(&config, &new);
+                    config = new;
+                    glyphs.save_to_disk();
+                    (all_fonts, font_choice) = (fonts, 0);
+                    config.line_metrics = all_fonts[font_choice].line_metrics(&config.glyphs);
+                    if reopen {
+                        output.close();
+                        output = open_output(&config);
+                        output_scale = output.init(DEFAULT_SCALE, &config);
+                    }
+                    demo = config.demo.then(|| Demo::new(&config, output_scale));
+                    scene_scale = 1.0;
+                    scale = config.glyphs.clamp_scale(output_scale * scene_scale);
+                    glyphs = new_glyphs(scale, &config, &all_fonts[font_choice]);
+                    centered = None;
+                    burn_in = (config.burn_in > 0).then(|| BurnIn::new(&config));
+                    webhooks = Webhooks::new(&config);
+                    dnd = (!config.ignore_dnd).then(DoNotDisturb::watch);
+                    // The old one's dropped first, so the new one knows what to put back
+                    drop(lock_screen.take());
+                    lock_screen = config.lock_screen.then(|| LockScreen::new(&config, &all_fonts[font_choice]));
+                    toast = Some(Toast::new(format!("{}: {name}", config.locale.text("profile")), &config));
+                    compositor.invalidate_all();
+                    Ok(())
+                }
+                Request::FakeClock(change) => match config.time_source.clock.as_fake() {
+                    Some(clock) => match clock.change(change) {
+                        Ok(()) => {
+                            compositor.invalidate_all();
+                            Ok(())
+                        }
+                        Err(e) => Err(format!("couldn't change the fake clock: {e}")),
+                    },
+                    None => Err("the clock isn't fake (see `--fake-clock`), so it can't be changed".to_owned()),
+                },
+            };
+            answer.send(result);
+        }
 
-    let mut window = open_window();
-    while window.is_open() {
+        if let Some(new_scale) = demo.as_mut().and_then(|demo| demo.update(Instant::now(), &mut config)) {
+            glyphs.save_to_disk();
+            scene_scale = new_scale;
+            scale = config.glyphs.clamp_scale(output_scale * scene_scale);
+            glyphs = new_glyphs(scale, &config, &all_fonts[font_choice]);
+            compositor.invalidate_all();
+        }
+        if banner_until.is_some_and(|until| config.time_source.monotonic_now() >= until) {
+            banner_until = None;
+        }
+        let now = config.time_source.now();
+        let complications = config.complications.len();
+        config.complications.retain(|complication| !complication.expired(now));
+        if config.complications.len() != complications {
+            compositor.invalidate_all();
+        }
+        webhooks.check(now);
+        if let Some(lock_screen) = &mut lock_screen {
+            lock_screen.update(now, &config);
+        }
         let new_time =
-            if SHOW_SECONDS { local_time_to_the_second() }
-            else { local_time_to_the_minute() };
+            if config.show_seconds { to_the_second(now) }
+            else { to_the_minute(now) };
+        let ticked = new_time != last_tick;
+        if ticked {
+            if config.tick {
+                speaker.play(Sound::Tick);
+            }
+            let on_the_hour = new_time.minute() == 0 && new_time.second() == 0;
+            let dnd_active = || !config.mute && !config.ignore_dnd && dnd.as_ref().is_some_and(DoNotDisturb::active);
+            if config.chime && on_the_hour {
+                if dnd_active() { held_back = true } else { speaker.play(Sound::Chime) }
+            }
+            if let (Some(style), false, true) = (config.announce, config.mute, on_the_hour) {
+                if dnd_active() { held_back = true } else { announcer.say(&speech::words(new_time, style)) }
+            }
+            for complication in &config.complications {
+                if let Some(sound) = complication.sound(new_time) {
+                    if sound == Sound::Alarm {
+                        webhooks.alarm(complication.name(), new_time);
+                        let name = config.locale.text(complication.name());
+                        toast = Some(Toast::new(format!("{name}: {}", config.locale.text("done!")), &config));
+                    }
+                    if sound != Sound::Tick && dnd_active() { held_back = true } else { speaker.play(sound) }
+                }
+            }
+            if let (Some(intensity), true) = (config.rollover_flash, on_the_hour) {
+                if animation::allowed(&config) && quiz.is_none() && output.animates() {
+                    flash = Some(Flash::new(config.time_source.monotonic_now(), intensity));
+                }
+            }
+            // Only from the time just before, so it's not morphing from
+            // something that was never shown
+            let step = new_time - time;
+            let just_before = step > chrono::Duration::zero() && step <= chrono::Duration::minutes(1);
+            if config.morph && just_before && animation::allowed(&config) && quiz.is_none() && output.animates() {
+                morph = Some(Morph::new(config.time_source.monotonic_now(), time));
+            }
+            let pending = timers.len();
+            timers.retain(|timer| timer.pending().is_some());
+            if timers.len() != pending {
+                save_timers(&timers);
+            }
+            last_tick = new_time;
+        }
+        if rescaling {
+            rescaling = !glyphs.warmed_up();
+        }
+        let flash_strength = flash.and_then(|flash| flash.strength(config.time_source.monotonic_now()));
+        let morph_progress = morph.as_ref().and_then(|morph| morph.progress(config.time_source.monotonic_now()));
+        if morph.is_some() {
+            if morph_progress.is_none() {
+                morph = None;
+            }
+            compositor.invalidate(Layer::Background);
+        }
+        if flash.is_some() && flash_strength.is_none() {
+            flash = None;
+        }
+        if let Some(shown) = &mut splash {
+            // Does nothing if it's already going, but it might not be, if
+            // the glyphs were replaced since
+            glyphs.warm_up_in_background();
+            if glyphs.warmed_up() {
+                splash = None;
+                // The numerals come in once they're ready, not from when the
+                // splash went up
+                reveal = reveal.map(|_| config.time_source.monotonic_now());
+                compositor.invalidate_all();
+            } else if shown.draw(compositor.face_mut(), &mut glyphs, scale, &config) {
+                frame_scale = scale;
+                match &mut demo {
+                    Some(demo) => output.present(demo.fit(compositor.frame()), &config),
+                    None => output.present(compositor.frame(), &config),
+                }
+            }
+        }
+        // Within the minute, only the seconds change (and the complications,
+        // like timers, with them), unless a theme script changes the colors
         if new_time != time {
-            buffer = update_time(new_time, &mut glyphs);
+            if to_the_minute(new_time) == to_the_minute(time) && config.theme.is_none() {
+                compositor.invalidate(Layer::Digits);
+                compositor.invalidate(Layer::Complications);
+            } else {
+                compositor.invalidate(Layer::Background);
+            }
+        }
+        if reveal.is_some() {
+            compositor.invalidate(Layer::Background);
+        }
+        compositor.show(Layer::Effects, flash_strength.is_some());
+        if flash_strength.is_some() {
+            compositor.invalidate(Layer::Effects);
+        }
+        let monotonic_now = config.time_source.monotonic_now();
+        if toast.as_ref().is_some_and(|toast| toast.over(monotonic_now)) {
+            toast = None;
+        }
+        if toast.as_ref().is_some_and(|toast| toast.needs_drawing(monotonic_now)) {
+            compositor.invalidate(Layer::Overlay);
+        }
+        let tooltip = (hovering || banner_until.is_some()) && quiz.is_none();
+        compositor.show(Layer::Overlay, tooltip || held_back || toast.is_some());
+        if compositor.any_dirty() && !rescaling && splash.is_none() {
+            let started = Instant::now();
+            let frame_span = trace_span!("frame").entered();
+            // A quiz is only ever drawn in pixels
+            let presented = quiz.is_none() && {
+                let _span = trace_span!("present").entered();
+                output.present_vector(&mut || frame::build_svg(new_time, &mut glyphs, scale, &config))
+            };
+            if presented {
+                compositor.mark_clean();
+            } else {
+                if compositor.face_dirty() {
+                    match (&quiz, reveal) {
+                        (Some(quiz), _) => *compositor.face_mut() = quiz.draw(&mut glyphs, scale, &config),
+                        (None, Some(started)) => {
+                            let elapsed = config.time_source.monotonic_now().saturating_duration_since(started);
+                            let face = compositor.face_mut();
+                            if frame::build_revealing(face, new_time, &mut glyphs, scale, &config, elapsed) {
+                                reveal = None;
+                            }
+                        }
+                        (None, None) => match (&mut morph, morph_progress) {
+                            (Some(morph), Some(progress)) => {
+                                let face = compositor.face_mut();
+                                frame::build_morphing(face, new_time, &mut glyphs, scale, &config, morph, progress);
+                            }
+                            _ => compositor.draw_face(new_time, &mut glyphs, scale, &config),
+                        },
+                    }
+                }
+                let frame = compositor.composite(&mut glyphs, &config, |layer, frame, glyphs| match layer {
+                    Layer::Effects => {
+                        if let Some(strength) = flash_strength {
+                            animation::draw_flash(frame, glyphs.fg(), strength);
+                        }
+                    }
+                    Layer::Overlay => {
+                        if tooltip {
+                            tooltip::draw(frame, glyphs, new_time, &config);
+                        }
+                        if held_back {
+                            dnd::draw_held_back(frame, glyphs, &config);
+                        }
+                        if let Some(toast) = &mut toast {
+                            toast.draw(frame, glyphs, monotonic_now);
+                        }
+                    }
+                    Layer::Background | Layer::Digits | Layer::Complications => {}
+                });
+                frame_scale = scale;
+                let _span = trace_span!("present").entered();
+                let placed = match (&mut demo, &mut centered) {
+                    (Some(demo), _) => demo.fit(frame),
+                    (None, Some(centered)) => centered.fit(frame),
+                    (None, None) => frame,
+                };
+                match &mut burn_in {
+                    Some(burn_in) => output.present(burn_in.place(placed, new_time), &config),
+                    None => output.present(placed, &config),
+                }
+            }
+            drop(frame_span);
+            if let Some(log) = &mut tick_log {
+                log.frame(started.elapsed());
+            }
             time = new_time;
         }
-        window.update_with_buffer(buffer.buf.as_1d(), buffer.buf.width(), buffer.buf.height()).unwrap();
+        if let (Some(log), true) = (&mut tick_log, ticked) {
+            log.tick(new_time, config.time_source.now(), &config);
+        }
     }
+
+    glyphs.save_to_disk();
+    output.close();
+    // After the output, which saves the window's layout with whatever timers
+    // were around when it loaded the state
+    save_timers(&timers);
 }
 
-fn open_window() -> Window {
-    Window::new(
-        "D'ni Clock",
-        WINDOW_WIDTH,
-        WINDOW_HEIGHT,
-        WindowOptions::default(),
-    ).unwrap()
+/// Where the clock goes: a window, unless it's asked for somewhere else.
+fn open_output(config: &Config) -> Box<dyn ClockOutput> {
+    if config.headless {
+        Box::new(HeadlessOutput)
+    } else if let Some(format) = config.pipe_frames {
+        Box::new(PipeOutput::new(format, config.pipe_fps))
+    } else if let Some(path) = &config.svg_frames {
+        Box::new(SvgOutput::new(path.clone()))
+    } else {
+        Box::new(MinifbOutput::new(config))
+    }
 }
 
-fn update_time(time: DateTime<Local>, glyphs: &mut Glyphs) -> TextBuffer {
-    let mut buffer = TextBuffer {
-        buf: Vec2d::new(colors::BG, WINDOW_WIDTH, WINDOW_HEIGHT),
-        x: MARGIN,
-        y: MARGIN,
-        height: LINE_HEIGHT,
-    };
+/// Whether the output `a` opened would do for `b`, so switching profiles
+/// doesn't close and reopen the window for no reason. (`--discord` isn't
+/// here: it's started once, and stays as it was.)
+fn same_output(a: &Config, b: &Config) -> bool {
+    a.headless == b.headless
+        && a.pipe_frames == b.pipe_frames
+        && a.pipe_fps == b.pipe_fps
+        && a.svg_frames == b.svg_frames
+        && a.monitor == b.monitor
+        && a.anchor == b.anchor
+        && a.geometry == b.geometry
+        && a.screen_margin == b.screen_margin
+        && a.title == b.title
+        && a.borderless == b.borderless
+        && a.sticky == b.sticky
+        && a.snap_threshold == b.snap_threshold
+        && a.kiosk == b.kiosk
+        && a.keep_awake == b.keep_awake
+        && a.burn_in == b.burn_in
+        && a.resize == b.resize
+        && a.cursor_timeout == b.cursor_timeout
+}
 
-    buffer.write_glyph(glyphs.get_dni_number_one_digit(time.hour().try_into().unwrap()));
+/// Remembers the timers that haven't gone off yet, so they outlast a restart.
+fn save_timers(timers: &[Arc<Timer>]) {
+    let mut state = State::load();
+    state.set_timers(timers.iter().filter_map(|timer| timer.pending()).collect());
+    state.save();
+}
 
-    buffer.write_glyph(glyphs.get_colon());
+/// Shows the seconds or hides them. With `--seconds-toggle center`, every
+/// frame from then on is shown at the size of `frame`, the last one before.
+fn switch_seconds(on: bool, config: &mut Config, frame: &GlyphBuffer, centered: &mut Option<Centered>) {
+    if on == config.show_seconds {
+        return;
+    }
+    config.show_seconds = on;
+    if config.seconds_toggle == SecondsToggle::Center && centered.is_none() && frame.width() > 0 {
+        *centered = Some(Centered::new((frame.width(), frame.height())));
+    }
+}
 
-    buffer.write_glyph(glyphs.get_dni_number_two_digits(time.minute().try_into().unwrap()));
+/// A toast saying whether the colors are inverted now.
+fn inverted_toast(config: &Config) -> Toast {
+    let text = if config.invert { "colors inverted" } else { "colors restored" };
+    Toast::new(config.locale.text(text), config)
+}
 
-    if SHOW_SECONDS {
-        buffer.write_glyph(glyphs.get_colon());
+/// A toast saying `what` (like "seconds") is on now, or off.
+fn switched_toast(what: &str, on: bool, config: &Config) -> Toast {
+    let text = format!("{what} {}", if on { "on" } else { "off" });
+    Toast::new(config.locale.text(&text), config)
+}
 
-        buffer.write_glyph(glyphs.get_dni_number_two_digits(time.second().try_into().unwrap()));
+fn new_glyphs(scale: f32, config: &Config, fonts: &Fonts) -> Glyphs {
+    let mut glyphs = Glyphs::with_starting_scale(scale, &config.glyphs, fonts);
+    glyphs.set_colors(config.fg, config.bg);
+    if config.disk_cache {
+        glyphs.use_disk_cache();
+    }
+    if config.warm_up {
+        glyphs.warm_up_in_background();
     }
+    glyphs
+}
 
-    buffer
+/// A time that's definitely not now, to force a redraw.
+fn long_ago() -> DateTime<FixedOffset> {
+    Utc::today().pred().and_hms(0, 0, 0).into()
 }
 
-fn local_time_to_the_minute() -> DateTime<Local> {
-    Local::now()
-        .duration_trunc(chrono::Duration::minutes(1))
-        .unwrap()
+fn to_the_minute(time: DateTime<FixedOffset>) -> DateTime<FixedOffset> {
+    time.duration_trunc(chrono::Duration::minutes(1)).unwrap()
 }
 
-fn local_time_to_the_second() -> DateTime<Local> {
-    Local::now()
-        .duration_trunc(chrono::Duration::seconds(1))
-        .unwrap()
+fn to_the_second(time: DateTime<FixedOffset>) -> DateTime<FixedOffset> {
+    time.duration_trunc(chrono::Duration::seconds(1)).unwrap()
 }
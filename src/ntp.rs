@@ -0,0 +1,138 @@
+//! Getting the time from an NTP server instead of the system clock, for
+//! machines whose clock is wrong and can't be fixed.
+//!
+//! The server's only asked now and then (SNTP, RFC 4330); in between, the time
+//! is carried forward from the last answer by the monotonic clock, which
+//! doesn't care what the system clock says.
+
+use std::{
+    net::UdpSocket,
+    sync::{Arc, Mutex, Once},
+    thread,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, TimeZone, Utc};
+
+use crate::time_source::ClockSource;
+
+/// How often to ask the server again. Monotonic clocks drift by seconds a day
+/// at worst, so this is plenty.
+const INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// How soon to try again after the server didn't answer.
+const RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Seconds from when NTP counts from (1900) to when Unix does (1970).
+const NTP_TO_UNIX: i64 = 2_208_988_800;
+
+/// Seconds in an NTP era, after which its seconds wrap around (next in 2036).
+const ERA: i64 = 1 << 32;
+
+/// What time the server said it was, and when (by the monotonic clock) that
+/// was.
+#[derive(Debug, Clone, Copy)]
+struct Answer {
+    time: DateTime<Utc>,
+    at: Instant,
+}
+
+/// The time according to an NTP server.
+#[derive(Debug)]
+pub struct NtpClock {
+    server: String,
+    /// `None` until it's answered.
+    answer: Arc<Mutex<Option<Answer>>>,
+    /// Asking starts the first time the time's wanted.
+    started: Once,
+}
+
+impl NtpClock {
+    /// A clock set by `server` (a host name, with `:port` if it's not 123).
+    pub fn new(server: String) -> Self {
+        Self { server, answer: Arc::default(), started: Once::new() }
+    }
+
+    fn start(&self) {
+        let (server, last_answer) = (self.server.clone(), self.answer.clone());
+        thread::spawn(move || loop {
+            match query(&server) {
+                Ok(answer) => {
+                    *last_answer.lock().unwrap() = Some(answer);
+                    thread::sleep(INTERVAL);
+                }
+                Err(e) => {
+                    eprintln!("warning: couldn't get the time from {server}: {e}");
+                    thread::sleep(RETRY_INTERVAL);
+                }
+            }
+        });
+    }
+}
+
+impl ClockSource for NtpClock {
+    /// The current time, going by the server. It's the system clock's until
+    /// the server first answers, since that's the best there is.
+    fn now(&self) -> DateTime<Utc> {
+        self.started.call_once(|| self.start());
+        match *self.answer.lock().unwrap() {
+            Some(Answer { time, at }) => {
+                let elapsed = chrono::Duration::from_std(at.elapsed()).unwrap_or_else(|_| chrono::Duration::zero());
+                (time + elapsed).with_timezone(&Utc)
+            }
+            None => Utc::now(),
+        }
+    }
+
+    fn monotonic_now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Asks `server` what time it is.
+fn query(server: &str) -> Result<Answer, String> {
+    let address = if server.contains(':') { server.to_owned() } else { format!("{server}:123") };
+    let socket = UdpSocket::bind("0.0.0.0:0").map_err(|e| e.to_string())?;
+    socket.set_read_timeout(Some(TIMEOUT)).map_err(|e| e.to_string())?;
+    socket.connect(&address).map_err(|e| e.to_string())?;
+
+    // Version 3, client mode. The transmit timestamp's just for telling our
+    // answer from any other, so it's random; the rest can all be 0
+    let mut packet = [0; 48];
+    packet[0] = 0x1b;
+    let nonce = fastrand::u64(..).to_be_bytes();
+    packet[40..48].copy_from_slice(&nonce);
+    let sent = Instant::now();
+    socket.send(&packet).map_err(|e| e.to_string())?;
+    let len = socket.recv(&mut packet).map_err(|e| e.to_string())?;
+    let received = Instant::now();
+    if len < 48 {
+        return Err("the answer was cut off".to_owned());
+    }
+    // Mode 4 is server, 5 broadcast; anything else isn't an answer
+    if !matches!(packet[0] & 0x7, 4 | 5) {
+        return Err("that wasn't an NTP answer".to_owned());
+    }
+    // The server sends back what we sent as the origin timestamp
+    if packet[24..32] != nonce {
+        return Err("that was an answer to something else".to_owned());
+    }
+    if packet[1] == 0 {
+        return Err("the server says it isn't synchronized".to_owned());
+    }
+
+    // When the answer was sent, in seconds (and 2^32ths of one) since 1900,
+    // or since 2036 once they've wrapped around (RFC 4330 section 3)
+    let seconds = u32::from_be_bytes(packet[40..44].try_into().unwrap());
+    let fraction = u32::from_be_bytes(packet[44..48].try_into().unwrap());
+    let nanos = (u64::from(fraction) * 1_000_000_000) >> 32;
+    let era = if seconds < 1 << 31 { ERA } else { 0 };
+    let transmitted = Utc.timestamp_opt(era + i64::from(seconds) - NTP_TO_UNIX, nanos as u32)
+        .single()
+        .ok_or("the time it gave is out of range")?;
+    // It took about half the round trip to get here
+    let trip = chrono::Duration::from_std((received - sent) / 2).unwrap_or_else(|_| chrono::Duration::zero());
+    Ok(Answer { time: transmitted + trip, at: received })
+}
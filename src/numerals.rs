@@ -0,0 +1,196 @@
+//! Ways of writing numbers. The clock's made for D'ni numerals, but all it
+//! needs to know about them is here, so other systems can stand in for them:
+//! how many values a digit has, what each one looks like, and how they're put
+//! together into a numeral.
+//!
+//! Glyphs come from the digit font (`--dni-font`), falling back to the label
+//! font, so systems other than D'ni need a font that has their digits.
+
+use std::fmt;
+
+/// The most digits any system has glyphs for: base 60, plus one to spare (like
+/// D'ni 25).
+pub const MAX_DIGITS: usize = 61;
+
+/// How the digits of a numeral go together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Composition {
+    /// Each digit is drawn in a frame, and neighbors share the wall between
+    /// them (see [`crate::glyphs::Overlap`]).
+    SharedWalls,
+    /// Side by side, just as they are.
+    SideBySide,
+}
+
+/// A way of writing numbers.
+pub trait NumeralSystem: Sync {
+    /// What it's called, for `--numerals` (and to tell cached glyphs apart).
+    fn name(&self) -> &'static str;
+
+    /// How many values one digit has. At least 8, so no number on the clock
+    /// (59 at most) takes more than two digits.
+    fn base(&self) -> u8;
+
+    /// The character drawn for the digit `n`, if there is one. Every digit
+    /// less than [`Self::base`] has one; some systems have a few more (D'ni
+    /// has 25 as a digit of its own).
+    fn glyph(&self, n: u8) -> Option<char>;
+
+    /// How digits go together into a numeral.
+    fn composition(&self) -> Composition;
+
+    /// The digits of `n`, most significant first, with zeros in front to make
+    /// it at least `min_len` long.
+    fn digits(&self, n: u64, min_len: usize) -> Vec<u8> {
+        DniDigits::in_base(n, self.base(), min_len).collect()
+    }
+
+    /// How many digits it takes to write 59, and so how many minutes and
+    /// seconds are padded to (two, in D'ni).
+    fn padded_len(&self) -> usize {
+        if self.base() >= 60 { 1 } else { 2 }
+    }
+}
+
+/// The digits of a number, most significant first: base 25 (D'ni) unless
+/// it's made with [`Self::in_base`]. For drawing numbers with glyphs of
+/// your own.
+///
+/// ```
+/// use dni_clock::numerals::DniDigits;
+///
+/// // 1 × 25² + 2 × 25 + 3
+/// assert_eq!(DniDigits::new(678).collect::<Vec<_>>(), [1, 2, 3]);
+/// assert_eq!(DniDigits::new(0).collect::<Vec<_>>(), [0]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DniDigits {
+    /// What's left of the number, below the digits already given.
+    rest: u64,
+    /// What the next digit counts: a power of the base, or 0 once they've all
+    /// been given.
+    place: u64,
+    base: u64,
+}
+
+impl DniDigits {
+    /// The digits of `n` in D'ni.
+    pub fn new(n: u64) -> Self {
+        Self::in_base(n, Dni.base(), 1)
+    }
+
+    /// The digits of `n` in `base`, with zeros in front to make at least
+    /// `min_len` of them (and always at least one).
+    ///
+    /// Panics if `base` is less than 2.
+    pub fn in_base(n: u64, base: u8, min_len: usize) -> Self {
+        assert!(base >= 2, "there's no base {base}");
+        let base = u64::from(base);
+        let (mut place, mut len) = (1, 1);
+        while place <= n / base || len < min_len {
+            place = place.checked_mul(base).expect("more digits than fit in a u64");
+            len += 1;
+        }
+        Self { rest: n, place, base }
+    }
+}
+
+impl Iterator for DniDigits {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.place == 0 {
+            return None;
+        }
+        let digit = self.rest / self.place;
+        self.rest %= self.place;
+        self.place /= self.base;
+        Some(digit as u8)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let mut len = 0;
+        let mut place = self.place;
+        while place > 0 {
+            len += 1;
+            place /= self.base;
+        }
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for DniDigits {}
+
+impl fmt::Debug for dyn NumeralSystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl PartialEq for dyn NumeralSystem {
+    fn eq(&self, other: &Self) -> bool {
+        self.name() == other.name()
+    }
+}
+
+/// Every system there is, the default first.
+pub const SYSTEMS: &[&dyn NumeralSystem] = &[&Dni, &Mayan];
+
+/// Finds a system by its [`NumeralSystem::name`].
+pub fn by_name(name: &str) -> Result<&'static dyn NumeralSystem, String> {
+    SYSTEMS.iter().copied().find(|system| system.name() == name).ok_or_else(|| {
+        let names: Vec<_> = SYSTEMS.iter().map(|system| format!("`{}`", system.name())).collect();
+        format!("expected one of {}", names.join(", "))
+    })
+}
+
+/// D'ni numerals, base 25, drawn with the bundled fonts' ASCII mapping.
+pub struct Dni;
+
+impl NumeralSystem for Dni {
+    fn name(&self) -> &'static str {
+        "dni"
+    }
+
+    fn base(&self) -> u8 {
+        25
+    }
+
+    fn glyph(&self, n: u8) -> Option<char> {
+        const DIGITS: &[u8] = b"\
+        0123456789\
+        )!@#$%^&*(\
+        []\\{}|\
+        ";
+        const _: () = assert!(DIGITS[0] == b'0');
+        const _: () = assert!(DIGITS[25] == b'|');
+        const _: () = assert!(DIGITS.len() == 26);
+        DIGITS.get(usize::from(n)).map(|&c| c.into())
+    }
+
+    fn composition(&self) -> Composition {
+        Composition::SharedWalls
+    }
+}
+
+/// Maya numerals, base 20, from the Unicode block for them (which a font like
+/// Noto Sans Mayan Numerals has).
+pub struct Mayan;
+
+impl NumeralSystem for Mayan {
+    fn name(&self) -> &'static str {
+        "mayan"
+    }
+
+    fn base(&self) -> u8 {
+        20
+    }
+
+    fn glyph(&self, n: u8) -> Option<char> {
+        (n < self.base()).then(|| char::from_u32(0x1D2E0 + u32::from(n)).unwrap())
+    }
+
+    fn composition(&self) -> Composition {
+        Composition::SideBySide
+    }
+}
@@ -0,0 +1,103 @@
+//! Where the clock's frames end up.
+//!
+//! The clock itself only knows how to draw frames; it's up to a [`ClockOutput`]
+//! to show them somewhere (a window, a terminal, over the network, ...) and to
+//! tell it about anything that happened there that it should care about.
+
+use std::time::{Duration, Instant};
+
+use crate::{colors::PixelFormat, config::Config, glyphs::GlyphBuffer, tick};
+
+/// Something that happened at an output that the clock needs to react to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    /// The user wants the clock to stop.
+    Quit,
+    /// Frames should be drawn at a different scale from now on.
+    Rescale(f32),
+    /// The last frame was lost somehow, so send it again (or a new one).
+    Redraw,
+    /// The user typed a character (only the ones anything has a use for).
+    Typed(char),
+    /// The user pressed Backspace.
+    Backspace,
+    /// The user pressed Enter.
+    Enter,
+    /// The user wants the digits in the next D'ni font.
+    NextFont,
+    /// The user wants to hear the time.
+    Announce,
+    /// The user wants light and dark swapped.
+    Invert,
+    /// The user wants the seconds shown, or not, whichever they aren't.
+    ToggleSeconds,
+    /// The pointer came to rest over the clock (`true`), or left (`false`).
+    Hover(bool),
+}
+
+/// A place to show the clock.
+pub trait ClockOutput {
+    /// Gets ready to show frames, and picks the scale they should be drawn at.
+    /// `default_scale` is what to use if the output has no opinion.
+    ///
+    /// This and the other methods given the `config` get it as it is at the
+    /// time, so it's never out of date, even as it changes.
+    fn init(&mut self, default_scale: f32, config: &Config) -> f32;
+
+    /// How the backend wants its pixels laid out. Frames still come to
+    /// [`Self::present`] as [`Color`](crate::colors::Color)s, and outputs
+    /// with another format convert them with [`PixelFormat::encode_into`].
+    fn pixel_format(&self) -> PixelFormat {
+        PixelFormat::Xrgb8888
+    }
+
+    /// Shows a new frame. Only called when it actually changes.
+    fn present(&mut self, frame: &GlyphBuffer, config: &Config);
+
+    /// Shows a new frame drawn as vectors, by `draw`ing it as an SVG document,
+    /// if that's what it'd rather have. Returns whether it did, and if not,
+    /// the frame goes to [`Self::present`] instead. Only called when it
+    /// actually changes.
+    fn present_vector(&mut self, _draw: &mut dyn FnMut() -> String) -> bool {
+        false
+    }
+
+    /// Whether it shows what comes between one time and the next: the numerals
+    /// coming in, the splash, flashes and morphs, and the stretched frame while
+    /// rescaling. Ones that only want the finished frame for each time don't.
+    fn animates(&self) -> bool {
+        true
+    }
+
+    /// Called as often as possible, between frames. Returns whatever happened
+    /// since the last call.
+    ///
+    /// Outputs that nap between polls should wake by `next_tick`, when the
+    /// time on the clock face changes, so the new frame isn't late.
+    fn poll_events(&mut self, next_tick: Instant, config: &Config) -> Vec<Event>;
+
+    /// Cleans up after the clock has stopped.
+    fn close(&mut self) {}
+}
+
+/// Shows the clock nowhere. The clock keeps ticking without a window, for when
+/// all anyone's interested in is what it does besides drawing itself.
+pub struct HeadlessOutput;
+
+impl HeadlessOutput {
+    /// How long to nap between polls, since there's no window to pace things.
+    const POLL_INTERVAL: Duration = Duration::from_millis(50);
+}
+
+impl ClockOutput for HeadlessOutput {
+    fn init(&mut self, default_scale: f32, _config: &Config) -> f32 {
+        default_scale
+    }
+
+    fn present(&mut self, _frame: &GlyphBuffer, _config: &Config) {}
+
+    fn poll_events(&mut self, next_tick: Instant, _config: &Config) -> Vec<Event> {
+        tick::sleep_until_tick(Self::POLL_INTERVAL, next_tick);
+        Vec::new()
+    }
+}
@@ -0,0 +1,158 @@
+//! Writing raw video frames to stdout, for recording with something like
+//! ffmpeg, e.g.:
+//!
+//! ```text
+//! dni-clock --pipe-frames y4m | ffmpeg -i - clock.mp4
+//! ```
+//!
+//! Raw formats need the size, rate, and ffmpeg's name for the format spelled
+//! out (`rgba`, `bgr0` for `xrgb8888`, `rgb565le`, or `x2rgb10le` for
+//! `xrgb2101010`):
+//!
+//! ```text
+//! dni-clock --pipe-frames rgb565 | ffmpeg -f rawvideo -pixel_format rgb565le -video_size 300x70 -framerate 30 -i - clock.mp4
+//! ```
+
+use std::{
+    io::{self, Write},
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    colors::{self, PixelFormat},
+    config::Config,
+    glyphs::GlyphBuffer,
+    output::{ClockOutput, Event},
+    tick,
+};
+
+/// How each frame is encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PipeFormat {
+    /// Bare pixels, one frame after another. Whatever's reading them has to
+    /// be told the format, size, and frame rate separately.
+    Raw(PixelFormat),
+    /// YUV4MPEG2, which describes itself, so ffmpeg and friends can take it
+    /// as-is.
+    Y4m,
+}
+
+impl FromStr for PipeFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "y4m" => Ok(Self::Y4m),
+            _ => s.parse().map(Self::Raw)
+                .map_err(|_| "expected `y4m`, `rgba`, `xrgb8888`, `rgb565`, or `xrgb2101010`".to_owned()),
+        }
+    }
+}
+
+/// Writes the latest frame to stdout at a steady rate, whether or not it's
+/// changed, so the video's timing matches the clock's.
+pub struct PipeOutput {
+    format: PipeFormat,
+    fps: u32,
+    frame: GlyphBuffer,
+    /// The frame converted to [`ClockOutput::pixel_format`], kept to save
+    /// allocating each time.
+    bytes: Vec<u8>,
+    /// When the next frame is due. Each deadline is computed from the last one,
+    /// rather than from when the frame actually went out, so that the rate
+    /// doesn't drift.
+    next_frame: Instant,
+    wrote_header: bool,
+}
+
+impl PipeOutput {
+    pub fn new(format: PipeFormat, fps: u32) -> Self {
+        Self {
+            format,
+            fps: fps.max(1),
+            frame: GlyphBuffer::default(),
+            bytes: Vec::new(),
+            next_frame: Instant::now(),
+            wrote_header: false,
+        }
+    }
+
+    fn frame_interval(&self) -> Duration {
+        Duration::from_secs(1) / self.fps
+    }
+
+    fn write_frame(&mut self, out: &mut impl Write) -> io::Result<()> {
+        let (width, height) = (self.frame.width(), self.frame.height());
+        match self.format {
+            PipeFormat::Raw(_) => {
+                self.bytes.clear();
+                self.pixel_format().encode_into(self.frame.as_1d(), &mut self.bytes);
+                out.write_all(&self.bytes)?;
+            }
+            PipeFormat::Y4m => {
+                if !self.wrote_header {
+                    writeln!(out, "YUV4MPEG2 W{width} H{height} F{}:1 Ip A1:1 C444", self.fps)?;
+                    self.wrote_header = true;
+                }
+                out.write_all(b"FRAME\n")?;
+                out.write_all(&to_yuv444_planes(self.frame.as_1d()))?;
+            }
+        }
+        out.flush()
+    }
+}
+
+impl ClockOutput for PipeOutput {
+    fn init(&mut self, default_scale: f32, _config: &Config) -> f32 {
+        self.next_frame = Instant::now();
+        default_scale
+    }
+
+    fn pixel_format(&self) -> PixelFormat {
+        match self.format {
+            PipeFormat::Raw(format) => format,
+            // Converted to YUV separately
+            PipeFormat::Y4m => PixelFormat::Xrgb8888,
+        }
+    }
+
+    fn present(&mut self, frame: &GlyphBuffer, _config: &Config) {
+        self.frame = frame.clone();
+    }
+
+    fn poll_events(&mut self, next_tick: Instant, _config: &Config) -> Vec<Event> {
+        let now = Instant::now();
+        if now < self.next_frame {
+            // Wake for a tick that lands mid-wait, so it still gets drawn
+            // before the frame goes out
+            tick::sleep_until_tick(self.next_frame - now, next_tick);
+            return Vec::new();
+        }
+        self.next_frame += self.frame_interval();
+        if self.frame.width() == 0 {
+            return Vec::new();
+        }
+        match self.write_frame(&mut io::stdout().lock()) {
+            Ok(()) => Vec::new(),
+            // Most likely whatever was reading hung up, which is a perfectly
+            // normal way to stop recording
+            Err(_) => vec![Event::Quit],
+        }
+    }
+}
+
+/// Converts pixels to full-resolution Y, U, and V planes (BT.601, studio range).
+fn to_yuv444_planes(pixels: &[colors::Color]) -> Vec<u8> {
+    let mut planes = vec![0; pixels.len() * 3];
+    let (y_plane, rest) = planes.split_at_mut(pixels.len());
+    let (u_plane, v_plane) = rest.split_at_mut(pixels.len());
+    for (i, &color) in pixels.iter().enumerate() {
+        let (r, g, b) = colors::to_u8_rgb(color);
+        let (r, g, b) = (f32::from(r), f32::from(g), f32::from(b));
+        y_plane[i] = (16.0 + 0.257 * r + 0.504 * g + 0.098 * b).round() as u8;
+        u_plane[i] = (128.0 - 0.148 * r - 0.291 * g + 0.439 * b).round() as u8;
+        v_plane[i] = (128.0 + 0.439 * r - 0.368 * g - 0.071 * b).round() as u8;
+    }
+    planes
+}
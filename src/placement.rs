@@ -0,0 +1,477 @@
+//! Figuring out where on which screen the window goes.
+
+use std::str::FromStr;
+
+use display_info::DisplayInfo;
+
+/// A rectangle of screen space, in (physical) pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Monitor {
+    /// What the OS knows it by, for looking up its [`name`](Self::name).
+    pub id: u32,
+    pub x: isize,
+    pub y: isize,
+    pub width: usize,
+    pub height: usize,
+    pub is_primary: bool,
+}
+
+impl Monitor {
+    /// Lists the connected monitors, in the order the OS reports them.
+    ///
+    /// Returns an empty list if they can't be queried (e.g., no display server).
+    pub fn all() -> Vec<Self> {
+        DisplayInfo::all()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|info| Self {
+                id: info.id,
+                x: info.x as isize,
+                y: info.y as isize,
+                width: info.width as usize,
+                height: info.height as usize,
+                is_primary: info.is_primary,
+            })
+            .collect()
+    }
+
+    /// What it's called, the way the OS names its outputs: like `HDMI-1` on
+    /// X11, or `\\.\DISPLAY1` on Windows. macOS doesn't name them without
+    /// asking AppKit, so there it's the display ID. Unlike where it comes in
+    /// [`Monitor::all`], it stays the same as monitors come and go.
+    pub fn name(&self) -> Option<String> {
+        platform::name(self)
+    }
+
+    /// Whether any part of the given rectangle is on this monitor.
+    fn overlaps(&self, x: isize, y: isize, width: usize, height: usize) -> bool {
+        x < self.x + self.width as isize
+            && self.x < x + width as isize
+            && y < self.y + self.height as isize
+            && self.y < y + height as isize
+    }
+}
+
+/// Identifies an arrangement of monitors, e.g. "docked at the desk" versus
+/// "just the laptop screen". Monitors are sorted so that the order the OS
+/// happens to list them in doesn't matter.
+pub fn topology_key(monitors: &[Monitor]) -> String {
+    let mut rects: Vec<String> = monitors.iter()
+        .map(|m| format!("{}x{}+{}+{}", m.width, m.height, m.x, m.y))
+        .collect();
+    rects.sort();
+    rects.join(",")
+}
+
+/// Whether a window at this position and size would be at least partly visible.
+pub fn is_on_screen(
+    monitors: &[Monitor],
+    x: isize,
+    y: isize,
+    width: usize,
+    height: usize,
+) -> bool {
+    monitors.iter().any(|m| m.overlaps(x, y, width, height))
+}
+
+/// How the user picks a monitor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MonitorSelector {
+    /// Whichever one the OS considers the main one.
+    Primary,
+    /// By position in [`Monitor::all`]. That's whatever order the OS lists
+    /// them in, which can change when one's plugged in or unplugged, or even
+    /// after a restart, so a [`Name`](Self::Name) is steadier.
+    Index(usize),
+    /// By [`Monitor::name`].
+    Name(String),
+}
+
+impl MonitorSelector {
+    /// Picks the matching monitor out of `monitors`.
+    pub fn select(&self, monitors: &[Monitor]) -> Option<Monitor> {
+        match self {
+            Self::Primary => monitors.iter()
+                .find(|m| m.is_primary)
+                .or_else(|| monitors.first())
+                .copied(),
+            Self::Index(i) => monitors.get(*i).copied(),
+            Self::Name(name) => monitors.iter().find(|m| m.name().as_ref() == Some(name)).copied(),
+        }
+    }
+}
+
+impl FromStr for MonitorSelector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "primary" {
+            Ok(Self::Primary)
+        } else if let Ok(i) = s.parse() {
+            Ok(Self::Index(i))
+        } else if !s.is_empty() {
+            Ok(Self::Name(s.to_owned()))
+        } else {
+            Err("expected a monitor number, a monitor name, or `primary`".to_owned())
+        }
+    }
+}
+
+/// A spot on the monitor to stick the window to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    const NAMES: [(&'static str, Anchor); 9] = [
+        ("top-left", Self::TopLeft),
+        ("top", Self::Top),
+        ("top-right", Self::TopRight),
+        ("left", Self::Left),
+        ("center", Self::Center),
+        ("right", Self::Right),
+        ("bottom-left", Self::BottomLeft),
+        ("bottom", Self::Bottom),
+        ("bottom-right", Self::BottomRight),
+    ];
+
+    /// Where the top left of a `width`×`height` window goes so that it sits at
+    /// this anchor on `monitor`, `margin` pixels in from the edges it touches.
+    pub fn position(
+        self,
+        monitor: &Monitor,
+        width: usize,
+        height: usize,
+        margin: usize,
+    ) -> (isize, isize) {
+        /// Start, middle, or end of one axis
+        fn place(start: isize, len: usize, size: usize, margin: usize, align: i8) -> isize {
+            let (len, size, margin) = (len as isize, size as isize, margin as isize);
+            match align {
+                -1 => start + margin,
+                0 => start + (len - size) / 2,
+                _ => start + len - size - margin,
+            }
+        }
+
+        let (align_x, align_y) = self.alignment();
+        (
+            place(monitor.x, monitor.width, width, margin, align_x),
+            place(monitor.y, monitor.height, height, margin, align_y),
+        )
+    }
+
+    /// -1 for left/top, 0 for centered, 1 for right/bottom.
+    fn alignment(self) -> (i8, i8) {
+        match self {
+            Self::TopLeft => (-1, -1),
+            Self::Top => (0, -1),
+            Self::TopRight => (1, -1),
+            Self::Left => (-1, 0),
+            Self::Center => (0, 0),
+            Self::Right => (1, 0),
+            Self::BottomLeft => (-1, 1),
+            Self::Bottom => (0, 1),
+            Self::BottomRight => (1, 1),
+        }
+    }
+}
+
+impl FromStr for Anchor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::NAMES.iter()
+            .find(|(name, _)| *name == s)
+            .map(|&(_, anchor)| anchor)
+            .ok_or_else(|| "not a known anchor".to_owned())
+    }
+}
+
+/// X11-style window geometry, `WxH+X+Y`, where each part can be in pixels or
+/// a percentage of the monitor (like `25%x10%+70%+5%`), so the same one works
+/// on any screen. Either half can be left off.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Geometry {
+    /// The most room the window can take up: it's drawn at whatever scale
+    /// fits.
+    pub size: Option<(Length, Length)>,
+    pub position: Option<(Offset, Offset)>,
+}
+
+/// A distance along one side of a monitor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Length {
+    Pixels(usize),
+    /// Out of 100.
+    Percent(f32),
+}
+
+/// How far the window is from one of the monitor's edges: the left or top for
+/// `+`, the right or bottom for `-`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Offset {
+    pub from_end: bool,
+    pub length: Length,
+}
+
+impl Geometry {
+    /// The room the window has on `monitor`, if the geometry says.
+    pub fn size_on(&self, monitor: &Monitor) -> Option<(usize, usize)> {
+        let (width, height) = self.size?;
+        Some((width.resolve(monitor.width), height.resolve(monitor.height)))
+    }
+
+    /// Where the top left of a `width`×`height` window goes on `monitor`, if
+    /// the geometry says.
+    pub fn position_on(&self, monitor: &Monitor, width: usize, height: usize) -> Option<(isize, isize)> {
+        let (x, y) = self.position?;
+        Some((x.resolve(monitor.x, monitor.width, width), y.resolve(monitor.y, monitor.height, height)))
+    }
+}
+
+impl Length {
+    /// In pixels, out of `whole`.
+    fn resolve(self, whole: usize) -> usize {
+        match self {
+            Self::Pixels(pixels) => pixels,
+            Self::Percent(percent) => (whole as f32 * percent / 100.0).round() as usize,
+        }
+    }
+}
+
+impl Offset {
+    /// Where a window `size` long starts, on a side of a monitor that starts at
+    /// `start` and is `len` long.
+    fn resolve(self, start: isize, len: usize, size: usize) -> isize {
+        let offset = self.length.resolve(len) as isize;
+        if self.from_end { start + len as isize - size as isize - offset } else { start + offset }
+    }
+}
+
+impl FromStr for Geometry {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const BAD: &str = "expected a geometry like `25%x10%+70%+5%` or `400x100-0+0`";
+        let (size, position) = s.split_at(s.find(['+', '-']).unwrap_or(s.len()));
+        let size = if size.is_empty() {
+            None
+        } else {
+            let (width, height) = size.split_once('x').ok_or(BAD)?;
+            Some((width.parse()?, height.parse()?))
+        };
+        let position = if position.is_empty() {
+            None
+        } else {
+            let second = position[1..].find(['+', '-']).ok_or(BAD)? + 1;
+            let (x, y) = position.split_at(second);
+            Some((x.parse()?, y.parse()?))
+        };
+        if size.is_none() && position.is_none() {
+            return Err(BAD.to_owned());
+        }
+        Ok(Self { size, position })
+    }
+}
+
+impl FromStr for Length {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_suffix('%') {
+            Some(percent) => match percent.parse() {
+                Ok(percent) if (0.0..=100.0).contains(&percent) => Ok(Self::Percent(percent)),
+                _ => Err("expected a percentage from 0% to 100%".to_owned()),
+            },
+            None => s.parse().map(Self::Pixels).map_err(|_| "expected a number of pixels".to_owned()),
+        }
+    }
+}
+
+impl FromStr for Offset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let from_end = match s.get(..1) {
+            Some("+") => false,
+            Some("-") => true,
+            _ => return Err("expected an offset starting with `+` or `-`".to_owned()),
+        };
+        Ok(Self { from_end, length: s[1..].parse()? })
+    }
+}
+
+/// Nudges a window at `(x, y)` so that, if any of its edges is within
+/// `threshold` pixels of lining up `margin` pixels in from a monitor edge, it
+/// lines up exactly. Corners fall out of this naturally, since both axes are
+/// snapped independently.
+pub fn snap(
+    monitors: &[Monitor],
+    (x, y): (isize, isize),
+    width: usize,
+    height: usize,
+    threshold: usize,
+    margin: usize,
+) -> (isize, isize) {
+    /// Snaps one axis to the closest target within the threshold.
+    fn snap_axis(
+        pos: isize,
+        size: usize,
+        targets: &[(isize, usize)],
+        threshold: usize,
+        margin: usize,
+    ) -> isize {
+        let (size, margin) = (size as isize, margin as isize);
+        targets.iter()
+            .flat_map(|&(start, len)| {
+                let end = start + len as isize;
+                // Window's near edge against the monitor's near edge, far against far
+                [start + margin, end - margin - size]
+            })
+            .filter(|target| target.abs_diff(pos) <= threshold)
+            .min_by_key(|target| target.abs_diff(pos))
+            .unwrap_or(pos)
+    }
+
+    // Only snap to monitors the window is actually on, or else it would also
+    // get pulled toward the far edges of neighboring monitors
+    let nearby: Vec<&Monitor> = monitors.iter()
+        .filter(|m| m.overlaps(x, y, width, height))
+        .collect();
+    let xs: Vec<_> = nearby.iter().map(|m| (m.x, m.width)).collect();
+    let ys: Vec<_> = nearby.iter().map(|m| (m.y, m.height)).collect();
+    (
+        snap_axis(x, width, &xs, threshold, margin),
+        snap_axis(y, height, &ys, threshold, margin),
+    )
+}
+
+/// Works out where to open a `width`×`height` window, per the user's choice of
+/// monitor, and geometry or anchor. `None` means let the OS decide.
+pub fn initial_position(
+    monitor: Option<&MonitorSelector>,
+    anchor: Option<Anchor>,
+    geometry: Option<Geometry>,
+    margin: usize,
+    width: usize,
+    height: usize,
+    monitors: &[Monitor],
+) -> Option<(isize, isize)> {
+    let positioned = geometry.is_some_and(|geometry| geometry.position.is_some());
+    if monitor.is_none() && anchor.is_none() && !positioned {
+        return None;
+    }
+    let Some(monitor) = monitor.unwrap_or(&MonitorSelector::Primary).select(monitors) else {
+        eprintln!("warning: couldn't find the requested monitor; leaving placement to the OS");
+        return None;
+    };
+    if let Some(position) = geometry.and_then(|geometry| geometry.position_on(&monitor, width, height)) {
+        return Some(position);
+    }
+    let anchor = anchor.unwrap_or(Anchor::Center);
+    Some(anchor.position(&monitor, width, height, margin))
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use xcb::{randr, x, Xid};
+
+    use super::Monitor;
+
+    /// The name of the RandR output `monitor` is, which is what its ID is.
+    pub fn name(monitor: &Monitor) -> Option<String> {
+        let (conn, screen) = xcb::Connection::connect_with_extensions(None, &[], &[xcb::Extension::RandR]).ok()?;
+        let root = conn.get_setup().roots().nth(screen as usize)?.root();
+        let resources = conn.wait_for_reply(conn.send_request(&randr::GetScreenResources { window: root })).ok()?;
+        let output = *resources.outputs().iter().find(|output| output.resource_id() == monitor.id)?;
+        let info = conn.wait_for_reply(conn.send_request(&randr::GetOutputInfo {
+            output,
+            config_timestamp: x::CURRENT_TIME,
+        }));
+        Some(String::from_utf8_lossy(info.ok()?.name()).into_owned())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::Monitor;
+
+    pub fn name(monitor: &Monitor) -> Option<String> {
+        Some(monitor.id.to_string())
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::Monitor;
+
+    const MONITOR_DEFAULTTONULL: u32 = 0;
+
+    #[repr(C)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    #[repr(C)]
+    #[derive(Default)]
+    struct Rect {
+        left: i32,
+        top: i32,
+        right: i32,
+        bottom: i32,
+    }
+
+    /// `MONITORINFOEXW`.
+    #[repr(C)]
+    #[derive(Default)]
+    struct MonitorInfo {
+        size: u32,
+        monitor: Rect,
+        work: Rect,
+        flags: u32,
+        device: [u16; 32],
+    }
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn MonitorFromPoint(point: Point, flags: u32) -> isize;
+        fn GetMonitorInfoW(monitor: isize, info: *mut MonitorInfo) -> i32;
+    }
+
+    /// The GDI device name of the monitor at `monitor`'s top left corner.
+    pub fn name(monitor: &Monitor) -> Option<String> {
+        let corner = Point { x: monitor.x as i32, y: monitor.y as i32 };
+        // SAFETY: It only takes a point and flags
+        let handle = unsafe { MonitorFromPoint(corner, MONITOR_DEFAULTTONULL) };
+        if handle == 0 {
+            return None;
+        }
+        let mut info = MonitorInfo { size: std::mem::size_of::<MonitorInfo>() as u32, ..Default::default() };
+        // SAFETY: `info` is a `MONITORINFOEXW` with its size filled in
+        if unsafe { GetMonitorInfoW(handle, &mut info) } == 0 {
+            return None;
+        }
+        let len = info.device.iter().position(|&c| c == 0).unwrap_or(info.device.len());
+        Some(String::from_utf16_lossy(&info.device[..len]))
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+mod platform {
+    use super::Monitor;
+
+    pub fn name(_monitor: &Monitor) -> Option<String> {
+        None
+    }
+}
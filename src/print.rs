@@ -0,0 +1,34 @@
+//! `dni-clock --print`: the time as plain text on stdout, for shell prompts
+//! and scripts.
+
+use std::{
+    io::{self, Write},
+    thread,
+    time::Instant,
+};
+
+use chrono::DurationRound;
+
+use crate::{
+    config::Config,
+    frame,
+    tick,
+};
+
+/// Writes the time (as [`frame::text`]) once, or with `follow`, again every
+/// time it changes, until whatever's reading stops.
+pub fn run(config: &Config, follow: bool) {
+    let precision = if config.show_seconds { chrono::Duration::seconds(1) } else { chrono::Duration::minutes(1) };
+    let mut out = io::stdout().lock();
+    loop {
+        let now = config.time_source.now();
+        let time = now.duration_trunc(precision).unwrap();
+        // Most likely whatever was reading hung up, which is a perfectly
+        // normal way to stop following
+        if writeln!(out, "{}", frame::text(time, config)).and_then(|()| out.flush()).is_err() || !follow {
+            return;
+        }
+        let next_tick = tick::next(config.time_source.now(), config.show_seconds);
+        thread::sleep(next_tick.saturating_duration_since(Instant::now()));
+    }
+}
@@ -0,0 +1,135 @@
+//! Named sets of options, like "desk", "bedside", or "stream-overlay", kept in
+//! a file so they don't have to be typed out each time (not to be confused
+//! with [`crate::profile`], which times things).
+//!
+//! The file is `dni-clock/profiles` in the user's config directory. Each
+//! profile starts with its name in brackets, and then has the options it
+//! stands for, one to a line, written just like on the command line (but
+//! without quotes: everything after the option's name is its value):
+//!
+//! ```text
+//! # Lit low, for the nightstand
+//! [bedside]
+//! --fg #ff6020
+//! --no-seconds
+//! --kiosk
+//! --burn-in 4
+//!
+//! [stream-overlay]
+//! --pipe-frames y4m
+//! --label hours=Local time
+//! ```
+//!
+//! A few come built in (see [`BUILT_IN`]), and a profile in the file by the
+//! same name takes the place of one.
+
+use std::{io, path::PathBuf};
+
+/// Profiles that are there without being written down, by name.
+pub const BUILT_IN: &[(&str, &[&str])] = &[
+    // For dropping into OBS (or the like) with a color key filter
+    ("stream-overlay", &["--chroma-key", "green", "--borderless"]),
+    // Chunky and retro, and it makes for small GIFs
+    ("pixel", &["--pixel-art", "16"]),
+];
+
+/// Where profiles are kept, if there's a config directory.
+pub fn path() -> Option<PathBuf> {
+    Some(dirs::config_dir()?.join("dni-clock").join("profiles"))
+}
+
+/// The options profile `name` stands for, as arguments.
+pub fn load(name: &str) -> Result<Vec<String>, String> {
+    let built_in = BUILT_IN
+        .iter()
+        .find(|(built_in, _)| *built_in == name)
+        .map(|(_, args)| args.iter().map(|&arg| arg.to_owned()).collect());
+    let Some(path) = path() else {
+        return built_in.ok_or_else(|| "there's no config directory to keep profiles in".to_owned());
+    };
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound && built_in.is_some() => String::new(),
+        Err(e) => return Err(format!("couldn't read profiles from {}: {e}", path.display())),
+    };
+    let mut current = None;
+    let mut args = None;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some(section) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            current = Some(section.trim());
+            if current == Some(name) {
+                args.get_or_insert_with(Vec::new);
+            }
+            continue;
+        }
+        let error = |message: &str| format!("{}, line {}: {message}", path.display(), i + 1);
+        if current.is_none() {
+            return Err(error("options have to go under a `[name]`"));
+        }
+        if !line.starts_with('-') {
+            return Err(error(&format!("expected an option, like `--no-seconds`, not `{line}`")));
+        }
+        if current != Some(name) {
+            continue;
+        }
+        let (option, value) = match line.split_once(char::is_whitespace) {
+            Some((option, value)) => (option, Some(value.trim())),
+            None => (line, None),
+        };
+        if option == "--profile" {
+            return Err(error("a profile can't use another one"));
+        }
+        let args = args.as_mut().expect("set on entering the section");
+        args.push(option.to_owned());
+        args.extend(value.map(str::to_owned));
+    }
+    args.or(built_in).ok_or_else(|| format!("there's no profile `{name}` in {}", path.display()))
+}
+
+/// The names of the profiles in the file, in order (not counting built-in
+/// ones it doesn't have), if there is a file.
+pub fn names() -> Result<Vec<String>, String> {
+    let Some(path) = path() else { return Ok(Vec::new()) };
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("couldn't read profiles from {}: {e}", path.display())),
+    };
+    Ok(text.lines()
+        .filter_map(|line| line.trim().strip_prefix('[')?.strip_suffix(']'))
+        .map(|name| name.trim().to_owned())
+        .collect())
+}
+
+/// `args`, with `--profile <name>` swapped for the options it stands for, so
+/// they can be overridden by ones that come after it.
+pub fn expand(mut args: impl Iterator<Item = String>) -> Result<Vec<String>, String> {
+    let mut expanded = Vec::new();
+    while let Some(arg) = args.next() {
+        if arg != "--profile" {
+            expanded.push(arg);
+            continue;
+        }
+        let name = args.next().ok_or("`--profile` needs a name")?;
+        expanded.extend(load(&name).map_err(|e| format!("bad profile `{name}`: {e}"))?);
+    }
+    Ok(expanded)
+}
+
+/// `args`, but using profile `name` in place of whichever it used before (or
+/// before all the options, if it didn't use one), for switching to it.
+pub fn switch_args(args: impl Iterator<Item = String>, name: &str) -> Vec<String> {
+    let mut args: Vec<String> = args.collect();
+    match args.iter().position(|arg| arg == "--profile") {
+        Some(i) if i + 1 < args.len() => args[i + 1] = name.to_owned(),
+        _ => {
+            let first_option = args.iter().position(|arg| arg.starts_with('-')).unwrap_or(args.len());
+            args.splice(first_option..first_option, ["--profile".to_owned(), name.to_owned()]);
+        }
+    }
+    args
+}
@@ -0,0 +1,131 @@
+//! `dni-clock quiz`: practice reading D'ni numbers. A random one comes up in
+//! the window, and the user types what it is in decimal.
+
+use crate::{
+    buf2d::Vec2d,
+    config::Config,
+    frame,
+    glyphs::{GlyphBuffer, Glyphs},
+    labels,
+    layout::Line,
+    line,
+    output::Event,
+};
+
+/// Numbers to ask about go up to (but not including) this: everything with
+/// one or two digits.
+const LIMIT: u32 = 25 * 25;
+
+/// Lines of text under the number: the answer so far, how the last one went,
+/// and the score.
+const TEXT_LINES: usize = 3;
+
+/// How the last answer went.
+enum Feedback {
+    Right,
+    /// It was actually this.
+    Wrong(u32),
+}
+
+/// The state of a round of questions.
+pub struct Quiz {
+    /// What's being asked about.
+    number: u32,
+    /// What the user's typed so far.
+    answer: String,
+    feedback: Option<Feedback>,
+    right: u32,
+    asked: u32,
+    /// Right answers in a row.
+    streak: u32,
+}
+
+impl Quiz {
+    pub fn new() -> Self {
+        Self {
+            number: fastrand::u32(..LIMIT),
+            answer: String::new(),
+            feedback: None,
+            right: 0,
+            asked: 0,
+            streak: 0,
+        }
+    }
+
+    /// Reacts to the user typing. Returns whether anything changed.
+    pub fn handle(&mut self, event: Event) -> bool {
+        match event {
+            // Nothing that big has been asked about, so there's no sense in
+            // typing more
+            Event::Typed(c @ '0'..='9') if self.answer.len() < LIMIT.to_string().len() => {
+                self.answer.push(c);
+            }
+            Event::Backspace => {
+                self.answer.pop();
+            }
+            Event::Enter if !self.answer.is_empty() => self.check(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Marks the answer, and moves on to another number.
+    fn check(&mut self) {
+        self.asked += 1;
+        if self.answer.parse() == Ok(self.number) {
+            self.right += 1;
+            self.streak += 1;
+            self.feedback = Some(Feedback::Right);
+        } else {
+            self.streak = 0;
+            self.feedback = Some(Feedback::Wrong(self.number));
+        }
+        self.answer.clear();
+        // Asking the same thing twice in a row would look like it didn't take
+        let last = self.number;
+        while self.number == last {
+            self.number = fastrand::u32(..LIMIT);
+        }
+    }
+
+    /// Draws the number, with everything else under it.
+    pub fn draw(&self, glyphs: &mut Glyphs, scale: f32, config: &Config) -> GlyphBuffer {
+        let (width, height) = window_size(scale, config);
+        let mut buf = Vec2d::new(glyphs.bg(), width, height);
+
+        let line_height = frame::line_height(scale, config);
+        let digits = glyphs.options().numerals.digits(self.number.into(), 1);
+        let number = line::draw(glyphs, &[digits]);
+        let x = width.saturating_sub(number.width()) / 2;
+        let y = config.margins.top + line_height.saturating_sub(number.height()) / 2;
+        buf.copy_to_from(x, y, &number.cropped(width, line_height));
+
+        let locale = config.locale;
+        let feedback = match self.feedback {
+            None => locale.text("type its value, then press Enter").to_owned(),
+            Some(Feedback::Right) => locale.text("right!").to_owned(),
+            Some(Feedback::Wrong(n)) => format!("{} {n}", locale.text("no, that was")),
+        };
+        let (score, streak) = (locale.text("score"), locale.text("streak"));
+        let score = format!("{score} {}/{}    {streak} {}", self.right, self.asked, self.streak);
+        let text = [format!("> {}_", self.answer), feedback, score];
+        let text_height = labels::line_height(scale);
+        for (i, text) in text.iter().enumerate() {
+            let y = config.margins.top + line_height + text_height * i;
+            Line::new()
+                .small(text)
+                .within(0, y, width, text_height)
+                .align_center()
+                .render_into(&mut buf, glyphs);
+        }
+        buf
+    }
+}
+
+/// How big the window needs to be for the quiz at `scale`.
+pub fn window_size(scale: f32, config: &Config) -> (usize, usize) {
+    let line_height = frame::line_height(scale, config);
+    let width = line_height * 4;
+    let height = line_height + labels::line_height(scale) * TEXT_LINES;
+    (width, config.margins.top + height + config.margins.bottom)
+}
@@ -0,0 +1,48 @@
+//! Shapes that aren't glyphs. They're drawn as coverage masks, the same as
+//! glyphs are cached, so they get tinted the same way (see
+//! [`GlyphBuffer::tint_from`](crate::glyphs::GlyphBuffer::tint_from)).
+
+use std::f32::consts::TAU;
+
+use crate::{buf2d::Vec2d, glyphs::MaskBuffer};
+
+/// How bright the unfilled part of a progress ring is, from `BG` (0.0) to `FG`
+/// (1.0).
+pub const TRACK_BRIGHTNESS: f32 = 0.25;
+
+/// How many samples each pixel is split into, each way, to work out how much of
+/// it a shape covers.
+const SAMPLES: usize = 4;
+
+/// An arc of a ring `diameter` pixels across and `thickness` thick, starting at
+/// the top and going clockwise `sweep` of the way around (1.0 being the whole
+/// ring).
+pub fn arc(diameter: usize, thickness: f32, sweep: f32) -> MaskBuffer {
+    let mut mask = Vec2d::new(0, diameter, diameter);
+    if sweep <= 0.0 {
+        return mask;
+    }
+    let outer = diameter as f32 / 2.0;
+    let inner = (outer - thickness).max(0.0);
+    let center = outer;
+    let step = 1.0 / SAMPLES as f32;
+    for y in 0..diameter {
+        for (x, px) in mask.row_mut(y).iter_mut().enumerate() {
+            let mut covered = 0;
+            for sample_y in 0..SAMPLES {
+                for sample_x in 0..SAMPLES {
+                    let dx = x as f32 + (sample_x as f32 + 0.5) * step - center;
+                    let dy = y as f32 + (sample_y as f32 + 0.5) * step - center;
+                    let distance = dx.hypot(dy);
+                    // Clockwise from the top, from 0.0 to 1.0
+                    let angle = dx.atan2(-dy).rem_euclid(TAU) / TAU;
+                    if distance >= inner && distance <= outer && angle <= sweep {
+                        covered += 1;
+                    }
+                }
+            }
+            *px = (covered * usize::from(u8::MAX) / (SAMPLES * SAMPLES)) as u8;
+        }
+    }
+    mask
+}
@@ -0,0 +1,239 @@
+//! Short sounds for the clock to make: a tick, a chime, and an alarm.
+//!
+//! They're synthesized at startup and saved as WAV files in the cache
+//! directory, then played by whatever command-line player the system has
+//! (`paplay`, `pw-play`, or `aplay` on Linux, `afplay` on macOS, PowerShell on
+//! Windows), which is looked for once at startup. If there isn't one, or
+//! there's no audio device, the clock says so the first time it has something
+//! to play, and carries on silently.
+
+use std::{
+    f32::consts::TAU,
+    fs,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+};
+
+#[cfg(all(unix, not(target_os = "macos")))]
+use crate::util::on_path;
+
+const SAMPLE_RATE: u32 = 22_050;
+
+/// A sound there is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sound {
+    /// A click, every second.
+    Tick,
+    /// A bell, on the hour.
+    Chime,
+    /// Beeping, when a timer's done.
+    Alarm,
+}
+
+impl Sound {
+    const ALL: [Self; 3] = [Self::Tick, Self::Chime, Self::Alarm];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Tick => "tick",
+            Self::Chime => "chime",
+            Self::Alarm => "alarm",
+        }
+    }
+
+    /// The sound itself, at full volume, from -1 to 1.
+    fn samples(self) -> Vec<f32> {
+        let tone = |seconds: f32, f: &dyn Fn(f32) -> f32| -> Vec<f32> {
+            (0..(seconds * SAMPLE_RATE as f32) as usize)
+                .map(|i| f(i as f32 / SAMPLE_RATE as f32))
+                .collect()
+        };
+        match self {
+            Self::Tick => tone(0.02, &|t| (TAU * 2000.0 * t).sin() * (-t * 300.0).exp()),
+            // A fifth apart, ringing out, like a small bell
+            Self::Chime => tone(1.5, &|t| {
+                let ring = (TAU * 880.0 * t).sin() + 0.5 * (TAU * 1320.0 * t).sin();
+                ring / 1.5 * (-t * 3.0).exp()
+            }),
+            // Four quick beeps
+            Self::Alarm => tone(1.6, &|t| {
+                let on = (t * 2.5).fract() < 0.5;
+                if on { (TAU * 1000.0 * t).sin() * 0.8 } else { 0.0 }
+            }),
+        }
+    }
+}
+
+/// How loud each sound is, from 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Volumes {
+    pub tick: f32,
+    pub chime: f32,
+    pub alarm: f32,
+}
+
+impl Volumes {
+    fn get(&self, sound: Sound) -> f32 {
+        match sound {
+            Sound::Tick => self.tick,
+            Sound::Chime => self.chime,
+            Sound::Alarm => self.alarm,
+        }
+    }
+
+    /// Sets one sound's volume from `sound=percent`, like `alarm=50`.
+    pub fn set(&mut self, setting: &str) -> Result<(), String> {
+        const BAD: &str = "expected `sound=percent`, like `alarm=50`, for tick, chime, or alarm";
+        let (sound, percent) = setting.split_once('=').ok_or(BAD)?;
+        let percent: f32 = percent.parse().map_err(|_| BAD)?;
+        if !(0.0..=100.0).contains(&percent) {
+            return Err("the volume has to be from 0 to 100".to_owned());
+        }
+        let volume = match sound {
+            "tick" => &mut self.tick,
+            "chime" => &mut self.chime,
+            "alarm" => &mut self.alarm,
+            _ => return Err(BAD.to_owned()),
+        };
+        *volume = percent / 100.0;
+        Ok(())
+    }
+}
+
+impl Default for Volumes {
+    fn default() -> Self {
+        Self { tick: 0.3, chime: 0.6, alarm: 0.8 }
+    }
+}
+
+/// Plays sounds, if it can.
+pub struct Speaker {
+    /// The saved sounds, in the order of [`Sound::ALL`]. `None` if muted, or
+    /// if they couldn't be saved.
+    files: Option<Vec<PathBuf>>,
+    /// `None` if the system hasn't got one.
+    player: Option<Player>,
+    /// Sounds that might still be playing, to be waited on once they're done
+    /// so they don't linger as zombies.
+    playing: Vec<Child>,
+    /// Set once playing's failed, so it's only complained about once.
+    broken: bool,
+}
+
+impl Speaker {
+    /// Gets every sound ready to play at `volumes`, unless `mute`.
+    pub fn new(volumes: &Volumes, mute: bool) -> Self {
+        let files = (!mute).then(|| prepare(volumes)).flatten();
+        Self { files, player: Player::find(), playing: Vec::new(), broken: false }
+    }
+
+    /// Starts playing `sound`, without waiting for it to finish.
+    pub fn play(&mut self, sound: Sound) {
+        let Some(files) = &self.files else { return };
+        if self.broken {
+            return;
+        }
+        let Some(player) = &self.player else {
+            eprintln!("warning: couldn't find a program to play sounds with, like `paplay`; carrying on without them");
+            self.broken = true;
+            return;
+        };
+        self.playing.retain_mut(|child| matches!(child.try_wait(), Ok(None)));
+        let index = Sound::ALL.iter().position(|&s| s == sound).unwrap();
+        match player.command(&files[index]).spawn() {
+            Ok(child) => self.playing.push(child),
+            Err(e) => {
+                eprintln!("warning: couldn't play sounds ({e}); carrying on without them");
+                self.broken = true;
+            }
+        }
+    }
+}
+
+/// Synthesizes every sound at `volumes` and saves them where a player can get
+/// at them.
+fn prepare(volumes: &Volumes) -> Option<Vec<PathBuf>> {
+    let dir = dirs::cache_dir()?.join("dni-clock").join("sounds");
+    let result = fs::create_dir_all(&dir).and_then(|()| {
+        Sound::ALL.iter()
+            .map(|&sound| {
+                let volume = volumes.get(sound);
+                let path = dir.join(format!("{}-{:03}.wav", sound.name(), (volume * 100.0).round()));
+                if !path.exists() {
+                    fs::write(&path, wav(&sound.samples(), volume))?;
+                }
+                Ok(path)
+            })
+            .collect()
+    });
+    result.map_err(|e| eprintln!("warning: couldn't save sounds to {}: {e}", dir.display())).ok()
+}
+
+/// `samples` as a 16-bit mono WAV file, scaled by `volume`.
+fn wav(samples: &[f32], volume: f32) -> Vec<u8> {
+    let data_len = samples.len() as u32 * 2;
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16_u32.to_le_bytes());
+    // PCM, one channel
+    wav.extend_from_slice(&1_u16.to_le_bytes());
+    wav.extend_from_slice(&1_u16.to_le_bytes());
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2_u16.to_le_bytes());
+    wav.extend_from_slice(&16_u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+    for &sample in samples {
+        let sample = (sample * volume).clamp(-1.0, 1.0) * f32::from(i16::MAX);
+        wav.extend_from_slice(&(sample as i16).to_le_bytes());
+    }
+    wav
+}
+
+/// A command-line player for WAV files.
+struct Player {
+    program: &'static str,
+    /// What goes before the file to play.
+    args: &'static [&'static str],
+}
+
+impl Player {
+    /// The system's player, or `None` if it hasn't got one.
+    #[cfg(all(unix, not(target_os = "macos")))]
+    fn find() -> Option<Self> {
+        // PulseAudio (or PipeWire pretending to be), then PipeWire, then bare ALSA
+        let program = ["paplay", "pw-play", "aplay"].into_iter().find(|program| on_path(program))?;
+        let args: &[&str] = if program == "aplay" { &["-q"] } else { &[] };
+        Some(Self { program, args })
+    }
+
+    #[cfg(target_os = "macos")]
+    fn find() -> Option<Self> {
+        // It comes with the system
+        Some(Self { program: "afplay", args: &[] })
+    }
+
+    #[cfg(windows)]
+    fn find() -> Option<Self> {
+        // Told which file in `DNI_CLOCK_SOUND`, so the path's never part of
+        // the script
+        Some(Self {
+            program: "powershell",
+            args: &["-NoProfile", "-Command", "(New-Object Media.SoundPlayer $env:DNI_CLOCK_SOUND).PlaySync()"],
+        })
+    }
+
+    /// A command that plays the WAV file at `path`.
+    fn command(&self, path: &Path) -> Command {
+        let mut command = Command::new(self.program);
+        command.args(self.args).stdout(Stdio::null()).stderr(Stdio::null());
+        #[cfg(windows)]
+        command.env("DNI_CLOCK_SOUND", path);
+        #[cfg(not(windows))]
+        command.arg(path);
+        command
+    }
+}
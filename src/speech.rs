@@ -0,0 +1,134 @@
+//! Saying the time out loud, on the hour or when asked, with whatever
+//! text-to-speech the system has (`spd-say` or `espeak` on Linux, `say` on
+//! macOS, PowerShell on Windows). Without any, it stays quiet.
+
+use std::{
+    process::{Command, Stdio},
+    str::FromStr,
+    thread,
+};
+
+use chrono::{DateTime, FixedOffset, Timelike};
+
+use crate::dni_time::DniTime;
+#[cfg(all(unix, not(target_os = "macos")))]
+use crate::util::on_path;
+
+/// How to say the time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// The usual way, like "It's 3:17 PM".
+    Surface,
+    /// The D'ni time, in D'ni number words, like "fah gartahvo, rishgahsen
+    /// tahvo, ...".
+    Dni,
+}
+
+impl FromStr for Style {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "surface" => Ok(Self::Surface),
+            "dni" => Ok(Self::Dni),
+            _ => Err("expected `surface` or `dni`".to_owned()),
+        }
+    }
+}
+
+/// What to say for `time`.
+pub fn words(time: DateTime<FixedOffset>, style: Style) -> String {
+    match style {
+        Style::Surface => {
+            let (pm, hour) = time.hour12();
+            let meridiem = if pm { "PM" } else { "AM" };
+            match time.minute() {
+                0 => format!("It's {hour} o'clock"),
+                minute => format!("It's {hour}:{minute:02} {meridiem}"),
+            }
+        }
+        Style::Dni => {
+            let dni = DniTime::from_surface(&time);
+            format!(
+                "{} gartahvo, {} tahvo, {} gorahn",
+                dni_number(dni.gartahvo),
+                dni_number(dni.tahvo),
+                dni_number(dni.gorahn),
+            )
+        }
+    }
+}
+
+/// The D'ni word for `n` (0-24): a multiple of five, then `gah` and what's
+/// left over.
+fn dni_number(n: u8) -> String {
+    const ONES: [&str; 5] = ["roon", "fah", "bree", "sen", "tor"];
+    const FIVES: [&str; 5] = ["", "vaht", "nayvoo", "heebor", "rish"];
+    let (fives, ones) = (usize::from(n / 5), usize::from(n % 5));
+    match (fives, ones) {
+        (0, _) => ONES[ones].to_owned(),
+        (_, 0) => FIVES[fives].to_owned(),
+        // Vaht goes to `vahgah`, not `vahtgah`
+        (1, _) => format!("vahgah{}", ONES[ones]),
+        _ => format!("{}gah{}", FIVES[fives], ONES[ones]),
+    }
+}
+
+/// Says things out loud, if it can.
+pub struct Announcer {
+    /// Set once speaking's failed, so it's only complained about once.
+    broken: bool,
+}
+
+impl Announcer {
+    pub fn new() -> Self {
+        Self { broken: false }
+    }
+
+    /// Starts saying `text`, without waiting for it to finish.
+    pub fn say(&mut self, text: &str) {
+        if self.broken {
+            return;
+        }
+        match speaker(text).stdout(Stdio::null()).stderr(Stdio::null()).spawn() {
+            // Waited on out of the way, so it doesn't linger as a zombie
+            Ok(mut child) => drop(thread::spawn(move || child.wait())),
+            Err(e) => {
+                eprintln!("warning: couldn't speak ({e}); carrying on without it");
+                self.broken = true;
+            }
+        }
+    }
+}
+
+/// A command that says `text`.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn speaker(text: &str) -> Command {
+    // Speech Dispatcher, which uses whatever voice the desktop's set up, then
+    // eSpeak directly
+    let program = ["spd-say", "espeak-ng", "espeak"]
+        .into_iter()
+        .find(|program| on_path(program))
+        .unwrap_or("spd-say");
+    let mut command = Command::new(program);
+    command.arg(text);
+    command
+}
+
+#[cfg(target_os = "macos")]
+fn speaker(text: &str) -> Command {
+    let mut command = Command::new("say");
+    command.arg(text);
+    command
+}
+
+#[cfg(windows)]
+fn speaker(text: &str) -> Command {
+    let mut command = Command::new("powershell");
+    let script = format!(
+        "Add-Type -AssemblyName System.Speech; (New-Object System.Speech.Synthesis.SpeechSynthesizer).Speak('{}')",
+        text.replace('\'', "''"),
+    );
+    command.args(["-NoProfile", "-Command", &script]);
+    command
+}
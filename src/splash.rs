@@ -0,0 +1,74 @@
+//! What's shown at startup while the digits render, when they're so big that
+//! takes a noticeable moment (like fullscreen): "shorah" (hello) in D'ni
+//! script, over a bar that fills up as the digits come in. The word is small,
+//! so it's ready right away, and it's drawn in a frame the size the clock will
+//! be, so the window doesn't change size once the clock takes over.
+
+use crate::{
+    colors,
+    config::Config,
+    frame,
+    glyphs::{GlyphBuffer, Glyphs, MaskBuffer},
+    shapes, synth,
+};
+
+/// Clocks drawn bigger than this get a splash. Smaller ones render quickly
+/// enough that it would only flicker by.
+pub const ABOVE_SCALE: f32 = 200.0;
+
+/// How big "shorah" is drawn.
+const WORD_SCALE: f32 = 48.0;
+
+/// "Shorah", spelled the way D'ni fonts expect: `S` is "sh", and `A` is "ah".
+const SHORAH: &str = "SorA";
+
+pub struct Splash {
+    word: MaskBuffer,
+    /// How full the bar was last drawn, so it's only drawn again when it
+    /// moves.
+    shown: Option<f32>,
+}
+
+impl Splash {
+    pub fn new(glyphs: &Glyphs, config: &Config) -> Self {
+        let mut word = glyphs.render_dni_word(SHORAH, WORD_SCALE);
+        if config.glyphs.high_contrast {
+            synth::threshold(&mut word);
+        }
+        Self { word, shown: None }
+    }
+
+    /// Draws it into `frame` at the size the clock will be at `scale`, if
+    /// there's been progress since it was last drawn. Returns whether it drew
+    /// anything.
+    pub fn draw(&mut self, frame: &mut GlyphBuffer, glyphs: &mut Glyphs, scale: f32, config: &Config) -> bool {
+        let progress = glyphs.warm_up_progress();
+        if self.shown == Some(progress) {
+            return false;
+        }
+        self.shown = Some(progress);
+
+        let (width, height) = frame::window_size(scale, config);
+        let (fg, bg) = (glyphs.fg(), glyphs.bg());
+        frame.reset(bg, width, height);
+        let word = self.word.cropped(width, height);
+        let bar_height = (WORD_SCALE / 12.0).round().max(2.0) as usize;
+        let gap = (WORD_SCALE / 4.0) as usize;
+        let top = height.saturating_sub(word.height() + gap + bar_height) / 2;
+        frame.tint_from((width - word.width()) / 2, top, &word, fg);
+
+        let bar_width = width / 3;
+        let (x, y) = ((width - bar_width) / 2, (top + word.height() + gap).min(height - bar_height));
+        let filled = (bar_width as f32 * progress).round() as usize;
+        if config.glyphs.draws_tracks() {
+            let track = colors::lerp(bg, fg, shapes::TRACK_BRIGHTNESS);
+            for row in y..(y + bar_height) {
+                frame.row_mut(row)[x..(x + bar_width)].fill(track);
+            }
+        }
+        for row in y..(y + bar_height) {
+            frame.row_mut(row)[x..(x + filled)].fill(fg);
+        }
+        true
+    }
+}
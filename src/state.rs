@@ -0,0 +1,193 @@
+//! Stuff that's remembered between runs.
+//!
+//! It's saved as a little tab-separated text file in the user's state directory,
+//! one record per line, with the record kind as the first column. Records of
+//! kinds it doesn't know are kept as they are, so a newer version of the clock
+//! adding record kinds won't break an older one (or lose them to it). Records
+//! it knows but can't make sense of are skipped, with a warning.
+
+use std::{collections::BTreeMap, fs, path::PathBuf, time::Duration};
+
+use chrono::DateTime;
+
+use crate::timer::{self, SavedTimer, TimerOptions};
+
+/// Where and how big the window was on a particular monitor arrangement.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Layout {
+    pub x: isize,
+    pub y: isize,
+    pub scale: f32,
+}
+
+/// Everything that gets persisted.
+#[derive(Debug, Default)]
+pub struct State {
+    /// Keyed by [`crate::placement::topology_key`].
+    layouts: BTreeMap<String, Layout>,
+    /// Timers that hadn't gone off yet.
+    timers: Vec<SavedTimer>,
+    /// Lines with record kinds from some other version, written back as they
+    /// were.
+    unknown: Vec<String>,
+}
+
+impl State {
+    /// Loads the state file, or starts fresh if there isn't one.
+    pub fn load() -> Self {
+        let Some(path) = path() else { return Self::default() };
+        let Ok(contents) = fs::read_to_string(&path) else { return Self::default() };
+        let (state, skipped) = Self::parse(&contents);
+        for line in skipped {
+            eprintln!("warning: ignoring line {line} of {}", path.display());
+        }
+        state
+    }
+
+    /// Reads the state from what's in the state file, and the numbers of any
+    /// lines it couldn't make sense of (counting from 1).
+    fn parse(contents: &str) -> (Self, Vec<usize>) {
+        let mut state = Self::default();
+        let skipped = contents.lines()
+            .enumerate()
+            .filter(|(_, line)| state.parse_line(line).is_none())
+            .map(|(i, _)| i + 1)
+            .collect();
+        (state, skipped)
+    }
+
+    /// Writes the state file, warning (but carrying on) if that fails.
+    pub fn save(&self) {
+        let Some(path) = path() else { return };
+        let contents = self.contents();
+        // Written alongside and then moved into place, so it's never left
+        // half written
+        let temporary = path.with_extension("tsv.tmp");
+        let result = path.parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(&temporary, contents))
+            .and_then(|()| fs::rename(&temporary, &path));
+        if let Err(e) = result {
+            eprintln!("warning: couldn't save {}: {e}", path.display());
+        }
+    }
+
+    /// What goes in the state file.
+    fn contents(&self) -> String {
+        let mut contents = String::new();
+        for (topology, layout) in &self.layouts {
+            let Layout { x, y, scale } = layout;
+            contents += &format!("layout\t{topology}\t{x}\t{y}\t{scale}\n");
+        }
+        for SavedTimer { options, start } in &self.timers {
+            let kind = if options.t_minus { "t-minus" } else { "timer" };
+            // The name goes last, so it can have tabs in it
+            let name = options.name.replace('\n', " ");
+            contents += &format!("{kind}\t{}\t{}\t{name}\n", start.to_rfc3339(), options.duration.as_secs());
+        }
+        for line in &self.unknown {
+            contents += line;
+            contents.push('\n');
+        }
+        contents
+    }
+
+    /// The layout last used with this monitor arrangement, if any.
+    pub fn layout(&self, topology: &str) -> Option<Layout> {
+        self.layouts.get(topology).copied()
+    }
+
+    pub fn set_layout(&mut self, topology: String, layout: Layout) {
+        self.layouts.insert(topology, layout);
+    }
+
+    pub fn timers(&self) -> &[SavedTimer] {
+        &self.timers
+    }
+
+    pub fn set_timers(&mut self, timers: Vec<SavedTimer>) {
+        self.timers = timers;
+    }
+
+    fn parse_line(&mut self, line: &str) -> Option<()> {
+        let mut fields = line.split('\t');
+        match fields.next()? {
+            "layout" => {
+                let topology = fields.next()?.to_owned();
+                let layout = Layout {
+                    x: fields.next()?.parse().ok()?,
+                    y: fields.next()?.parse().ok()?,
+                    scale: fields.next()?.parse().ok()?,
+                };
+                self.layouts.insert(topology, layout);
+            }
+            kind @ ("timer" | "t-minus") => {
+                let start = DateTime::parse_from_rfc3339(fields.next()?).ok()?;
+                let duration = timer::check_duration(Duration::from_secs(fields.next()?.parse().ok()?)).ok()?;
+                let name = fields.collect::<Vec<_>>().join("\t");
+                let options = TimerOptions { duration, name, t_minus: kind == "t-minus" };
+                self.timers.push(SavedTimer { options, start });
+            }
+            "" => {}
+            _ => self.unknown.push(line.to_owned()),
+        }
+        Some(())
+    }
+}
+
+/// The state file's location, if the platform has somewhere sensible for it.
+fn path() -> Option<PathBuf> {
+    let dir = dirs::state_dir().or_else(dirs::data_local_dir)?;
+    Some(dir.join("dni-clock").join("state.tsv"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn comes_back_as_it_was_saved() {
+        let mut state = State::default();
+        state.set_layout("1920x1080+0+0".to_owned(), Layout { x: -40, y: 25, scale: 62.5 });
+        state.set_layout("3840x2160+0+0".to_owned(), Layout { x: 100, y: 0, scale: 100.0 });
+        let tea = TimerOptions { duration: Duration::from_secs(300), name: "tea\tfor two".to_owned(), t_minus: false };
+        state.set_timers(vec![
+            SavedTimer {
+                options: tea,
+                start: DateTime::parse_from_rfc3339("2026-10-17T12:40:07+02:00").unwrap(),
+            },
+            SavedTimer {
+                options: TimerOptions { duration: Duration::from_secs(90), name: String::new(), t_minus: true },
+                start: DateTime::parse_from_rfc3339("2026-10-17T12:41:00Z").unwrap(),
+            },
+        ]);
+        state.unknown.push("from-the-future\twhatever".to_owned());
+
+        let contents = state.contents();
+        let (loaded, skipped) = State::parse(&contents);
+        assert_eq!(skipped, Vec::<usize>::new());
+        assert_eq!(loaded.layout("1920x1080+0+0"), state.layout("1920x1080+0+0"));
+        assert_eq!(loaded.layout("3840x2160+0+0"), state.layout("3840x2160+0+0"));
+        assert_eq!(loaded.timers(), state.timers());
+        assert_eq!(loaded.contents(), contents);
+    }
+
+    #[test]
+    fn skips_lines_it_cant_make_sense_of() {
+        let contents = [
+            "layout\tsomewhere\t1\t2\t50",
+            "layout\tsomewhere else\tleft\t2\t50",
+            "timer\tyesterday\t300\ttea",
+            "timer\t2026-10-17T12:40:07Z\t999999999\tforever",
+            "",
+            "t-minus\t2026-10-17T12:40:07Z\t60\tlaunch",
+            "layout",
+        ].join("\n");
+        let (state, skipped) = State::parse(&contents);
+        assert_eq!(skipped, [2, 3, 4, 7]);
+        assert_eq!(state.layout("somewhere"), Some(Layout { x: 1, y: 2, scale: 50.0 }));
+        assert_eq!(state.layout("somewhere else"), None);
+        assert_eq!(state.timers().len(), 1);
+        assert_eq!(state.timers()[0].options.name, "launch");
+    }
+}
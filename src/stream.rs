@@ -0,0 +1,81 @@
+//! The clock's frames as an iterator, for programs that want the pictures but
+//! not the window: bots, dashboards, screen sharing, and the like.
+//!
+//! ```no_run
+//! use dni_clock::{config::Config, stream::ClockStream};
+//!
+//! for (time, frame) in ClockStream::new(Config::default()).unwrap().take(3) {
+//!     println!("{time}: {}x{}", frame.width(), frame.height());
+//! }
+//! ```
+
+use std::{thread, time::Instant};
+
+use chrono::{DateTime, DurationRound, FixedOffset};
+
+use crate::{
+    colors,
+    config::Config,
+    fonts::Fonts,
+    frame::{self, DEFAULT_SCALE},
+    glyphs::{GlyphBuffer, Glyphs},
+    tick,
+};
+
+/// Every frame of the clock, one per tick, as it would be shown in a window.
+/// Each one waits for its tick, so it never ends.
+pub struct ClockStream {
+    config: Config,
+    glyphs: Glyphs,
+    scale: f32,
+    /// Drawn into for every frame, then copied out.
+    frame: GlyphBuffer,
+    /// The time of the last frame, so the next one waits for another.
+    last: Option<DateTime<FixedOffset>>,
+}
+
+impl ClockStream {
+    /// The clock as `config` describes it, at the usual scale.
+    pub fn new(config: Config) -> Result<Self, String> {
+        Self::with_scale(config, DEFAULT_SCALE)
+    }
+
+    /// The clock as `config` describes it, with its numbers about `scale`
+    /// pixels tall. Fails if the fonts it asks for can't be loaded.
+    pub fn with_scale(mut config: Config, scale: f32) -> Result<Self, String> {
+        let fonts = Fonts::load_all(&config.glyphs)?.swap_remove(0);
+        config.line_metrics = fonts.line_metrics(&config.glyphs);
+        let scale = config.glyphs.clamp_scale(scale);
+        let mut glyphs = Glyphs::with_starting_scale(scale, &config.glyphs, &fonts);
+        glyphs.set_colors(config.fg, config.bg);
+        Ok(Self { config, glyphs, scale, frame: GlyphBuffer::default(), last: None })
+    }
+
+    /// The time as it's shown: to the second, or to the minute without the
+    /// seconds.
+    fn shown_time(&self) -> DateTime<FixedOffset> {
+        let precision =
+            if self.config.show_seconds { chrono::Duration::seconds(1) } else { chrono::Duration::minutes(1) };
+        self.config.time_source.now().duration_trunc(precision).unwrap()
+    }
+}
+
+impl Iterator for ClockStream {
+    type Item = (DateTime<FixedOffset>, GlyphBuffer);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut time = self.shown_time();
+        while self.last == Some(time) {
+            let next_tick = tick::next(self.config.time_source.now(), self.config.show_seconds);
+            thread::sleep(next_tick.saturating_duration_since(Instant::now()));
+            time = self.shown_time();
+        }
+        self.last = Some(time);
+        frame::build_into(&mut self.frame, time, &mut self.glyphs, self.scale, &self.config);
+        // Cached glyphs are all light on dark, like in the window
+        if self.config.invert {
+            colors::invert_all(&mut self.frame);
+        }
+        Some((time, self.frame.clone()))
+    }
+}
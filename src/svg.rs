@@ -0,0 +1,336 @@
+//! Drawing glyphs as vector outlines, for SVG files that stay crisp at any
+//! size.
+//!
+//! Everything's laid out in the same pixel coordinates as the raster glyphs, so
+//! the two line up exactly, just without the pixels.
+
+use std::{f32::consts::TAU, fmt::Write};
+
+use ab_glyph::{point, Font, FontArc, Glyph, Outline, OutlineCurve, Point, PxScaleFactor, ScaleFont};
+
+use crate::{
+    colors,
+    frame::{Canvas, Number, Sign},
+    glyphs::{GlyphOptions, Glyphs},
+    shapes,
+};
+
+/// The `d` attribute of a `<path>` tracing `c` at `scale`, from the first of
+/// `fonts` that has it, with the top left of its raster glyph at 0, 0. `None`
+/// if none of them have it.
+pub fn glyph_path(fonts: &[&FontArc], c: char, scale: f32) -> Option<String> {
+    fonts.iter().find_map(|font| try_glyph_path(font, c, scale))
+}
+
+fn try_glyph_path(font: &FontArc, c: char, scale: f32) -> Option<String> {
+    let id = font.glyph_id(c);
+    // ID 0 is `.notdef`, what fonts give for characters they don't have
+    if id.0 == 0 {
+        return None;
+    }
+    let outline = font.outline(id)?;
+    let factor = font.as_scaled(scale).scale_factor();
+    let top_left = outline.px_bounds(factor, point(0.0, 0.0)).min;
+    Some(outline_path(&outline, factor, point(-top_left.x, -top_left.y)))
+}
+
+/// The `d` attribute of a `<path>` tracing `glyphs` (already laid out in a
+/// line, at `scale`) in `font`.
+pub fn text_path(font: &FontArc, glyphs: &[Glyph], scale: f32) -> String {
+    let factor = font.as_scaled(scale).scale_factor();
+    glyphs.iter()
+        .filter_map(|glyph| {
+            let outline = font.outline(glyph.id)?;
+            Some(outline_path(&outline, factor, glyph.position))
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Traces `outline` scaled by `factor`, with its origin (on the baseline) at
+/// `origin`.
+fn outline_path(outline: &Outline, factor: PxScaleFactor, origin: Point) -> String {
+    // Font units are y-up, pixels are y-down
+    let to_px = |p: &Point| (p.x * factor.horizontal + origin.x, -p.y * factor.vertical + origin.y);
+
+    let mut d = String::new();
+    let mut pen: Option<Point> = None;
+    for curve in &outline.curves {
+        let (start, end) = match curve {
+            OutlineCurve::Line(p0, p1) | OutlineCurve::Quad(p0, _, p1) | OutlineCurve::Cubic(p0, _, _, p1) => (p0, p1),
+        };
+        // The curves come one at a time, so a new contour is anywhere one
+        // doesn't pick up where the last left off
+        if pen != Some(*start) {
+            if pen.is_some() {
+                d.push('Z');
+            }
+            let (x, y) = to_px(start);
+            write!(d, "M{x:.2} {y:.2}").unwrap();
+        }
+        match curve {
+            OutlineCurve::Line(_, p1) => {
+                let (x, y) = to_px(p1);
+                write!(d, "L{x:.2} {y:.2}").unwrap();
+            }
+            OutlineCurve::Quad(_, p1, p2) => {
+                let ((x1, y1), (x, y)) = (to_px(p1), to_px(p2));
+                write!(d, "Q{x1:.2} {y1:.2} {x:.2} {y:.2}").unwrap();
+            }
+            OutlineCurve::Cubic(_, p1, p2, p3) => {
+                let ((x1, y1), (x2, y2), (x, y)) = (to_px(p1), to_px(p2), to_px(p3));
+                write!(d, "C{x1:.2} {y1:.2} {x2:.2} {y2:.2} {x:.2} {y:.2}").unwrap();
+            }
+        }
+        pen = Some(*end);
+    }
+    if pen.is_some() {
+        d.push('Z');
+    }
+    d
+}
+
+/// A path for the box drawn in place of a glyph no font has, to match
+/// [`crate::glyphs`]' raster one.
+pub fn tofu_path(scale: f32) -> String {
+    let width = (scale * 0.6).round().max(3.0);
+    let height = (scale * 0.8).round().max(3.0);
+    let stroke = (scale / 20.0).round().clamp(1.0, (width / 3.0).floor());
+    box_path(width, height, stroke)
+}
+
+/// A path for an empty box `width`×`height` with walls `stroke` thick, to
+/// match [`crate::glyphs`]' raster ones.
+pub fn box_path(width: f32, height: f32, stroke: f32) -> String {
+    // An outer box with an inner one cut out of it
+    format!(
+        "M0 0H{width}V{height}H0Z M{stroke} {stroke}V{inner_bottom}H{inner_right}V{stroke}Z",
+        inner_bottom = height - stroke,
+        inner_right = width - stroke,
+    )
+}
+
+/// The `d` attribute of a `<path>` tracing a `sign`, with a bar across at
+/// `top`, and for a plus, one up and down at `left` from `bar_top` as long as
+/// the sign is wide. All the bars are `stroke` thick.
+pub fn sign_path(sign: Sign, width: f32, top: f32, left: f32, bar_top: f32, stroke: f32) -> String {
+    let (bottom, right) = (top + stroke, left + stroke);
+    match sign {
+        Sign::Minus => format!("M0 {top}H{width}V{bottom}H0Z"),
+        // One outline all the way around, so nothing overlaps
+        Sign::Plus => format!(
+            "M{left} {bar_top}H{right}V{top}H{width}V{bottom}H{right}V{bar_bottom}H{left}V{bottom}H0V{top}H{left}Z",
+            bar_bottom = bar_top + width,
+        ),
+    }
+}
+
+/// An SVG document being built up, one glyph at a time.
+pub struct Svg {
+    width: usize,
+    height: usize,
+    fg: colors::Color,
+    bg: colors::Color,
+    /// How far the glow reaches out from everything, and how strong it is.
+    glow: Option<(f32, f32)>,
+    body: String,
+}
+
+impl Svg {
+    /// Starts an empty `width`×`height` picture, filled with the background
+    /// color.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, height, fg: colors::FG, bg: colors::BG, glow: None, body: String::new() }
+    }
+
+    /// Draws everything from here on in `fg`, and the background in `bg`.
+    pub fn set_colors(&mut self, fg: colors::Color, bg: colors::Color) {
+        self.fg = fg;
+        self.bg = bg;
+    }
+
+    /// Makes everything glow, `strength` (from 0 to 1) of the way toward its
+    /// color right next to it and fading out over `reach`, like a theme
+    /// script's glow in pixels.
+    pub fn set_glow(&mut self, reach: f32, strength: f32) {
+        self.glow = Some((reach, strength));
+    }
+
+    /// Adds a glyph traced by `path` (from [`glyph_path`]), with the top left
+    /// of where its raster glyph would be at `x`, `y`. The raster glyph is
+    /// `height` tall, with whatever synthetic styles `options` asks for, which
+    /// are faked here to match.
+    pub fn glyph(&mut self, path: &str, x: f32, y: f32, height: usize, options: &GlyphOptions) {
+        let mut transform = format!("translate({x} {y})");
+        let mut style = format!(r#"fill="{}""#, hex(self.fg));
+        let weight = options.stroke_weight();
+        if weight > 0 {
+            // Synthetic bold grows the raster glyph this much on each side,
+            // and a round stroke thickens it the same way the dilation does
+            write!(transform, " translate({weight} {weight})").unwrap();
+            write!(
+                style,
+                r#" stroke="{}" stroke-width="{}" stroke-linejoin="round""#,
+                hex(self.fg),
+                weight * 2,
+            ).unwrap();
+        }
+        if options.slant != 0.0 {
+            // Lean over about the bottom row, same as `synth::slant`
+            let tan = options.slant.to_radians().tan();
+            let bottom = height.saturating_sub(1) as f32;
+            let origin = (-bottom * tan).max(0.0);
+            // The raster glyph is emboldened before it's slanted, so the bold
+            // offset goes inside the skew
+            let weight = weight as f32;
+            transform = format!(
+                "translate({x} {y}) matrix(1 0 {} 1 {} 0) translate({weight} {weight})",
+                -tan,
+                bottom * tan + origin,
+            );
+        }
+        writeln!(self.body, r#"<path transform="{transform}" {style} d="{path}"/>"#).unwrap();
+    }
+
+    /// Adds a label traced by `path` (from [`text_path`]), with the top left of
+    /// its line at `x`, `y`, cut off at `width`×`height`.
+    pub fn label(&mut self, path: &str, x: usize, y: usize, width: usize, height: usize) {
+        // A nested `<svg>` clips whatever's outside it
+        writeln!(
+            self.body,
+            r#"<svg x="{x}" y="{y}" width="{width}" height="{height}"><path fill="{}" d="{path}"/></svg>"#,
+            hex(self.fg),
+        ).unwrap();
+    }
+
+    /// Adds a progress ring, like [`Canvas::ring`] draws. The dimmer track
+    /// around the rest of it is left out if `track` is false.
+    pub fn ring(&mut self, x: usize, y: usize, diameter: usize, thickness: f32, progress: f32, track: bool) {
+        // Strokes are centered on the path, so the path runs down the middle
+        let radius = diameter as f32 / 2.0 - thickness / 2.0;
+        let (cx, cy) = (x as f32 + diameter as f32 / 2.0, y as f32 + diameter as f32 / 2.0);
+        let stroke = format!(r#"fill="none" stroke-width="{thickness}""#);
+        if track {
+            let color = colors::lerp(self.bg, self.fg, shapes::TRACK_BRIGHTNESS);
+            writeln!(self.body, r#"<circle cx="{cx}" cy="{cy}" r="{radius}" {stroke} stroke="{}"/>"#, hex(color))
+                .unwrap();
+        }
+        let progress = progress.clamp(0.0, 1.0);
+        if progress >= 1.0 {
+            writeln!(self.body, r#"<circle cx="{cx}" cy="{cy}" r="{radius}" {stroke} stroke="{}"/>"#, hex(self.fg))
+                .unwrap();
+        } else if progress > 0.0 {
+            let angle = progress * TAU;
+            let (end_x, end_y) = (cx + radius * angle.sin(), cy - radius * angle.cos());
+            let large_arc = u8::from(progress > 0.5);
+            writeln!(
+                self.body,
+                r#"<path {stroke} stroke="{}" d="M{cx} {top}A{radius} {radius} 0 {large_arc} 1 {end_x} {end_y}"/>"#,
+                hex(self.fg),
+                top = cy - radius,
+            ).unwrap();
+        }
+    }
+
+    /// The finished document.
+    pub fn finish(self) -> String {
+        let body = match self.glow {
+            None => self.body,
+            // A blurred copy, under everything as it is
+            Some((reach, strength)) => format!(
+                concat!(
+                    r#"<filter id="glow" x="-20%" y="-20%" width="140%" height="140%">"#,
+                    r#"<feGaussianBlur stdDeviation="{deviation}"/>"#,
+                    r#"<feComponentTransfer><feFuncA type="linear" slope="{slope}"/></feComponentTransfer>"#,
+                    r#"<feMerge><feMergeNode/><feMergeNode in="SourceGraphic"/></feMerge></filter>"#,
+                    "\n",
+                    r#"<g filter="url(#glow)">"#,
+                    "\n{body}</g>\n",
+                ),
+                // Most of a Gaussian is within twice its deviation, and it
+                // leaves things about half as opaque right at the edges
+                deviation = reach / 2.0,
+                slope = strength * 2.0,
+                body = self.body,
+            ),
+        };
+        format!(
+            concat!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+                "\n",
+                r#"<rect width="100%" height="100%" fill="{bg}"/>"#,
+                "\n{body}</svg>\n",
+            ),
+            w = self.width,
+            h = self.height,
+            bg = hex(self.bg),
+            body = body,
+        )
+    }
+}
+
+/// A color as `#rrggbb`.
+fn hex(color: colors::Color) -> String {
+    let (r, g, b) = colors::to_u8_rgb(color);
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+impl Canvas for Svg {
+    fn number(&mut self, glyphs: &mut Glyphs, number: Number, x: usize, y: usize) {
+        if number == Number::MidnightMark {
+            let height = glyphs.get_midnight_mark().height();
+            let path = glyphs.midnight_mark_path();
+            self.glyph(&path, x as f32, y as f32, height, glyphs.options());
+            return;
+        }
+        let digits = number.digits(glyphs.options().numerals);
+        // Laid out like the numeral is composed, each digit over the last
+        let mut x = x;
+        for (i, &digit) in digits.iter().enumerate() {
+            if i > 0 {
+                x -= glyphs.overlap(digits[i - 1], digit);
+            }
+            let glyph = glyphs.get_dni_number_one_digit(digit);
+            let (width, height) = (glyph.width(), glyph.height());
+            let path = glyphs.dni_digit_path(digit);
+            self.glyph(&path, x as f32, y as f32, height, glyphs.options());
+            x += width;
+        }
+    }
+
+    fn colon(&mut self, glyphs: &mut Glyphs, x: usize, y: usize) {
+        let height = glyphs.get_colon().height();
+        self.glyph(&glyphs.colon_path(), x as f32, y as f32, height, glyphs.options());
+    }
+
+    fn sign(&mut self, glyphs: &mut Glyphs, sign: Sign, x: usize, y: usize) {
+        let height = glyphs.get_sign(sign).height();
+        self.glyph(&glyphs.sign_path(sign), x as f32, y as f32, height, glyphs.options());
+    }
+
+    fn fg(&self, _glyphs: &Glyphs) -> colors::Color {
+        self.fg
+    }
+
+    fn set_fg(&mut self, _glyphs: &mut Glyphs, fg: colors::Color) {
+        self.fg = fg;
+    }
+
+    fn label(&mut self, glyphs: &mut Glyphs, text: &str, x: usize, y: usize, width: usize, height: usize) {
+        let path = glyphs.label_path(text);
+        Svg::label(self, &path, x, y, width, height);
+    }
+
+    fn ring(
+        &mut self,
+        glyphs: &mut Glyphs,
+        x: usize,
+        y: usize,
+        diameter: usize,
+        thickness: f32,
+        progress: f32,
+    ) {
+        let track = glyphs.options().draws_tracks();
+        Svg::ring(self, x, y, diameter, thickness, progress, track);
+    }
+}
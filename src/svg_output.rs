@@ -0,0 +1,75 @@
+//! Writing each frame as an SVG document, for things that would rather have
+//! vectors than pixels, like web dashboards or plotters.
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config::Config,
+    glyphs::GlyphBuffer,
+    output::{ClockOutput, Event},
+    tick,
+};
+
+/// Draws the clock as SVG, to a file that's replaced every frame (so whatever's
+/// watching it never sees half a frame), or to stdout.
+pub struct SvgOutput {
+    /// Where to write each frame. `-` means stdout, where they just go one
+    /// after another.
+    path: PathBuf,
+    /// Set when writing fails, to stop at the next poll.
+    failed: bool,
+}
+
+impl SvgOutput {
+    /// How long to nap between polls, since there's no window to pace things.
+    const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, failed: false }
+    }
+
+    fn write(&self, svg: &str) -> io::Result<()> {
+        if self.path.as_os_str() == "-" {
+            let mut stdout = io::stdout().lock();
+            stdout.write_all(svg.as_bytes())?;
+            return stdout.flush();
+        }
+        let mut temp = self.path.clone().into_os_string();
+        temp.push(".tmp");
+        fs::write(&temp, svg)?;
+        fs::rename(&temp, &self.path)
+    }
+}
+
+impl ClockOutput for SvgOutput {
+    fn init(&mut self, default_scale: f32, _config: &Config) -> f32 {
+        default_scale
+    }
+
+    fn present(&mut self, _frame: &GlyphBuffer, _config: &Config) {}
+
+    fn present_vector(&mut self, draw: &mut dyn FnMut() -> String) -> bool {
+        if let Err(e) = self.write(&draw()) {
+            eprintln!("error: couldn't write {}: {e}", self.path.display());
+            self.failed = true;
+        }
+        true
+    }
+
+    fn animates(&self) -> bool {
+        false
+    }
+
+    fn poll_events(&mut self, next_tick: Instant, _config: &Config) -> Vec<Event> {
+        if self.failed {
+            return vec![Event::Quit];
+        }
+        tick::sleep_until_tick(Self::POLL_INTERVAL, next_tick);
+        Vec::new()
+    }
+}
@@ -0,0 +1,217 @@
+//! Faking styles a font doesn't come in, by reworking its rendered glyphs.
+
+use crate::{
+    buf2d::Vec2d,
+    glyphs::{MaskBuffer, MaskView},
+};
+
+/// Snaps every pixel of `glyph` to either covered or not, whichever it's
+/// closer to, for edges as sharp as they can be.
+pub fn threshold(glyph: &mut MaskBuffer) {
+    for y in 0..glyph.height() {
+        for px in glyph.row_mut(y) {
+            *px = if *px > u8::MAX / 2 { u8::MAX } else { 0 };
+        }
+    }
+}
+
+/// Blows `glyph` up `factor` times over, each pixel snapped to covered or not
+/// and drawn as a square block, for a pixel art look. Pixels a third covered
+/// count, more than [`threshold`] would, so thin strokes don't break up.
+pub fn pixelate(glyph: &MaskBuffer, factor: usize) -> MaskBuffer {
+    let mut blown_up = glyph.stretched(glyph.width() * factor, glyph.height() * factor);
+    for y in 0..blown_up.height() {
+        for px in blown_up.row_mut(y) {
+            *px = if *px > u8::MAX / 3 { u8::MAX } else { 0 };
+        }
+    }
+    blown_up
+}
+
+/// Thickens every stroke of `glyph` by `px` pixels on each side, for a bold
+/// look. The buffer grows by as much, so nothing gets cut off.
+///
+/// Each pixel takes the most coverage within a circle of radius `px` around
+/// it, which keeps rounded strokes round (a square would square them off).
+pub fn embolden(glyph: &MaskBuffer, px: usize) -> MaskBuffer {
+    if px == 0 {
+        return glyph.clone();
+    }
+    let reach = px as isize;
+    let offsets: Vec<(isize, isize)> = (-reach..=reach)
+        .flat_map(|dy| (-reach..=reach).map(move |dx| (dx, dy)))
+        .filter(|(dx, dy)| dx * dx + dy * dy <= reach * reach)
+        .collect();
+
+    let (width, height) = (glyph.width(), glyph.height());
+    let mut bold = Vec2d::new(0, width + px * 2, height + px * 2);
+    for y in 0..height {
+        for (x, &value) in glyph.row(y).iter().enumerate() {
+            if value == 0 {
+                continue;
+            }
+            // Spread each lit pixel out, rather than gathering into each
+            // output pixel, so the (usually many) empty ones cost nothing
+            let (center_x, center_y) = ((x + px) as isize, (y + px) as isize);
+            for &(dx, dy) in &offsets {
+                let out = &mut bold[((center_x + dx) as usize, (center_y + dy) as usize)];
+                *out = (*out).max(value);
+            }
+        }
+    }
+    bold
+}
+
+/// Moves the sides of every stroke of `glyph` out by `px` pixels (in, if it's
+/// negative), to even out how heavy fonts look at different sizes. Unlike
+/// [`embolden`], the buffer stays the same size, so the glyph's outer edges
+/// stay put (or shrink in) and it lines up as it was.
+///
+/// Fractions of a pixel blend between the whole numbers of pixels on either
+/// side, since at small sizes a whole pixel is a lot.
+pub fn normalize_strokes(glyph: &MaskBuffer, px: f32) -> MaskBuffer {
+    let (whole, fraction) = (px.abs().trunc() as isize * px.signum() as isize, px.abs().fract());
+    let near = morph(glyph, whole);
+    if fraction == 0.0 {
+        return near;
+    }
+    let far = morph(glyph, whole + px.signum() as isize);
+    let mut out = near;
+    for y in 0..out.height() {
+        for (near, &far) in out.row_mut(y).iter_mut().zip(far.row(y)) {
+            *near = (f32::from(*near) + (f32::from(far) - f32::from(*near)) * fraction).round() as u8;
+        }
+    }
+    out
+}
+
+/// Each pixel of `glyph` takes the most coverage within a circle of radius
+/// `px` around it (or the least, if `px` is negative, counting anything off
+/// the edge as empty), in a buffer the same size.
+fn morph(glyph: &MaskBuffer, px: isize) -> MaskBuffer {
+    if px == 0 {
+        return glyph.clone();
+    }
+    if px > 0 {
+        // The same as emboldening, without the room it grows into
+        let reach = px as usize;
+        return embolden(glyph, reach).sub_view(reach, reach, glyph.width(), glyph.height()).to_vec2d();
+    }
+    let reach = px.abs();
+    let offsets: Vec<(isize, isize)> = (-reach..=reach)
+        .flat_map(|dy| (-reach..=reach).map(move |dx| (dx, dy)))
+        .filter(|(dx, dy)| dx * dx + dy * dy <= reach * reach)
+        .collect();
+
+    let mut out = Vec2d::new(0, glyph.width(), glyph.height());
+    for y in 0..glyph.height() {
+        for x in 0..glyph.width() {
+            // Empty pixels stay empty, and there are usually plenty of them
+            if glyph[(x, y)] == 0 {
+                continue;
+            }
+            let neighbors = offsets.iter().map(|&(dx, dy)| {
+                // Off the edge counts as empty (off the top or left wraps
+                // around to huge, so it's off the edge too)
+                let (x, y) = ((x as isize + dx) as usize, (y as isize + dy) as usize);
+                glyph.get(x, y).copied().unwrap_or(0)
+            });
+            out[(x, y)] = neighbors.min().unwrap_or(0);
+        }
+    }
+    out
+}
+
+/// How far each pixel of `glyph` is from the edge of its strokes, in pixels:
+/// negative inside them, and positive outside. Anything off the edge of the
+/// buffer counts as outside.
+///
+/// It's a chamfer distance, which is close enough to the true distance, and
+/// takes just two passes over the glyph.
+pub fn signed_distance(glyph: MaskView<'_>) -> Vec2d<f32> {
+    let (width, height) = (glyph.width(), glyph.height());
+    let inside = |x: usize, y: usize| glyph.row(y)[x] > u8::MAX / 2;
+    let to_inside = chamfer(width, height, f32::INFINITY, inside);
+    let to_outside = chamfer(width, height, 0.0, |x, y| !inside(x, y));
+    // The edge is halfway between the last pixel in and the first out
+    Vec2d::from_fn(width, height, |x, y| {
+        if inside(x, y) { 0.5 - to_outside[(x, y)] } else { to_inside[(x, y)] - 0.5 }
+    })
+}
+
+/// How far each pixel of a `width`×`height` buffer is from the nearest that's
+/// `seed` (or from off the edge, which is `edge` away from its neighbors).
+fn chamfer(width: usize, height: usize, edge: f32, seed: impl Fn(usize, usize) -> bool) -> Vec2d<f32> {
+    const DIAGONAL: f32 = std::f32::consts::SQRT_2;
+    let mut distance = Vec2d::from_fn(width, height, |x, y| if seed(x, y) { 0.0 } else { f32::INFINITY });
+    let at = |distance: &Vec2d<f32>, x: isize, y: isize| {
+        let inside = (0..width as isize).contains(&x) && (0..height as isize).contains(&y);
+        if inside { distance[(x as usize, y as usize)] } else { edge }
+    };
+    // Down and right from the neighbors above and to the left, then back up
+    // from the ones below and to the right
+    let forward = [(-1, 0, 1.0), (-1, -1, DIAGONAL), (0, -1, 1.0), (1, -1, DIAGONAL)];
+    let backward = forward.map(|(dx, dy, cost)| (-dx, -dy, cost));
+    for (neighbors, backwards) in [(forward, false), (backward, true)] {
+        for i in 0..width * height {
+            let i = if backwards { width * height - 1 - i } else { i };
+            let (x, y) = (i % width, i / width);
+            let nearest = neighbors
+                .iter()
+                .map(|&(dx, dy, cost)| at(&distance, x as isize + dx, y as isize + dy) + cost)
+                .fold(distance[(x, y)], f32::min);
+            distance[(x, y)] = nearest;
+        }
+    }
+    distance
+}
+
+/// How much wider slanting a glyph `height` pixels tall by `degrees` makes it.
+pub fn slant_width(height: usize, degrees: f32) -> usize {
+    (height.saturating_sub(1) as f32 * degrees.to_radians().tan().abs()).ceil() as usize
+}
+
+/// Leans `glyph` over by `degrees` (clockwise, like italics; negative leans it
+/// back), keeping the bottom row where it was. It gets wider by
+/// [`slant_width`], so nothing gets cut off.
+pub fn slant(glyph: &MaskBuffer, degrees: f32) -> MaskBuffer {
+    let tan = degrees.to_radians().tan();
+    let width = glyph.width() + slant_width(glyph.height(), degrees);
+    // Leaning back, it's the top that has to stay put instead
+    let origin = (-(glyph.height().saturating_sub(1) as f32) * tan).max(0.0);
+    shear(glyph, tan, origin, width)
+}
+
+/// Undoes [`slant`], near enough: the strokes come out a little blurrier than
+/// they went in, but they're where they were.
+pub fn unslant(glyph: &MaskBuffer, degrees: f32) -> MaskBuffer {
+    let tan = degrees.to_radians().tan();
+    let width = glyph.width().saturating_sub(slant_width(glyph.height(), degrees));
+    let origin = (glyph.height().saturating_sub(1) as f32 * tan).min(0.0);
+    shear(glyph, -tan, origin, width)
+}
+
+/// Shifts each row of `glyph` right by `origin`, plus however far up from the
+/// bottom it is times `tan`, into a buffer `width` wide.
+///
+/// The shifts aren't whole pixels, so each output pixel blends the two input
+/// pixels it falls between, which keeps the edges smooth.
+fn shear(glyph: &MaskBuffer, tan: f32, origin: f32, width: usize) -> MaskBuffer {
+    let height = glyph.height();
+    let mut sheared = Vec2d::new(0, width, height);
+    for y in 0..height {
+        let row = glyph.row(y);
+        let offset = origin + (height - 1 - y) as f32 * tan;
+        let px_at = |x: isize| usize::try_from(x).ok().and_then(|x| row.get(x)).copied();
+        for (x, out) in sheared.row_mut(y).iter_mut().enumerate() {
+            let source = x as f32 - offset;
+            let left = source.floor();
+            let fraction = source - left;
+            let left = left as isize;
+            let a = f32::from(px_at(left).unwrap_or(0));
+            let b = f32::from(px_at(left + 1).unwrap_or(0));
+            *out = (a + (b - a) * fraction).round() as u8;
+        }
+    }
+    sheared
+}
@@ -0,0 +1,105 @@
+//! The `system` complication: how busy the CPU is and how full memory is, each
+//! as a single D'ni digit (in 24ths, so 24 is all of it), sampled on a
+//! background thread.
+//!
+//! The numbers come from [`sysinfo`], so this works on Linux, macOS, and
+//! Windows; elsewhere it just says so.
+
+use std::{
+    sync::{Arc, Mutex, Once},
+    thread,
+    time::Duration,
+};
+
+use chrono::{DateTime, FixedOffset};
+use sysinfo::{CpuRefreshKind, MemoryRefreshKind, RefreshKind, System};
+
+use crate::{
+    complication::Complication,
+    config::Config,
+    frame::{self, Canvas, Number},
+    glyphs::Glyphs,
+};
+
+/// How often to sample. CPU usage is worked out between samples, so this is
+/// also what it's averaged over.
+const INTERVAL: Duration = Duration::from_secs(2);
+
+/// CPU and memory use, each from 0 to 1.
+#[derive(Debug, Clone, Copy)]
+struct Usage {
+    cpu: f32,
+    memory: f32,
+}
+
+#[derive(Debug)]
+pub struct SystemStats {
+    /// `None` until there've been two samples to compare.
+    usage: Arc<Mutex<Option<Usage>>>,
+    /// Sampling starts the first time it's drawn.
+    started: Once,
+}
+
+impl SystemStats {
+    pub fn new() -> Self {
+        Self { usage: Arc::default(), started: Once::new() }
+    }
+
+    fn start(&self) {
+        let usage = self.usage.clone();
+        thread::spawn(move || {
+            if !sysinfo::IS_SUPPORTED_SYSTEM {
+                eprintln!("warning: can't read CPU usage on this system");
+                return;
+            }
+            let mut system = System::new_with_specifics(
+                RefreshKind::nothing()
+                    .with_cpu(CpuRefreshKind::nothing().with_cpu_usage())
+                    .with_memory(MemoryRefreshKind::nothing().with_ram()),
+            );
+            loop {
+                thread::sleep(INTERVAL.max(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL));
+                system.refresh_cpu_usage();
+                system.refresh_memory_specifics(MemoryRefreshKind::nothing().with_ram());
+                let total = system.total_memory() as f32;
+                if total == 0.0 {
+                    continue;
+                }
+                let cpu = system.global_cpu_usage() / 100.0;
+                let memory = 1.0 - system.available_memory() as f32 / total;
+                *usage.lock().unwrap() = Some(Usage { cpu, memory });
+            }
+        });
+    }
+}
+
+impl Complication for SystemStats {
+    fn name(&self) -> &str {
+        "cpu : memory"
+    }
+
+    fn measure(&self, scale: f32, config: &Config) -> (usize, usize) {
+        let numbers = [Number::OneDigit(0), Number::OneDigit(0)];
+        (frame::numbers_width(&numbers, scale, config), frame::line_height(scale, config))
+    }
+
+    fn render(
+        &self,
+        canvas: &mut dyn Canvas,
+        glyphs: &mut Glyphs,
+        _time: DateTime<FixedOffset>,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) {
+        self.started.call_once(|| self.start());
+        let Some(usage) = *self.usage.lock().unwrap() else {
+            let label_height = glyphs.get_label("...").height();
+            canvas.label(glyphs, "...", x, y + height.saturating_sub(label_height) / 2, width, label_height);
+            return;
+        };
+        let in_24ths = |fraction: f32| Number::OneDigit((fraction.clamp(0.0, 1.0) * 24.0).round() as u8);
+        frame::write_numbers(canvas, glyphs, &[in_24ths(usage.cpu), in_24ths(usage.memory)], x, y, height);
+    }
+}
@@ -0,0 +1,243 @@
+//! `--theme-script`: working out the clock's colors (and its glow, and
+//! labels) from the time with a [Rhai](https://rhai.rs) script, so they can
+//! shift through the day, or change on the hour, however anyone likes.
+//!
+//! The script is run each time the face is drawn, with these in scope:
+//!
+//! - `hour`, `minute`, and `second`, the surface time;
+//! - `gartahvo`, `tahvo`, `gorahn`, and `prorahn`, the D'ni time;
+//! - `fg` and `bg`, the colors it'd otherwise be, like `"#ffffff"`;
+//!
+//! and it returns a map with any of
+//!
+//! - `fg` and `bg`, new colors;
+//! - `glow`, how strongly the numbers glow, from 0 (not at all, the default)
+//!   to 1;
+//! - `labels`, a map from fields (`hours`, `tahvo`, and so on) to the text to
+//!   label them with, instead of the `--label`s;
+//!
+//! like
+//!
+//! ```text
+//! #{ fg: hsl(hour * 15, 0.6, 0.7), glow: if hour < 6 { 0.5 } else { 0 } }
+//! ```
+//!
+//! Labels take up room, so a script that gives them where there are no
+//! `--label`s makes the window taller: give one to start with, even an empty
+//! one, and it stays the same size.
+//!
+//! Colors are written like `"#ff8800"`, and made with `rgb(r, g, b)` (each
+//! 0-255), `hsl(hue, saturation, lightness)` (the hue in degrees, the others
+//! from 0 to 1), or `mix(a, b, t)` (from `a` at 0 to `b` at 1).
+//!
+//! A script that takes too long is stopped, so it can't hang the clock. One
+//! that fails leaves the colors as they'd otherwise be, and says so (once).
+
+use std::{
+    fmt,
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use chrono::{DateTime, TimeZone, Timelike};
+use rhai::{Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+
+use crate::{
+    colors::{self, Color},
+    dni_time::DniTime,
+    labels::Field,
+};
+
+/// How much a script can do each time before it's stopped, which is far more
+/// than working out a color takes.
+const MAX_OPERATIONS: u64 = 100_000;
+
+/// What a `--theme-script` makes of the clock at some time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Look {
+    pub fg: Color,
+    pub bg: Color,
+    /// How strongly the numbers glow, from 0 (not at all) to 1.
+    pub glow: f32,
+    /// Text to label fields with, instead of the `--label`s.
+    pub labels: Vec<(Field, String)>,
+}
+
+impl Look {
+    /// Just the colors, with no glow or labels.
+    fn plain(fg: Color, bg: Color) -> Self {
+        Self { fg, bg, glow: 0.0, labels: Vec::new() }
+    }
+}
+
+/// A compiled `--theme-script`.
+pub struct ThemeScript {
+    path: PathBuf,
+    engine: Engine,
+    ast: AST,
+    /// Set once it's failed, so the warning's only given once.
+    failed: AtomicBool,
+}
+
+impl ThemeScript {
+    /// Reads and compiles the script at `path`.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = fs::read_to_string(path).map_err(|e| format!("couldn't read {}: {e}", path.display()))?;
+        Self::compile(path, &source)
+    }
+
+    /// Compiles `source`, which was read from `path`.
+    fn compile(path: &Path, source: &str) -> Result<Self, String> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.register_fn("rgb", rgb);
+        engine.register_fn("hsl", hsl);
+        engine.register_fn("mix", mix);
+        let ast = engine.compile(source).map_err(|e| format!("in {}: {e}", path.display()))?;
+        Ok(Self { path: path.to_owned(), engine, ast, failed: AtomicBool::new(false) })
+    }
+
+    /// How the clock looks at `time`, with `fg` and `bg` as the colors it'd
+    /// otherwise be (and is, with no glow or labels, if the script fails).
+    pub fn look<Tz: TimeZone>(&self, time: &DateTime<Tz>, fg: Color, bg: Color) -> Look {
+        match self.run(time, fg, bg) {
+            Ok(look) => look,
+            Err(e) => {
+                if !self.failed.swap(true, Ordering::Relaxed) {
+                    eprintln!("warning: {}: {e}", self.path.display());
+                }
+                Look::plain(fg, bg)
+            }
+        }
+    }
+
+    fn run<Tz: TimeZone>(&self, time: &DateTime<Tz>, fg: Color, bg: Color) -> Result<Look, String> {
+        let dni = DniTime::from_surface(time);
+        let mut scope = Scope::new();
+        scope.push_constant("hour", i64::from(time.hour()));
+        scope.push_constant("minute", i64::from(time.minute()));
+        scope.push_constant("second", i64::from(time.second()));
+        scope.push_constant("gartahvo", i64::from(dni.gartahvo));
+        scope.push_constant("tahvo", i64::from(dni.tahvo));
+        scope.push_constant("gorahn", i64::from(dni.gorahn));
+        scope.push_constant("prorahn", i64::from(dni.prorahn));
+        scope.push_constant("fg", hex(fg));
+        scope.push_constant("bg", hex(bg));
+        let result: Dynamic = self.engine.eval_ast_with_scope(&mut scope, &self.ast).map_err(|e| e.to_string())?;
+        let Some(map) = result.try_cast::<Map>() else {
+            return Err("expected it to return a map, like `#{ fg: \"#ff8800\" }`".to_owned());
+        };
+        let color = |name: &str, otherwise: Color| match map.get(name) {
+            None => Ok(otherwise),
+            Some(value) => {
+                let value = value.clone().into_string().map_err(|_| format!("expected `{name}` to be a color"))?;
+                colors::parse(&value).map_err(|e| format!("bad `{name}` `{value}`: {e}"))
+            }
+        };
+        let glow = match map.get("glow") {
+            None => 0.0,
+            Some(value) => number(value).map_err(|_| "expected `glow` to be a number".to_owned())?.clamp(0.0, 1.0),
+        };
+        let mut labels = Vec::new();
+        if let Some(value) = map.get("labels") {
+            let Some(texts) = value.read_lock::<Map>() else {
+                return Err("expected `labels` to be a map, like `#{ hours: \"surface\" }`".to_owned());
+            };
+            for (name, text) in texts.iter() {
+                let field: Field = name.parse().map_err(|e| format!("bad label `{name}`: {e}"))?;
+                let text = text.clone().into_string().map_err(|_| format!("expected label `{name}` to be text"))?;
+                labels.push((field, text));
+            }
+        }
+        Ok(Look { fg: color("fg", fg)?, bg: color("bg", bg)?, glow: glow as f32, labels })
+    }
+}
+
+impl fmt::Debug for ThemeScript {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ThemeScript").field(&self.path).finish()
+    }
+}
+
+/// `color` written like `#ff8800`, the way scripts see them.
+fn hex(color: Color) -> String {
+    format!("#{color:06x}")
+}
+
+/// A number from a script, which might be written as a whole one.
+fn number(value: &Dynamic) -> Result<f64, Box<EvalAltResult>> {
+    value.as_float()
+        .or_else(|_| value.as_int().map(|n| n as f64))
+        .map_err(|_| format!("expected a number, not {}", value.type_name()).into())
+}
+
+/// A color from a script.
+fn color(value: &str) -> Result<Color, Box<EvalAltResult>> {
+    colors::parse(value).map_err(|e| format!("bad color `{value}`: {e}").into())
+}
+
+fn rgb(r: Dynamic, g: Dynamic, b: Dynamic) -> Result<String, Box<EvalAltResult>> {
+    let channel = |value: &Dynamic| number(value).map(|c| c.clamp(0.0, 255.0).round() as u8);
+    Ok(hex(colors::from_u8_rgb(channel(&r)?, channel(&g)?, channel(&b)?)))
+}
+
+fn hsl(hue: Dynamic, saturation: Dynamic, lightness: Dynamic) -> Result<String, Box<EvalAltResult>> {
+    let hue = number(&hue)? / 360.0;
+    let saturation = number(&saturation)?.clamp(0.0, 1.0);
+    let lightness = number(&lightness)?.clamp(0.0, 1.0);
+    Ok(hex(colors::from_hsl(hue as f32, saturation as f32, lightness as f32)))
+}
+
+fn mix(a: &str, b: &str, t: Dynamic) -> Result<String, Box<EvalAltResult>> {
+    let t = number(&t)?.clamp(0.0, 1.0);
+    Ok(hex(colors::lerp(color(a)?, color(b)?, t as f32)))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+
+    fn look_at(source: &str, hour: u32) -> Look {
+        let script = ThemeScript::compile(Path::new("test.rhai"), source).unwrap();
+        script.look(&Utc.ymd(2026, 10, 17).and_hms(hour, 0, 0), colors::FG, colors::BG)
+    }
+
+    #[test]
+    fn works_out_colors_from_the_time() {
+        let source = "#{ fg: hsl(hour * 15, 1, 0.5), bg: rgb(10, 20, 30) }";
+        assert_eq!(look_at(source, 0), Look::plain(0xff0000, 0x0a141e));
+        assert_eq!(look_at(source, 8).fg, 0x00ff00);
+        assert_eq!(look_at(source, 16).fg, 0x0000ff);
+    }
+
+    #[test]
+    fn leaves_out_colors_as_they_were() {
+        assert_eq!(look_at("#{ bg: mix(\"#000000\", \"#ffffff\", 2) }", 0), Look::plain(colors::FG, colors::FG));
+        assert_eq!(look_at("#{}", 0), Look::plain(colors::FG, colors::BG));
+    }
+
+    #[test]
+    fn clamps_saturation_and_lightness() {
+        assert_eq!(look_at("#{ fg: hsl(0, 5, 0.5) }", 0).fg, look_at("#{ fg: hsl(0, 1, 0.5) }", 0).fg);
+        assert_eq!(look_at("#{ fg: hsl(0, -1, 0.5) }", 0).fg, look_at("#{ fg: hsl(0, 0, 0.5) }", 0).fg);
+        assert_eq!(look_at("#{ fg: hsl(0, 1, 7) }", 0).fg, 0xffffff);
+    }
+
+    #[test]
+    fn gives_glow_and_labels() {
+        let look = look_at("#{ glow: if hour < 6 { 2 } else { 0.25 }, labels: #{ hours: `${hour}h` } }", 3);
+        assert_eq!(look.glow, 1.0);
+        assert_eq!(look.labels, [(Field::Hours, "3h".to_owned())]);
+        assert_eq!(look_at("#{ glow: 0.25 }", 12).glow, 0.25);
+    }
+
+    #[test]
+    fn keeps_the_colors_if_it_fails() {
+        for source in ["42", "#{ fg: \"orange\" }", "#{ labels: #{ hour: \"h\" } }", "loop {}"] {
+            assert_eq!(look_at(source, 0), Look::plain(colors::FG, colors::BG), "{source}");
+        }
+    }
+}
@@ -0,0 +1,29 @@
+//! Keeping redraws in step with the wall clock, so the clock changes when other
+//! clocks do rather than whenever it next happens to look.
+
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, FixedOffset, Timelike};
+
+/// When the time on the clock face next changes, if it's `now`: the start of
+/// the next second, or of the next minute if seconds aren't shown.
+///
+/// It's worked out from the clock's time each time it's asked, so it can't
+/// drift from it, even if the system clock gets adjusted.
+pub fn next(now: DateTime<FixedOffset>, show_seconds: bool) -> Instant {
+    // Over a billion during a leap second
+    let nanos = u64::from(now.nanosecond().min(999_999_999));
+    let into_second = Duration::from_nanos(nanos);
+    let until = if show_seconds {
+        Duration::from_secs(1) - into_second
+    } else {
+        Duration::from_secs(60) - Duration::from_secs(now.second().into()) - into_second
+    };
+    Instant::now() + until
+}
+
+/// Sleeps for `interval`, or until `tick`, whichever comes first.
+pub fn sleep_until_tick(interval: Duration, tick: Instant) {
+    let until_tick = tick.saturating_duration_since(Instant::now());
+    std::thread::sleep(interval.min(until_tick));
+}
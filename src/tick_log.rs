@@ -0,0 +1,56 @@
+//! `--log-ticks`: a line on stdout every time the clock face changes, saying
+//! what it changed to and how drawing it went, for chasing down late or
+//! dropped frames (or for piping into something else).
+
+use std::{
+    io::{self, Write},
+    time::Duration,
+};
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::{config::Config, frame};
+
+/// Frame statistics, gathered between ticks.
+pub struct TickLog {
+    /// Frames drawn since the last tick (redraws included).
+    frames: usize,
+    /// How long the slowest of them took to draw.
+    slowest: Duration,
+}
+
+impl TickLog {
+    pub fn new() -> Self {
+        Self { frames: 0, slowest: Duration::ZERO }
+    }
+
+    /// Counts a frame that took `took` to draw.
+    pub fn frame(&mut self, took: Duration) {
+        self.frames += 1;
+        self.slowest = self.slowest.max(took);
+    }
+
+    /// Writes a line for the tick to `time`, logged at `now`, like:
+    ///
+    /// ```text
+    /// 12:34:56    12:[1 9]:[2 6]    late=1.3ms    frames=1    draw=0.42ms
+    /// ```
+    ///
+    /// Fields are separated by tabs. When the face has more than one row (say,
+    /// with `--dni-time`), they're all in the second field, separated by ` | `.
+    pub fn tick(&mut self, time: DateTime<FixedOffset>, now: DateTime<FixedOffset>, config: &Config) {
+        let late = (now - time).to_std().unwrap_or_default();
+        let line = format!(
+            "{}\t{}\tlate={:.1}ms\tframes={}\tdraw={:.2}ms",
+            time.format("%H:%M:%S"),
+            frame::text(time, config).replace('\n', " | "),
+            late.as_secs_f64() * 1000.0,
+            self.frames,
+            self.slowest.as_secs_f64() * 1000.0,
+        );
+        let mut out = io::stdout().lock();
+        // Nobody reading isn't worth stopping the clock over
+        let _ = writeln!(out, "{line}").and_then(|()| out.flush());
+        *self = Self::new();
+    }
+}
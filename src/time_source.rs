@@ -0,0 +1,319 @@
+//! Where the time on the clock comes from, and what time zone it's in.
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, FixedOffset, Local, Offset, TimeZone, Utc};
+
+use crate::{timer, zones::Tz};
+
+#[derive(Debug, Clone)]
+pub struct TimeSource {
+    pub clock: Arc<dyn ClockSource>,
+    /// Show the time in this zone, instead of in the system's time zone (for
+    /// when that's wrong, or there isn't one).
+    pub zone: Option<Tz>,
+}
+
+impl Default for TimeSource {
+    fn default() -> Self {
+        Self { clock: Arc::new(SystemClock), zone: None }
+    }
+}
+
+/// Something that knows what time it is.
+pub trait ClockSource: fmt::Debug + Send + Sync {
+    /// The time of day, which can jump around (when the clock gets set, or
+    /// the computer wakes up).
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Time that only ever goes forward, at a steady rate, for measuring how
+    /// long something's been on screen.
+    fn monotonic_now(&self) -> Instant;
+
+    /// This clock, if it's one that can be moved around by hand.
+    fn as_fake(&self) -> Option<&FakeClock> {
+        None
+    }
+}
+
+/// The system clock (the usual).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl ClockSource for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    fn monotonic_now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that starts at a made-up time and then only does what it's told,
+/// for seeing what the clock does at midnight, or when the clocks change,
+/// or after the computer's been asleep, without waiting around for it.
+///
+/// It runs along in real time like any other clock unless it's paused.
+#[derive(Debug)]
+pub struct FakeClock {
+    state: Mutex<FakeState>,
+}
+
+#[derive(Debug)]
+struct FakeState {
+    /// The time of day as of `anchor`.
+    time: DateTime<Utc>,
+    /// The monotonic time as of `anchor`.
+    monotonic: Instant,
+    /// When (really) the two were last set.
+    anchor: Instant,
+    paused: bool,
+}
+
+impl FakeState {
+    fn elapsed(&self) -> Duration {
+        if self.paused { Duration::ZERO } else { self.anchor.elapsed() }
+    }
+
+    /// Folds the time that's gone by into `time` and `monotonic`, so they can
+    /// be changed from where they are now.
+    fn catch_up(&mut self) {
+        let elapsed = self.elapsed();
+        self.time += chrono_duration(elapsed);
+        self.monotonic += elapsed;
+        self.anchor = Instant::now();
+    }
+}
+
+/// A change to make to a [`FakeClock`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockChange {
+    /// Set the time of day, as if someone changed the system clock.
+    Set(DateTime<Utc>),
+    /// Let this much time go by at once.
+    Advance(Duration),
+    /// Let this much time go by while asleep: the time of day jumps ahead,
+    /// but the monotonic clock doesn't.
+    Suspend(Duration),
+    /// Stop the clock where it is.
+    Pause,
+    /// Start it going again.
+    Resume,
+}
+
+impl ClockChange {
+    /// Parses a change from the words for it, like `advance 1h` or `pause`.
+    pub fn parse(kind: &str, value: Option<&str>) -> Result<Self, String> {
+        let value = || value.ok_or_else(|| format!("`{kind}` needs a value"));
+        match kind {
+            "set" => parse_time(value()?).map(Self::Set),
+            "advance" => timer::parse_span(value()?).map(Self::Advance),
+            "suspend" => timer::parse_span(value()?).map(Self::Suspend),
+            "pause" => Ok(Self::Pause),
+            "resume" => Ok(Self::Resume),
+            _ => Err(format!("expected `set`, `advance`, `suspend`, `pause`, or `resume`, not `{kind}`")),
+        }
+    }
+
+    /// The words [`parse`](Self::parse) turns back into this.
+    pub fn to_words(self) -> String {
+        match self {
+            Self::Set(time) => format!("set {}", time.to_rfc3339()),
+            Self::Advance(duration) => format!("advance {}s", duration.as_secs()),
+            Self::Suspend(duration) => format!("suspend {}s", duration.as_secs()),
+            Self::Pause => "pause".to_owned(),
+            Self::Resume => "resume".to_owned(),
+        }
+    }
+}
+
+/// Parses a moment in RFC 3339 form, like `2024-03-10T01:59:50-07:00`.
+pub fn parse_time(s: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|_| "expected a time like `2024-03-10T01:59:50-07:00`".to_owned())
+}
+
+impl FakeClock {
+    /// A clock that says it's `start`, and keeps going from there.
+    pub fn new(start: DateTime<Utc>) -> Self {
+        let now = Instant::now();
+        Self { state: Mutex::new(FakeState { time: start, monotonic: now, anchor: now, paused: false }) }
+    }
+
+    /// Makes `change`, or leaves it be if the time would go out of range.
+    pub fn change(&self, change: ClockChange) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        state.catch_up();
+        let later = |time: DateTime<Utc>, duration| {
+            chrono::Duration::from_std(duration).ok()
+                .and_then(|duration| time.checked_add_signed(duration))
+                .ok_or_else(|| format!("can't go {}s past {time}", duration.as_secs()))
+        };
+        match change {
+            ClockChange::Set(time) => state.time = time,
+            ClockChange::Advance(duration) => {
+                let monotonic = state.monotonic.checked_add(duration).ok_or("can't go that far ahead")?;
+                state.time = later(state.time, duration)?;
+                state.monotonic = monotonic;
+            }
+            ClockChange::Suspend(duration) => state.time = later(state.time, duration)?,
+            ClockChange::Pause => state.paused = true,
+            ClockChange::Resume => state.paused = false,
+        }
+        Ok(())
+    }
+}
+
+impl ClockSource for FakeClock {
+    fn now(&self) -> DateTime<Utc> {
+        let state = self.state.lock().unwrap();
+        state.time + chrono_duration(state.elapsed())
+    }
+
+    fn monotonic_now(&self) -> Instant {
+        let state = self.state.lock().unwrap();
+        state.monotonic + state.elapsed()
+    }
+
+    fn as_fake(&self) -> Option<&FakeClock> {
+        Some(self)
+    }
+}
+
+/// `duration`, which is only ever how long it's been running, as chrono's.
+fn chrono_duration(duration: Duration) -> chrono::Duration {
+    chrono::Duration::from_std(duration).unwrap_or_else(|_| chrono::Duration::max_value())
+}
+
+impl TimeSource {
+    pub fn now(&self) -> DateTime<FixedOffset> {
+        let now = self.clock.now();
+        now.with_timezone(&self.offset_at(now))
+    }
+
+    /// See [`ClockSource::monotonic_now`].
+    pub fn monotonic_now(&self) -> Instant {
+        self.clock.monotonic_now()
+    }
+
+    /// The UTC offset in effect at `time`, in the zone asked for, if any, or
+    /// otherwise the system's.
+    fn offset_at(&self, time: DateTime<Utc>) -> FixedOffset {
+        match self.zone {
+            Some(zone) => zone.offset_at(time),
+            None => Local.offset_from_utc_datetime(&time.naive_utc()).fix(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Timelike;
+
+    use super::*;
+
+    /// A fake clock paused at about `start`, so nothing moves unless it's told
+    /// to, and where it stopped: pausing it takes the moment it took to get
+    /// to.
+    fn paused_at(start: &str) -> (TimeSource, DateTime<Utc>, Instant) {
+        let clock = FakeClock::new(parse_time(start).unwrap());
+        clock.change(ClockChange::Pause).unwrap();
+        let (time, monotonic) = (clock.now(), clock.monotonic_now());
+        (TimeSource { clock: Arc::new(clock), zone: None }, time, monotonic)
+    }
+
+    fn change(source: &TimeSource, change: ClockChange) -> Result<(), String> {
+        source.clock.as_fake().unwrap().change(change)
+    }
+
+    fn seconds(n: u64) -> Duration {
+        Duration::from_secs(n)
+    }
+
+    fn chrono_seconds(n: i64) -> chrono::Duration {
+        chrono::Duration::seconds(n)
+    }
+
+    #[test]
+    fn paused_clock_stands_still() {
+        let (source, time, monotonic) = paused_at("2026-10-17T12:40:07Z");
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(source.clock.now(), time);
+        assert_eq!(source.monotonic_now(), monotonic);
+    }
+
+    #[test]
+    fn advancing_moves_both_clocks_past_midnight() {
+        let (source, time, monotonic) = paused_at("2026-10-17T23:59:50Z");
+        change(&source, ClockChange::Advance(seconds(15))).unwrap();
+        assert_eq!(source.clock.now(), time + chrono_seconds(15));
+        assert_eq!(source.clock.now().date_naive(), parse_time("2026-10-18T00:00:00Z").unwrap().date_naive());
+        assert_eq!(source.monotonic_now(), monotonic + seconds(15));
+    }
+
+    #[test]
+    fn suspending_only_moves_the_time_of_day() {
+        let (source, time, monotonic) = paused_at("2026-10-17T12:00:00Z");
+        change(&source, ClockChange::Suspend(seconds(8 * 60 * 60))).unwrap();
+        assert_eq!(source.clock.now(), time + chrono_seconds(8 * 60 * 60));
+        assert_eq!(source.monotonic_now(), monotonic);
+    }
+
+    #[test]
+    fn setting_jumps_without_moving_monotonic_time() {
+        let (source, _, monotonic) = paused_at("2026-10-17T12:00:00Z");
+        change(&source, ClockChange::Set(parse_time("1998-04-21T10:35:18Z").unwrap())).unwrap();
+        assert_eq!(source.clock.now(), parse_time("1998-04-21T10:35:18Z").unwrap());
+        assert_eq!(source.monotonic_now(), monotonic);
+    }
+
+    #[test]
+    fn springs_forward_over_the_clock_change() {
+        let (mut source, _, _) = paused_at("2024-03-10T01:59:50-08:00");
+        source.zone = Some("America/Los_Angeles".parse().unwrap());
+        assert_eq!((source.now().hour(), source.now().offset().local_minus_utc()), (1, -8 * 60 * 60));
+        change(&source, ClockChange::Advance(seconds(10))).unwrap();
+        assert_eq!((source.now().hour(), source.now().offset().local_minus_utc()), (3, -7 * 60 * 60));
+    }
+
+    #[test]
+    fn out_of_range_changes_leave_it_be() {
+        let (source, time, monotonic) = paused_at("9999-12-31T23:00:00Z");
+        assert!(change(&source, ClockChange::Advance(seconds(u64::MAX))).is_err());
+        assert!(change(&source, ClockChange::Suspend(seconds(u64::MAX))).is_err());
+        assert_eq!(source.clock.now(), time);
+        assert_eq!(source.monotonic_now(), monotonic);
+    }
+
+    #[test]
+    fn huge_spans_are_refused() {
+        assert!(ClockChange::parse("advance", Some("18446744073709551615s")).is_err());
+        assert!(ClockChange::parse("suspend", Some("100001h")).is_err());
+        assert_eq!(
+            ClockChange::parse("advance", Some("100000h")),
+            Ok(ClockChange::Advance(seconds(100_000 * 60 * 60))),
+        );
+    }
+
+    #[test]
+    fn changes_survive_being_written_out() {
+        let changes = [
+            ClockChange::Set(parse_time("2024-03-10T01:59:50-07:00").unwrap()),
+            ClockChange::Advance(seconds(90)),
+            ClockChange::Suspend(seconds(3600)),
+            ClockChange::Pause,
+            ClockChange::Resume,
+        ];
+        for change in changes {
+            let words = change.to_words();
+            let (kind, value) = words.split_once(' ').map_or((words.as_str(), None), |(k, v)| (k, Some(v)));
+            assert_eq!(ClockChange::parse(kind, value), Ok(change));
+        }
+    }
+}
@@ -0,0 +1,289 @@
+//! Countdown timers, like `dni-clock timer 5m --name tea`. Each shows under the
+//! time as a complication until a little while after it's done.
+//!
+//! With `--t-minus`, it's a launch-style countdown instead: shown with a minus
+//! sign until zero, then a plus sign (and another color), counting back up.
+//!
+//! Ones that haven't gone off yet are kept in the state file, so restarting the
+//! clock doesn't lose them. If one ran out while the clock wasn't running, it
+//! goes off as soon as it's back, saying it was missed.
+
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::{
+    colors::Color,
+    complication::Complication,
+    config::Config,
+    dni_time::{DniDuration, DurationUnits},
+    frame::{self, Canvas, Number, Sign},
+    glyphs::Glyphs,
+    locale::Locale,
+    sound::Sound,
+    synth,
+};
+
+/// The longest a timer can run: as long as a single D'ni digit of hours can
+/// count down from.
+const LONGEST: Duration = Duration::from_secs(25 * 60 * 60 - 1);
+
+/// How long a finished timer stays up, saying it's done.
+const DONE_FOR: Duration = Duration::from_secs(60);
+
+/// What `dni-clock timer` was asked to do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimerOptions {
+    pub duration: Duration,
+    pub name: String,
+    /// Whether to keep counting past zero, T-minus style.
+    pub t_minus: bool,
+}
+
+/// Parses a duration like `5m`, `90s`, or `1h30m`.
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    check_duration(parse_span(s)?)
+}
+
+/// Makes sure a timer can run for `duration`, however it was given.
+pub fn check_duration(duration: Duration) -> Result<Duration, String> {
+    if duration > LONGEST {
+        return Err("timers can't be longer than 24h59m59s".to_owned());
+    }
+    Ok(duration)
+}
+
+/// The longest [`parse_span`] takes, which is years more than anything needs,
+/// and far enough from where clocks overflow.
+const LONGEST_SPAN: Duration = Duration::from_secs(100_000 * 60 * 60);
+
+/// Parses a length of time like [`parse_duration`], but of any length up to
+/// 100000 hours (about 11 years).
+pub fn parse_span(s: &str) -> Result<Duration, String> {
+    const BAD: &str = "expected a duration like `5m`, `90s`, or `1h30m`";
+    let mut seconds = 0_u64;
+    let mut rest = s;
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).ok_or(BAD)?;
+        let n: u64 = rest[..digits].parse().map_err(|_| BAD)?;
+        let unit = match &rest[digits..=digits] {
+            "h" => 60 * 60,
+            "m" => 60,
+            "s" => 1,
+            _ => return Err(BAD.to_owned()),
+        };
+        seconds = n.checked_mul(unit).and_then(|n| n.checked_add(seconds)).ok_or(BAD)?;
+        rest = &rest[digits + 1..];
+    }
+    let duration = Duration::from_secs(seconds);
+    if duration.is_zero() {
+        return Err(BAD.to_owned());
+    }
+    if duration > LONGEST_SPAN {
+        return Err("can't be longer than 100000h".to_owned());
+    }
+    Ok(duration)
+}
+
+/// A timer as it's saved between runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavedTimer {
+    pub options: TimerOptions,
+    /// When it was started, which is kept rather than when it ends so it can
+    /// be saved as it was asked for.
+    pub start: DateTime<FixedOffset>,
+}
+
+/// A timer counting down to `end`.
+#[derive(Debug)]
+pub struct Timer {
+    saved: SavedTimer,
+    end: DateTime<FixedOffset>,
+    /// What units what's left is shown in.
+    units: DurationUnits,
+    /// What color it's shown in past zero, for a T-minus countdown.
+    t_plus_color: Color,
+    locale: Locale,
+    /// Whether it ran out while the clock wasn't running.
+    missed: bool,
+    /// When to take it down.
+    done_until: DateTime<FixedOffset>,
+    /// Whether the alarm's gone off yet.
+    rang: AtomicBool,
+}
+
+impl Timer {
+    /// A timer for `duration` from `now`, shown the way `config` says.
+    pub fn new(options: TimerOptions, now: DateTime<FixedOffset>, config: &Config) -> Self {
+        Self::resume(SavedTimer { options, start: now }, now, config)
+    }
+
+    /// Picks a saved timer back up as of `now`.
+    pub fn resume(saved: SavedTimer, now: DateTime<FixedOffset>, config: &Config) -> Self {
+        let end = saved.start + chrono::Duration::from_std(saved.options.duration).unwrap();
+        let missed = end <= now;
+        let done_until = if saved.options.t_minus {
+            // Counts up until it can't show any more
+            end + chrono::Duration::from_std(LONGEST).unwrap()
+        } else {
+            // A missed one stays up as long as any other, from when it's noticed
+            end.max(now) + chrono::Duration::from_std(DONE_FOR).unwrap()
+        };
+        Self {
+            saved,
+            end,
+            units: config.duration_units,
+            t_plus_color: config.t_plus_color,
+            locale: config.locale,
+            missed,
+            done_until,
+            rang: AtomicBool::new(false),
+        }
+    }
+
+    /// What to save so it can be picked back up, if it hasn't gone off yet.
+    pub fn pending(&self) -> Option<SavedTimer> {
+        (!self.rang.load(Ordering::Relaxed)).then(|| self.saved.clone())
+    }
+
+    /// What's left, `left` seconds, as numerals.
+    fn numbers(&self, left: i64) -> [Number; 3] {
+        match self.units {
+            DurationUnits::Surface => [
+                Number::OneDigit((left / 3600).try_into().unwrap()),
+                Number::TwoDigits((left / 60 % 60).try_into().unwrap()),
+                Number::TwoDigits((left % 60).try_into().unwrap()),
+            ],
+            DurationUnits::Dni => DniDuration::from_duration(chrono::Duration::seconds(left)).numerals(),
+        }
+    }
+}
+
+impl Complication for Timer {
+    fn name(&self) -> &str {
+        &self.saved.options.name
+    }
+
+    fn measure(&self, scale: f32, config: &Config) -> (usize, usize) {
+        let numbers = self.numbers(0);
+        let line_height = frame::line_height(scale, config);
+        let mut width = frame::numbers_width(&numbers, scale, config);
+        if self.saved.options.t_minus {
+            // The sign is about two thirds as wide as a digit
+            let slant_width = synth::slant_width(line_height, config.glyphs.slant);
+            width += line_height * 2 / 3 + slant_width + sign_gap(line_height);
+        }
+        (width, line_height)
+    }
+
+    fn render(
+        &self,
+        canvas: &mut dyn Canvas,
+        glyphs: &mut Glyphs,
+        time: DateTime<FixedOffset>,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) {
+        let left = (self.end - time).num_seconds();
+        if self.saved.options.t_minus {
+            let (sign, seconds) = if left > 0 { (Sign::Minus, left) } else { (Sign::Plus, -left) };
+            let fg = canvas.fg(glyphs);
+            if sign == Sign::Plus {
+                canvas.set_fg(glyphs, self.t_plus_color);
+            }
+            let (sign_width, sign_height) = {
+                let glyph = glyphs.get_sign(sign);
+                (glyph.width(), glyph.height())
+            };
+            canvas.sign(glyphs, sign, x, y + height.saturating_sub(sign_height) / 2);
+            let x = x + sign_width + sign_gap(height);
+            frame::write_numbers(canvas, glyphs, &self.numbers(seconds), x, y, height);
+            canvas.set_fg(glyphs, fg);
+            return;
+        }
+        if left <= 0 {
+            let label = self.locale.text(if self.missed { "missed!" } else { "done!" });
+            let label_height = glyphs.get_label(label).height();
+            canvas.label(glyphs, label, x, y + height.saturating_sub(label_height) / 2, width, label_height);
+            return;
+        }
+        frame::write_numbers(canvas, glyphs, &self.numbers(left), x, y, height);
+    }
+
+    fn sound(&self, time: DateTime<FixedOffset>) -> Option<Sound> {
+        let done = time >= self.end;
+        (done && !self.rang.swap(true, Ordering::Relaxed)).then_some(Sound::Alarm)
+    }
+
+    fn expired(&self, time: DateTime<FixedOffset>) -> bool {
+        time >= self.done_until
+    }
+}
+
+/// The space between a T-minus countdown's sign and its numbers, in a line
+/// `line_height` tall.
+fn sign_gap(line_height: usize) -> usize {
+    line_height / 6
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::time_source::{self, ClockChange, FakeClock};
+
+    /// The clock, on a fake clock paused at `start`.
+    fn config_at(start: &str) -> (Config, Arc<FakeClock>) {
+        let clock = Arc::new(FakeClock::new(time_source::parse_time(start).unwrap()));
+        clock.change(ClockChange::Pause).unwrap();
+        let mut config = Config::default();
+        config.time_source.clock = clock.clone();
+        (config, clock)
+    }
+
+    fn tea() -> TimerOptions {
+        TimerOptions { duration: parse_duration("5m").unwrap(), name: "tea".to_owned(), t_minus: false }
+    }
+
+    #[test]
+    fn goes_off_once_when_it_runs_out() {
+        let (config, clock) = config_at("2026-10-17T12:40:07Z");
+        let timer = Timer::new(tea(), config.time_source.now(), &config);
+        clock.change(ClockChange::Advance(Duration::from_secs(299))).unwrap();
+        assert_eq!(timer.sound(config.time_source.now()), None);
+        clock.change(ClockChange::Advance(Duration::from_secs(1))).unwrap();
+        assert_eq!(timer.sound(config.time_source.now()), Some(Sound::Alarm));
+        assert_eq!(timer.sound(config.time_source.now()), None);
+        assert!(!timer.missed);
+    }
+
+    #[test]
+    fn one_that_ran_out_while_asleep_was_missed() {
+        let (config, clock) = config_at("2026-10-17T23:58:00Z");
+        let timer = Timer::new(tea(), config.time_source.now(), &config);
+        let saved = timer.pending().unwrap();
+        clock.change(ClockChange::Suspend(Duration::from_secs(8 * 60 * 60))).unwrap();
+        let resumed = Timer::resume(saved, config.time_source.now(), &config);
+        assert!(resumed.missed);
+        assert_eq!(resumed.sound(config.time_source.now()), Some(Sound::Alarm));
+        assert!(resumed.pending().is_none());
+        assert!(!resumed.expired(config.time_source.now()));
+        clock.change(ClockChange::Advance(DONE_FOR)).unwrap();
+        assert!(resumed.expired(config.time_source.now()));
+    }
+
+    #[test]
+    fn durations_have_to_fit_in_a_digit_of_hours() {
+        assert_eq!(parse_duration("24h59m59s"), Ok(LONGEST));
+        assert!(parse_duration("25h").is_err());
+        assert!(check_duration(Duration::from_secs(360_000)).is_err());
+        assert!(parse_duration("0s").is_err());
+        assert!(parse_duration("5x").is_err());
+    }
+}
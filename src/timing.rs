@@ -0,0 +1,196 @@
+//! How long the expensive bits take in the running clock, so slowdowns can be
+//! measured rather than guessed at. Unlike `dni-clock bench`, it's what
+//! actually happened, frame by frame.
+//!
+//! The work's wrapped in [`tracing`] spans named after a [`Stage`], like
+//! `trace_span!("render")`, and once [`Timings`] is [`install`]ed, each one's
+//! time goes into a histogram for its stage. `dni-clock stats` asks the
+//! running clock for a [`report`].
+//!
+//! Everything's kept in atomics, so recording doesn't lock or allocate.
+
+use std::{
+    cell::RefCell,
+    fmt::Write,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Instant,
+};
+
+use tracing::{span, Subscriber};
+use tracing_subscriber::{layer::Context, prelude::*, registry::LookupSpan, Layer};
+
+/// Something worth timing, by the name of its spans.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Rendering a glyph from its font (or drawing one, like the midnight
+    /// mark), to go in the cache.
+    Render,
+    /// Putting digits together into a numeral, to go in the cache.
+    Compose,
+    /// Tinting a glyph onto a frame.
+    Blit,
+    /// Handing a finished frame to the output.
+    Present,
+    /// A whole frame, from drawing it to presenting it.
+    Frame,
+}
+
+impl Stage {
+    const ALL: [Self; 5] = [Self::Frame, Self::Render, Self::Compose, Self::Blit, Self::Present];
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Render => "render",
+            Self::Compose => "compose",
+            Self::Blit => "blit",
+            Self::Present => "present",
+            Self::Frame => "frame",
+        }
+    }
+
+    fn histogram(self) -> &'static Histogram {
+        &HISTOGRAMS[self as usize]
+    }
+
+    /// The stage a span's for, if it's one of the clock's.
+    fn of(metadata: &tracing::Metadata<'_>) -> Option<Self> {
+        if !metadata.target().starts_with(env!("CARGO_CRATE_NAME")) {
+            return None;
+        }
+        Self::ALL.into_iter().find(|stage| stage.name() == metadata.name())
+    }
+}
+
+/// How many buckets each histogram has. Bucket `i` is for times under 2^`i`
+/// microseconds (and over the one before), and the last is for anything
+/// longer, about half a second or more.
+const BUCKETS: usize = 21;
+
+/// Times for one stage.
+struct Histogram {
+    buckets: [AtomicU64; BUCKETS],
+    count: AtomicU64,
+    total_micros: AtomicU64,
+    max_micros: AtomicU64,
+}
+
+impl Histogram {
+    const fn new() -> Self {
+        #[allow(clippy::declare_interior_mutable_const)] // only to fill the array
+        const ZERO: AtomicU64 = AtomicU64::new(0);
+        Self {
+            buckets: [ZERO; BUCKETS],
+            count: ZERO,
+            total_micros: ZERO,
+            max_micros: ZERO,
+        }
+    }
+
+    fn record(&self, micros: u64) {
+        let bucket = (u64::BITS - micros.leading_zeros()) as usize;
+        self.buckets[bucket.min(BUCKETS - 1)].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.total_micros.fetch_add(micros, Ordering::Relaxed);
+        self.max_micros.fetch_max(micros, Ordering::Relaxed);
+    }
+
+    /// About how long it took `fraction` of the time (0.5 for the median), in
+    /// microseconds: the top of the bucket it falls in.
+    fn percentile(&self, fraction: f64) -> u64 {
+        let max = self.max_micros.load(Ordering::Relaxed);
+        let count = self.count.load(Ordering::Relaxed);
+        let target = ((count as f64 * fraction).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, bucket) in self.buckets.iter().enumerate() {
+            seen += bucket.load(Ordering::Relaxed);
+            if seen >= target {
+                return max.min(1 << i);
+            }
+        }
+        max
+    }
+}
+
+static HISTOGRAMS: [Histogram; Stage::ALL.len()] = [
+    Histogram::new(),
+    Histogram::new(),
+    Histogram::new(),
+    Histogram::new(),
+    Histogram::new(),
+];
+
+thread_local! {
+    /// When each of the spans this thread's in was entered, innermost last.
+    /// (Kept here rather than with the spans, which would mean allocating.)
+    static ENTERED: RefCell<Vec<Instant>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Records how long each [`Stage`]'s spans are entered for.
+pub struct Timings;
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for Timings {
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        if ctx.metadata(id).and_then(Stage::of).is_some() {
+            ENTERED.with_borrow_mut(|entered| entered.push(Instant::now()));
+        }
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        let Some(stage) = ctx.metadata(id).and_then(Stage::of) else { return };
+        if let Some(start) = ENTERED.with_borrow_mut(Vec::pop) {
+            let micros = start.elapsed().as_micros().try_into().unwrap_or(u64::MAX);
+            stage.histogram().record(micros);
+        }
+    }
+}
+
+/// Starts timing, for the rest of the process.
+pub fn install() {
+    if tracing::subscriber::set_global_default(tracing_subscriber::registry().with(Timings)).is_err() {
+        eprintln!("warning: something else is already collecting timings, so `dni-clock stats` won't have any");
+    }
+}
+
+/// Every stage's times so far, with a histogram for each, like:
+///
+/// ```text
+/// frame    n=120  mean=0.41ms  p50=0.51ms  p99=0.93ms  max=0.93ms
+///    <256µs  12 ##
+///    <512µs  96 ################
+///   <1024µs  12 ##
+/// ```
+///
+/// (Percentiles are only as exact as the buckets.)
+pub fn report() -> String {
+    let mut report = String::new();
+    for stage in Stage::ALL {
+        let histogram = stage.histogram();
+        let count = histogram.count.load(Ordering::Relaxed);
+        if count == 0 {
+            writeln!(report, "{:<8} n=0", stage.name()).unwrap();
+            continue;
+        }
+        let ms = |micros: u64| micros as f64 / 1000.0;
+        writeln!(
+            report,
+            "{:<8} n={count}  mean={:.2}ms  p50={:.2}ms  p99={:.2}ms  max={:.2}ms",
+            stage.name(),
+            ms(histogram.total_micros.load(Ordering::Relaxed) / count),
+            ms(histogram.percentile(0.5)),
+            ms(histogram.percentile(0.99)),
+            ms(histogram.max_micros.load(Ordering::Relaxed)),
+        ).unwrap();
+        let biggest = histogram.buckets.iter().map(|b| b.load(Ordering::Relaxed)).max().unwrap_or(0);
+        for (i, bucket) in histogram.buckets.iter().enumerate() {
+            let n = bucket.load(Ordering::Relaxed);
+            if n == 0 {
+                continue;
+            }
+            // Up to 40 wide
+            let bar = "#".repeat(((n * 40).div_ceil(biggest)) as usize);
+            let bound = if i == BUCKETS - 1 { format!(">={}µs", 1_u64 << (i - 1)) } else { format!("<{}µs", 1_u64 << i) };
+            writeln!(report, "  {bound:>9} {n:>6} {bar}").unwrap();
+        }
+    }
+    report
+}
@@ -0,0 +1,81 @@
+//! The window's title, which can show the time, for window-manager rules and
+//! scripts that look for it, and for screen readers, which announce a window
+//! by its title (the clock itself is just pixels to them).
+
+use std::str::FromStr;
+
+use chrono::{DateTime, FixedOffset, Timelike};
+
+use crate::{
+    config::Command,
+    dni_time::DniTime,
+    speech::{self, Style},
+};
+
+/// What the window's called, as given by `--title`: text with tokens in braces
+/// that get filled in as the clock runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Title {
+    template: String,
+}
+
+/// The tokens a title can have, and what they stand for.
+const TOKENS: &[&str] = &["{time}", "{dni}", "{spoken}", "{mode}"];
+
+impl Title {
+    /// Whether it has anything that changes with the time, so it needs
+    /// updating every tick.
+    pub fn is_live(&self) -> bool {
+        ["{time}", "{dni}", "{spoken}"].iter().any(|token| self.template.contains(token))
+    }
+
+    /// The same title, but with the time in words after it, if it doesn't
+    /// say it already, for `--accessible`.
+    pub fn spoken(mut self) -> Self {
+        if !self.template.contains("{spoken}") {
+            self.template.push_str(": {spoken}");
+        }
+        self
+    }
+
+    /// The title as of `time`, while doing `command`.
+    pub fn format(&self, time: &DateTime<FixedOffset>, command: &Command) -> String {
+        let mode = match command {
+            Command::Quiz => "quiz",
+            _ => "clock",
+        };
+        let mut title = self.template.replace("{mode}", mode);
+        if self.is_live() {
+            let surface = format!("{:02}:{:02}:{:02}", time.hour(), time.minute(), time.second());
+            let dni = DniTime::from_surface(time);
+            let dni = format!("{}:{}:{}", dni.gartahvo, dni.tahvo, dni.gorahn);
+            title = title.replace("{time}", &surface).replace("{dni}", &dni);
+            // Only to the minute, so it isn't read out again every second
+            title = title.replace("{spoken}", &speech::words(*time, Style::Surface));
+        }
+        title
+    }
+}
+
+impl Default for Title {
+    fn default() -> Self {
+        Self { template: "D'ni Clock".to_owned() }
+    }
+}
+
+impl FromStr for Title {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Catch typos, rather than leaving them in the title for good
+        let mut rest = s;
+        while let Some(start) = rest.find('{') {
+            let token = rest[start..].find('}').map(|end| &rest[start..=start + end]);
+            match token {
+                Some(token) if TOKENS.contains(&token) => rest = &rest[start + token.len()..],
+                _ => return Err(format!("unknown token in `{}`; expected {}", &rest[start..], TOKENS.join(", "))),
+            }
+        }
+        Ok(Self { template: s.to_owned() })
+    }
+}
@@ -0,0 +1,120 @@
+//! Brief notes over the clock saying something happened, like "seconds off"
+//! after pressing S, or a timer running out. They're written in the label
+//! font, in a box like the tooltip's, and fade out after a couple of seconds
+//! (or just go, without animations). A new one takes the place of the last.
+
+use std::time::{Duration, Instant};
+
+use crate::{
+    animation,
+    colors,
+    config::Config,
+    glyphs::{GlyphBuffer, Glyphs},
+    layout::Line,
+    tooltip,
+};
+
+/// How long a toast stays up, fading out included.
+const SHOWN_FOR: Duration = Duration::from_millis(2500);
+
+/// How long it takes to fade out, at the end.
+const FADE_FOR: Duration = Duration::from_millis(500);
+
+/// Room around the text, inside the box.
+const PADDING: usize = 4;
+
+pub struct Toast {
+    text: String,
+    shown_at: Instant,
+    fades: bool,
+    /// Whether it's been drawn since it went up.
+    drawn: bool,
+    /// The box and its text, blended over the frame as it fades. Kept, so
+    /// each frame of the fade doesn't allocate one.
+    scratch: GlyphBuffer,
+}
+
+impl Toast {
+    /// Puts `text` up, as of now.
+    pub fn new(text: impl Into<String>, config: &Config) -> Self {
+        Self {
+            text: text.into(),
+            shown_at: config.time_source.monotonic_now(),
+            fades: animation::allowed(config),
+            drawn: false,
+            scratch: GlyphBuffer::default(),
+        }
+    }
+
+    fn ends_at(&self) -> Instant {
+        self.shown_at + SHOWN_FOR
+    }
+
+    /// Whether it's time to take it down.
+    pub fn over(&self, now: Instant) -> bool {
+        now >= self.ends_at()
+    }
+
+    fn fading(&self, now: Instant) -> bool {
+        self.fades && now + FADE_FOR >= self.ends_at()
+    }
+
+    /// Whether it needs drawing again: it's new, or it's fading.
+    pub fn needs_drawing(&self, now: Instant) -> bool {
+        !self.drawn || self.fading(now)
+    }
+
+    /// How long until it looks any different: until it starts to fade, or the
+    /// next frame once it has, or until it's over if it doesn't fade.
+    pub fn changes_in(&self, now: Instant) -> Duration {
+        let until_end = self.ends_at().saturating_duration_since(now);
+        if !self.fades {
+            until_end
+        } else if self.fading(now) {
+            animation::FRAME_INTERVAL.min(until_end)
+        } else {
+            until_end - FADE_FOR
+        }
+    }
+
+    /// How solid it is at `now`, from 0 (gone) to 1.
+    fn opacity(&self, now: Instant) -> f32 {
+        if !self.fades {
+            return 1.0;
+        }
+        let left = self.ends_at().saturating_duration_since(now);
+        (left.as_secs_f32() / FADE_FOR.as_secs_f32()).min(1.0)
+    }
+
+    /// Draws it over the middle of `frame`, as of `now`, or nothing if it
+    /// won't fit.
+    pub fn draw(&mut self, frame: &mut GlyphBuffer, glyphs: &mut Glyphs, now: Instant) {
+        self.drawn = true;
+        let line_height = glyphs.get_label("0").height();
+        let width = (glyphs.measure_label(&self.text) + PADDING * 2).min(frame.width());
+        let height = line_height + PADDING * 2;
+        if height > frame.height() {
+            return;
+        }
+        let x = (frame.width() - width) / 2;
+        let y = (frame.height() - height) / 2;
+        let line = Line::new().small(self.text.as_str()).align_center();
+
+        // Over a chroma key, there's no box, and nothing to fade into but the
+        // key, so it's there until it isn't
+        if glyphs.options().keyed_background {
+            line.within(x, y + PADDING, width, line_height).render_into(frame, glyphs);
+            return;
+        }
+        let fill = colors::lerp(glyphs.bg(), glyphs.fg(), tooltip::BOX_BRIGHTNESS);
+        self.scratch.reset(fill, width, height);
+        line.within(0, PADDING, width, line_height).render_into(&mut self.scratch, glyphs);
+        let opacity = self.opacity(now);
+        for row in 0..height {
+            let under = &mut frame.row_mut(y + row)[x..x + width];
+            for (pixel, &toast) in under.iter_mut().zip(self.scratch.row(row)) {
+                *pixel = colors::lerp(*pixel, toast, opacity);
+            }
+        }
+    }
+}
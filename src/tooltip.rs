@@ -0,0 +1,78 @@
+//! A note over the clock saying what it says in plain digits and words, for
+//! anyone who can't read the numerals (yet). It's shown while the pointer rests
+//! on the window, and for a few seconds after `dni-clock announce`.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, FixedOffset};
+
+use crate::{
+    colors,
+    config::Config,
+    dni_time::DniTime,
+    glyphs::{GlyphBuffer, Glyphs},
+    layout::Line,
+};
+
+/// How long the note stays up after `dni-clock announce`.
+pub const BANNER_FOR: Duration = Duration::from_secs(5);
+
+/// Room around the text, inside the box.
+const PADDING: usize = 4;
+
+/// How bright the box is, from the background (0.0) to the foreground (1.0),
+/// so it stands out from the clock behind it.
+pub const BOX_BRIGHTNESS: f32 = 0.15;
+
+/// What the note says: the time, the date, the time zone, and the D'ni date.
+fn lines(time: DateTime<FixedOffset>, config: &Config) -> [String; 4] {
+    let zone = match config.time_source.zone {
+        Some(zone) => format!("{} (UTC{})", zone.name(), time.offset()),
+        None => format!("UTC{}", time.offset()),
+    };
+    [
+        time.format("%H:%M:%S").to_string(),
+        date(time, config),
+        zone,
+        DniTime::from_surface(&time).date(),
+    ]
+}
+
+/// Like "Mon 5 Feb 2024", in the clock's language.
+fn date(time: DateTime<FixedOffset>, config: &Config) -> String {
+    let locale = config.locale;
+    format!("{} {} {} {}", locale.weekday(time.weekday()), time.day(), locale.month(time.month()), time.year())
+}
+
+/// Draws the note for `time` over the middle of `frame`. Lines that don't fit
+/// are left off the bottom.
+pub fn draw(frame: &mut GlyphBuffer, glyphs: &mut Glyphs, time: DateTime<FixedOffset>, config: &Config) {
+    let lines = lines(time, config);
+    let line_height = glyphs.get_label("0").height();
+    let room = frame.height().saturating_sub(PADDING * 2) / line_height.max(1);
+    let lines = &lines[..lines.len().min(room)];
+    if lines.is_empty() {
+        return;
+    }
+    let text_width = lines.iter().map(|line| glyphs.measure_label(line)).max().unwrap_or(0);
+    let width = (text_width + PADDING * 2).min(frame.width());
+    let height = line_height * lines.len() + PADDING * 2;
+    let x = (frame.width() - width) / 2;
+    let y = (frame.height() - height) / 2;
+
+    let (fg, bg) = (glyphs.fg(), glyphs.bg());
+    // Over a chroma key, the text has to make do without its box
+    if !glyphs.options().keyed_background {
+        let fill = colors::lerp(bg, fg, BOX_BRIGHTNESS);
+        for row in y..y + height {
+            frame.row_mut(row)[x..x + width].fill(fill);
+        }
+    }
+    for (i, line) in lines.iter().enumerate() {
+        Line::new()
+            .small(line.as_str())
+            .within(x, y + PADDING + line_height * i, width, line_height)
+            .align_center()
+            .render_into(frame, glyphs);
+    }
+}
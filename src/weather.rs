@@ -0,0 +1,142 @@
+//! The `weather` complication: the current temperature somewhere, checked now
+//! and then on a background thread, so drawing never waits on the network.
+
+use std::{
+    str::FromStr,
+    sync::{Arc, Mutex, Once},
+    thread,
+    time::Duration,
+};
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::{
+    complication::Complication,
+    config::Config,
+    frame::{self, Canvas, Number},
+    glyphs::Glyphs,
+    layout::Line,
+    labels,
+};
+
+/// Open-Meteo's current weather, which needs no API key.
+pub const OPEN_METEO_URL: &str =
+    "https://api.open-meteo.com/v1/forecast?latitude={lat}&longitude={lon}&current_weather=true";
+
+/// How long to wait on the weather service before giving up.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Where to get the weather for, as given by `weather=52.52,13.41`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Location {
+    latitude: f32,
+    longitude: f32,
+}
+
+impl FromStr for Location {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const BAD: &str = "expected a latitude and longitude, like `52.52,13.41`";
+        let (latitude, longitude) = s.split_once(',').ok_or(BAD)?;
+        let latitude: f32 = latitude.trim().parse().map_err(|_| BAD)?;
+        let longitude: f32 = longitude.trim().parse().map_err(|_| BAD)?;
+        if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+            return Err(BAD.to_owned());
+        }
+        Ok(Self { latitude, longitude })
+    }
+}
+
+/// The temperature, in °C, as of the last time it was checked. `None` until
+/// the first check comes back.
+type Reading = Arc<Mutex<Option<f32>>>;
+
+#[derive(Debug)]
+pub struct Weather {
+    url: String,
+    interval: Duration,
+    reading: Reading,
+    /// The checking starts the first time it's drawn, rather than as soon as
+    /// it's configured, so things that never draw it don't go online.
+    started: Once,
+}
+
+impl Weather {
+    pub fn new(location: Location, config: &Config) -> Self {
+        let url = config.weather_url
+            .replace("{lat}", &location.latitude.to_string())
+            .replace("{lon}", &location.longitude.to_string());
+        Self {
+            url,
+            interval: config.weather_interval,
+            reading: Reading::default(),
+            started: Once::new(),
+        }
+    }
+
+    fn start(&self) {
+        let (url, interval, reading) = (self.url.clone(), self.interval, self.reading.clone());
+        let agent: ureq::Agent = ureq::Agent::config_builder().timeout_global(Some(TIMEOUT)).build().into();
+        thread::spawn(move || loop {
+            match fetch_temperature(&agent, &url) {
+                Ok(celsius) => *reading.lock().unwrap() = Some(celsius),
+                // The last reading's better than nothing, so it stays up
+                Err(e) => eprintln!("warning: couldn't check the weather: {e}"),
+            }
+            thread::sleep(interval);
+        });
+    }
+}
+
+impl Complication for Weather {
+    fn name(&self) -> &str {
+        "weather"
+    }
+
+    fn measure(&self, scale: f32, config: &Config) -> (usize, usize) {
+        // A minus sign, the number, and the unit
+        let text_width = labels::line_height(scale) * 2;
+        let number_width = frame::numbers_width(&[Number::TwoDigits(0)], scale, config);
+        (text_width + number_width, frame::line_height(scale, config))
+    }
+
+    fn render(
+        &self,
+        canvas: &mut dyn Canvas,
+        glyphs: &mut Glyphs,
+        _time: DateTime<FixedOffset>,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) {
+        self.started.call_once(|| self.start());
+        let line = Line::new().within(x, y, width, height);
+        let Some(celsius) = *self.reading.lock().unwrap() else {
+            line.small("...").render_into(canvas, glyphs);
+            return;
+        };
+        let degrees = celsius.round();
+        let line = if degrees < 0.0 { line.small("-") } else { line };
+        // Two digits go up to 59, which is about as hot as it gets
+        let number = Number::TwoDigits(degrees.abs().min(59.0) as u8);
+        line.number(number).small("°C").render_into(canvas, glyphs);
+    }
+}
+
+/// Asks `url` for the current weather, and picks the temperature out of the
+/// answer.
+fn fetch_temperature(agent: &ureq::Agent, url: &str) -> Result<f32, String> {
+    let body = agent.get(url)
+        .call()
+        .and_then(|mut response| response.body_mut().read_to_string())
+        .map_err(|e| e.to_string())?;
+    let answer: serde_json::Value = serde_json::from_str(&body).map_err(|e| format!("the answer isn't JSON: {e}"))?;
+    // Open-Meteo's answer looks like
+    // `{..., "current_weather": {..., "temperature": 12.3, ...}, ...}`
+    answer["current_weather"]["temperature"]
+        .as_f64()
+        .map(|celsius| celsius as f32)
+        .ok_or_else(|| "no `current_weather.temperature` in the answer".to_owned())
+}
@@ -0,0 +1,209 @@
+//! `--webhook`: POSTing the time somewhere when it gets to be a certain time,
+//! like the top of the hour or of the D'ni gartahvo, or when a timer goes off,
+//! so home automation can do something about it (turn the lights up, say).
+//!
+//! What's sent is JSON, like
+//!
+//! ```text
+//! {"event":"hourly","time":"2026-10-17T13:00:00+00:00",
+//!  "surface":{"hour":13,"minute":0,"second":0},
+//!  "dni":{"hahr":9675,"vailee":5,"yahr":27,"gartahvo":1,"tahvo":0,
+//!         "gorahn":5,"prorahn":1,"date":"Leevot 27, 9675 DE"}}
+//! ```
+//!
+//! with `"timer"` and its name too, for an alarm. The vailee and yahr are
+//! counted from 1, like in the date.
+//!
+//! They're sent on a thread each, so a slow server never holds up the clock,
+//! and only while it's running: ones it wasn't running for aren't sent late.
+
+use std::{str::FromStr, thread};
+
+use chrono::{DateTime, FixedOffset, SecondsFormat, Timelike};
+
+use crate::{config::Config, dni_time::DniTime, http, util::json_string};
+
+/// The longest it can go between checks and still send what came due in
+/// between. Any longer, and the clock was stopped (or the computer asleep),
+/// or it's only just started.
+const MAX_GAP_SECONDS: i64 = 2 * 60;
+
+/// When a webhook's sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum When {
+    /// At the top of every hour.
+    Hourly,
+    /// At the top of every D'ni gartahvo (a little over six hours).
+    Gartahvo,
+    /// At the top of every D'ni tahvo (about 14½ minutes).
+    Tahvo,
+    /// Every this many minutes, on the minute (from midnight).
+    Every(u32),
+    /// Every day at this hour and minute.
+    At(u32, u32),
+    /// When a timer goes off.
+    Alarm,
+}
+
+impl When {
+    /// What the event's called in the JSON.
+    fn name(self) -> &'static str {
+        match self {
+            Self::Hourly => "hourly",
+            Self::Gartahvo => "gartahvo",
+            Self::Tahvo => "tahvo",
+            Self::Every(_) => "every",
+            Self::At(..) => "at",
+            Self::Alarm => "alarm",
+        }
+    }
+
+    /// Whether it came due after `from`, up to and including `to`.
+    fn due_between(self, from: DateTime<FixedOffset>, to: DateTime<FixedOffset>) -> bool {
+        // In local time, so the hours and days line up with the clock's
+        let local = |time: DateTime<FixedOffset>| time.timestamp() + i64::from(time.offset().local_minus_utc());
+        let (from_secs, to_secs) = (local(from), local(to));
+        let changed = |period: i64, offset: i64| {
+            (from_secs - offset).div_euclid(period) != (to_secs - offset).div_euclid(period)
+        };
+        let (from_dni, to_dni) = (DniTime::from_surface(&from), DniTime::from_surface(&to));
+        let gartahvo = |dni: &DniTime| (dni.hahr, dni.vailee, dni.yahr, dni.gartahvo);
+        match self {
+            Self::Hourly => changed(60 * 60, 0),
+            Self::Every(minutes) => changed(i64::from(minutes) * 60, 0),
+            Self::At(hour, minute) => changed(24 * 60 * 60, i64::from(hour * 60 + minute) * 60),
+            Self::Gartahvo => gartahvo(&from_dni) != gartahvo(&to_dni),
+            Self::Tahvo => (gartahvo(&from_dni), from_dni.tahvo) != (gartahvo(&to_dni), to_dni.tahvo),
+            Self::Alarm => false,
+        }
+    }
+}
+
+impl FromStr for When {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const BAD: &str =
+            "expected `hourly`, `gartahvo`, `tahvo`, `alarm`, `every:<minutes>`, or a time like `07:30`";
+        Ok(match s {
+            "hourly" => Self::Hourly,
+            "gartahvo" => Self::Gartahvo,
+            "tahvo" => Self::Tahvo,
+            "alarm" => Self::Alarm,
+            _ => {
+                if let Some(minutes) = s.strip_prefix("every:") {
+                    match minutes.parse() {
+                        Ok(minutes) if minutes > 0 => Self::Every(minutes),
+                        _ => return Err(format!("`{minutes}` isn't a number of minutes")),
+                    }
+                } else {
+                    let (hour, minute) = s.split_once(':').ok_or(BAD)?;
+                    let hour: u32 = hour.parse().map_err(|_| BAD)?;
+                    let minute: u32 = minute.parse().map_err(|_| BAD)?;
+                    if hour > 23 || minute > 59 {
+                        return Err(format!("`{s}` isn't a time of day"));
+                    }
+                    Self::At(hour, minute)
+                }
+            }
+        })
+    }
+}
+
+/// A URL to POST to, and when, as given by `--webhook <when>=<url>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Webhook {
+    pub when: When,
+    pub url: String,
+}
+
+impl FromStr for Webhook {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (when, url) = s.split_once('=').ok_or("expected `<when>=<url>`, like `hourly=http://...`")?;
+        if !url.starts_with("http://") {
+            return Err("only http:// URLs are supported".to_owned());
+        }
+        Ok(Self { when: when.parse()?, url: url.to_owned() })
+    }
+}
+
+/// Sends webhooks as they come due.
+pub struct Webhooks {
+    hooks: Vec<Webhook>,
+    /// When it last checked, to the second.
+    last: Option<DateTime<FixedOffset>>,
+}
+
+impl Webhooks {
+    pub fn new(config: &Config) -> Self {
+        Self { hooks: config.webhooks.clone(), last: None }
+    }
+
+    /// Sends any that came due since it last checked, as of `now`. Cheap
+    /// enough to call every frame.
+    pub fn check(&mut self, now: DateTime<FixedOffset>) {
+        if self.last.is_some_and(|last| last.timestamp() == now.timestamp()) {
+            return;
+        }
+        if let Some(last) = self.last {
+            let gap = now.timestamp() - last.timestamp();
+            if (0..=MAX_GAP_SECONDS).contains(&gap) {
+                for hook in &self.hooks {
+                    if hook.when.due_between(last, now) {
+                        send(hook, payload(hook.when, now, None));
+                    }
+                }
+            }
+        }
+        self.last = Some(now);
+    }
+
+    /// Sends the ones for alarms, for timer `name` going off at `time`.
+    pub fn alarm(&self, name: &str, time: DateTime<FixedOffset>) {
+        for hook in self.hooks.iter().filter(|hook| hook.when == When::Alarm) {
+            send(hook, payload(When::Alarm, time, Some(name)));
+        }
+    }
+}
+
+/// POSTs `json` to `hook`, in the background.
+fn send(hook: &Webhook, json: String) {
+    let url = hook.url.clone();
+    thread::spawn(move || {
+        if let Err(e) = http::post_json(&url, &json) {
+            eprintln!("warning: couldn't call webhook {url}: {e}");
+        }
+    });
+}
+
+/// What's sent for `when` at `time`.
+fn payload(when: When, time: DateTime<FixedOffset>, timer: Option<&str>) -> String {
+    let dni = DniTime::from_surface(&time);
+    let mut json = format!(
+        concat!(
+            r#"{{"event":"{}","time":"{}","#,
+            r#""surface":{{"hour":{},"minute":{},"second":{}}},"#,
+            r#""dni":{{"hahr":{},"vailee":{},"yahr":{},"gartahvo":{},"tahvo":{},"gorahn":{},"prorahn":{},"date":{}}}"#,
+        ),
+        when.name(),
+        time.to_rfc3339_opts(SecondsFormat::Secs, false),
+        time.hour(),
+        time.minute(),
+        time.second(),
+        dni.hahr,
+        dni.vailee + 1,
+        dni.yahr + 1,
+        dni.gartahvo,
+        dni.tahvo,
+        dni.gorahn,
+        dni.prorahn,
+        json_string(&dni.date()),
+    );
+    if let Some(name) = timer {
+        json.push_str(&format!(r#","timer":{}"#, json_string(name)));
+    }
+    json.push('}');
+    json
+}
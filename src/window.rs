@@ -0,0 +1,573 @@
+//! Showing the clock in a desktop window, with [`minifb`].
+
+use std::{
+    process::Command,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use minifb::{HasRawWindowHandle, Key, KeyRepeat, MouseMode, ScaleMode, Window, WindowOptions};
+use raw_window_handle::RawWindowHandle;
+
+use crate::{
+    burn_in,
+    colors,
+    config::Config,
+    cursor::CursorHider,
+    drag::WindowDrag,
+    frame,
+    glyphs::GlyphBuffer,
+    kiosk::{self, ScreenBlankInhibitor},
+    output::{ClockOutput, Event},
+    placement::{self, Monitor, MonitorSelector},
+    state::{Layout, State},
+    tick,
+    util::on_path,
+};
+
+/// How often to check whether monitors were plugged in or unplugged.
+const MONITOR_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How often to update the window when there's no new frame to show: often
+/// enough that dragging it around stays smooth.
+const FRAME_INTERVAL: Duration = Duration::from_micros(1_000_000 / 60);
+
+/// How long the pointer has to rest over the clock before it counts as
+/// hovering.
+const HOVER_DELAY: Duration = Duration::from_millis(500);
+
+/// How much bigger each press of `+` (or notch of Ctrl+scroll) makes the clock.
+const ZOOM_STEP: f32 = 1.1;
+
+/// How small or big zooming can make the scale.
+const MIN_SCALE: f32 = 12.0;
+const MAX_SCALE: f32 = 500.0;
+
+/// How a resizable window (see `--resize`) is filled, when it's not the same
+/// shape as the clock.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResizePolicy {
+    /// The clock's drawn at the same scale as ever, and stretched to fill the
+    /// window, squashing it if need be.
+    Stretch,
+    /// The clock's drawn as big as fits in the window, keeping its shape, with
+    /// the rest of the window left blank around it.
+    Letterbox,
+    /// The clock's drawn at the same scale as ever, then blown up by as many
+    /// whole times as fit, so every pixel stays crisp.
+    Integer,
+}
+
+impl FromStr for ResizePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stretch" => Ok(Self::Stretch),
+            "letterbox" => Ok(Self::Letterbox),
+            "integer" => Ok(Self::Integer),
+            _ => Err("expected `stretch`, `letterbox`, or `integer`".to_owned()),
+        }
+    }
+}
+
+/// The clock in a window: either a normal one, which remembers where it was
+/// left, or a fullscreen kiosk.
+pub struct MinifbOutput {
+    state: State,
+    monitors: Vec<Monitor>,
+    topology: String,
+    /// Set in kiosk mode, to the monitor it fills.
+    kiosk_monitor: Option<Monitor>,
+    scale: f32,
+    /// How big the window is inside. Frames are usually this size too, but if
+    /// one isn't (say, a complication was added), the window's made to fit,
+    /// unless it's resizable.
+    size: (usize, usize),
+    /// How to fill the window, if it's resizable (never in kiosk mode).
+    resize: Option<ResizePolicy>,
+    /// Set when the scale needs working out again, to fit the window.
+    refit: bool,
+    /// `None` until [`ClockOutput::init`].
+    window: Option<Window>,
+    /// The last frame shown, to keep showing between updates. The window may
+    /// hang on to a pointer into it, so it's only ever swapped for a whole
+    /// frame, never drawn into.
+    front: GlyphBuffer,
+    /// Where the next frame is copied before it's swapped in. Kept around so
+    /// its memory gets reused.
+    back: GlyphBuffer,
+    /// Whether `front` is new since the window was last updated.
+    fresh: bool,
+    last_update: Instant,
+    layout: Layout,
+    last_monitor_check: Instant,
+    drag: WindowDrag,
+    inhibitor: ScreenBlankInhibitor,
+    cursor_hider: Option<CursorHider>,
+    /// When the pointer came over the window, if it's there.
+    pointer_since: Option<Instant>,
+    /// Whether [`Event::Hover`] last said it was hovering.
+    hovering: bool,
+    /// The title last given to the window, so it's only changed when it has
+    /// to be (screen readers can be told about every change).
+    title: String,
+}
+
+impl MinifbOutput {
+    pub fn new(config: &Config) -> Self {
+        let monitors = Monitor::all();
+        let topology = placement::topology_key(&monitors);
+        let kiosk_monitor = if config.kiosk {
+            let monitor = config.monitor.as_ref().unwrap_or(&MonitorSelector::Primary).select(&monitors);
+            if monitor.is_none() {
+                eprintln!("warning: couldn't find a monitor for kiosk mode; opening a normal window");
+            }
+            monitor
+        } else {
+            None
+        };
+        let cursor_hider = (config.cursor_timeout > 0.0 && kiosk_monitor.is_none())
+            .then(|| CursorHider::new(Duration::from_secs_f32(config.cursor_timeout)));
+        let resize = config.resize.filter(|_| kiosk_monitor.is_none());
+        Self {
+            state: State::load(),
+            monitors,
+            topology,
+            kiosk_monitor,
+            scale: 0.0,
+            size: (0, 0),
+            resize,
+            refit: false,
+            window: None,
+            front: GlyphBuffer::default(),
+            back: GlyphBuffer::default(),
+            fresh: false,
+            last_update: Instant::now(),
+            layout: Layout { x: 0, y: 0, scale: 0.0 },
+            last_monitor_check: Instant::now(),
+            drag: WindowDrag::new(),
+            inhibitor: ScreenBlankInhibitor::new(),
+            cursor_hider,
+            pointer_since: None,
+            hovering: false,
+            title: String::new(),
+        }
+    }
+
+    /// The biggest scale that fits the size in `--geometry`, on the monitor
+    /// it's for, if there's a size.
+    fn geometry_scale(&self, config: &Config) -> Option<f32> {
+        let geometry = config.geometry?;
+        let monitor = config.monitor.as_ref().unwrap_or(&MonitorSelector::Primary).select(&self.monitors)?;
+        let (width, height) = geometry.size_on(&monitor)?;
+        Some(kiosk::fit_scale_within(width, height, |scale| window_size(scale, config)))
+    }
+
+    fn window(&mut self) -> &mut Window {
+        self.window.as_mut().expect("window used before init")
+    }
+
+    /// Checks for monitors coming and going, and moves (or resizes) the window
+    /// to suit. Returns the new scale, if it changed.
+    fn follow_monitors(&mut self, config: &Config) -> Option<f32> {
+        // A kiosk stays put on its monitor, so there's nothing to keep track of
+        if self.kiosk_monitor.is_some() || self.last_monitor_check.elapsed() < MONITOR_POLL_INTERVAL {
+            return None;
+        }
+        self.last_monitor_check = Instant::now();
+        let new_monitors = Monitor::all();
+        let new_topology = placement::topology_key(&new_monitors);
+        if new_topology == self.topology {
+            return None;
+        }
+        // `layout` is from before the change, so the OS hasn't had a chance to
+        // shove the window somewhere else yet
+        let old_topology = std::mem::replace(&mut self.topology, new_topology);
+        self.state.set_layout(old_topology, self.layout);
+        self.monitors = new_monitors;
+
+        // A size given as a share of the monitor has to be worked out again
+        if let Some(scale) = self.geometry_scale(config) {
+            let monitors = &self.monitors;
+            if scale != self.scale {
+                self.scale = scale;
+                self.size = window_size(scale, config);
+                self.window = Some(open_window(config, self.size, None, monitors));
+                return Some(scale);
+            }
+            let window = self.window.as_mut().expect("window used before init");
+            move_on_screen(window, config, self.size, None, monitors);
+            return None;
+        }
+        let remembered = self.state.layout(&self.topology);
+        if let Some(remembered) = remembered.filter(|l| l.scale != self.scale) {
+            self.scale = remembered.scale;
+            self.size = window_size(self.scale, config);
+            self.window = Some(open_window(config, self.size, Some(remembered), &self.monitors));
+            Some(self.scale)
+        } else {
+            let (size, monitors) = (self.size, &self.monitors);
+            let window = self.window.as_mut().expect("window used before init");
+            move_on_screen(window, config, size, remembered, monitors);
+            None
+        }
+    }
+}
+
+impl ClockOutput for MinifbOutput {
+    fn init(&mut self, default_scale: f32, config: &Config) -> f32 {
+        // Explicitly asking for a spot beats wherever it was last time
+        let remembered = if config.monitor.is_none()
+            && config.anchor.is_none()
+            && config.geometry.is_none()
+            && !config.kiosk
+        {
+            self.state.layout(&self.topology)
+        } else {
+            None
+        };
+        self.scale = match (&self.kiosk_monitor, self.geometry_scale(config)) {
+            (Some(monitor), _) => kiosk::fit_scale(monitor, |scale| window_size(scale, config)),
+            (None, Some(scale)) => scale,
+            (None, None) => remembered.map_or(default_scale, |layout| layout.scale),
+        };
+        self.size = window_size(self.scale, config);
+        let window = match &self.kiosk_monitor {
+            Some(monitor) => open_kiosk_window(monitor, config),
+            None => open_window(config, self.size, remembered, &self.monitors),
+        };
+        self.layout = current_layout(&window, self.scale);
+        self.window = Some(window);
+        self.scale
+    }
+
+    fn present(&mut self, frame: &GlyphBuffer, config: &Config) {
+        // The window gets handed the buffer on every poll anyway (it needs it
+        // to repaint after being covered up), so it'll be shown momentarily.
+        // It's finished before it's swapped in, so there's never a moment
+        // where the window could catch it half-copied.
+        // A kiosk's window is the whole monitor anyway, with the frame in the
+        // middle, but a normal one's only as big as the frame
+        let (width, height) = (frame.width(), frame.height());
+        match self.resize {
+            None if self.kiosk_monitor.is_none() && (width, height) != self.size => {
+                self.size = (width, height);
+                let here = Some(self.layout);
+                self.window = Some(open_window(config, self.size, here, &self.monitors));
+            }
+            Some(ResizePolicy::Letterbox) if width > self.size.0 || height > self.size.1 => {
+                self.refit = true;
+            }
+            _ => {}
+        }
+        if self.resize == Some(ResizePolicy::Integer) {
+            let times = usize::min(self.size.0 / width.max(1), self.size.1 / height.max(1)).max(1);
+            self.back.stretch_from(frame, width * times, height * times);
+        } else {
+            self.back.clone_from(frame);
+        }
+        // The window's background shows around the frame, so it has to match
+        // it (whether or not it's inverted). The corner's always background
+        if self.resize.is_some() || self.kiosk_monitor.is_some() {
+            if let Some(&px) = frame.as_1d().first() {
+                let (r, g, b) = colors::to_u8_rgb(px);
+                self.window().set_background_color(r.into(), g.into(), b.into());
+            }
+        }
+        std::mem::swap(&mut self.front, &mut self.back);
+        self.fresh = true;
+        if config.title.is_live() {
+            let title = config.title.format(&config.time_source.now(), &config.command);
+            if title != self.title {
+                self.window().set_title(&title);
+                self.title = title;
+            }
+        }
+    }
+
+    fn poll_events(&mut self, next_tick: Instant, config: &Config) -> Vec<Event> {
+        let mut events = Vec::new();
+
+        if let Some(monitor) = self.kiosk_monitor {
+            if !self.window().is_open() {
+                // Closing it the normal way (e.g., Alt+F4) isn't allowed here
+                self.window = Some(open_kiosk_window(&monitor, config));
+                events.push(Event::Redraw);
+            }
+            if kiosk::exit_requested(self.window()) {
+                events.push(Event::Quit);
+                return events;
+            }
+        } else if !self.window().is_open() {
+            events.push(Event::Quit);
+            return events;
+        }
+
+        if config.keep_awake || self.kiosk_monitor.is_some() {
+            if let Some(window) = &self.window {
+                self.inhibitor.hold_for(window);
+            }
+        }
+
+        if let Some(scale) = self.follow_monitors(config) {
+            events.push(Event::Rescale(scale));
+        }
+
+        let window = self.window.as_mut().expect("window used before init");
+        if config.borderless && self.kiosk_monitor.is_none() {
+            let (threshold, margin) = (config.snap_threshold, config.screen_margin);
+            self.drag.update(window, &self.monitors, threshold, margin);
+        }
+        if let Some(cursor_hider) = &mut self.cursor_hider {
+            cursor_hider.update(window);
+        }
+        let over = window.get_mouse_pos(MouseMode::Discard).is_some();
+        self.pointer_since = match self.pointer_since {
+            Some(since) if over => Some(since),
+            _ => over.then(Instant::now),
+        };
+        let hovering = self.pointer_since.is_some_and(|since| since.elapsed() >= HOVER_DELAY);
+        if hovering != self.hovering {
+            self.hovering = hovering;
+            events.push(Event::Hover(hovering));
+        }
+
+        // A new frame goes out right away, so it lands on the tick it's for;
+        // otherwise there's no hurry
+        if !self.fresh {
+            let since = self.last_update.elapsed();
+            if since < FRAME_INTERVAL {
+                tick::sleep_until_tick(FRAME_INTERVAL - since, next_tick);
+            }
+        }
+        self.last_update = Instant::now();
+        self.fresh = false;
+
+        let frame = &self.front;
+        if frame.width() == 0 {
+            window.update();
+        } else if let Err(e) = window.update_with_buffer(frame.as_1d(), frame.width(), frame.height()) {
+            eprintln!("warning: couldn't show the clock in its window ({e}), so it's closing");
+            events.push(Event::Quit);
+            return events;
+        }
+        if let (Some(policy), true) = (self.resize, window.is_open()) {
+            let size = window.get_size();
+            if size != self.size {
+                self.size = size;
+                match policy {
+                    ResizePolicy::Stretch => {}
+                    ResizePolicy::Letterbox => self.refit = true,
+                    // It's blown up as it comes in, so it needs to come in again
+                    ResizePolicy::Integer => events.push(Event::Redraw),
+                }
+            }
+        }
+        if std::mem::take(&mut self.refit) {
+            let (width, height) = self.size;
+            let scale = kiosk::fit_scale_within(width, height, |scale| window_size(scale, config));
+            if scale != self.scale {
+                self.scale = scale;
+                events.push(Event::Rescale(scale));
+            }
+        }
+
+        let keys = window.get_keys_pressed(KeyRepeat::Yes);
+        let mut zoom: i32 = keys.iter().copied().map(zoom_steps).sum();
+        let ctrl = window.is_key_down(Key::LeftCtrl) || window.is_key_down(Key::RightCtrl);
+        if let (true, Some((_, scroll))) = (ctrl, window.get_scroll_wheel()) {
+            zoom += scroll.signum() as i32;
+        }
+        if zoom != 0 {
+            // The window's resized when the first frame at the new scale
+            // comes in (see `present`)
+            let scale = (self.scale * ZOOM_STEP.powi(zoom)).round().clamp(MIN_SCALE, MAX_SCALE);
+            if scale != self.scale {
+                self.scale = scale;
+                events.push(Event::Rescale(scale));
+            }
+        }
+        if window.is_open() {
+            self.layout = current_layout(window, self.scale);
+        }
+        events.extend(keys.into_iter().filter_map(key_event));
+        events
+    }
+
+    fn close(&mut self) {
+        if self.kiosk_monitor.is_none() {
+            self.state.set_layout(self.topology.clone(), self.layout);
+            self.state.save();
+        }
+    }
+}
+
+/// What pressing `key` means, if it's one of the few that mean anything.
+fn key_event(key: Key) -> Option<Event> {
+    let digit = match key {
+        Key::Backspace => return Some(Event::Backspace),
+        Key::Enter | Key::NumPadEnter => return Some(Event::Enter),
+        Key::F => return Some(Event::NextFont),
+        Key::T => return Some(Event::Announce),
+        Key::I => return Some(Event::Invert),
+        Key::S => return Some(Event::ToggleSeconds),
+        Key::Key0 | Key::NumPad0 => 0,
+        Key::Key1 | Key::NumPad1 => 1,
+        Key::Key2 | Key::NumPad2 => 2,
+        Key::Key3 | Key::NumPad3 => 3,
+        Key::Key4 | Key::NumPad4 => 4,
+        Key::Key5 | Key::NumPad5 => 5,
+        Key::Key6 | Key::NumPad6 => 6,
+        Key::Key7 | Key::NumPad7 => 7,
+        Key::Key8 | Key::NumPad8 => 8,
+        Key::Key9 | Key::NumPad9 => 9,
+        _ => return None,
+    };
+    char::from_digit(digit, 10).map(Event::Typed)
+}
+
+/// How many steps pressing `key` zooms in (or out, if negative).
+fn zoom_steps(key: Key) -> i32 {
+    match key {
+        // `+` is Shift+`=` on most keyboards, but there's no need to insist
+        Key::Equal | Key::NumPadPlus => 1,
+        Key::Minus | Key::NumPadMinus => -1,
+        _ => 0,
+    }
+}
+
+/// How big the window is at `scale`: the clock, and room for it to wander in
+/// with `--burn-in`.
+fn window_size(scale: f32, config: &Config) -> (usize, usize) {
+    burn_in::with_room(frame::window_size(scale, config), config)
+}
+
+/// Opens a normal window, `size` big inside.
+fn open_window(
+    config: &Config,
+    size: (usize, usize),
+    remembered: Option<Layout>,
+    monitors: &[Monitor],
+) -> Window {
+    let (width, height) = size;
+    let mut window = Window::new(
+        &config.title.format(&config.time_source.now(), &config.command),
+        width,
+        height,
+        WindowOptions {
+            borderless: config.borderless,
+            title: !config.borderless,
+            resize: config.resize.is_some(),
+            scale_mode: match config.resize {
+                Some(ResizePolicy::Letterbox | ResizePolicy::Integer) => ScaleMode::Center,
+                Some(ResizePolicy::Stretch) | None => ScaleMode::Stretch,
+            },
+            ..WindowOptions::default()
+        },
+    ).unwrap();
+    // It's paced by `poll_events` instead, which knows when a frame's waiting
+    window.limit_update_rate(None);
+    move_on_screen(&mut window, config, size, remembered, monitors);
+    if config.sticky {
+        make_sticky(&window);
+    }
+    window
+}
+
+/// Opens a window covering all of `monitor`, with the clock in the middle.
+///
+/// There's no real fullscreen mode to ask for, so it's faked with a borderless
+/// window that's always on top.
+fn open_kiosk_window(monitor: &Monitor, config: &Config) -> Window {
+    let mut window = Window::new(
+        &config.title.format(&config.time_source.now(), &config.command),
+        monitor.width,
+        monitor.height,
+        WindowOptions {
+            borderless: true,
+            title: false,
+            topmost: true,
+            scale_mode: ScaleMode::Center,
+            ..WindowOptions::default()
+        },
+    ).unwrap();
+    window.limit_update_rate(None);
+    window.set_position(monitor.x, monitor.y);
+    window.set_cursor_visibility(false);
+    if config.sticky {
+        make_sticky(&window);
+    }
+    window
+}
+
+/// Asks the window manager to show `window` on every workspace.
+///
+/// minifb can't, so it's left to `wmctrl` or `xdotool`, which only works on
+/// X11. Wayland has no way for a window to ask, and on macOS and Windows it's
+/// up to the user (in the Dock's Options menu, or Task View).
+fn make_sticky(window: &Window) {
+    let id = match window.raw_window_handle() {
+        RawWindowHandle::Xlib(handle) => handle.window,
+        RawWindowHandle::Xcb(handle) => handle.window.into(),
+        _ => {
+            eprintln!("warning: `--sticky` only works on X11");
+            return;
+        }
+    };
+    let mut command = if on_path("wmctrl") {
+        let mut command = Command::new("wmctrl");
+        command.args(["-i", "-r", &format!("{id:#x}"), "-b", "add,sticky"]);
+        command
+    } else {
+        // -1 is every desktop
+        let mut command = Command::new("xdotool");
+        command.args(["set_desktop_for_window", &id.to_string(), "-1"]);
+        command
+    };
+    if let Err(e) = command.status() {
+        eprintln!("warning: couldn't make the window sticky (is wmctrl or xdotool installed?): {e}");
+    }
+}
+
+/// Puts the window back where it was remembered to be, or failing that, where
+/// the config says it goes. Otherwise the OS's placement is left alone, unless
+/// it's completely off-screen.
+fn move_on_screen(
+    window: &mut Window,
+    config: &Config,
+    (width, height): (usize, usize),
+    remembered: Option<Layout>,
+    monitors: &[Monitor],
+) {
+    let remembered = remembered
+        .map(|layout| (layout.x, layout.y))
+        .filter(|&(x, y)| placement::is_on_screen(monitors, x, y, width, height));
+    let position = remembered
+        .or_else(|| placement::initial_position(
+            config.monitor.as_ref(),
+            config.anchor,
+            config.geometry,
+            config.screen_margin,
+            width,
+            height,
+            monitors,
+        ))
+        .or_else(|| {
+            let (x, y) = window.get_position();
+            if monitors.is_empty() || placement::is_on_screen(monitors, x, y, width, height) {
+                return None;
+            }
+            // Lost off the edge somewhere, so rescue it to the middle of the main screen
+            let primary = Some(&placement::MonitorSelector::Primary);
+            placement::initial_position(primary, None, None, 0, width, height, monitors)
+        });
+    if let Some((x, y)) = position {
+        window.set_position(x, y);
+    }
+}
+
+fn current_layout(window: &Window, scale: f32) -> Layout {
+    let (x, y) = window.get_position();
+    Layout { x, y, scale }
+}
@@ -0,0 +1,160 @@
+//! Time zones, for the world clock and for the time on the clock itself: all
+//! the ways of saying one (offsets like `+09:00`, and names from the tz
+//! database like `Asia/Tokyo`) are worked out here, so they all mean the same
+//! thing wherever they're given.
+
+use std::str::FromStr;
+
+use chrono::{DateTime, FixedOffset, Offset, TimeZone, Utc};
+
+/// Nicknames for zones, besides the names in the tz database.
+const ALIASES: &[(&str, chrono_tz::Tz)] = &[
+    // What the cavern's clocks keep, going by the DRC's in New Mexico
+    ("cavern", chrono_tz::America::Denver),
+];
+
+/// A time zone: either a fixed offset from UTC, or a named one from the tz
+/// database, whose offset changes with daylight saving time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tz {
+    Fixed(FixedOffset),
+    Named(chrono_tz::Tz),
+}
+
+impl Tz {
+    /// The offset from UTC in effect at `time`.
+    pub fn offset_at(self, time: DateTime<Utc>) -> FixedOffset {
+        match self {
+            Self::Fixed(offset) => offset,
+            Self::Named(tz) => tz.offset_from_utc_datetime(&time.naive_utc()).fix(),
+        }
+    }
+
+    /// What it's called: its name in the time zone database, or its offset.
+    pub fn name(self) -> String {
+        match self {
+            Self::Fixed(offset) => format!("UTC{offset}"),
+            Self::Named(tz) => tz.name().to_owned(),
+        }
+    }
+
+    /// `time`, as it is in this zone.
+    pub fn convert<Tz2: TimeZone>(self, time: &DateTime<Tz2>) -> DateTime<FixedOffset> {
+        let time = time.with_timezone(&Utc);
+        time.with_timezone(&self.offset_at(time))
+    }
+}
+
+impl FromStr for Tz {
+    type Err = String;
+
+    /// Parses an offset (see [`parse_offset`]) or a zone name, which doesn't
+    /// have to be capitalized right, and can be just the city (`tokyo`), with
+    /// spaces for underscores. If it's not any of those, the error suggests
+    /// the closest name there is.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with(['+', '-']) || s.eq_ignore_ascii_case("utc") || s == "Z" {
+            return parse_offset(s).map(Self::Fixed);
+        }
+        if let Some(tz) = alias(s) {
+            return Ok(Self::Named(tz));
+        }
+        let wanted = s.trim().replace(' ', "_").to_ascii_lowercase();
+        let mut closest = None;
+        for &tz in &chrono_tz::TZ_VARIANTS {
+            let name = tz.name().to_ascii_lowercase();
+            let city = name.rsplit('/').next().unwrap_or(&name);
+            let distance = usize::min(edit_distance(&wanted, &name), edit_distance(&wanted, city));
+            if distance == 0 {
+                return Ok(Self::Named(tz));
+            }
+            if closest.is_none_or(|(closest, _)| distance < closest) {
+                closest = Some((distance, tz));
+            }
+        }
+        // Anything further off than a typo or two is probably not what they meant
+        match closest.filter(|&(distance, _)| distance <= wanted.len() / 3 + 1) {
+            Some((_, tz)) => Err(format!("unknown time zone (did you mean `{}`?)", tz.name())),
+            None => Err("expected a time zone like `Asia/Tokyo`, or an offset like `+09:00`".to_owned()),
+        }
+    }
+}
+
+/// A place to show the time for, as given by `--zone Tokyo=Asia/Tokyo`, or
+/// just `--zone Asia/Tokyo`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Zone {
+    /// What to call it on the clock face.
+    pub name: String,
+    pub tz: Tz,
+}
+
+impl FromStr for Zone {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((name, tz)) => Ok(Self { name: name.to_owned(), tz: tz.parse()? }),
+            None => {
+                let tz = s.parse()?;
+                // Named after the city, like the tz database's names are
+                let name = match tz {
+                    Tz::Named(tz) if alias(s).is_none() => {
+                        tz.name().rsplit('/').next().unwrap_or(s).replace('_', " ")
+                    }
+                    _ => s.to_owned(),
+                };
+                Ok(Self { name, tz })
+            }
+        }
+    }
+}
+
+/// The zone `s` is a nickname for, if it's one of [`ALIASES`].
+fn alias(s: &str) -> Option<chrono_tz::Tz> {
+    ALIASES.iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(s.trim()))
+        .map(|&(_, tz)| tz)
+}
+
+/// How many characters have to be added, removed, or changed to turn `a` into
+/// `b` (the Levenshtein distance).
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    // Only the last row of the table is needed at a time
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &b_char) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a_char != b_char);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Parses a UTC offset like `+09:00`, `-5`, `+0545`, or `UTC`.
+pub fn parse_offset(s: &str) -> Result<FixedOffset, String> {
+    const BAD: &str = "expected an offset like `+09:00`, `-5`, or `UTC`";
+    if s.eq_ignore_ascii_case("utc") || s == "Z" {
+        return Ok(FixedOffset::east(0));
+    }
+    let (sign, rest) = match s.as_bytes().first() {
+        Some(b'+') => (1, &s[1..]),
+        Some(b'-') => (-1, &s[1..]),
+        _ => return Err(BAD.to_owned()),
+    };
+    let (hours, minutes) = match rest.split_once(':') {
+        Some((h, m)) => (h, m),
+        None if rest.len() > 2 => rest.split_at(rest.len() - 2),
+        None => (rest, "0"),
+    };
+    let hours: i32 = hours.parse().map_err(|_| BAD)?;
+    let minutes: i32 = minutes.parse().map_err(|_| BAD)?;
+    if hours > 23 || minutes > 59 {
+        return Err(BAD.to_owned());
+    }
+    Ok(FixedOffset::east(sign * (hours * 60 + minutes) * 60))
+}